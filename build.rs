@@ -0,0 +1,12 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=proto/ggw.proto");
+    #[cfg(feature = "grpc")]
+    {
+        let descriptors = protox::compile(["proto/ggw.proto"], ["proto"])
+            .expect("failed to parse proto/ggw.proto");
+        tonic_prost_build::configure()
+            .compile_fds(descriptors)
+            .expect("failed to generate gRPC bindings");
+    }
+}
@@ -0,0 +1,156 @@
+#ifndef GGW_WORLD_H
+#define GGW_WORLD_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum GgwStatus {
+  GGW_STATUS_OK = 0,
+  GGW_STATUS_NULL_POINTER = 1,
+  GGW_STATUS_INVALID_BODY_TYPE = 2,
+  GGW_STATUS_INVALID_ORBIT = 3,
+  GGW_STATUS_UNKNOWN_BODY = 4,
+  /**
+   * The output buffer is too short; the required length was written to
+   * `out_len`.
+   */
+  GGW_STATUS_BUFFER_TOO_SMALL = 5,
+  /**
+   * The requested body id is taken, was used by a removed body, or is
+   * `u64::MAX`.
+   */
+  GGW_STATUS_INVALID_BODY_ID = 6,
+} GgwStatus;
+
+/**
+ * Opaque handle owned by the caller between `ggw_world_new` and
+ * `ggw_world_free`.
+ */
+typedef struct GgwWorld GgwWorld;
+
+typedef struct GgwOrbit {
+  double semi_major_axis;
+  double eccentricity;
+  double arg_of_periapsis;
+  double mean_anomaly_at_epoch;
+  double epoch;
+} GgwOrbit;
+
+/**
+ * Flattened body snapshot. `body_type` uses the same codes as the wasm
+ * bindings: 0 ship, 1 asteroid, 2 debris, 3 missile.
+ */
+typedef struct GgwBodyState {
+  uint64_t id;
+  uint8_t body_type;
+  double mass;
+  double radius;
+  double x;
+  double y;
+  double vx;
+  double vy;
+} GgwBodyState;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create a world around a planet with gravitational parameter `mu`, using
+ * the default game config. Release it with `ggw_world_free`.
+ */
+struct GgwWorld *ggw_world_new(double mu);
+
+/**
+ * # Safety
+ * `world` must be null or a pointer returned by `ggw_world_new` that has
+ * not already been freed.
+ */
+void ggw_world_free(struct GgwWorld *world);
+
+/**
+ * # Safety
+ * `world` must be a live handle from `ggw_world_new`.
+ */
+enum GgwStatus ggw_world_step(struct GgwWorld *world, double dt);
+
+/**
+ * # Safety
+ * `world` must be a live handle from `ggw_world_new`.
+ */
+enum GgwStatus ggw_world_warp_to(struct GgwWorld *world, double target_time);
+
+/**
+ * Current simulation time in seconds, or NaN for a null handle.
+ *
+ * # Safety
+ * `world` must be null or a live handle from `ggw_world_new`.
+ */
+double ggw_world_sim_time(const struct GgwWorld *world);
+
+/**
+ * # Safety
+ * `world` must be null or a live handle from `ggw_world_new`.
+ */
+uint64_t ggw_world_state_hash(const struct GgwWorld *world);
+
+/**
+ * Spawn a body on `orbit`. `id` 0 lets the world assign one; the id used
+ * is written to `out_id` when it is non-null.
+ *
+ * # Safety
+ * `world` must be a live handle, `orbit` must point to a valid
+ * `GgwOrbit`, and `out_id` must be null or writable.
+ */
+enum GgwStatus ggw_world_spawn_body(struct GgwWorld *world,
+                                    uint64_t id,
+                                    uint8_t body_type,
+                                    double mass,
+                                    double radius,
+                                    const struct GgwOrbit *orbit,
+                                    uint64_t *out_id);
+
+/**
+ * # Safety
+ * `world` must be a live handle from `ggw_world_new`.
+ */
+enum GgwStatus ggw_world_remove_body(struct GgwWorld *world, uint64_t id);
+
+/**
+ * Apply an instantaneous chemical burn to `body_id`.
+ *
+ * # Safety
+ * `world` must be a live handle from `ggw_world_new`.
+ */
+enum GgwStatus ggw_world_apply_thrust(struct GgwWorld *world,
+                                      uint64_t body_id,
+                                      double time,
+                                      double delta_v_x,
+                                      double delta_v_y);
+
+/**
+ * Copy every body into `out`, which holds `capacity` entries. The number
+ * of bodies is always written to `out_len`; if it exceeds `capacity`
+ * nothing is copied and `BufferTooSmall` is returned, so callers can size
+ * the buffer with a first call passing `capacity` 0.
+ *
+ * # Safety
+ * `world` must be a live handle, `out_len` must be writable, and `out`
+ * must be valid for `capacity` writes (it may be null when `capacity` is
+ * 0).
+ */
+enum GgwStatus ggw_world_body_states(const struct GgwWorld *world,
+                                     struct GgwBodyState *out,
+                                     size_t capacity,
+                                     size_t *out_len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* GGW_WORLD_H */
@@ -0,0 +1,88 @@
+//! Angle helpers shared by the orbit math and by callers doing attitude,
+//! guidance or UI work. Everything is in radians unless the name says
+//! otherwise.
+
+use core::f64::consts::{PI, TAU};
+
+use crate::math::Float;
+
+/// Wrap `angle` into `[-π, π)`. Values already in range come back
+/// unchanged, bit for bit.
+pub fn wrap_pi(angle: f64) -> f64 {
+    if (-PI..PI).contains(&angle) {
+        return angle;
+    }
+    wrap_two_pi(angle + PI) - PI
+}
+
+/// Wrap `angle` into `[0, 2π)`.
+pub fn wrap_two_pi(angle: f64) -> f64 {
+    if (0.0..TAU).contains(&angle) {
+        return angle;
+    }
+    let wrapped = Float::rem_euclid(angle, TAU);
+    // A tiny negative input can round up to exactly 2π.
+    if wrapped >= TAU {
+        0.0
+    } else {
+        wrapped
+    }
+}
+
+/// Signed rotation in `[-π, π)` that takes `from` to `to` the short way
+/// round; positive is counter-clockwise.
+pub fn shortest_difference(from: f64, to: f64) -> f64 {
+    wrap_pi(to - from)
+}
+
+/// Interpolate from `from` towards `to` along the shorter arc. The result
+/// is wrapped into `[-π, π)`.
+pub fn lerp(from: f64, to: f64, t: f64) -> f64 {
+    wrap_pi(from + shortest_difference(from, to) * t)
+}
+
+pub fn to_degrees(radians: f64) -> f64 {
+    radians * (180.0 / PI)
+}
+
+pub fn from_degrees(degrees: f64) -> f64 {
+    degrees * (PI / 180.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn wrapping_uses_half_open_ranges() {
+        assert_eq!(wrap_pi(PI), -PI);
+        assert_eq!(wrap_pi(-PI), -PI);
+        approx_eq(wrap_pi(3.0 * PI + 0.5), -PI + 0.5);
+        approx_eq(wrap_pi(-7.0 * TAU - 0.25), -0.25);
+        assert_eq!(wrap_two_pi(TAU), 0.0);
+        assert_eq!(wrap_two_pi(-1e-20), 0.0);
+        approx_eq(wrap_two_pi(-0.5), TAU - 0.5);
+        assert_eq!(wrap_pi(0.123), 0.123);
+    }
+
+    #[test]
+    fn difference_and_lerp_take_the_short_way() {
+        approx_eq(
+            shortest_difference(from_degrees(350.0), from_degrees(10.0)),
+            from_degrees(20.0),
+        );
+        approx_eq(
+            shortest_difference(from_degrees(10.0), from_degrees(350.0)),
+            from_degrees(-20.0),
+        );
+        approx_eq(
+            to_degrees(lerp(from_degrees(170.0), from_degrees(-170.0), 0.5)),
+            -180.0,
+        );
+        approx_eq(lerp(0.2, 0.6, 0.25), 0.3);
+    }
+}
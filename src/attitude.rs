@@ -0,0 +1,103 @@
+//! RCS attitude control.
+//!
+//! A body given a heading with `World::set_attitude_target` is turned
+//! towards it every tick by a PD controller, with gains and limits from
+//! `config::AttitudeConfig`. The controller runs in slices of at most
+//! `ATTITUDE_STEP_S`, so a turn settles the same way under time warp as it
+//! does stepped. Bodies without a target spin freely, and a
+//! `TorqueEvent` kicks a body's spin directly.
+
+use crate::angle;
+use crate::config::AttitudeConfig;
+
+/// Longest slice the controller is run for at once.
+pub const ATTITUDE_STEP_S: f64 = 0.1;
+
+/// Orientation and spin `dt` seconds on for a body steering from
+/// `orientation` and `angular_velocity` towards the heading `target`.
+pub fn steer(
+    orientation: f64,
+    angular_velocity: f64,
+    target: f64,
+    config: &AttitudeConfig,
+    dt: f64,
+) -> (f64, f64) {
+    let max_rate = config.max_angular_velocity_rad_s.max(0.0);
+    let max_accel = config.max_angular_acceleration_rad_s2.max(0.0);
+    let (mut orientation, mut angular_velocity) = (orientation, angular_velocity);
+    let mut remaining = dt;
+    while remaining > 0.0 {
+        let error = angle::shortest_difference(orientation, target);
+        if error.abs() < 1e-9 && angular_velocity.abs() < 1e-9 {
+            // Settled; hold the heading exactly.
+            return (angle::wrap_pi(target), 0.0);
+        }
+        let step = remaining.min(ATTITUDE_STEP_S);
+        let wanted = config.proportional_gain * error - config.derivative_gain * angular_velocity;
+        angular_velocity = (angular_velocity + wanted.clamp(-max_accel, max_accel) * step)
+            .clamp(-max_rate, max_rate);
+        orientation = angle::wrap_pi(orientation + angular_velocity * step);
+        remaining -= step;
+    }
+    (orientation, angular_velocity)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f64::consts::PI;
+
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::error::GgwError;
+    use crate::{BodyState, Radians, Seconds, TorqueEvent, World};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn rcs_turns_to_the_target_within_limits() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        assert_eq!(
+            world.set_attitude_target(ship + 1, Some(Radians(1.0))),
+            Err(GgwError::UnknownBody(ship + 1))
+        );
+
+        // A kick is capped at the configured rate.
+        world
+            .apply_torque_event(&TorqueEvent {
+                body_id: ship,
+                delta_angular_velocity: 2.0,
+            })
+            .unwrap();
+        assert_eq!(world.body(ship).unwrap().angular_velocity, 0.5);
+
+        // Turning the long way round from a spin the wrong way.
+        world
+            .set_attitude_target(ship, Some(Radians(-PI / 2.0)))
+            .unwrap();
+        assert_eq!(world.attitude_target(ship), Some(Radians(-PI / 2.0)));
+        let mut peak: f64 = 0.0;
+        for _ in 0..300 {
+            world.step(Seconds(0.1));
+            peak = peak.max(world.body(ship).unwrap().angular_velocity.abs());
+        }
+        assert!(peak <= 0.5);
+        let body = world.body(ship).unwrap();
+        assert!(angle::shortest_difference(body.orientation, -PI / 2.0).abs() < 1e-3);
+        assert!(body.angular_velocity.abs() < 1e-3);
+
+        // Warping gets to the same heading.
+        world
+            .set_attitude_target(ship, Some(Radians(PI / 2.0)))
+            .unwrap();
+        world.warp_to(world.sim_time() + Seconds(120.0));
+        let body = world.body(ship).unwrap();
+        assert!(angle::shortest_difference(body.orientation, PI / 2.0).abs() < 1e-3);
+
+        // Cleared, the body coasts.
+        world.set_attitude_target(ship, None).unwrap();
+        assert_eq!(world.attitude_target(ship), None);
+    }
+}
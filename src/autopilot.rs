@@ -0,0 +1,419 @@
+//! Autopilots: station-keeping and velocity matching.
+//!
+//! A body given an `Autopilot` with `World::set_autopilot` flies itself
+//! until told to stop. Every `config::AutopilotConfig::interval_s` it
+//! checks how it is doing and burns if it has to, with ordinary thrust
+//! events paid for in propellant like any other, so they show up as
+//! `ThrustApplied`, or `ThrustRejected` once the propellant runs out.
+//!
+//! Holding a station, on a reference orbit around its primary or at a
+//! fixed world-frame offset from another body, it works out where the
+//! station will be one interval on and the velocity that would carry it
+//! there, by `transfer::lambert`, and burns the difference with RCS, capped
+//! at `max_correction_mps`. A body within `deadband_m` of its station that
+//! would still be within it by the next check is left alone, so a body on
+//! station costs nothing to keep there.
+//!
+//! Matching velocity with a target, it finds their next closest approach
+//! and, once that is less than an interval off, schedules a main-engine
+//! burn at exactly that moment to leave it moving with the target. A
+//! difference no bigger than `max_correction_mps`, like what is left after
+//! that burn as the two drift under slightly different gravity, is trimmed
+//! straight away with RCS, down to `match_tolerance_mps`.
+//!
+//! Checks are made at most once a step, so under time warp they come no
+//! more often than `WARP_SUBSTEP_S`. A landed body doesn't fly, and an
+//! autopilot flying relative to another body stops when that body leaves
+//! the world.
+
+use alloc::vec::Vec;
+
+use crate::transfer::{self, INTERCEPT_SEARCH_STEPS};
+use crate::{error, BodyState, GgwError, OrbitState, ThrustEvent, ThrustType, Vec2, World};
+
+/// What an autopilot does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Autopilot {
+    /// Hold this orbit around the body's own primary.
+    Orbit(OrbitState),
+    /// Hold `offset` from `body_id`, in the world frame.
+    Offset { body_id: u64, offset: Vec2 },
+    /// Kill the velocity relative to `target_id` at closest approach, and
+    /// keep it killed.
+    MatchVelocity { target_id: u64 },
+}
+
+impl Autopilot {
+    /// Other body the autopilot flies relative to, if any.
+    pub fn follows(&self) -> Option<u64> {
+        match *self {
+            Autopilot::Orbit(_) => None,
+            Autopilot::Offset { body_id, .. } => Some(body_id),
+            Autopilot::MatchVelocity { target_id } => Some(target_id),
+        }
+    }
+}
+
+/// An engaged autopilot and when it next checks on things.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct AutopilotState {
+    pub(crate) autopilot: Autopilot,
+    pub(crate) next_correction: f64,
+    /// Burn it has scheduled and not yet seen carried out.
+    pub(crate) planned: Option<ThrustEvent>,
+}
+
+/// `v` mirrored across the x axis, turning clockwise motion anticlockwise.
+fn mirror(v: Vec2) -> Vec2 {
+    Vec2::new(v.x, -v.y)
+}
+
+impl World {
+    /// Hand `body_id` over to `autopilot` from now on, or take it back with
+    /// `None`; a burn the old autopilot had scheduled is cancelled. Fails
+    /// with `UnknownBody` if the body doesn't exist, or if the autopilot
+    /// follows a body that doesn't or follows the body itself.
+    pub fn set_autopilot(
+        &mut self,
+        body_id: u64,
+        autopilot: Option<Autopilot>,
+    ) -> error::Result<()> {
+        if self.body(body_id).is_none() {
+            return Err(GgwError::UnknownBody(body_id));
+        }
+        if let Some(other) = autopilot.and_then(|autopilot| autopilot.follows()) {
+            if other == body_id || self.body(other).is_none() {
+                return Err(GgwError::UnknownBody(other));
+            }
+        }
+        self.disengage_autopilot(body_id);
+        if let Some(autopilot) = autopilot {
+            let state = AutopilotState {
+                autopilot,
+                next_correction: self.sim_time().0,
+                planned: None,
+            };
+            self.autopilots.insert(body_id, state);
+        }
+        Ok(())
+    }
+
+    /// Autopilot flying `body_id`, if it has one.
+    pub fn autopilot(&self, body_id: u64) -> Option<Autopilot> {
+        self.autopilots.get(&body_id).map(|state| state.autopilot)
+    }
+
+    /// Drop `body_id`'s autopilot and any burn it has scheduled.
+    fn disengage_autopilot(&mut self, body_id: u64) {
+        let Some(state) = self.autopilots.remove(&body_id) else {
+            return;
+        };
+        if let Some(planned) = state.planned {
+            if let Some(at) = self.scheduled_thrusts.iter().position(|e| *e == planned) {
+                self.scheduled_thrusts.remove(at);
+            }
+        }
+    }
+
+    /// Drop the autopilots of `body_id` and of every body flying relative
+    /// to it, as it leaves the world.
+    pub(crate) fn forget_autopilots(&mut self, body_id: u64) {
+        let following: Vec<u64> = self
+            .autopilots
+            .iter()
+            .filter(|(&id, state)| id == body_id || state.autopilot.follows() == Some(body_id))
+            .map(|(&id, _)| id)
+            .collect();
+        for id in following {
+            self.disengage_autopilot(id);
+        }
+    }
+
+    /// Make every check that has come due.
+    pub(crate) fn fly_autopilots(&mut self) {
+        if self.autopilots.is_empty() {
+            return;
+        }
+        let now = self.sim_time().0;
+        let due: Vec<u64> = self
+            .autopilots
+            .iter()
+            .filter(|(_, state)| state.next_correction <= now)
+            .map(|(&id, _)| id)
+            .collect();
+        let interval = self.config.autopilot.interval_s.max(1.0);
+        for body_id in due {
+            if let Some(state) = self.autopilots.get_mut(&body_id) {
+                state.next_correction = now + interval;
+                state.planned = None;
+            }
+            if self.landed.contains_key(&body_id) {
+                continue;
+            }
+            let autopilot = self.autopilots[&body_id].autopilot;
+            let correction = match autopilot {
+                Autopilot::MatchVelocity { target_id } => {
+                    self.match_velocity(body_id, target_id, now, interval)
+                }
+                _ => self.station_correction(body_id, now, interval),
+            };
+            let event = match correction {
+                Ok(Some(event)) => event,
+                Ok(None) => continue,
+                Err(err) => {
+                    tracing::warn!(body = body_id, error = %err, "autopilot failed");
+                    continue;
+                }
+            };
+            if event.time <= now {
+                // A rejected burn is reported as `ThrustRejected`.
+                let _ = self.apply_thrust_event(&event);
+                continue;
+            }
+            // Checked again the moment the burn is done.
+            let time = event.time;
+            if let Err(err) = self.schedule_thrust(event.clone()) {
+                tracing::warn!(body = body_id, error = %err, "autopilot burn dropped");
+                continue;
+            }
+            if let Some(state) = self.autopilots.get_mut(&body_id) {
+                state.next_correction = time;
+                state.planned = Some(event);
+            }
+        }
+    }
+
+    /// Where `body`'s station is at `time`, in the world frame.
+    fn station_at(
+        &self,
+        body: &BodyState,
+        autopilot: &Autopilot,
+        time: f64,
+    ) -> error::Result<Vec2> {
+        match *autopilot {
+            Autopilot::Offset { body_id, offset } => {
+                let other = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+                Ok(self.state_at(other, time)?.0 + offset)
+            }
+            Autopilot::Orbit(orbit) => {
+                let primaries = self.primaries_at(time);
+                Ok(primaries.get(body.parent).state_at(&orbit, time)?.0)
+            }
+            Autopilot::MatchVelocity { .. } => Ok(self.state_at(body, time)?.0),
+        }
+    }
+
+    /// The burn that puts `body_id` back on station one `interval` after
+    /// `now`, or `None` if it is close enough already.
+    fn station_correction(
+        &self,
+        body_id: u64,
+        now: f64,
+        interval: f64,
+    ) -> error::Result<Option<ThrustEvent>> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        let autopilot = self.autopilots[&body_id].autopilot;
+        let config = &self.config.autopilot;
+        let later = now + interval;
+        // Positions are where the last step left them; work from orbits,
+        // as burns do.
+        let (position, velocity) = self.state_at(body, now)?;
+        let target = self.station_at(body, &autopilot, later)?;
+        let off_station = position.distance(self.station_at(body, &autopilot, now)?);
+        let drift = self.state_at(body, later)?.0.distance(target);
+        if off_station <= config.deadband_m && drift <= config.deadband_m {
+            return Ok(None);
+        }
+
+        let primary = *self.primaries_at(now).get(body.parent);
+        let r1 = position - primary.position;
+        let r2 = target - self.primaries_at(later).get(body.parent).position;
+        let velocity = velocity - primary.velocity;
+        // `lambert` only flies anticlockwise; solve a clockwise orbit
+        // mirrored.
+        let needed = match r1.cross(velocity) < 0.0 {
+            true => mirror(transfer::lambert(mirror(r1), mirror(r2), interval, primary.mu)?.0),
+            false => transfer::lambert(r1, r2, interval, primary.mu)?.0,
+        };
+        let mut delta_v = needed - velocity;
+        let max = config.max_correction_mps.max(0.0);
+        if delta_v.length() > max {
+            delta_v = delta_v.normalized() * max;
+        }
+        Ok(Some(ThrustEvent {
+            body_id,
+            time: now,
+            delta_v,
+            thrust_type: ThrustType::Rcs,
+        }))
+    }
+
+    /// The burn that kills `body_id`'s velocity relative to `target_id`:
+    /// an RCS trim now if the difference is small, a main-engine burn at
+    /// closest approach if that comes within `interval`, or else `None`.
+    fn match_velocity(
+        &self,
+        body_id: u64,
+        target_id: u64,
+        now: f64,
+        interval: f64,
+    ) -> error::Result<Option<ThrustEvent>> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        let target = self
+            .body(target_id)
+            .ok_or(GgwError::UnknownBody(target_id))?;
+        let config = &self.config.autopilot;
+        let relative_velocity = |time: f64| -> error::Result<Vec2> {
+            Ok(self.state_at(target, time)?.1 - self.state_at(body, time)?.1)
+        };
+        let delta_v = relative_velocity(now)?;
+        if delta_v.length() <= config.match_tolerance_mps {
+            return Ok(None);
+        }
+        if delta_v.length() <= config.max_correction_mps {
+            return Ok(Some(ThrustEvent {
+                body_id,
+                time: now,
+                delta_v,
+                thrust_type: ThrustType::Rcs,
+            }));
+        }
+
+        let mu = self.primaries_at(now).get(body.parent).mu;
+        let period = body.orbit.period(mu);
+        let horizon = match period.is_finite() {
+            true => period,
+            false => interval * INTERCEPT_SEARCH_STEPS as f64,
+        };
+        let distance = |tof: f64| {
+            let time = now + tof;
+            match (self.state_at(body, time), self.state_at(target, time)) {
+                (Ok((a, _)), Ok((b, _))) => a.distance(b),
+                _ => f64::INFINITY,
+            }
+        };
+        let closest = match transfer::cheapest_flight_time(horizon, distance) {
+            Some(tof) if distance(tof) < distance(0.0) => tof,
+            _ => 0.0,
+        };
+        if closest > interval {
+            return Ok(None);
+        }
+        Ok(Some(ThrustEvent {
+            body_id,
+            time: now + closest,
+            delta_v: relative_velocity(now + closest)?,
+            thrust_type: ThrustType::Chemical,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::propellant::Propellant;
+    use crate::{Seconds, WorldEvent};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn stations_are_held_with_rcs_burns() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let station = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        // 140 m behind where it should be.
+        let laggard = world
+            .add_body(BodyState::circular(7_000_000.0, -2e-5))
+            .unwrap();
+        // 70 m along the orbit from the station, holding 40 m off it.
+        let escort = world
+            .add_body(BodyState::circular(7_000_000.0, 1e-5))
+            .unwrap();
+        let reserve = Propellant {
+            fuel_kg: 10.0,
+            xenon_kg: 0.0,
+        };
+        for id in [laggard, escort] {
+            world.set_propellant(id, Some(reserve)).unwrap();
+        }
+        let reference = BodyState::circular(7_000_000.0, 0.0).orbit;
+        world
+            .set_autopilot(laggard, Some(Autopilot::Orbit(reference)))
+            .unwrap();
+        let offset = Vec2::new(0.0, 40.0);
+        let hold = Autopilot::Offset {
+            body_id: station,
+            offset,
+        };
+        world.set_autopilot(escort, Some(hold)).unwrap();
+        assert_eq!(world.autopilot(escort), Some(hold));
+        assert_eq!(
+            world.set_autopilot(99, None),
+            Err(GgwError::UnknownBody(99))
+        );
+
+        for _ in 0..900 {
+            world.step(Seconds(1.0));
+        }
+        let deadband = world.config.autopilot.deadband_m;
+        let target = world.body(station).unwrap().position;
+        let laggard_body = world.body(laggard).unwrap();
+        assert!(laggard_body.position.distance(target) < 2.0 * deadband);
+        let escort_body = world.body(escort).unwrap();
+        assert!(escort_body.position.distance(target + offset) < 2.0 * deadband);
+        for id in [laggard, escort] {
+            let left = world.propellant(id).unwrap().fuel_kg;
+            assert!(left < reserve.fuel_kg && left > 0.0);
+        }
+
+        // The station leaving the world ends the hold.
+        world.remove_body(station);
+        assert_eq!(world.autopilot(escort), None);
+        assert_eq!(world.autopilot(laggard), Some(Autopilot::Orbit(reference)));
+    }
+
+    #[test]
+    fn velocity_is_matched_at_closest_approach() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let target = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        // 5 km lower and 35 km behind, catching up over about 70 minutes.
+        let chaser = world
+            .add_body(BodyState::circular(6_995_000.0, -5e-3))
+            .unwrap();
+        let hunt = Autopilot::MatchVelocity { target_id: target };
+        world.set_autopilot(chaser, Some(hunt)).unwrap();
+        assert_eq!(
+            world.set_autopilot(chaser, Some(Autopilot::MatchVelocity { target_id: chaser })),
+            Err(GgwError::UnknownBody(chaser))
+        );
+
+        let matched = |world: &World| {
+            world.events().iter().any(|event| {
+                matches!(event, WorldEvent::ThrustApplied(burn)
+                    if burn.body_id == chaser && burn.thrust_type == ThrustType::Chemical)
+            })
+        };
+        let mut steps = 0;
+        while !matched(&world) {
+            world.step(Seconds(10.0));
+            steps += 1;
+            assert!(steps < 600, "never matched velocity");
+            // Taking the body back cancels the burn it has planned.
+            if !world.scheduled_thrusts().is_empty() {
+                let mut manual = world.clone();
+                manual.set_autopilot(chaser, None).unwrap();
+                assert_eq!(manual.autopilot(chaser), None);
+                assert!(manual.scheduled_thrusts().is_empty());
+            }
+        }
+        let (a, b) = (world.body(chaser).unwrap(), world.body(target).unwrap());
+        assert!(a.position.distance(b.position) < 5_100.0);
+        assert!(a.velocity.distance(b.velocity) < 0.1);
+    }
+}
@@ -0,0 +1,189 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::{config::AutosaveConfig, World};
+
+const SAVE_EXTENSION: &str = "json";
+const TEMP_EXTENSION: &str = "json.tmp";
+
+/// Write `world` to `path` without ever leaving a truncated file behind:
+/// the save goes to a sibling temp file, is flushed to disk, and is then
+/// renamed over `path`.
+pub fn save_world(world: &World, path: &Path) -> io::Result<()> {
+    let temp_path = path.with_extension(TEMP_EXTENSION);
+    {
+        let file = File::create(&temp_path)?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &world.save()).map_err(io::Error::other)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+    }
+    fs::rename(&temp_path, path)?;
+    sync_parent_dir(path);
+    Ok(())
+}
+
+pub fn load_world(path: &Path) -> io::Result<World> {
+    let reader = BufReader::new(File::open(path)?);
+    let save = serde_json::from_reader(reader)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    World::load(save).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Persist the rename itself; best effort, since not every platform lets a
+/// directory be opened for syncing.
+fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// Rotates saves for one world through `slots` files named
+/// `<prefix>_<n>.json` in `directory`, always overwriting the oldest.
+#[derive(Clone, Debug)]
+pub struct Autosaver {
+    directory: PathBuf,
+    prefix: String,
+    interval: Duration,
+    slots: u32,
+    next_slot: u32,
+    last_save: Instant,
+}
+
+impl Autosaver {
+    pub fn new(config: &AutosaveConfig, prefix: &str) -> Self {
+        let directory = PathBuf::from(&config.directory);
+        let slots = config.slots.max(1);
+        let next_slot = match newest_slot(&directory, prefix, slots) {
+            Some((slot, _)) => (slot + 1) % slots,
+            None => 0,
+        };
+        Self {
+            directory,
+            prefix: prefix.to_string(),
+            interval: Duration::from_secs_f64(config.interval_s.max(1.0)),
+            slots,
+            next_slot,
+            last_save: Instant::now(),
+        }
+    }
+
+    pub fn slot_path(&self, slot: u32) -> PathBuf {
+        slot_path(&self.directory, &self.prefix, slot)
+    }
+
+    /// Most recently written save for this prefix, if any.
+    pub fn latest(&self) -> Option<PathBuf> {
+        newest_slot(&self.directory, &self.prefix, self.slots).map(|(_, path)| path)
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        now.duration_since(self.last_save) >= self.interval
+    }
+
+    /// Save if the interval has elapsed. Returns the path written.
+    pub fn tick(&mut self, world: &World, now: Instant) -> io::Result<Option<PathBuf>> {
+        if !self.is_due(now) {
+            return Ok(None);
+        }
+        self.last_save = now;
+        self.save_now(world).map(Some)
+    }
+
+    pub fn save_now(&mut self, world: &World) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.slot_path(self.next_slot);
+        save_world(world, &path)?;
+        self.next_slot = (self.next_slot + 1) % self.slots;
+        Ok(path)
+    }
+}
+
+fn slot_path(directory: &Path, prefix: &str, slot: u32) -> PathBuf {
+    directory.join(format!("{}_{}.{}", prefix, slot, SAVE_EXTENSION))
+}
+
+fn newest_slot(directory: &Path, prefix: &str, slots: u32) -> Option<(u32, PathBuf)> {
+    (0..slots)
+        .filter_map(|slot| {
+            let path = slot_path(directory, prefix, slot);
+            let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok()?;
+            Some((modified, slot, path))
+        })
+        .max_by_key(|(modified, _, _)| *modified)
+        .map(|(_, slot, path)| (slot, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{BodyState, OrbitState, Seconds};
+    use std::collections::BTreeMap;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ggw_autosave_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn test_world() -> World {
+        let mut world = World::new(3.986004418e14, GameConfig::default());
+        world
+            .add_body(BodyState {
+                tags: BTreeMap::from([(String::from("faction"), String::from("miners"))]),
+                ..BodyState::orbiting(OrbitState {
+                    semi_major_axis: 7_000_000.0,
+                    eccentricity: 0.01,
+                    arg_of_periapsis: 0.3,
+                    mean_anomaly_at_epoch: 0.7,
+                    epoch: 0.0,
+                })
+            })
+            .unwrap();
+        world.step(Seconds(1.0));
+        world
+    }
+
+    #[test]
+    fn save_round_trips_world_state() {
+        let dir = temp_dir("round_trip");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("world.json");
+        let world = test_world();
+        save_world(&world, &path).unwrap();
+        assert!(!path.with_extension(TEMP_EXTENSION).exists());
+        let loaded = load_world(&path).unwrap();
+        assert_eq!(loaded.state_hash(), world.state_hash());
+        assert_eq!(loaded.bodies[0].tag("faction"), Some("miners"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn autosaver_rotates_through_slots() {
+        let dir = temp_dir("rotate");
+        let config = AutosaveConfig {
+            enabled: true,
+            directory: dir.display().to_string(),
+            interval_s: 60.0,
+            slots: 2,
+        };
+        let world = test_world();
+        let mut saver = Autosaver::new(&config, "session");
+        let first = saver.save_now(&world).unwrap();
+        let second = saver.save_now(&world).unwrap();
+        let third = saver.save_now(&world).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first, third);
+        assert_eq!(saver.latest(), Some(third));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,239 @@
+//! Propagation of many orbits at once, for collision sweeps and previews
+//! over large debris fields.
+//!
+//! Elements are stored column-wise and Kepler's equation is solved
+//! `LANES` bodies at a time with branch-free updates, so the per-lane
+//! arithmetic vectorizes. Lanes that have converged stop changing but keep
+//! riding along until the whole group is done. Each lane takes exactly the
+//! steps the scalar solver would, so the results are bit-for-bit those of
+//! `orbit_to_cartesian`.
+
+use alloc::vec::Vec;
+
+use crate::math::Float;
+use crate::{angle, kepler_seed, validate_orbit, OrbitState, Vec2};
+
+/// Bodies solved together per group.
+pub const LANES: usize = 8;
+
+const MAX_ITERATIONS: usize = 32;
+const TOLERANCE: f64 = 1e-12;
+
+/// Orbits prepared for batch propagation. Invalid orbits keep their slot so
+/// results line up with the input, but always propagate to `None`.
+#[derive(Clone, Debug, Default)]
+pub struct OrbitBatch {
+    valid: Vec<bool>,
+    semi_major_axis: Vec<f64>,
+    eccentricity: Vec<f64>,
+    mean_motion: Vec<f64>,
+    sqrt_one_minus_e2: Vec<f64>,
+    mean_anomaly_at_epoch: Vec<f64>,
+    epoch: Vec<f64>,
+    /// `(sin, cos)` of the argument of periapsis.
+    rotation: Vec<(f64, f64)>,
+}
+
+impl OrbitBatch {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            valid: Vec::with_capacity(capacity),
+            semi_major_axis: Vec::with_capacity(capacity),
+            eccentricity: Vec::with_capacity(capacity),
+            mean_motion: Vec::with_capacity(capacity),
+            sqrt_one_minus_e2: Vec::with_capacity(capacity),
+            mean_anomaly_at_epoch: Vec::with_capacity(capacity),
+            epoch: Vec::with_capacity(capacity),
+            rotation: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn from_orbits<'a>(orbits: impl IntoIterator<Item = &'a OrbitState>, mu: f64) -> Self {
+        let orbits = orbits.into_iter();
+        let mut batch = Self::with_capacity(orbits.size_hint().0);
+        for orbit in orbits {
+            batch.push(orbit, mu);
+        }
+        batch
+    }
+
+    /// Append `orbit`, returning whether it can be propagated.
+    pub fn push(&mut self, orbit: &OrbitState, mu: f64) -> bool {
+        let valid = validate_orbit(orbit).is_ok();
+        let (a, e) = if valid {
+            (orbit.semi_major_axis, orbit.eccentricity)
+        } else {
+            (1.0, 0.0)
+        };
+        self.valid.push(valid);
+        self.semi_major_axis.push(a);
+        self.eccentricity.push(e);
+        self.mean_motion.push(Float::sqrt(mu / (a * a * a)));
+        self.sqrt_one_minus_e2
+            .push(Float::sqrt((1.0 - e * e).max(0.0)));
+        self.mean_anomaly_at_epoch.push(orbit.mean_anomaly_at_epoch);
+        self.epoch.push(orbit.epoch);
+        self.rotation.push(Float::sin_cos(orbit.arg_of_periapsis));
+        valid
+    }
+
+    pub fn len(&self) -> usize {
+        self.valid.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.valid.is_empty()
+    }
+
+    /// Drop every orbit, keeping the allocations for reuse.
+    pub fn clear(&mut self) {
+        self.valid.clear();
+        self.semi_major_axis.clear();
+        self.eccentricity.clear();
+        self.mean_motion.clear();
+        self.sqrt_one_minus_e2.clear();
+        self.mean_anomaly_at_epoch.clear();
+        self.epoch.clear();
+        self.rotation.clear();
+    }
+
+    /// Eccentric anomaly of every orbit at `t`, written to `out` (cleared
+    /// first). Invalid orbits get `NaN`.
+    pub fn eccentric_anomalies(&self, t: f64, out: &mut Vec<f64>) {
+        out.clear();
+        out.reserve(self.len());
+        for start in (0..self.len()).step_by(LANES) {
+            let solved = self.solve_group(start, t);
+            for (lane, e_anom) in solved
+                .iter()
+                .take(LANES.min(self.len() - start))
+                .enumerate()
+            {
+                out.push(if self.valid[start + lane] {
+                    *e_anom
+                } else {
+                    f64::NAN
+                });
+            }
+        }
+    }
+
+    /// Position and velocity of every orbit at `t`, in input order.
+    pub fn propagate(&self, t: f64) -> Vec<Option<(Vec2, Vec2)>> {
+        let mut states = Vec::new();
+        self.propagate_into(t, &mut states);
+        states
+    }
+
+    /// `propagate` into `out` (cleared first), so a caller stepping every
+    /// tick can keep one buffer.
+    pub fn propagate_into(&self, t: f64, out: &mut Vec<Option<(Vec2, Vec2)>>) {
+        out.clear();
+        out.reserve(self.len());
+        for start in (0..self.len()).step_by(LANES) {
+            let solved = self.solve_group(start, t);
+            for (lane, &e_anom) in solved
+                .iter()
+                .take(LANES.min(self.len() - start))
+                .enumerate()
+            {
+                let i = start + lane;
+                out.push(self.valid[i].then(|| self.state(i, e_anom)));
+            }
+        }
+    }
+
+    /// Eccentric anomalies at `t` of the group of orbits starting at
+    /// `start`. Lanes past the end of the batch are padding.
+    fn solve_group(&self, start: usize, t: f64) -> [f64; LANES] {
+        let count = LANES.min(self.len() - start);
+        let mut m = [0.0; LANES];
+        let mut e = [0.0; LANES];
+        for lane in 0..count {
+            let i = start + lane;
+            let phase = self.mean_anomaly_at_epoch[i] + self.mean_motion[i] * (t - self.epoch[i]);
+            m[lane] = angle::wrap_pi(phase);
+            e[lane] = self.eccentricity[i];
+        }
+        solve_lanes(&m, &e)
+    }
+
+    fn state(&self, i: usize, e_anom: f64) -> (Vec2, Vec2) {
+        let a = self.semi_major_axis[i];
+        let e = self.eccentricity[i];
+        let n = self.mean_motion[i];
+        let sqrt_one_minus_e2 = self.sqrt_one_minus_e2[i];
+        let cos_e = Float::cos(e_anom);
+        let sin_e = Float::sin(e_anom);
+        let factor = 1.0 - e * cos_e;
+
+        let x_orb = a * (cos_e - e);
+        let y_orb = a * sqrt_one_minus_e2 * sin_e;
+        let vx_orb = -a * sin_e * n / factor;
+        let vy_orb = a * sqrt_one_minus_e2 * cos_e * n / factor;
+
+        let (sin, cos) = self.rotation[i];
+        let rotate = |x: f64, y: f64| Vec2::new(cos * x - sin * y, sin * x + cos * y);
+        (rotate(x_orb, y_orb), rotate(vx_orb, vy_orb))
+    }
+}
+
+/// Newton's method on `LANES` instances of Kepler's equation at once. A lane
+/// is frozen as soon as the scalar solver would have stopped on it.
+fn solve_lanes(m: &[f64; LANES], e: &[f64; LANES]) -> [f64; LANES] {
+    let mut e_anom = [0.0; LANES];
+    for lane in 0..LANES {
+        e_anom[lane] = kepler_seed(m[lane], e[lane]);
+    }
+    let mut active = [true; LANES];
+    for _ in 0..MAX_ITERATIONS {
+        let mut any_active = false;
+        for lane in 0..LANES {
+            let x = e_anom[lane];
+            let f = x - e[lane] * Float::sin(x) - m[lane];
+            let f_prime = 1.0 - e[lane] * Float::cos(x);
+            let delta = f / f_prime;
+            let step = active[lane] && f_prime.abs() >= 1e-12;
+            e_anom[lane] = if step { x - delta } else { x };
+            active[lane] = step && delta.abs() >= TOLERANCE;
+            any_active |= active[lane];
+        }
+        if !any_active {
+            break;
+        }
+    }
+    e_anom
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbit_to_cartesian;
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn batch_matches_scalar_propagation_bit_for_bit() {
+        let mut orbits: Vec<OrbitState> = (0..21)
+            .map(|i| OrbitState {
+                semi_major_axis: 7_000_000.0 + 150_000.0 * i as f64,
+                eccentricity: (i as f64 * 0.047) % 0.97,
+                arg_of_periapsis: i as f64 * 0.9 - 3.0,
+                mean_anomaly_at_epoch: i as f64 * 1.3,
+                epoch: i as f64 * -20.0,
+            })
+            .collect();
+        orbits[5].eccentricity = 1.2;
+
+        let batch = OrbitBatch::from_orbits(&orbits, MU_EARTH);
+        assert_eq!(batch.len(), orbits.len());
+        let t = 98_765.4;
+        for (orbit, state) in orbits.iter().zip(batch.propagate(t)) {
+            match (orbit_to_cartesian(orbit, MU_EARTH, t), state) {
+                (Ok(expected), Some(actual)) => assert_eq!(expected, actual),
+                (Err(_), None) => {}
+                (expected, actual) => panic!("{:?} vs {:?}", expected, actual),
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Bevy integration: `GgwWorldPlugin` steps the simulation on Bevy's fixed
+//! timestep and mirrors it into the ECS. Bodies and the interior pawn get
+//! one entity each, kept in sync after every step; collisions, scenario
+//! events, sphere-of-influence changes, despawns and the world's event feed
+//! are forwarded as Bevy events.
+
+use std::collections::HashMap;
+
+use bevy_app::{App, FixedUpdate, Plugin};
+use bevy_ecs::prelude::*;
+use bevy_time::{Fixed, Time};
+
+use crate::{
+    config::GameConfig, interior::PawnStatus, preview::SoiEvent, scenario::ScenarioEvent,
+    BodyDespawned, BodyType, CollisionEvent, Seconds, Vec2, World, WorldEvent,
+};
+
+const MU_EARTH: f64 = 3.986_004_418e14;
+
+/// The simulated world. Insert one before adding the plugin to start from
+/// a prepared scenario; otherwise an empty world is created.
+#[derive(Resource)]
+pub struct SimWorld(pub World);
+
+/// Mirror of one `BodyState`, refreshed after every fixed step.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct SimBody {
+    pub id: u64,
+    pub body_type: BodyType,
+    pub mass: f64,
+    pub radius: f64,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+/// Mirror of the interior pawn.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct SimPawn {
+    pub id: u64,
+    pub x: u32,
+    pub y: u32,
+    pub status: PawnStatus,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct SimCollision(pub CollisionEvent);
+
+#[derive(Event, Clone, Debug)]
+pub struct SimScenarioEvent(pub ScenarioEvent);
+
+#[derive(Event, Clone, Debug)]
+pub struct SimSoiEvent(pub SoiEvent);
+
+#[derive(Event, Clone, Debug)]
+pub struct SimBodyDespawned(pub BodyDespawned);
+
+#[derive(Event, Clone, Debug)]
+pub struct SimWorldEvent(pub WorldEvent);
+
+/// Simulation body id to the entity mirroring it.
+#[derive(Resource, Default)]
+pub struct BodyEntities(pub HashMap<u64, Entity>);
+
+/// Ordering for the plugin's `FixedUpdate` systems, so game systems can run
+/// after the mirror is up to date.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SimSet {
+    Step,
+    Sync,
+}
+
+pub struct GgwWorldPlugin {
+    /// Gravitational parameter for the world created when no `SimWorld`
+    /// was inserted up front.
+    pub mu: f64,
+}
+
+impl Default for GgwWorldPlugin {
+    fn default() -> Self {
+        Self { mu: MU_EARTH }
+    }
+}
+
+impl Plugin for GgwWorldPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<SimWorld>() {
+            app.insert_resource(SimWorld(World::new(self.mu, GameConfig::default())));
+        }
+        app.init_resource::<BodyEntities>()
+            .add_event::<SimCollision>()
+            .add_event::<SimScenarioEvent>()
+            .add_event::<SimSoiEvent>()
+            .add_event::<SimBodyDespawned>()
+            .add_event::<SimWorldEvent>()
+            .configure_sets(FixedUpdate, (SimSet::Step, SimSet::Sync).chain())
+            .add_systems(FixedUpdate, step_world.in_set(SimSet::Step))
+            .add_systems(FixedUpdate, (sync_bodies, sync_pawn).in_set(SimSet::Sync));
+    }
+}
+
+fn step_world(
+    time: Res<Time<Fixed>>,
+    mut world: ResMut<SimWorld>,
+    mut collisions: EventWriter<SimCollision>,
+    mut scenario_events: EventWriter<SimScenarioEvent>,
+    mut soi_events: EventWriter<SimSoiEvent>,
+    mut despawns: EventWriter<SimBodyDespawned>,
+    mut world_events: EventWriter<SimWorldEvent>,
+) {
+    let dt = Seconds(time.delta_secs_f64());
+    if dt <= Seconds::ZERO {
+        return;
+    }
+    let world = &mut world.0;
+    collisions.write_batch(
+        world
+            .detect_collisions_tiered(dt)
+            .into_iter()
+            .map(SimCollision),
+    );
+    world.step(dt);
+    scenario_events.write_batch(
+        world
+            .timeline
+            .drain_events()
+            .into_iter()
+            .map(SimScenarioEvent),
+    );
+    soi_events.write_batch(world.drain_soi_events().into_iter().map(SimSoiEvent));
+    despawns.write_batch(
+        world
+            .drain_despawn_events()
+            .into_iter()
+            .map(SimBodyDespawned),
+    );
+    world_events.write_batch(world.drain_events().into_iter().map(SimWorldEvent));
+}
+
+fn sync_bodies(
+    mut commands: Commands,
+    world: Res<SimWorld>,
+    mut entities: ResMut<BodyEntities>,
+    mut bodies: Query<&mut SimBody>,
+) {
+    for body in &world.0.bodies {
+        let mirror = SimBody {
+            id: body.id,
+            body_type: body.body_type,
+            mass: body.mass,
+            radius: body.radius,
+            position: body.position,
+            velocity: body.velocity,
+        };
+        match entities.0.get(&body.id) {
+            Some(&entity) => {
+                if let Ok(mut existing) = bodies.get_mut(entity) {
+                    *existing = mirror;
+                }
+            }
+            None => {
+                let entity = commands.spawn(mirror).id();
+                entities.0.insert(body.id, entity);
+            }
+        }
+    }
+    entities.0.retain(|id, entity| {
+        let alive = world.0.body(*id).is_some();
+        if !alive {
+            commands.entity(*entity).despawn();
+        }
+        alive
+    });
+}
+
+fn sync_pawn(mut commands: Commands, world: Res<SimWorld>, mut pawns: Query<&mut SimPawn>) {
+    let pawn = &world.0.interior.pawn;
+    let mirror = SimPawn {
+        id: pawn.id,
+        x: pawn.x,
+        y: pawn.y,
+        status: pawn.status,
+    };
+    match pawns.single_mut() {
+        Ok(mut existing) => *existing = mirror,
+        Err(_) => {
+            commands.spawn(mirror);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BodyState, OrbitState};
+    use std::time::Duration;
+
+    fn fixed_step(app: &mut App) {
+        app.world_mut()
+            .resource_mut::<Time<Fixed>>()
+            .advance_by(Duration::from_secs(1));
+        app.world_mut().run_schedule(FixedUpdate);
+    }
+
+    #[test]
+    fn plugin_mirrors_bodies_into_entities() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let id = world
+            .add_body(BodyState::orbiting(OrbitState {
+                semi_major_axis: 7_000_000.0,
+                eccentricity: 0.01,
+                arg_of_periapsis: 0.0,
+                mean_anomaly_at_epoch: 0.0,
+                epoch: 0.0,
+            }))
+            .unwrap();
+
+        let mut app = App::new();
+        app.init_resource::<Time<Fixed>>()
+            .insert_resource(SimWorld(world))
+            .add_plugins(GgwWorldPlugin::default());
+        fixed_step(&mut app);
+
+        assert_eq!(
+            app.world().resource::<SimWorld>().0.sim_time(),
+            Seconds(1.0)
+        );
+        let entity = app.world().resource::<BodyEntities>().0[&id];
+        let mirrored = app.world().get::<SimBody>(entity).unwrap().clone();
+        assert_eq!(
+            mirrored.position,
+            app.world().resource::<SimWorld>().0.bodies[0].position
+        );
+        let mut pawns = app.world_mut().query::<&SimPawn>();
+        assert_eq!(pawns.iter(app.world()).count(), 1);
+
+        app.world_mut().resource_mut::<SimWorld>().0.bodies.clear();
+        fixed_step(&mut app);
+        assert!(app.world().get_entity(entity).is_err());
+        assert!(app.world().resource::<BodyEntities>().0.is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+//! Missile warheads going off.
+//!
+//! A missile whose fuze trips, see `guidance`, blows up where it came
+//! closest to its target. Every body whose edge is within
+//! `config::BlastConfig::radius_m` of that point takes damage and an
+//! impulse pushing it straight away, both falling off linearly from the
+//! full `damage` and `impulse_ns` at the heart of the blast to nothing at
+//! its edge. Damage comes off the body's hit points, see `damage`, so a
+//! body brought down to 0 is destroyed and breaks into debris. The missile
+//! itself breaks up the same way.
+//!
+//! Landed bodies take damage but aren't moved.
+
+use alloc::vec::Vec;
+
+use crate::math::Float;
+use crate::{DespawnReason, Vec2, World, Wreck};
+
+/// Where and when a missile went off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Detonation {
+    pub(crate) time: f64,
+    /// World-frame position and velocity of the missile as it went off.
+    pub(crate) position: Vec2,
+    pub(crate) velocity: Vec2,
+}
+
+/// A body caught in a blast, as it was when the missile went off.
+struct Caught {
+    id: u64,
+    /// World-frame position and velocity.
+    position: Vec2,
+    velocity: Vec2,
+    mass: f64,
+    /// Share of the full damage and impulse it takes.
+    strength: f64,
+    /// Unit vector from the heart of the blast towards it.
+    away: Vec2,
+}
+
+impl World {
+    /// Blow up `missile_id` as `detonation` says, homing on `target`, and
+    /// deal with everything the blast reaches.
+    pub(crate) fn detonate(&mut self, missile_id: u64, target: u64, detonation: Detonation) {
+        let Some(missile) = self.forget_body(missile_id, DespawnReason::Detonated { target })
+        else {
+            return;
+        };
+        let config = self.config.blast.clone();
+        let Detonation {
+            time,
+            position: center,
+            velocity,
+        } = detonation;
+        let radius = config.radius_m.max(0.0);
+        let caught: Vec<Caught> = self
+            .bodies
+            .iter()
+            .filter_map(|body| {
+                let (position, velocity) = self.tracked_state(body, time).ok()?;
+                let offset = position - center;
+                let reach = (offset.length() - body.radius).max(0.0);
+                if reach >= radius {
+                    return None;
+                }
+                let away = if offset.length_squared() > 0.0 {
+                    offset.normalized()
+                } else {
+                    Vec2::zero()
+                };
+                Some(Caught {
+                    id: body.id,
+                    position,
+                    velocity,
+                    mass: body.mass,
+                    strength: 1.0 - reach / radius,
+                    away,
+                })
+            })
+            .collect();
+
+        for caught in caught {
+            let dealt = config.damage.max(0.0) * caught.strength;
+            if self.deal_damage(caught.id, dealt) || self.landed.contains_key(&caught.id) {
+                continue;
+            }
+            let kick = caught.away * (config.impulse_ns.max(0.0) * caught.strength / caught.mass);
+            let pushed = self
+                .refit_orbit(caught.id, caught.position, caught.velocity + kick, time)
+                .and_then(|orbit| self.set_orbit(caught.id, orbit));
+            if let Err(err) = pushed {
+                tracing::warn!(body = caught.id, error = %err, "blast impulse dropped");
+            }
+        }
+
+        let debris = &self.config.damage;
+        let count = self.debris_pieces(debris.debris_count, missile.mass, &[]);
+        if count == 0 {
+            return;
+        }
+        let wreck = Wreck {
+            position: center,
+            velocity,
+            mass: missile.mass,
+            radius: missile.radius,
+            parent: missile.parent,
+        };
+        let heading = Float::atan2(velocity.y, velocity.x);
+        let added = self
+            .scatter(&wreck, count, debris.debris_speed_mps, heading, time)
+            .and_then(|pieces| {
+                pieces
+                    .into_iter()
+                    .try_for_each(|piece| self.add_body(piece).map(drop))
+            });
+        if let Err(err) = added {
+            tracing::warn!(missile = missile_id, error = %err, "missile debris dropped");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::GameConfig;
+    use crate::guidance::Guidance;
+    use crate::{BodyState, BodyType, DespawnReason, Seconds, World, WorldEvent};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+    const RADIUS: f64 = 7_000_000.0;
+
+    fn body_at(along: f64, body_type: BodyType) -> BodyState {
+        BodyState {
+            body_type,
+            ..BodyState::circular(RADIUS, along / RADIUS)
+        }
+    }
+
+    #[test]
+    fn blasts_wreck_the_target_and_shove_bystanders() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world.add_body(body_at(0.0, BodyType::Ship)).unwrap();
+        let bystander = world.add_body(body_at(150.0, BodyType::Ship)).unwrap();
+        let far = world.add_body(body_at(7_000.0, BodyType::Ship)).unwrap();
+        let missile = world
+            .add_body(body_at(-7_000.0, BodyType::Missile))
+            .unwrap();
+        world.set_missile_target(missile, ship).unwrap();
+        let guidance = Guidance {
+            max_acceleration_mps2: 30.0,
+            fuel_mps: 1_000.0,
+            proximity_radius_m: 10.0,
+        };
+        world.set_missile_guidance(missile, Some(guidance)).unwrap();
+        world.drain_events();
+
+        world.warp_to(Seconds(400.0));
+        let despawns = world.drain_despawn_events();
+        assert_eq!(
+            despawns
+                .iter()
+                .map(|event| (event.id, event.reason))
+                .collect::<alloc::vec::Vec<_>>(),
+            [
+                (missile, DespawnReason::Detonated { target: ship }),
+                (ship, DespawnReason::Destroyed),
+            ]
+        );
+        assert_eq!(world.bodies_of_type(BodyType::Debris).count(), 12);
+
+        // Caught at the edge: hurt, and pushed onto a different orbit.
+        let hit_points = world.hit_points(bystander).unwrap();
+        assert!(hit_points.hp > 0.0 && hit_points.hp < hit_points.max_hp);
+        let orbit = world.body(bystander).unwrap().orbit;
+        assert!((orbit.semi_major_axis - RADIUS).abs() > 100.0);
+        assert_eq!(world.hit_points(far).unwrap().hp, 1_000.0);
+        assert_eq!(world.body(far).unwrap().orbit.semi_major_axis, RADIUS);
+
+        let hits: alloc::vec::Vec<u64> = world
+            .drain_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                WorldEvent::Damaged { body_id, .. } => Some(body_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.contains(&ship) && hits.contains(&bystander));
+    }
+}
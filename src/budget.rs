@@ -0,0 +1,49 @@
+//! Per-tick work budget for the interior.
+//!
+//! Costs are counted in abstract work units, roughly one per tile visited,
+//! rather than wall-clock time, so a budgeted step stays deterministic and
+//! runs the same on every lockstep peer and on `no_std` targets.
+
+/// Work units left for the current tick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorkBudget {
+    limit: Option<u64>,
+    spent: u64,
+}
+
+impl WorkBudget {
+    /// A budget of `units` per tick; 0 means unlimited.
+    pub fn new(units: u64) -> Self {
+        Self {
+            limit: (units > 0).then_some(units),
+            spent: 0,
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(0)
+    }
+
+    /// Spend `cost` units if they fit, returning whether they did. The first
+    /// spend of a tick always succeeds, so every tick makes some progress
+    /// however small the budget.
+    pub fn try_spend(&mut self, cost: u64) -> bool {
+        let fits = match self.limit {
+            Some(limit) => self.spent == 0 || self.spent.saturating_add(cost) <= limit,
+            None => true,
+        };
+        if fits {
+            self.spent = self.spent.saturating_add(cost);
+        }
+        fits
+    }
+
+    pub fn spent(&self) -> u64 {
+        self.spent
+    }
+
+    /// Units left, or `None` if the budget is unlimited.
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|limit| limit.saturating_sub(self.spent))
+    }
+}
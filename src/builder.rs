@@ -0,0 +1,189 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use crate::{
+    clock::SimClock,
+    collision::{CollisionScheduler, CollisionScratch},
+    config::GameConfig,
+    error,
+    interior::InteriorWorld,
+    scenario::Timeline,
+    sleep::SleepTracker,
+    BodyState, Meters, World, PLANET_RADIUS_M,
+};
+
+const MU_EARTH: f64 = 3.986_004_418e14;
+
+/// Explicit construction for `World`. Anything not set falls back to the
+/// same defaults `World::new` uses: Earth's `mu` and radius, the default
+/// config, the test ship interior, an empty timeline and seed 0.
+#[derive(Clone, Debug, Default)]
+pub struct WorldBuilder {
+    mu: Option<f64>,
+    planet_radius: Option<Meters>,
+    config: Option<GameConfig>,
+    interior: Option<InteriorWorld>,
+    timeline: Option<Timeline>,
+    seed: u64,
+    bodies: Vec<BodyState>,
+    interior_body: Option<u64>,
+}
+
+impl WorldBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gravitational parameter of the central body, in m^3/s^2.
+    pub fn mu(mut self, mu: f64) -> Self {
+        self.mu = Some(mu);
+        self
+    }
+
+    pub fn planet_radius(mut self, radius: Meters) -> Self {
+        self.planet_radius = Some(radius);
+        self
+    }
+
+    pub fn config(mut self, config: GameConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Use `interior` instead of building the test ship from the config.
+    pub fn interior(mut self, interior: InteriorWorld) -> Self {
+        self.interior = Some(interior);
+        self
+    }
+
+    /// Put the ship interior aboard body `body_id`, which must be among
+    /// the bodies added; see `World::set_interior_body`.
+    pub fn interior_body(mut self, body_id: u64) -> Self {
+        self.interior_body = Some(body_id);
+        self
+    }
+
+    pub fn with_scenario(mut self, timeline: Timeline) -> Self {
+        self.timeline = Some(timeline);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Add `body` when the world is built, with the same id rules as
+    /// `World::add_body`.
+    pub fn with_body(mut self, body: BodyState) -> Self {
+        self.bodies.push(body);
+        self
+    }
+
+    pub fn with_bodies(mut self, bodies: impl IntoIterator<Item = BodyState>) -> Self {
+        self.bodies.extend(bodies);
+        self
+    }
+
+    /// Build the world, failing if any queued body has an invalid orbit or
+    /// the interior body isn't among them.
+    pub fn build(self) -> error::Result<World> {
+        let config = self.config.unwrap_or_default();
+        let interior = self
+            .interior
+            .unwrap_or_else(|| InteriorWorld::new_test_ship(&config));
+        let mut world = World {
+            mu: self.mu.unwrap_or(MU_EARTH),
+            clock: SimClock::default(),
+            tick_count: 0,
+            bodies: Vec::new(),
+            planet_radius: self
+                .planet_radius
+                .map_or(PLANET_RADIUS_M, |radius| radius.0),
+            central_bodies: Vec::new(),
+            interior,
+            config,
+            timeline: self.timeline.unwrap_or_default(),
+            seed: self.seed,
+            missile_targets: BTreeMap::new(),
+            missile_guidance: BTreeMap::new(),
+            kepler_cache: BTreeMap::new(),
+            collision_scheduler: CollisionScheduler::new(),
+            collision_scratch: CollisionScratch::new(),
+            collision_filters: BTreeMap::new(),
+            sleep_tracker: SleepTracker::new(),
+            soi_events: Vec::new(),
+            despawn_events: Vec::new(),
+            events: Vec::new(),
+            attitude_targets: BTreeMap::new(),
+            reentry_damage: BTreeMap::new(),
+            landed: BTreeMap::new(),
+            sensor_suites: BTreeMap::new(),
+            compositions: BTreeMap::new(),
+            autopilots: BTreeMap::new(),
+            conjunction_tracked: BTreeSet::new(),
+            conjunctions: Vec::new(),
+            next_conjunction_check: 0.0,
+            ascents: BTreeMap::new(),
+            hit_points: BTreeMap::new(),
+            messages: Vec::new(),
+            docked: BTreeMap::new(),
+            propellant: BTreeMap::new(),
+            interior_body: None,
+            numeric_bodies: BTreeMap::new(),
+            burn_programs: Vec::new(),
+            maneuver_nodes: Vec::new(),
+            scheduled_thrusts: Vec::new(),
+            next_id: 1,
+        };
+        for body in self.bodies {
+            world.add_body(body)?;
+        }
+        world.set_interior_body(self.interior_body)?;
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::ScenarioAction;
+    use crate::GgwError;
+
+    #[test]
+    fn builder_applies_settings_and_bodies() {
+        let mut timeline = Timeline::new();
+        timeline.schedule(5.0, ScenarioAction::SolarStorm { duration_s: 10.0 });
+        let world = World::builder()
+            .mu(4.9048695e12)
+            .planet_radius(Meters::from_km(1_737.4))
+            .with_scenario(timeline)
+            .with_seed(42)
+            .with_body(BodyState::circular(2_000_000.0, 0.0))
+            .build()
+            .unwrap();
+        assert_eq!(world.mu, 4.9048695e12);
+        assert_eq!(world.planet_radius, 1_737_400.0);
+        assert_eq!(world.seed, 42);
+        assert_eq!(world.timeline.pending_len(), 1);
+        assert_eq!(world.bodies.len(), 1);
+        assert!(world.bodies[0].position.length() > 1_999_000.0);
+
+        let seeded = World::builder().with_seed(7).build().unwrap();
+        assert_ne!(
+            seeded.state_hash(),
+            World::builder().build().unwrap().state_hash()
+        );
+    }
+
+    #[test]
+    fn builder_rejects_invalid_bodies() {
+        let result = World::builder()
+            .with_body(BodyState::circular(-1.0, 0.0))
+            .build();
+        assert!(matches!(
+            result,
+            Err(GgwError::InvalidSemiMajorAxis(a)) if a == -1.0
+        ));
+    }
+}
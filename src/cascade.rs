@@ -0,0 +1,208 @@
+//! Debris breaking up more debris.
+//!
+//! Debris hitting debris or an asteroid faster than
+//! `config::CollisionConfig::fragmentation_speed_mps`, resolved with
+//! `World::resolve_collision`, breaks both up as `World::fragment_collision`
+//! would, with `config::DebrisConfig::cascade_probability`. The pieces can
+//! go on to hit more, so a crowded orbital shell grinds itself down over
+//! time. The roll is drawn from the world's seed, the tick and the pair, so
+//! every peer in a lockstep session agrees on it. Debris meets debris by
+//! default while `cascade_probability` is above 0, and passes through it
+//! otherwise; `collision::CollisionFilter`s can change either.
+//!
+//! Every breakup, destroyed body and missile going off makes no piece of
+//! debris lighter than `min_fragment_kg`, and none past `max_debris` in the
+//! world, breaking into fewer and heavier pieces instead. One that can't
+//! make two makes none: the collision bounces or merges as usual, and a
+//! destroyed body or missile leaves nothing behind.
+
+use crate::lockstep::StateHasher;
+use crate::math::Float;
+use crate::{BodyState, BodyType, World};
+
+/// A number in `[0, 1)` drawn from `seed`, `tick` and the pair, whichever
+/// way round it is given.
+fn roll(seed: u64, tick: u64, a: u64, b: u64) -> f64 {
+    let mut hasher = StateHasher::new();
+    for value in [seed, tick, a.min(b), a.max(b)] {
+        hasher.write_u64(value);
+    }
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl World {
+    /// Whether `a` and `b` hitting at `impact_speed` break up in a
+    /// cascade.
+    pub(crate) fn cascades(&self, a: &BodyState, b: &BodyState, impact_speed: f64) -> bool {
+        let debris_pair = match (a.body_type, b.body_type) {
+            (BodyType::Debris, other) | (other, BodyType::Debris) => {
+                matches!(other, BodyType::Debris | BodyType::Asteroid)
+            }
+            _ => false,
+        };
+        let config = &self.config;
+        debris_pair
+            && impact_speed > config.collision.fragmentation_speed_mps
+            && roll(self.seed, self.tick_count, a.id, b.id) < config.debris.cascade_probability
+            && self.debris_pieces(
+                config.collision.fragment_count,
+                a.mass + b.mass,
+                &[a.id, b.id],
+            ) > 0
+    }
+
+    /// How many pieces of debris, up to `wanted` but at least 2, a wreck of
+    /// `mass` taking the place of `replaced` may break into; 0 if that is
+    /// fewer than 2, or `wanted` is 0.
+    pub(crate) fn debris_pieces(&self, wanted: u32, mass: f64, replaced: &[u64]) -> u32 {
+        if wanted == 0 {
+            return 0;
+        }
+        let config = &self.config.debris;
+        let mut pieces = wanted.max(2);
+        if config.min_fragment_kg > 0.0 {
+            pieces = pieces.min(Float::floor(mass / config.min_fragment_kg) as u32);
+        }
+        if config.max_debris > 0 {
+            let kept = self
+                .bodies_of_type(BodyType::Debris)
+                .filter(|body| !replaced.contains(&body.id))
+                .count();
+            let room = (config.max_debris as usize).saturating_sub(kept);
+            pieces = pieces.min(room.min(u32::MAX as usize) as u32);
+        }
+        if pieces < 2 {
+            0
+        } else {
+            pieces
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collision::CollisionOutcome;
+    use crate::config::GameConfig;
+    use crate::math::Float;
+    use crate::{cartesian_to_orbit, BodyState, BodyType, Seconds, Vec2, World, PLANET_RADIUS_M};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    /// 1,000 kg of `target` about to be run into by 50 kg of `projectile`
+    /// at 1,500 m/s, under `config`.
+    fn approach(config: GameConfig, target: BodyType, projectile: BodyType) -> World {
+        let mut world = World::new(MU_EARTH, config);
+        let radius = PLANET_RADIUS_M + 600_000.0;
+        let position = Vec2::new(radius, 0.0);
+        let velocity = Vec2::new(0.0, Float::sqrt(MU_EARTH / radius));
+        let body = |mass, body_type, position, velocity| BodyState {
+            mass,
+            body_type,
+            ..BodyState::orbiting(cartesian_to_orbit(position, velocity, MU_EARTH, 0.0).unwrap())
+        };
+        world
+            .add_body(body(1_000.0, target, position, velocity))
+            .unwrap();
+        world
+            .add_body(body(
+                50.0,
+                projectile,
+                position + Vec2::new(20.0, 0.0),
+                velocity - Vec2::new(1_500.0, 0.0),
+            ))
+            .unwrap();
+        world
+    }
+
+    /// A 1,000 kg asteroid run into by 50 kg of `projectile`, resolved with
+    /// `config`.
+    fn crash(config: GameConfig, projectile: BodyType) -> (World, CollisionOutcome) {
+        let mut world = approach(config, BodyType::Asteroid, projectile);
+        let event = world.detect_collisions(Seconds(1.0)).remove(0);
+        let outcome = world.resolve_collision(&event).unwrap();
+        (world, outcome)
+    }
+
+    fn debris(world: &World) -> usize {
+        world.bodies_of_type(BodyType::Debris).count()
+    }
+
+    #[test]
+    fn debris_hitting_asteroids_breaks_up_more_debris() {
+        let mut config = GameConfig::default();
+        config.debris.cascade_probability = 1.0;
+        // Bounces that wreck both bodies would muddy the count.
+        config.damage.collision_hp_per_ns = 0.0;
+        let (world, outcome) = crash(config.clone(), BodyType::Debris);
+        assert_eq!(outcome, CollisionOutcome::Fragmented);
+        assert_eq!(debris(&world), 6);
+        assert_eq!(world.bodies.len(), 6);
+        let mass: f64 = world.bodies.iter().map(|body| body.mass).sum();
+        assert!((mass - 1_050.0).abs() < 1e-9);
+
+        // Ships aren't debris.
+        let (world, outcome) = crash(config.clone(), BodyType::Ship);
+        assert_eq!(outcome, CollisionOutcome::Bounced);
+        assert_eq!(debris(&world), 0);
+
+        // The cap counts the debris being replaced as gone.
+        config.debris.max_debris = 4;
+        let (world, outcome) = crash(config.clone(), BodyType::Debris);
+        assert_eq!(outcome, CollisionOutcome::Fragmented);
+        assert_eq!(debris(&world), 4);
+
+        config.debris.max_debris = 0;
+        config.debris.min_fragment_kg = 400.0;
+        let (world, _) = crash(config.clone(), BodyType::Debris);
+        assert_eq!(debris(&world), 2);
+        assert!(world.bodies.iter().all(|body| body.mass >= 400.0));
+
+        // Too light to make two pieces, so it bounces.
+        config.debris.min_fragment_kg = 600.0;
+        let (world, outcome) = crash(config.clone(), BodyType::Debris);
+        assert_eq!(outcome, CollisionOutcome::Bounced);
+        assert_eq!(world.bodies.len(), 2);
+
+        config.debris.min_fragment_kg = 1.0;
+        config.debris.cascade_probability = 0.0;
+        let (_, outcome) = crash(config, BodyType::Debris);
+        assert_eq!(outcome, CollisionOutcome::Bounced);
+    }
+
+    #[test]
+    fn debris_meets_debris_while_it_can_cascade() {
+        // Under the default filters and config.
+        let mut config = GameConfig::default();
+        let mut world = approach(config.clone(), BodyType::Debris, BodyType::Debris);
+        assert_eq!(world.detect_collisions(Seconds(1.0)).len(), 1);
+
+        config.debris.cascade_probability = 1.0;
+        config.damage.collision_hp_per_ns = 0.0;
+        world = approach(config.clone(), BodyType::Debris, BodyType::Debris);
+        let event = world.detect_collisions(Seconds(1.0)).remove(0);
+        assert_eq!(
+            world.resolve_collision(&event),
+            Ok(CollisionOutcome::Fragmented)
+        );
+        assert_eq!(debris(&world), 6);
+
+        config.debris.cascade_probability = 0.0;
+        world = approach(config, BodyType::Debris, BodyType::Debris);
+        assert!(world.detect_collisions(Seconds(1.0)).is_empty());
+    }
+
+    #[test]
+    fn destroyed_bodies_leave_no_more_debris_than_the_cap() {
+        let mut config = GameConfig::default();
+        config.debris.max_debris = 8;
+        let mut world = World::new(MU_EARTH, config);
+        let ship = |mean_anomaly| BodyState::circular(7_000_000.0, mean_anomaly);
+        let ids = [0.0, 1.0, 2.0].map(|mean_anomaly| world.add_body(ship(mean_anomaly)).unwrap());
+        let counts = ids.map(|id| {
+            assert_eq!(world.apply_damage(id, 1e9), Ok(true));
+            debris(&world)
+        });
+        // 6, then the 2 that fit, then none.
+        assert_eq!(counts, [6, 8, 8]);
+    }
+}
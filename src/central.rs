@@ -0,0 +1,577 @@
+//! Gravity sources besides the planet.
+//!
+//! The planet sits at the origin with `World::mu` and `World::planet_radius`,
+//! at the root of a tree of `CentralBody`s on fixed Keplerian orbits: moons
+//! around the planet, and sub-moons around a moon named by their `parent`.
+//! Each one's orbit is relative to its parent and propagated with the
+//! parent's `mu`, and its world-frame position is its parent's plus its
+//! own offset, so positions compose down the tree. A body whose `parent`
+//! names a moon has its `OrbitState` relative to that moon in the same
+//! way; its `position` and `velocity` are still in the planet-centred world
+//! frame, like every other body's.
+//!
+//! Each step hands a body over, patched-conic style, to a child of its
+//! primary whose sphere of influence it has entered, and up to its
+//! primary's parent once it is `SOI_EXIT_FACTOR` times the radius out
+//! again; see `World::drain_soi_events`. Only closed, prograde orbits are
+//! supported, so a body that would be on an escape trajectory or going
+//! clockwise around its new primary stays with the old one.
+
+use alloc::vec::Vec;
+
+use crate::math::Float;
+use crate::preview::SoiTransition;
+use crate::{
+    cartesian_to_orbit, error, orbit_to_cartesian, OrbitState, Vec2, GRAVITY_WELL_RADIUS_M,
+    PLANET_ROTATION_RAD_S,
+};
+
+/// A body leaves a moon's sphere of influence this far past its radius, so
+/// one skimming the boundary isn't handed back and forth every step.
+pub const SOI_EXIT_FACTOR: f64 = 1.01;
+
+/// A moon: a gravity source with its own `mu` and surface, orbiting the
+/// planet or another moon.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CentralBody {
+    /// Drawn from the same sequence as body ids, so the two never clash.
+    pub id: u64,
+    pub mu: f64,
+    pub radius: f64,
+    /// Moon this one orbits, or `None` for the planet. Always earlier in
+    /// `World::central_bodies`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub parent: Option<u64>,
+    /// Orbit around the parent.
+    pub orbit: OrbitState,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+impl CentralBody {
+    /// Radius of its sphere of influence around a parent of `parent_mu`,
+    /// `a * (mu / parent_mu)^(2/5)`. Inside it the moon's pull dominates.
+    pub fn soi_radius(&self, parent_mu: f64) -> f64 {
+        self.orbit.semi_major_axis * Float::powf(self.mu / parent_mu, 0.4)
+    }
+
+    /// This moon placed at its current `position`, around a parent of
+    /// `parent_mu`.
+    pub fn primary(&self, parent_mu: f64) -> Primary {
+        Primary {
+            id: Some(self.id),
+            mu: self.mu,
+            radius: self.radius,
+            gravity_well_radius: self.soi_radius(parent_mu),
+            position: self.position,
+            velocity: self.velocity,
+            max_speed: crate::collision::max_speed(&self.orbit, parent_mu),
+            rotation_rate: self.orbit.mean_motion(parent_mu),
+        }
+    }
+}
+
+/// `mu` of what `moon` orbits: its parent moon's, or `planet_mu`.
+pub(crate) fn parent_mu(planet_mu: f64, central_bodies: &[CentralBody], moon: &CentralBody) -> f64 {
+    moon.parent
+        .and_then(|parent| central_bodies.iter().find(|other| other.id == parent))
+        .map_or(planet_mu, |parent| parent.mu)
+}
+
+/// World-frame state of each of `central_bodies` at `t`, worked down the
+/// tree from the planet, or `None` for a moon whose orbit, or whose
+/// parent's, can't be propagated.
+pub(crate) fn states_at(
+    planet_mu: f64,
+    central_bodies: &[CentralBody],
+    t: f64,
+) -> Vec<Option<(Vec2, Vec2)>> {
+    let mut states: Vec<Option<(Vec2, Vec2)>> = Vec::with_capacity(central_bodies.len());
+    for moon in central_bodies {
+        let (mu, origin) = match moon.parent {
+            None => (planet_mu, Some((Vec2::zero(), Vec2::zero()))),
+            Some(parent) => match central_bodies.iter().position(|other| other.id == parent) {
+                Some(index) => (
+                    central_bodies[index].mu,
+                    states.get(index).copied().flatten(),
+                ),
+                None => (planet_mu, None),
+            },
+        };
+        let state = origin.and_then(|(origin_position, origin_velocity)| {
+            let (position, velocity) = orbit_to_cartesian(&moon.orbit, mu, t).ok()?;
+            Some((origin_position + position, origin_velocity + velocity))
+        });
+        states.push(state);
+    }
+    states
+}
+
+/// What a body orbits, placed at one instant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Primary {
+    /// `None` for the planet.
+    pub id: Option<u64>,
+    pub mu: f64,
+    pub radius: f64,
+    /// Distance from the centre within which a body counts as inside this
+    /// primary's gravity well: `GRAVITY_WELL_RADIUS_M` for the planet, the
+    /// sphere of influence for a moon.
+    pub gravity_well_radius: f64,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    /// Fastest the primary itself ever moves; 0 for the planet.
+    pub max_speed: f64,
+    /// How fast the surface turns, counter-clockwise in radians per second:
+    /// `PLANET_ROTATION_RAD_S` for the planet, and a moon's mean motion, as
+    /// moons are tidally locked.
+    pub rotation_rate: f64,
+}
+
+impl Primary {
+    pub fn planet(mu: f64, radius: f64) -> Self {
+        Self {
+            id: None,
+            mu,
+            radius,
+            gravity_well_radius: GRAVITY_WELL_RADIUS_M,
+            position: Vec2::zero(),
+            velocity: Vec2::zero(),
+            max_speed: 0.0,
+            rotation_rate: PLANET_ROTATION_RAD_S,
+        }
+    }
+
+    /// Id reported as `body_b` of a surface impact: 0 for the planet.
+    pub fn collision_id(&self) -> u64 {
+        self.id.unwrap_or(0)
+    }
+
+    /// World-frame state of a body on `orbit` around this primary at `t`,
+    /// which should be the instant the primary was placed at.
+    pub fn state_at(&self, orbit: &OrbitState, t: f64) -> error::Result<(Vec2, Vec2)> {
+        let (position, velocity) = orbit_to_cartesian(orbit, self.mu, t)?;
+        Ok((self.position + position, self.velocity + velocity))
+    }
+}
+
+/// The planet and every moon, placed at one instant.
+#[derive(Clone, Debug)]
+pub struct Primaries {
+    planet: Primary,
+    moons: Vec<Primary>,
+    /// What each moon orbits, `None` for the planet.
+    parents: Vec<Option<u64>>,
+    /// Closest and farthest each moon's sphere of influence reaches from
+    /// the planet. A sub-moon's never reaches past its parent's, so it
+    /// shares it.
+    bands: Vec<(f64, f64)>,
+}
+
+impl Primaries {
+    /// Primaries where `central_bodies` currently are.
+    pub fn current(mu: f64, planet_radius: f64, central_bodies: &[CentralBody]) -> Self {
+        let mut moons: Vec<Primary> = Vec::with_capacity(central_bodies.len());
+        let mut bands: Vec<(f64, f64)> = Vec::with_capacity(central_bodies.len());
+        for moon in central_bodies {
+            let parent_mu = parent_mu(mu, central_bodies, moon);
+            let mut primary = moon.primary(parent_mu);
+            let parent = moon
+                .parent
+                .and_then(|parent| moons.iter().position(|other| other.id == Some(parent)));
+            let band = match parent {
+                Some(index) => {
+                    primary.max_speed += moons[index].max_speed;
+                    bands[index]
+                }
+                None => {
+                    let (periapsis, apoapsis) = apsides(&moon.orbit);
+                    let soi = primary.gravity_well_radius * SOI_EXIT_FACTOR;
+                    (periapsis - soi, apoapsis + soi)
+                }
+            };
+            moons.push(primary);
+            bands.push(band);
+        }
+        Self {
+            planet: Primary::planet(mu, planet_radius),
+            moons,
+            parents: central_bodies.iter().map(|moon| moon.parent).collect(),
+            bands,
+        }
+    }
+
+    /// Primaries with each moon moved to time `t`. A moon whose orbit
+    /// can't be propagated stays where it is.
+    pub fn at(mu: f64, planet_radius: f64, central_bodies: &[CentralBody], t: f64) -> Self {
+        let mut primaries = Self::current(mu, planet_radius, central_bodies);
+        let states = states_at(mu, central_bodies, t);
+        for (primary, state) in primaries.moons.iter_mut().zip(states) {
+            if let Some((position, velocity)) = state {
+                primary.position = position;
+                primary.velocity = velocity;
+            }
+        }
+        primaries
+    }
+
+    pub fn planet(&self) -> &Primary {
+        &self.planet
+    }
+
+    /// The planet, then every moon.
+    pub fn iter(&self) -> impl Iterator<Item = &Primary> {
+        core::iter::once(&self.planet).chain(&self.moons)
+    }
+
+    /// Primary of a body with `parent`, or `None` if no such moon exists.
+    pub fn find(&self, parent: Option<u64>) -> Option<&Primary> {
+        match parent {
+            None => Some(&self.planet),
+            Some(id) => self.moons.iter().find(|moon| moon.id == Some(id)),
+        }
+    }
+
+    /// Primary of a body with `parent`, falling back to the planet for a
+    /// moon that no longer exists.
+    pub fn get(&self, parent: Option<u64>) -> &Primary {
+        self.find(parent).unwrap_or(&self.planet)
+    }
+
+    /// Gravitational acceleration at world-frame `position` from the planet
+    /// and every moon together.
+    pub fn gravity_at(&self, position: Vec2) -> Vec2 {
+        self.iter().fold(Vec2::zero(), |sum, primary| {
+            let offset = primary.position - position;
+            let dist_sq = offset.length_squared();
+            if dist_sq == 0.0 {
+                return sum;
+            }
+            sum + offset * (primary.mu / (dist_sq * Float::sqrt(dist_sq)))
+        })
+    }
+
+    /// What moon `id` orbits, `None` for the planet.
+    pub fn parent_of(&self, id: u64) -> Option<u64> {
+        let index = self.moons.iter().position(|moon| moon.id == Some(id))?;
+        self.parents[index]
+    }
+
+    /// The moon orbiting `parent` whose sphere of influence contains
+    /// `position`, if any.
+    pub fn soi_containing(&self, parent: Option<u64>, position: Vec2) -> Option<&Primary> {
+        self.moons
+            .iter()
+            .zip(&self.parents)
+            .find(|(moon, moon_parent)| {
+                **moon_parent == parent
+                    && position.distance(moon.position) <= moon.gravity_well_radius
+            })
+            .map(|(moon, _)| moon)
+    }
+
+    /// Whether a body on `orbit` around the planet could ever come within
+    /// a moon's sphere of influence.
+    pub fn may_enter_soi(&self, orbit: &OrbitState) -> bool {
+        let (periapsis, apoapsis) = apsides(orbit);
+        self.bands
+            .iter()
+            .any(|&(inner, outer)| periapsis <= outer && apoapsis >= inner)
+    }
+}
+
+/// A patched-conic hand-over: the orbit and parent a body takes on, and the
+/// transition to report.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Handover {
+    pub orbit: OrbitState,
+    pub parent: Option<u64>,
+    pub transition: SoiTransition,
+}
+
+/// The hand-over due at time `t` for a body with `parent` at world-frame
+/// `position` and `velocity`, if it has entered the sphere of influence of
+/// a moon orbiting its primary or left its primary's own, and has a closed
+/// prograde orbit around its new primary.
+pub(crate) fn handover(
+    parent: Option<u64>,
+    (position, velocity): (Vec2, Vec2),
+    primaries: &Primaries,
+    t: f64,
+) -> Option<Handover> {
+    let (target, transition) = match (primaries.soi_containing(parent, position), parent) {
+        (Some(moon), _) => {
+            let central_body = moon.id.unwrap_or_default();
+            (moon, SoiTransition::EnteredSoi { central_body })
+        }
+        (None, None) => return None,
+        (None, Some(central_body)) => {
+            let moon = primaries.get(parent);
+            let limit = moon.gravity_well_radius * SOI_EXIT_FACTOR;
+            if moon.id.is_some() && position.distance(moon.position) <= limit {
+                return None;
+            }
+            let outer = primaries.get(primaries.parent_of(central_body));
+            (outer, SoiTransition::LeftSoi { central_body })
+        }
+    };
+    let (position, velocity) = (position - target.position, velocity - target.velocity);
+    // `OrbitState` only describes prograde orbits.
+    if position.cross(velocity) <= 0.0 {
+        return None;
+    }
+    let orbit = cartesian_to_orbit(position, velocity, target.mu, t).ok()?;
+    Some(Handover {
+        orbit,
+        parent: target.id,
+        transition,
+    })
+}
+
+fn apsides(orbit: &OrbitState) -> (f64, f64) {
+    let (a, e) = (orbit.semi_major_axis, orbit.eccentricity);
+    (a * (1.0 - e), a * (1.0 + e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SOI_EXIT_FACTOR;
+    use crate::config::GameConfig;
+    use crate::error::GgwError;
+    use crate::preview::{SoiChange, SoiTransition};
+    use crate::{
+        cartesian_to_orbit, orbit_to_cartesian, BodyState, BodyType, OrbitState, Seconds,
+        ThrustEvent, ThrustType, Vec2, World,
+    };
+
+    use alloc::vec::Vec;
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+    const MU_MOON: f64 = 4.904_869_5e12;
+    const MOON_RADIUS: f64 = 1_737_400.0;
+
+    fn circular(a: f64) -> OrbitState {
+        OrbitState {
+            semi_major_axis: a,
+            eccentricity: 0.0,
+            arg_of_periapsis: 0.0,
+            mean_anomaly_at_epoch: 0.0,
+            epoch: 0.0,
+        }
+    }
+
+    fn body(a: f64, parent: Option<u64>) -> BodyState {
+        BodyState {
+            mass: 100.0,
+            body_type: BodyType::Debris,
+            parent,
+            ..BodyState::circular(a, 0.0)
+        }
+    }
+
+    #[test]
+    fn bodies_orbit_and_hit_their_moon() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let moon = world
+            .add_central_body(MU_MOON, MOON_RADIUS, circular(384_400_000.0))
+            .unwrap();
+        assert_eq!(
+            world.add_body(body(2_000_000.0, Some(moon + 100))),
+            Err(GgwError::UnknownCentralBody(moon + 100))
+        );
+        let orbiter = world.add_body(body(2_000_000.0, Some(moon))).unwrap();
+        let grazer = world.add_body(body(MOON_RADIUS + 2.0, Some(moon))).unwrap();
+
+        for _ in 0..10 {
+            world.step(Seconds(60.0));
+        }
+        let moon_position = world.central_body(moon).unwrap().position;
+        assert!(moon_position.distance(Vec2::new(384_400_000.0, 0.0)) > 1_000.0);
+        let orbiter_body = world.body(orbiter).unwrap();
+        let distance = orbiter_body.position.distance(moon_position);
+        assert!((distance - 2_000_000.0).abs() < 1e-3, "{distance}");
+        assert!((world.altitude(orbiter_body).0 - (2_000_000.0 - MOON_RADIUS)).abs() < 1e-3);
+        assert!(world.is_inside_gravity_well(orbiter_body));
+        let soi = world.central_body(moon).unwrap().soi_radius(MU_EARTH);
+        assert!(soi > 60_000_000.0 && soi < 70_000_000.0, "{soi}");
+
+        // Only the grazer touches the moon's surface, and it is reported
+        // against the moon rather than the planet.
+        let hits: Vec<(u64, u64)> = world
+            .detect_collisions(Seconds(1.0))
+            .iter()
+            .map(|event| (event.body_a, event.body_b))
+            .collect();
+        assert_eq!(hits, [(grazer, moon)]);
+        assert_eq!(world.detect_collisions_tiered(Seconds(1.0))[0].body_b, moon);
+
+        // A burn is worked in the moon's frame, so a small one leaves the
+        // orbiter close to the moon.
+        world
+            .apply_thrust_event(&ThrustEvent {
+                body_id: orbiter,
+                time: world.sim_time().0,
+                delta_v: Vec2::new(5.0, 0.0),
+                thrust_type: ThrustType::Chemical,
+            })
+            .unwrap();
+        world.step(Seconds(60.0));
+        let orbiter_body = world.body(orbiter).unwrap();
+        let moon_position = world.central_body(moon).unwrap().position;
+        assert!(orbiter_body.position.distance(moon_position) < 2_100_000.0);
+    }
+
+    #[test]
+    fn bodies_are_handed_over_at_the_sphere_of_influence() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let moon = world
+            .add_central_body(MU_MOON, MOON_RADIUS, circular(384_400_000.0))
+            .unwrap();
+        let (moon_position, moon_velocity, soi) = {
+            let moon = world.central_body(moon).unwrap();
+            (moon.position, moon.velocity, moon.soi_radius(MU_EARTH))
+        };
+
+        // One body just outside the sphere of influence heading in, on a
+        // planet orbit; one just inside the exit radius heading out, on a
+        // lunar one. Both go round the moon anticlockwise.
+        let outside = Vec2::new(soi + 1_000.0, 0.0);
+        let mut incoming = body(1.0, None);
+        incoming.orbit = cartesian_to_orbit(
+            moon_position + outside,
+            moon_velocity + Vec2::new(-50.0, 20.0),
+            MU_EARTH,
+            0.0,
+        )
+        .unwrap();
+        let incoming = world.add_body(incoming).unwrap();
+        let inside = Vec2::new(-(soi * SOI_EXIT_FACTOR - 1_000.0), 0.0);
+        let mut outgoing = body(1.0, Some(moon));
+        outgoing.orbit = cartesian_to_orbit(inside, Vec2::new(-50.0, -20.0), MU_MOON, 0.0).unwrap();
+        let outgoing = world.add_body(outgoing).unwrap();
+
+        let dt = Seconds(60.0);
+        let entered = SoiTransition::EnteredSoi { central_body: moon };
+        let left = SoiTransition::LeftSoi { central_body: moon };
+        assert_eq!(
+            world.preview_step(dt).soi_changes,
+            [
+                SoiChange {
+                    body_id: incoming,
+                    transition: entered,
+                },
+                SoiChange {
+                    body_id: outgoing,
+                    transition: left,
+                },
+            ]
+        );
+        world.step(dt);
+        let events: Vec<(u64, SoiTransition)> = world
+            .drain_soi_events()
+            .iter()
+            .map(|event| (event.body_id, event.transition))
+            .collect();
+        assert_eq!(events, [(incoming, entered), (outgoing, left)]);
+        assert!(world.drain_soi_events().is_empty());
+
+        // The new orbits pick up exactly where the old ones left off.
+        for (id, parent) in [(incoming, Some(moon)), (outgoing, None)] {
+            let body = world.body(id).unwrap();
+            assert_eq!(body.parent, parent);
+            let (position, velocity) = world
+                .primary_of(body)
+                .state_at(&body.orbit, world.sim_time().0)
+                .unwrap();
+            assert!(position.distance(body.position) < 1e-3);
+            assert!(velocity.distance(body.velocity) < 1e-6);
+        }
+        world.step(dt);
+        assert!(world.drain_soi_events().is_empty());
+    }
+
+    #[test]
+    fn moons_of_moons_ride_along_and_take_bodies_in() {
+        const MU_PEBBLE: f64 = 1e10;
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let moon = world
+            .add_central_body(MU_MOON, MOON_RADIUS, circular(384_400_000.0))
+            .unwrap();
+        assert_eq!(
+            world.add_central_body_orbiting(moon + 100, MU_PEBBLE, 1.0, circular(1e7)),
+            Err(GgwError::UnknownCentralBody(moon + 100))
+        );
+        let pebble = world
+            .add_central_body_orbiting(moon, MU_PEBBLE, 100_000.0, circular(20_000_000.0))
+            .unwrap();
+        world.step(Seconds(600.0));
+
+        // Its position is the moon's plus its own orbit's around the moon.
+        let now = world.sim_time().0;
+        let (moon_position, moon_velocity) = {
+            let moon = world.central_body(moon).unwrap();
+            (moon.position, moon.velocity)
+        };
+        let (offset, drift) = orbit_to_cartesian(&circular(20_000_000.0), MU_MOON, now).unwrap();
+        let (position, velocity, soi) = {
+            let pebble = world.central_body(pebble).unwrap();
+            (pebble.position, pebble.velocity, pebble.soi_radius(MU_MOON))
+        };
+        assert!(position.distance(moon_position + offset) < 1e-6);
+        assert!(velocity.distance(moon_velocity + drift) < 1e-9);
+        let primaries = world.primaries();
+        assert_eq!(
+            primaries.find(Some(pebble)).unwrap().gravity_well_radius,
+            soi
+        );
+
+        // A body round the moon inside the pebble's sphere of influence
+        // drops into it, and one round the pebble past its edge climbs
+        // back out to the moon rather than to the planet.
+        let mut incoming = body(1.0, Some(moon));
+        incoming.orbit = cartesian_to_orbit(
+            offset + Vec2::new(soi / 2.0, 0.0),
+            drift + Vec2::new(0.0, 100.0),
+            MU_MOON,
+            now,
+        )
+        .unwrap();
+        let incoming = world.add_body(incoming).unwrap();
+        let mut outgoing = body(1.0, Some(pebble));
+        outgoing.orbit = cartesian_to_orbit(
+            Vec2::new(-soi * (SOI_EXIT_FACTOR + 0.01), 0.0),
+            Vec2::new(0.0, -70.0),
+            MU_PEBBLE,
+            now,
+        )
+        .unwrap();
+        let outgoing = world.add_body(outgoing).unwrap();
+
+        world.step(Seconds(1.0));
+        let events: Vec<(u64, SoiTransition)> = world
+            .drain_soi_events()
+            .iter()
+            .map(|event| (event.body_id, event.transition))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                (
+                    incoming,
+                    SoiTransition::EnteredSoi {
+                        central_body: pebble
+                    }
+                ),
+                (
+                    outgoing,
+                    SoiTransition::LeftSoi {
+                        central_body: pebble
+                    }
+                ),
+            ]
+        );
+        assert_eq!(world.body(incoming).unwrap().parent, Some(pebble));
+        assert_eq!(world.body(outgoing).unwrap().parent, Some(moon));
+    }
+}
@@ -0,0 +1,100 @@
+//! Simulation time as a whole number of fixed-length ticks. Adding up `f64`
+//! step lengths drifts by a rounding error every step, and by different
+//! amounts on peers that split the same span differently. A tick count
+//! doesn't drift; the `f64` seconds are derived from it when needed.
+
+use crate::math::Float;
+use crate::Seconds;
+
+/// Clock resolution. One tick is a microsecond, so a `u64` lasts well past
+/// any campaign and step lengths lose at most half a microsecond to rounding.
+pub const TICKS_PER_SECOND: u64 = 1_000_000;
+
+/// Length of one clock tick.
+pub const TICK_DURATION: Seconds = Seconds(1.0 / TICKS_PER_SECOND as f64);
+
+/// Round `duration` to the nearest whole tick. Negative and NaN durations
+/// become zero; durations beyond `u64::MAX` ticks saturate.
+pub fn ticks_from_seconds(duration: Seconds) -> u64 {
+    Float::floor(duration.0 * TICKS_PER_SECOND as f64 + 0.5) as u64
+}
+
+/// Like `ticks_from_seconds` but rounding up, for deadlines that must not
+/// be reached early.
+pub fn ticks_from_seconds_ceil(duration: Seconds) -> u64 {
+    (-Float::floor(-duration.0 * TICKS_PER_SECOND as f64)) as u64
+}
+
+pub fn seconds_from_ticks(ticks: u64) -> Seconds {
+    Seconds(ticks as f64 / TICKS_PER_SECOND as f64)
+}
+
+/// Elapsed simulation time since the world was created.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct SimClock {
+    ticks: u64,
+}
+
+impl SimClock {
+    pub const fn from_ticks(ticks: u64) -> Self {
+        Self { ticks }
+    }
+
+    pub fn from_seconds(time: Seconds) -> Self {
+        Self::from_ticks(ticks_from_seconds(time))
+    }
+
+    pub const fn ticks(self) -> u64 {
+        self.ticks
+    }
+
+    pub fn seconds(self) -> Seconds {
+        seconds_from_ticks(self.ticks)
+    }
+
+    /// Move the clock forward by `ticks`, saturating at `u64::MAX`.
+    pub fn advance(&mut self, ticks: u64) {
+        self.ticks = self.ticks.saturating_add(ticks);
+    }
+
+    /// Move the clock forward by `dt` rounded to whole ticks, returning the
+    /// duration actually advanced.
+    pub fn advance_by(&mut self, dt: Seconds) -> Seconds {
+        let before = self.ticks;
+        self.advance(ticks_from_seconds(dt));
+        seconds_from_ticks(self.ticks - before)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn many_small_steps_do_not_drift() {
+        let mut clock = SimClock::default();
+        let mut summed = 0.0;
+        for _ in 0..1_000_000 {
+            clock.advance_by(Seconds(0.1));
+            summed += 0.1;
+        }
+        assert_eq!(clock.seconds(), Seconds(100_000.0));
+        assert_ne!(summed, 100_000.0);
+    }
+
+    #[test]
+    fn conversions_round_and_clamp() {
+        assert_eq!(ticks_from_seconds(Seconds(1.5)), 1_500_000);
+        assert_eq!(ticks_from_seconds(Seconds(2.4e-7)), 0);
+        assert_eq!(ticks_from_seconds_ceil(Seconds(2.4e-7)), 1);
+        assert_eq!(ticks_from_seconds(Seconds(-3.0)), 0);
+        assert_eq!(ticks_from_seconds(Seconds(f64::NAN)), 0);
+        assert_eq!(seconds_from_ticks(250_000), Seconds(0.25));
+        assert_eq!(SimClock::from_seconds(Seconds(60.0)).ticks(), 60_000_000);
+    }
+}
@@ -0,0 +1,1324 @@
+//! Collision checks scheduled by encounter risk.
+//!
+//! Each body can move no faster than its speed at periapsis, so a pair
+//! separated by a gap `g` cannot touch for at least `g / (v_a + v_b)`.
+//! `CollisionScheduler` uses that bound to decide when each pair next needs
+//! a narrow-phase check:
+//!
+//! - high-risk pairs, which could meet within a tick, are checked every tick;
+//! - medium-risk pairs, a few ticks out, every few ticks;
+//! - low-risk pairs sit in the queue until their earliest possible
+//!   encounter.
+//!
+//! A pair is never skipped past the moment it could first touch, so the
+//! scheduler reports exactly what `World::detect_collisions` would. A burn
+//! changes a body's speed bound, so any change to a body's orbit puts all
+//! of its pairs back up for a check on the next tick.
+//!
+//! Checks cover the whole tick, not just its end, so a missile fast enough
+//! to cross its target within one tick still hits it. Each pair's
+//! separation is taken to change in a straight line between its values at
+//! the start and end of the tick, and an event reports the time the gap
+//! first closed. Over one tick the bend in two nearby bodies' paths mostly
+//! cancels out, so the straight line is a close fit where it matters.
+//! Surface impacts are timed from the body's orbit instead, which stays
+//! exact however long the interval.
+//!
+//! `BodyState::radius` is only a bound: bodies with a `HullShape` are
+//! tested shape against shape once their circles touch; see `hull`.
+//!
+//! Pairs whose `CollisionFilter`s keep them apart are never reported. By
+//! default each body is on the layer of its `BodyType` and collides with
+//! everything, except that debris passes through debris unless it can break
+//! up in a cascade; see `World::set_collision_filter` and `cascade`.
+//! Surfaces are hit whatever the filter.
+
+use alloc::collections::{BTreeMap, BinaryHeap};
+use alloc::vec::Vec;
+use core::cmp::{Ordering, Reverse};
+
+use crate::central::{Primaries, Primary};
+use crate::config::DebrisConfig;
+use crate::hull::{self, Contact, Shape};
+use crate::math::Float;
+use crate::sleep::SleepTracker;
+use crate::{
+    next_descent_time, orbit_to_cartesian, BodyState, BodyType, CollisionEvent, OrbitBatch,
+    OrbitState, Vec2,
+};
+
+/// Pairs that could meet within this many ticks count as medium risk.
+pub const MEDIUM_RISK_TICKS: f64 = 16.0;
+/// Relative travel between hull tests while stepping two bodies with hulls
+/// through the stretch where their bounding circles overlap.
+const HULL_SWEEP_STEP_M: f64 = 0.25;
+const MAX_HULL_SWEEP_STEPS: usize = 256;
+const HULL_SWEEP_BISECTIONS: usize = 24;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RiskTier {
+    High,
+    Medium,
+    Low,
+}
+
+impl RiskTier {
+    /// Tier of a pair that cannot meet for `safe_time` seconds, at ticks of
+    /// `dt` seconds.
+    pub fn classify(safe_time: f64, dt: f64) -> Self {
+        if safe_time <= dt {
+            RiskTier::High
+        } else if safe_time <= dt * MEDIUM_RISK_TICKS {
+            RiskTier::Medium
+        } else {
+            RiskTier::Low
+        }
+    }
+}
+
+/// Which bodies a body collides with: it sits on the layers set in
+/// `layer` and collides with bodies on any of the layers set in `mask`, as
+/// long as their masks take in its layer too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollisionFilter {
+    pub layer: u32,
+    pub mask: u32,
+}
+
+impl CollisionFilter {
+    pub const SHIPS: u32 = 1 << 0;
+    pub const ASTEROIDS: u32 = 1 << 1;
+    pub const DEBRIS: u32 = 1 << 2;
+    pub const MISSILES: u32 = 1 << 3;
+
+    /// Filter of a body of `body_type` not given its own: on its type's
+    /// layer, colliding with everything, except debris with debris. While
+    /// `config::DebrisConfig::cascade_probability` is above 0, debris takes
+    /// in debris too.
+    pub fn for_type(body_type: BodyType) -> Self {
+        let (layer, mask) = match body_type {
+            BodyType::Ship => (Self::SHIPS, u32::MAX),
+            BodyType::Asteroid => (Self::ASTEROIDS, u32::MAX),
+            BodyType::Debris => (Self::DEBRIS, !Self::DEBRIS),
+            BodyType::Missile => (Self::MISSILES, u32::MAX),
+        };
+        Self { layer, mask }
+    }
+
+    /// Whether bodies with these two filters collide.
+    pub fn collides_with(&self, other: &Self) -> bool {
+        self.layer & other.mask != 0 && other.layer & self.mask != 0
+    }
+}
+
+/// The filters bodies collide by.
+#[derive(Clone, Copy, Debug)]
+pub struct Filters<'a> {
+    /// Filters bodies have been given.
+    own: &'a BTreeMap<u64, CollisionFilter>,
+    /// Whether debris can break up in a cascade, so meets debris by default.
+    cascades: bool,
+}
+
+impl<'a> Filters<'a> {
+    /// The filters in `own`, and the defaults for the rest under `debris`.
+    pub fn new(own: &'a BTreeMap<u64, CollisionFilter>, debris: &DebrisConfig) -> Self {
+        Self {
+            own,
+            cascades: debris.cascade_probability > 0.0,
+        }
+    }
+
+    /// Filter of `body`: its own, or its type's.
+    pub fn of(&self, body: &BodyState) -> CollisionFilter {
+        if let Some(filter) = self.own.get(&body.id) {
+            return *filter;
+        }
+        let mut filter = CollisionFilter::for_type(body.body_type);
+        if self.cascades {
+            filter.mask |= CollisionFilter::DEBRIS;
+        }
+        filter
+    }
+
+    /// Whether `a` and `b` collide.
+    fn collide(&self, a: &BodyState, b: &BodyState) -> bool {
+        self.of(a).collides_with(&self.of(b))
+    }
+}
+
+/// What `World::resolve_collision` did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CollisionOutcome {
+    /// Already moving apart; nothing changed.
+    Separating,
+    Bounced,
+    /// `absorbed` was folded into `survivor` and removed from the world.
+    Merged {
+        survivor: u64,
+        absorbed: u64,
+    },
+    /// Both broke up into debris in a cascade; see `cascade`.
+    Fragmented,
+}
+
+/// What the last `CollisionScheduler::detect` call did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollisionStats {
+    /// Pairs given a narrow-phase check this tick.
+    pub pairs_checked: usize,
+    /// Pairs tracked in total.
+    pub pairs_tracked: usize,
+    /// Tiers the checked pairs were put in afterwards.
+    pub high_risk: usize,
+    pub medium_risk: usize,
+    pub low_risk: usize,
+}
+
+#[derive(Clone, Debug)]
+struct TrackedBody {
+    orbit: OrbitState,
+    parent: Option<u64>,
+    max_speed: f64,
+    revision: u32,
+    dormant: bool,
+}
+
+/// A pair's next check. Entries whose revisions no longer match their
+/// bodies were superseded by a newer entry and are dropped when popped.
+#[derive(Clone, Copy, Debug)]
+struct ScheduledCheck {
+    due: f64,
+    body_a: u64,
+    body_b: u64,
+    revision_a: u32,
+    revision_b: u32,
+}
+
+impl PartialEq for ScheduledCheck {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScheduledCheck {}
+
+impl PartialOrd for ScheduledCheck {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScheduledCheck {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.due
+            .total_cmp(&other.due)
+            .then(self.body_a.cmp(&other.body_a))
+            .then(self.body_b.cmp(&other.body_b))
+            .then(self.revision_a.cmp(&other.revision_a))
+            .then(self.revision_b.cmp(&other.revision_b))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CollisionScheduler {
+    bodies: BTreeMap<u64, TrackedBody>,
+    queue: BinaryHeap<Reverse<ScheduledCheck>>,
+    mu: f64,
+    stats: CollisionStats,
+}
+
+impl CollisionScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stats(&self) -> CollisionStats {
+        self.stats
+    }
+
+    /// Collisions among `bodies`, and with the surfaces of the primaries,
+    /// between `start_time` and `target_time`, with `start` and `end` the
+    /// primaries placed at those times. Pairs with a body that is dormant in
+    /// `sleep` wait until it wakes, bodies in `landed` sit out, and pairs
+    /// kept apart by `filters` aren't reported.
+    #[allow(clippy::too_many_arguments)]
+    pub fn detect(
+        &mut self,
+        bodies: &[BodyState],
+        start: &Primaries,
+        end: &Primaries,
+        (start_time, target_time): (f64, f64),
+        sleep: &SleepTracker,
+        landed: &BTreeMap<u64, f64>,
+        filters: Filters,
+    ) -> Vec<CollisionEvent> {
+        self.sync(bodies, end, sleep, landed);
+        let dt = target_time - start_time;
+        let mut batch = OrbitBatch::with_capacity(bodies.len());
+        for body in bodies {
+            batch.push(&body.orbit, end.get(body.parent).mu);
+        }
+        let mut start_states = batch.propagate(start_time);
+        place_in_world(bodies, start, &mut start_states);
+        let mut states = batch.propagate(target_time);
+        place_in_world(bodies, end, &mut states);
+        let index: BTreeMap<u64, usize> = bodies
+            .iter()
+            .enumerate()
+            .map(|(idx, body)| (body.id, idx))
+            .collect();
+
+        let mut stats = CollisionStats::default();
+        let mut hits = Vec::new();
+        let mut rescheduled = Vec::new();
+        while let Some(Reverse(check)) = self.queue.peek().copied() {
+            if check.due > target_time {
+                break;
+            }
+            self.queue.pop();
+            let (Some(a), Some(b)) = (
+                self.bodies.get(&check.body_a),
+                self.bodies.get(&check.body_b),
+            ) else {
+                continue;
+            };
+            if a.revision != check.revision_a || b.revision != check.revision_b {
+                continue;
+            }
+            if a.dormant || b.dormant {
+                // Waking bumps the revision, so this only comes due if the
+                // body sleeps through to its own wake time.
+                let wake = |id| sleep.wake_time(id).unwrap_or(target_time);
+                rescheduled.push(ScheduledCheck {
+                    due: wake(check.body_a).max(wake(check.body_b)),
+                    ..check
+                });
+                continue;
+            }
+            let (idx_a, idx_b) = (index[&check.body_a], index[&check.body_b]);
+            let (first, second) = (idx_a.min(idx_b), idx_a.max(idx_b));
+            stats.pairs_checked += 1;
+            let (Some(sweep_a), Some(sweep_b)) = (
+                Sweep::new(start_states[first], states[first]),
+                Sweep::new(start_states[second], states[second]),
+            ) else {
+                // Can't be propagated; wait for the orbit to change.
+                rescheduled.push(ScheduledCheck {
+                    due: f64::INFINITY,
+                    ..check
+                });
+                continue;
+            };
+            let (body_a, body_b) = (&bodies[first], &bodies[second]);
+            let interval = (start_time, target_time);
+            // Still scheduled, as the filters can change at any time.
+            let filtered = filters.collide(body_a, body_b);
+            let hit = filtered
+                .then(|| pair_collision(body_a, sweep_a, body_b, sweep_b, interval))
+                .flatten();
+            if let Some(event) = hit {
+                hits.push(((first, second), event));
+            }
+            let gap = sweep_a.end.0.distance(sweep_b.end.0) - body_a.radius - body_b.radius;
+            let safe_time = (gap / (a.max_speed + b.max_speed)).max(0.0);
+            match RiskTier::classify(safe_time, dt) {
+                RiskTier::High => stats.high_risk += 1,
+                RiskTier::Medium => stats.medium_risk += 1,
+                RiskTier::Low => stats.low_risk += 1,
+            }
+            rescheduled.push(ScheduledCheck {
+                due: target_time + safe_time,
+                ..check
+            });
+        }
+        self.queue.extend(rescheduled.into_iter().map(Reverse));
+        let live_bodies = self.bodies.len();
+        stats.pairs_tracked = live_bodies * live_bodies.saturating_sub(1) / 2;
+        if self.queue.len() > 2 * stats.pairs_tracked + 64 {
+            self.drop_superseded_checks();
+        }
+        self.stats = stats;
+
+        // Same order as the exhaustive sweep: pairs by index, then surfaces.
+        hits.sort_by_key(|(order, _)| *order);
+        let mut events: Vec<CollisionEvent> = hits.into_iter().map(|(_, event)| event).collect();
+        for (idx, body) in bodies.iter().enumerate() {
+            if sleep.is_dormant(body.id) || landed.contains_key(&body.id) {
+                continue;
+            }
+            if let Some(sweep) = Sweep::new(start_states[idx], states[idx]) {
+                let primaries = (start.get(body.parent), end.get(body.parent));
+                events.extend(surface_collision(
+                    body,
+                    sweep,
+                    primaries,
+                    (start_time, target_time),
+                ));
+            }
+        }
+        events
+    }
+
+    /// Clear out entries left behind by orbit changes and removed bodies,
+    /// which would otherwise sit in the queue until their due time.
+    fn drop_superseded_checks(&mut self) {
+        let bodies = &self.bodies;
+        self.queue.retain(|Reverse(check)| {
+            let current = |id, revision| bodies.get(&id).is_some_and(|b| b.revision == revision);
+            current(check.body_a, check.revision_a) && current(check.body_b, check.revision_b)
+        });
+    }
+
+    /// Start tracking new bodies, forget removed ones, and reschedule every
+    /// pair of a body whose orbit changed or that woke up. Landed bodies are
+    /// held like dormant ones.
+    fn sync(
+        &mut self,
+        bodies: &[BodyState],
+        primaries: &Primaries,
+        sleep: &SleepTracker,
+        landed: &BTreeMap<u64, f64>,
+    ) {
+        let mu = primaries.planet().mu;
+        if mu != self.mu {
+            self.bodies.clear();
+            self.queue.clear();
+            self.mu = mu;
+        }
+        if self.bodies.len() != bodies.len()
+            || bodies.iter().any(|b| !self.bodies.contains_key(&b.id))
+        {
+            let live: BTreeMap<u64, ()> = bodies.iter().map(|body| (body.id, ())).collect();
+            self.bodies.retain(|id, _| live.contains_key(id));
+        }
+        for body in bodies {
+            let dormant = sleep.is_dormant(body.id) || landed.contains_key(&body.id);
+            let revision = match self.bodies.get_mut(&body.id) {
+                Some(tracked)
+                    if tracked.orbit == body.orbit
+                        && tracked.parent == body.parent
+                        && (dormant || !tracked.dormant) =>
+                {
+                    tracked.dormant = dormant;
+                    continue;
+                }
+                Some(tracked) => tracked.revision.wrapping_add(1),
+                None => 0,
+            };
+            let primary = primaries.get(body.parent);
+            self.bodies.insert(
+                body.id,
+                TrackedBody {
+                    orbit: body.orbit,
+                    parent: body.parent,
+                    max_speed: max_speed(&body.orbit, primary.mu) + primary.max_speed,
+                    revision,
+                    dormant,
+                },
+            );
+            for (&other_id, other) in &self.bodies {
+                if other_id == body.id {
+                    continue;
+                }
+                let (body_a, body_b, revision_a, revision_b) = if body.id < other_id {
+                    (body.id, other_id, revision, other.revision)
+                } else {
+                    (other_id, body.id, other.revision, revision)
+                };
+                self.queue.push(Reverse(ScheduledCheck {
+                    due: f64::NEG_INFINITY,
+                    body_a,
+                    body_b,
+                    revision_a,
+                    revision_b,
+                }));
+            }
+        }
+    }
+}
+
+/// Fastest a body on `orbit` ever moves: its speed at periapsis.
+pub(crate) fn max_speed(orbit: &OrbitState, mu: f64) -> f64 {
+    let e = orbit.eccentricity;
+    let speed = Float::sqrt(mu * (1.0 + e) / (orbit.semi_major_axis * (1.0 - e)));
+    if speed.is_finite() {
+        speed
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Buffers the exhaustive check reuses from one call to the next, so that
+/// once they have grown to the body count a check allocates nothing.
+#[derive(Clone, Debug, Default)]
+pub struct CollisionScratch {
+    batch: OrbitBatch,
+    start_states: Vec<Option<(Vec2, Vec2)>>,
+    states: Vec<Option<(Vec2, Vec2)>>,
+    bounds: Vec<Option<(Vec2, Vec2)>>,
+    cells: Vec<(i64, i64, usize)>,
+    pairs: Vec<(usize, usize)>,
+}
+
+impl CollisionScratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Check every pair of bodies whose paths come near each other, and each
+/// body against the surface of its primary, between `start_time` and
+/// `end_time`, appending what touches to `events`. `start` and `end` are
+/// the primaries placed at those times. Bodies for which `sits_out` holds,
+/// dormant or landed, aren't checked, and pairs kept apart by `filters`
+/// aren't reported.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn detect_all(
+    bodies: &[BodyState],
+    start: &Primaries,
+    end: &Primaries,
+    (start_time, end_time): (f64, f64),
+    sits_out: impl Fn(u64) -> bool,
+    filters: Filters,
+    scratch: &mut CollisionScratch,
+    events: &mut Vec<CollisionEvent>,
+) {
+    let CollisionScratch {
+        batch,
+        start_states,
+        states,
+        bounds,
+        cells,
+        pairs,
+    } = scratch;
+    batch.clear();
+    for body in bodies {
+        batch.push(&body.orbit, end.get(body.parent).mu);
+    }
+    batch.propagate_into(start_time, start_states);
+    place_in_world(bodies, start, start_states);
+    batch.propagate_into(end_time, states);
+    place_in_world(bodies, end, states);
+    for (body, state) in bodies.iter().zip(states.iter_mut()) {
+        if sits_out(body.id) {
+            *state = None;
+        }
+    }
+    let sweep = |idx: usize| Sweep::new(start_states[idx], states[idx]);
+
+    bounds.clear();
+    bounds.extend(bodies.iter().enumerate().map(|(idx, body)| {
+        let Sweep { start, end } = sweep(idx)?;
+        let reach = Vec2::new(body.radius, body.radius);
+        let min = Vec2::new(start.0.x.min(end.0.x), start.0.y.min(end.0.y));
+        let max = Vec2::new(start.0.x.max(end.0.x), start.0.y.max(end.0.y));
+        Some((min - reach, max + reach))
+    }));
+    candidate_pairs(bounds, cells, pairs);
+    for &(i, j) in pairs.iter() {
+        let (Some(sweep_a), Some(sweep_b)) = (sweep(i), sweep(j)) else {
+            continue;
+        };
+        if !filters.collide(&bodies[i], &bodies[j]) {
+            continue;
+        }
+        events.extend(pair_collision(
+            &bodies[i],
+            sweep_a,
+            &bodies[j],
+            sweep_b,
+            (start_time, end_time),
+        ));
+    }
+
+    for (idx, body) in bodies.iter().enumerate() {
+        let Some(sweep) = sweep(idx) else {
+            continue;
+        };
+        events.extend(surface_collision(
+            body,
+            sweep,
+            (start.get(body.parent), end.get(body.parent)),
+            (start_time, end_time),
+        ));
+    }
+}
+
+/// Uniform grid bucketing axis-aligned bounds into square cells as large
+/// as the largest of them, so each bounds touches at most four cells. The
+/// broad phase pairs up bounds sharing a cell, and `World::bodies_within`
+/// and `World::nearest_bodies` look up the cells around a point.
+pub(crate) struct Grid<'a> {
+    size: f64,
+    /// `(x, y, index)` for every cell each bounds touches, sorted.
+    cells: &'a [(i64, i64, usize)],
+}
+
+impl<'a> Grid<'a> {
+    /// Bucket `bounds`, skipping `None`s, into `cells` (cleared first).
+    pub(crate) fn build(
+        bounds: &[Option<(Vec2, Vec2)>],
+        cells: &'a mut Vec<(i64, i64, usize)>,
+    ) -> Self {
+        cells.clear();
+        let size = bounds
+            .iter()
+            .flatten()
+            .map(|(min, max)| (max.x - min.x).max(max.y - min.y))
+            .fold(1.0, f64::max);
+        let cell = |value: f64| Float::floor(value / size) as i64;
+        for (idx, bound) in bounds.iter().enumerate() {
+            let Some((min, max)) = *bound else {
+                continue;
+            };
+            for x in cell(min.x)..=cell(max.x) {
+                for y in cell(min.y)..=cell(max.y) {
+                    cells.push((x, y, idx));
+                }
+            }
+        }
+        cells.sort_unstable();
+        Self { size, cells }
+    }
+
+    /// Side of a cell.
+    pub(crate) fn size(&self) -> f64 {
+        self.size
+    }
+
+    /// Column or row holding `value`.
+    fn cell(&self, value: f64) -> i64 {
+        Float::floor(value / self.size) as i64
+    }
+
+    /// Indices of every bounds touching a cell the box from `min` to `max`
+    /// touches, in order and once each. A superset of the bounds
+    /// overlapping the box.
+    pub(crate) fn touching(&self, min: Vec2, max: Vec2) -> Vec<usize> {
+        let (x0, x1) = (self.cell(min.x), self.cell(max.x));
+        let (y0, y1) = (self.cell(min.y), self.cell(max.y));
+        let in_box =
+            |&&(x, y, _): &&(i64, i64, usize)| (x0..=x1).contains(&x) && (y0..=y1).contains(&y);
+        let area = (x1 as f64 - x0 as f64 + 1.0) * (y1 as f64 - y0 as f64 + 1.0);
+        let mut found: Vec<usize> = if area > self.cells.len() as f64 {
+            self.cells
+                .iter()
+                .filter(in_box)
+                .map(|&(_, _, idx)| idx)
+                .collect()
+        } else {
+            let mut found = Vec::new();
+            for x in x0..=x1 {
+                for y in y0..=y1 {
+                    let first = self.cells.partition_point(|&(cx, cy, _)| (cx, cy) < (x, y));
+                    found.extend(
+                        self.cells[first..]
+                            .iter()
+                            .take_while(|&&(cx, cy, _)| (cx, cy) == (x, y))
+                            .map(|&(_, _, idx)| idx),
+                    );
+                }
+            }
+            found
+        };
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// Whether the box from `min` to `max` takes in every occupied cell.
+    pub(crate) fn covered_by(&self, min: Vec2, max: Vec2) -> bool {
+        let (x0, x1) = (self.cell(min.x), self.cell(max.x));
+        let (y0, y1) = (self.cell(min.y), self.cell(max.y));
+        self.cells
+            .iter()
+            .all(|&(x, y, _)| (x0..=x1).contains(&x) && (y0..=y1).contains(&y))
+    }
+}
+
+/// Broad phase: every pair of indices whose `bounds` overlap, once each and
+/// in index order, found by bucketing the bounds into a `Grid`. A pair is
+/// only taken from the cell holding the lower corner of its overlap, so
+/// pairs sharing several cells aren't repeated.
+fn candidate_pairs(
+    bounds: &[Option<(Vec2, Vec2)>],
+    cells: &mut Vec<(i64, i64, usize)>,
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    pairs.clear();
+    let grid = Grid::build(bounds, cells);
+    let cells = grid.cells;
+    for (start, &(x, y, i)) in cells.iter().enumerate() {
+        let Some((min_i, max_i)) = bounds[i] else {
+            continue;
+        };
+        let shared = cells[start + 1..]
+            .iter()
+            .take_while(|&&(other_x, other_y, _)| (other_x, other_y) == (x, y));
+        for &(_, _, j) in shared {
+            let Some((min_j, max_j)) = bounds[j] else {
+                continue;
+            };
+            let overlap_min = Vec2::new(min_i.x.max(min_j.x), min_i.y.max(min_j.y));
+            let overlap_max = Vec2::new(max_i.x.min(max_j.x), max_i.y.min(max_j.y));
+            if overlap_min.x <= overlap_max.x
+                && overlap_min.y <= overlap_max.y
+                && (grid.cell(overlap_min.x), grid.cell(overlap_min.y)) == (x, y)
+            {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs.sort_unstable();
+}
+
+/// Shift states propagated relative to each body's primary into the world
+/// frame.
+fn place_in_world(
+    bodies: &[BodyState],
+    primaries: &Primaries,
+    states: &mut [Option<(Vec2, Vec2)>],
+) {
+    for (body, state) in bodies.iter().zip(states.iter_mut()) {
+        if body.parent.is_none() {
+            continue;
+        }
+        if let Some((position, velocity)) = state {
+            let primary = primaries.get(body.parent);
+            *position += primary.position;
+            *velocity += primary.velocity;
+        }
+    }
+}
+
+/// A body's world-frame position and velocity at the start and end of the
+/// interval being checked.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Sweep {
+    start: (Vec2, Vec2),
+    end: (Vec2, Vec2),
+}
+
+impl Sweep {
+    fn new(start: Option<(Vec2, Vec2)>, end: Option<(Vec2, Vec2)>) -> Option<Self> {
+        Some(Self {
+            start: start?,
+            end: end?,
+        })
+    }
+
+    /// State `fraction` of the way through the interval.
+    fn at(&self, fraction: f64) -> (Vec2, Vec2) {
+        (
+            self.start.0.lerp(self.end.0, fraction),
+            self.start.1.lerp(self.end.1, fraction),
+        )
+    }
+}
+
+/// Fractions of an interval between which a point moving in a straight
+/// line from `start` to `end` is within `reach` of the origin, if it ever
+/// is.
+fn overlap_window(start: Vec2, end: Vec2, reach: f64) -> Option<(f64, f64)> {
+    let c = start.length_squared() - reach * reach;
+    let travel = end - start;
+    let a = travel.length_squared();
+    if a == 0.0 {
+        return (c <= 0.0).then_some((0.0, 1.0));
+    }
+    let b = 2.0 * start.dot(travel);
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = Float::sqrt(discriminant);
+    let enter = ((-b - root) / (2.0 * a)).max(0.0);
+    let exit = ((-b + root) / (2.0 * a)).min(1.0);
+    (enter <= exit).then_some((enter, exit))
+}
+
+/// Narrow phase for two bodies swept across `start_time..=end_time`. The
+/// bounding circles set the window in which they could touch; bodies with
+/// hulls are then stepped through it and tested shape against shape, and
+/// the first contact found is narrowed down by bisection. Hulls start at
+/// the bodies' current orientations and turn with their spin.
+pub(crate) fn pair_collision(
+    body_a: &BodyState,
+    sweep_a: Sweep,
+    body_b: &BodyState,
+    sweep_b: Sweep,
+    (start_time, end_time): (f64, f64),
+) -> Option<CollisionEvent> {
+    let start = sweep_b.start.0 - sweep_a.start.0;
+    let end = sweep_b.end.0 - sweep_a.end.0;
+    let (enter, exit) = overlap_window(start, end, body_a.radius + body_b.radius)?;
+    let event = |fraction: f64, contact: Contact| {
+        let (_, vel_a) = sweep_a.at(fraction);
+        let (_, vel_b) = sweep_b.at(fraction);
+        CollisionEvent {
+            time: start_time + (end_time - start_time) * fraction,
+            body_a: body_a.id,
+            body_b: body_b.id,
+            relative_velocity: vel_b - vel_a,
+            contact_point: contact.point,
+            normal: contact.normal,
+            penetration_depth: contact.depth,
+        }
+    };
+
+    let hull_a = convex_hull_of(body_a);
+    let hull_b = convex_hull_of(body_b);
+    if hull_a.is_none() && hull_b.is_none() {
+        // The window opens as the circles touch.
+        let (pos_a, _) = sweep_a.at(enter);
+        let (pos_b, _) = sweep_b.at(enter);
+        let contact = hull::circle_contact(pos_a, body_a.radius, pos_b, body_b.radius);
+        return Some(event(enter, contact));
+    }
+
+    let (mut placed_a, mut placed_b) = (Vec::new(), Vec::new());
+    let mut contact_at = |fraction: f64| {
+        let elapsed = (end_time - start_time) * fraction;
+        let (pos_a, _) = sweep_a.at(fraction);
+        let (pos_b, _) = sweep_b.at(fraction);
+        let turn_a = body_a.orientation + body_a.angular_velocity * elapsed;
+        let turn_b = body_b.orientation + body_b.angular_velocity * elapsed;
+        hull::contact(
+            shape(body_a, hull_a.as_deref(), pos_a, turn_a, &mut placed_a),
+            shape(body_b, hull_b.as_deref(), pos_b, turn_b, &mut placed_b),
+        )
+    };
+    let travel = (end - start).length() * (exit - enter);
+    let steps = ((travel / HULL_SWEEP_STEP_M) as usize).clamp(1, MAX_HULL_SWEEP_STEPS);
+    let mut clear = None;
+    for step in 0..=steps {
+        let mut fraction = enter + (exit - enter) * step as f64 / steps as f64;
+        let Some(mut contact) = contact_at(fraction) else {
+            clear = Some(fraction);
+            continue;
+        };
+        if let Some(mut clear) = clear {
+            for _ in 0..HULL_SWEEP_BISECTIONS {
+                let mid = 0.5 * (clear + fraction);
+                match contact_at(mid) {
+                    Some(found) => (fraction, contact) = (mid, found),
+                    None => clear = mid,
+                }
+            }
+        }
+        return Some(event(fraction, contact));
+    }
+    None
+}
+
+/// `body`'s hull as `hull::contact` needs it, if it has a usable one.
+fn convex_hull_of(body: &BodyState) -> Option<Vec<Vec2>> {
+    let shape = body.hull_shape.as_ref()?;
+    Some(hull::convex_hull(&shape.vertices)).filter(|hull| hull.len() >= 3)
+}
+
+fn shape<'a>(
+    body: &BodyState,
+    hull: Option<&[Vec2]>,
+    position: Vec2,
+    orientation: f64,
+    placed: &'a mut Vec<Vec2>,
+) -> Shape<'a> {
+    match hull {
+        Some(hull) => {
+            hull::place(hull, position, orientation, placed);
+            Shape::Polygon(placed)
+        }
+        None => Shape::Circle {
+            center: position,
+            radius: body.radius,
+        },
+    }
+}
+
+/// Impact with the surface of a primary, placed at the start and end of
+/// the interval by `primaries`, reported with `body_b` set to
+/// `Primary::collision_id`: 0 for the planet, the moon's id otherwise. The
+/// time comes from the body's orbit rather than a straight line, since a
+/// long interval's chord can cut through the primary.
+pub(crate) fn surface_collision(
+    body: &BodyState,
+    sweep: Sweep,
+    (start, end): (&Primary, &Primary),
+    (start_time, end_time): (f64, f64),
+) -> Option<CollisionEvent> {
+    let reach = end.radius + body.radius;
+    // Worked in the primary's frame.
+    let (time, (offset, relative_velocity)) = if sweep.start.0.distance(start.position) <= reach {
+        (
+            start_time,
+            (
+                sweep.start.0 - start.position,
+                sweep.start.1 - start.velocity,
+            ),
+        )
+    } else {
+        let time = next_descent_time(&body.orbit, end.mu, reach, start_time)?;
+        if time > end_time {
+            return None;
+        }
+        (time, orbit_to_cartesian(&body.orbit, end.mu, time).ok()?)
+    };
+    let fraction = if end_time > start_time {
+        (time - start_time) / (end_time - start_time)
+    } else {
+        0.0
+    };
+    let primary_position = start.position.lerp(end.position, fraction);
+    let contact_point = if offset.length() > 1e-6 {
+        primary_position + offset.normalized().scale(end.radius)
+    } else {
+        primary_position
+    };
+    let altitude = offset.length();
+    let normal = if altitude > 1e-6 {
+        -offset / altitude
+    } else {
+        Vec2::new(1.0, 0.0)
+    };
+    Some(CollisionEvent {
+        time,
+        body_a: body.id,
+        body_b: end.collision_id(),
+        relative_velocity,
+        contact_point,
+        normal,
+        penetration_depth: (reach - altitude).max(0.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::f64::consts::PI;
+
+    use super::{CollisionFilter, CollisionOutcome};
+    use crate::config::GameConfig;
+    use crate::{BodyState, BodyType, OrbitState, Seconds, ThrustEvent, ThrustType, Vec2, World};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn body(a: f64, e: f64, arg: f64, mean_anomaly: f64) -> BodyState {
+        BodyState {
+            mass: 100.0,
+            body_type: BodyType::Asteroid,
+            ..BodyState::orbiting(OrbitState {
+                semi_major_axis: a,
+                eccentricity: e,
+                arg_of_periapsis: arg,
+                mean_anomaly_at_epoch: mean_anomaly,
+                epoch: 0.0,
+            })
+        }
+    }
+
+    fn summary(events: &[crate::CollisionEvent]) -> Vec<(u64, u64)> {
+        events
+            .iter()
+            .map(|event| (event.body_a, event.body_b))
+            .collect()
+    }
+
+    #[test]
+    fn tiered_detection_matches_exhaustive_sweep() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        // Two bodies sharing a slot, and a field that never comes close.
+        let docked = world.add_body(body(7_000_000.0, 0.0, 0.0, 0.0)).unwrap();
+        world.add_body(body(7_000_000.0, 0.0, 0.0, 1e-6)).unwrap();
+        for i in 0..12 {
+            let i = i as f64;
+            world
+                .add_body(body(9_000_000.0 + 400_000.0 * i, 0.02, i, i * 0.5))
+                .unwrap();
+        }
+        let dt = Seconds(10.0);
+        let mut reused = Vec::new();
+        for tick in 0..120 {
+            if tick == 60 {
+                world
+                    .apply_thrust_event(&ThrustEvent {
+                        body_id: docked,
+                        time: world.sim_time().0,
+                        delta_v: Vec2::new(0.0, 30.0),
+                        thrust_type: ThrustType::Chemical,
+                    })
+                    .unwrap();
+            }
+            let expected = summary(&world.detect_collisions(dt));
+            assert_eq!(summary(&world.detect_collisions_tiered(dt)), expected);
+            world.detect_collisions_into(dt, &mut reused);
+            assert_eq!(summary(&reused), expected);
+            world.step(dt);
+        }
+        let stats = world.collision_stats();
+        assert_eq!(stats.pairs_tracked, 14 * 13 / 2);
+        assert!(stats.pairs_checked < stats.pairs_tracked / 4, "{:?}", stats);
+        assert!(stats.high_risk >= 1);
+    }
+
+    #[test]
+    fn fast_bodies_do_not_tunnel() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let target = world.add_body(body(7_000_000.0, 0.0, 0.0, 0.0)).unwrap();
+        // Closing at 3 km/s from 20 km out: through and past the target
+        // well within one 10 s tick.
+        let (position, velocity) =
+            crate::orbit_to_cartesian(&world.body(target).unwrap().orbit, MU_EARTH, 0.0).unwrap();
+        let radial = position.normalized();
+        let missile = world
+            .add_body(BodyState {
+                orbit: crate::cartesian_to_orbit(
+                    position + radial * 20_000.0,
+                    velocity - radial * 3_000.0,
+                    MU_EARTH,
+                    0.0,
+                )
+                .unwrap(),
+                ..body(0.0, 0.0, 0.0, 0.0)
+            })
+            .unwrap();
+        let dt = Seconds(10.0);
+        let events = world.detect_collisions(dt);
+        assert_eq!(summary(&events), [(target, missile)]);
+        let impact = (20_000.0 - 10.0) / 3_000.0;
+        assert!((events[0].time - impact).abs() < 0.01, "{}", events[0].time);
+        assert!((events[0].relative_velocity.length() - 3_000.0).abs() < 1.0);
+        assert_eq!(
+            summary(&world.detect_collisions_tiered(dt)),
+            [(target, missile)]
+        );
+
+        // Periapsis below the surface: the impact is timed on the orbit.
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let falling = world.add_body(body(6_500_000.0, 0.1, 0.0, PI)).unwrap();
+        let events = world.detect_collisions(Seconds(3_000.0));
+        assert_eq!(summary(&events), [(falling, 0)]);
+        let orbit = world.body(falling).unwrap().orbit;
+        let (position, _) = crate::orbit_to_cartesian(&orbit, MU_EARTH, events[0].time).unwrap();
+        let reach = world.primaries().planet().radius + 5.0;
+        assert!((position.length() - reach).abs() < 1e-3);
+        assert!(world
+            .detect_collisions(Seconds(events[0].time - 1.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn hulls_decide_contact_inside_the_bounding_circles() {
+        // 20 m long and 2 m wide, lying along the orbit.
+        let hull = crate::HullShape {
+            vertices: vec![
+                Vec2::new(-10.0, -1.0),
+                Vec2::new(10.0, -1.0),
+                Vec2::new(10.0, 1.0),
+                Vec2::new(-10.0, 1.0),
+            ],
+        };
+        let ship = |a: f64| BodyState {
+            radius: hull.bounding_radius(),
+            hull_shape: Some(hull.clone()),
+            orientation: PI / 2.0,
+            ..body(a, 0.0, 0.0, 0.0)
+        };
+        let dt = Seconds(1.0);
+
+        // Side by side 5 m apart: the circles overlap, the hulls don't.
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        world.add_body(ship(7_000_000.0)).unwrap();
+        world.add_body(ship(7_000_005.0)).unwrap();
+        assert!(world.detect_collisions(dt).is_empty());
+        assert!(world.detect_collisions_tiered(dt).is_empty());
+
+        // Turned across the orbit, or spinning round to it, one reaches
+        // the other.
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let a = world
+            .add_body(BodyState {
+                orientation: 0.0,
+                ..ship(7_000_000.0)
+            })
+            .unwrap();
+        let b = world.add_body(ship(7_000_005.0)).unwrap();
+        assert_eq!(summary(&world.detect_collisions(dt)), [(a, b)]);
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let a = world
+            .add_body(BodyState {
+                angular_velocity: PI / 2.0,
+                ..ship(7_000_000.0)
+            })
+            .unwrap();
+        let b = world.add_body(ship(7_000_005.0)).unwrap();
+        let events = world.detect_collisions(dt);
+        assert_eq!(summary(&events), [(a, b)]);
+        assert!(events[0].time > 0.0 && events[0].time < 1.0);
+
+        // 1.5 m apart they overlap by half a metre, side on.
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let a = world.add_body(ship(7_000_000.0)).unwrap();
+        let b = world.add_body(ship(7_000_001.5)).unwrap();
+        let events = world.detect_collisions(dt);
+        assert_eq!(summary(&events), [(a, b)]);
+        let event = &events[0];
+        assert_eq!(event.time, 0.0);
+        assert!((event.penetration_depth - 0.5).abs() < 1e-6);
+        assert!(event.normal.distance(Vec2::new(1.0, 0.0)) < 1e-6);
+        assert!((event.contact_point.x - 7_000_000.75).abs() < 1e-3);
+
+        // A small fast body is timed against the hull's side, not its
+        // bounding circle.
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let target = world.add_body(ship(7_000_000.0)).unwrap();
+        let (position, velocity) =
+            crate::orbit_to_cartesian(&world.body(target).unwrap().orbit, MU_EARTH, 0.0).unwrap();
+        let missile = world
+            .add_body(BodyState {
+                radius: 0.5,
+                orbit: crate::cartesian_to_orbit(
+                    position + Vec2::new(20_000.0, 0.0),
+                    velocity - Vec2::new(3_000.0, 0.0),
+                    MU_EARTH,
+                    0.0,
+                )
+                .unwrap(),
+                ..body(0.0, 0.0, 0.0, 0.0)
+            })
+            .unwrap();
+        let events = world.detect_collisions(Seconds(10.0));
+        assert_eq!(summary(&events), [(target, missile)]);
+        let impact = (20_000.0 - 1.5) / 3_000.0;
+        assert!((events[0].time - impact).abs() < 1e-3, "{}", events[0].time);
+        assert!(events[0].penetration_depth < 1e-3);
+    }
+
+    #[test]
+    fn collisions_bounce_or_merge() {
+        // A light body 12 m behind a heavy one on the same circular orbit,
+        // catching up at `closing` m/s.
+        let setup = |closing: f64| {
+            let mut world = World::new(MU_EARTH, GameConfig::default());
+            let ahead = world
+                .add_body(BodyState {
+                    mass: 300.0,
+                    ..body(7_000_000.0, 0.0, 0.0, 0.0)
+                })
+                .unwrap();
+            let behind = body(7_000_000.0, 0.0, 0.0, -12.0 / 7_000_000.0);
+            let (position, velocity) =
+                crate::orbit_to_cartesian(&behind.orbit, MU_EARTH, 0.0).unwrap();
+            let behind = world
+                .add_body(BodyState {
+                    orbit: crate::cartesian_to_orbit(
+                        position,
+                        velocity + velocity.normalized() * closing,
+                        MU_EARTH,
+                        0.0,
+                    )
+                    .unwrap(),
+                    ..behind
+                })
+                .unwrap();
+            let event = world.detect_collisions(Seconds(5.0)).remove(0);
+            assert_eq!((event.body_a, event.body_b), (ahead, behind));
+            (world, ahead, behind, event)
+        };
+        let velocity = |world: &World, id, time| {
+            let orbit = world.body(id).unwrap().orbit;
+            crate::orbit_to_cartesian(&orbit, MU_EARTH, time).unwrap().1
+        };
+        let momentum = |world: &World, time| {
+            world.bodies.iter().fold(Vec2::zero(), |sum, body| {
+                sum + velocity(world, body.id, time) * body.mass
+            })
+        };
+
+        let (mut world, ahead, behind, event) = setup(5.0);
+        let before = momentum(&world, event.time);
+        assert_eq!(
+            world.resolve_collision(&event),
+            Ok(CollisionOutcome::Bounced)
+        );
+        assert!(momentum(&world, event.time).distance(before) < 1.0);
+        let separation = velocity(&world, behind, event.time) - velocity(&world, ahead, event.time);
+        // Half the 5 m/s closing speed survives the bounce.
+        assert!((separation.dot(event.normal) - 2.5).abs() < 0.05);
+        assert_eq!(
+            world.resolve_collision(&event),
+            Ok(CollisionOutcome::Separating)
+        );
+
+        let (mut world, ahead, behind, event) = setup(1.0);
+        let before = momentum(&world, event.time);
+        assert_eq!(
+            world.resolve_collision(&event),
+            Ok(CollisionOutcome::Merged {
+                survivor: ahead,
+                absorbed: behind,
+            })
+        );
+        assert_eq!(world.bodies.len(), 1);
+        assert_eq!(world.body(ahead).unwrap().mass, 400.0);
+        let despawned = world.drain_despawn_events();
+        assert_eq!(despawned.len(), 1);
+        assert_eq!(despawned[0].id, behind);
+        assert_eq!(
+            despawned[0].reason,
+            crate::DespawnReason::Merged { survivor: ahead }
+        );
+        assert!(momentum(&world, event.time).distance(before) < 1.0);
+
+        let surface = crate::CollisionEvent { body_b: 0, ..event };
+        assert_eq!(
+            world.resolve_collision(&surface),
+            Err(crate::GgwError::UnknownBody(0))
+        );
+    }
+
+    #[test]
+    fn fast_impacts_break_up_into_debris() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let target = world.add_body(body(7_000_000.0, 0.0, 0.0, 0.0)).unwrap();
+        let (position, velocity) =
+            crate::orbit_to_cartesian(&world.body(target).unwrap().orbit, MU_EARTH, 0.0).unwrap();
+        world
+            .add_body(BodyState {
+                mass: 50.0,
+                orbit: crate::cartesian_to_orbit(
+                    position + Vec2::new(20.0, 0.0),
+                    velocity - Vec2::new(1_500.0, 0.0),
+                    MU_EARTH,
+                    0.0,
+                )
+                .unwrap(),
+                ..body(0.0, 0.0, 0.0, 0.0)
+            })
+            .unwrap();
+        let event = world.detect_collisions(Seconds(1.0)).remove(0);
+        let momentum = |world: &World| {
+            world.bodies.iter().fold(Vec2::zero(), |sum, body| {
+                let (_, velocity) =
+                    crate::orbit_to_cartesian(&body.orbit, MU_EARTH, event.time).unwrap();
+                sum + velocity * body.mass
+            })
+        };
+        let before = momentum(&world);
+
+        // Too gentle to count.
+        let nudge = crate::CollisionEvent {
+            relative_velocity: Vec2::new(500.0, 0.0),
+            ..event.clone()
+        };
+        assert!(world.fragment_collision(&nudge).unwrap().is_empty());
+        assert_eq!(world.bodies.len(), 2);
+
+        let fragments = world.fragment_collision(&event).unwrap();
+        assert_eq!(fragments.len(), 6);
+        assert_eq!(world.bodies.len(), 6);
+        assert!(world.body(target).is_none());
+        let despawned = world.drain_despawn_events();
+        assert_eq!(despawned.len(), 2);
+        assert!(despawned
+            .iter()
+            .all(|event| event.reason == crate::DespawnReason::Fragmented));
+        assert!(world
+            .bodies
+            .iter()
+            .all(|body| body.body_type == BodyType::Debris));
+        let mass: f64 = world.bodies.iter().map(|body| body.mass).sum();
+        assert!((mass - 150.0).abs() < 1e-9);
+        assert!(momentum(&world).distance(before) < 1.0);
+    }
+
+    #[test]
+    fn grid_broad_phase_finds_every_overlap() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        // A chain of bodies 8 m apart, each touching its neighbours, amid a
+        // scattered field.
+        let chain: Vec<u64> = (0..30)
+            .map(|i| {
+                let mean_anomaly = 8.0 * f64::from(i) / 7_000_000.0;
+                world
+                    .add_body(body(7_000_000.0, 0.0, 0.0, mean_anomaly))
+                    .unwrap()
+            })
+            .collect();
+        for i in 0..200 {
+            let i = f64::from(i);
+            world
+                .add_body(body(7_200_000.0 + 5_000.0 * i, 0.01, i, i * 0.031))
+                .unwrap();
+        }
+        let expected: Vec<(u64, u64)> = chain.windows(2).map(|w| (w[0], w[1])).collect();
+        let dt = Seconds(10.0);
+        for _ in 0..3 {
+            assert_eq!(summary(&world.detect_collisions(dt)), expected);
+            assert_eq!(summary(&world.detect_collisions_tiered(dt)), expected);
+            world.step(dt);
+        }
+    }
+
+    #[test]
+    fn filters_keep_pairs_apart() {
+        // Debris only passes through debris while it can't cascade.
+        let mut config = GameConfig::default();
+        config.debris.cascade_probability = 0.0;
+        let mut world = World::new(MU_EARTH, config);
+        // Two pairs of touching bodies, 8 m apart.
+        let pair = |world: &mut World, mean_anomaly: f64, types: [BodyType; 2]| {
+            [(0.0, types[0]), (8.0, types[1])].map(|(along, body_type)| {
+                let mut body = body(7_000_000.0, 0.0, 0.0, mean_anomaly + along / 7_000_000.0);
+                body.body_type = body_type;
+                world.add_body(body).unwrap()
+            })
+        };
+        let [debris_a, debris_b] = pair(&mut world, 0.0, [BodyType::Debris; 2]);
+        let [ship, missile] = pair(&mut world, 0.5, [BodyType::Ship, BodyType::Missile]);
+        let dt = Seconds(1.0);
+        let detected = |world: &mut World| {
+            let exhaustive = summary(&world.detect_collisions(dt));
+            assert_eq!(summary(&world.detect_collisions_tiered(dt)), exhaustive);
+            exhaustive
+        };
+        assert_eq!(
+            world.collision_filter(debris_a),
+            Some(CollisionFilter::for_type(BodyType::Debris))
+        );
+        assert_eq!(detected(&mut world), [(ship, missile)]);
+
+        // A missile just launched, kept off its launcher's layer.
+        let launcher = CollisionFilter {
+            layer: 1 << 8,
+            mask: u32::MAX,
+        };
+        world.set_collision_filter(ship, Some(launcher)).unwrap();
+        let filter = CollisionFilter {
+            layer: CollisionFilter::MISSILES,
+            mask: !launcher.layer,
+        };
+        world.set_collision_filter(missile, Some(filter)).unwrap();
+        assert!(detected(&mut world).is_empty());
+
+        world.set_collision_filter(missile, None).unwrap();
+        assert_eq!(detected(&mut world), [(ship, missile)]);
+        let everything = CollisionFilter {
+            layer: CollisionFilter::DEBRIS,
+            mask: u32::MAX,
+        };
+        for id in [debris_a, debris_b] {
+            world.set_collision_filter(id, Some(everything)).unwrap();
+        }
+        assert_eq!(
+            detected(&mut world),
+            [(debris_a, debris_b), (ship, missile)]
+        );
+    }
+}
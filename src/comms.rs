@@ -0,0 +1,155 @@
+//! Messages between bodies, at the speed of light.
+//!
+//! `World::send_message` puts a message in flight from one body to
+//! another. It arrives once light would have crossed the distance between
+//! them as it was when sent, at `SPEED_OF_LIGHT_MPS`: five seconds from
+//! the edge of the gravity well to its centre. It is handed over with a
+//! `MessageDelivered` event on the first step ending at or after that
+//! time. A message whose recipient is gone by then is lost; one whose
+//! sender is gone still arrives, already being on its way.
+//!
+//! Nothing blocks a message, not even the planet; a caller that wants it to
+//! can check `World::has_line_of_sight` before sending.
+
+use alloc::vec::Vec;
+
+use crate::{error, GgwError, World, WorldEvent};
+
+/// Speed every message travels at, in metres per second.
+pub const SPEED_OF_LIGHT_MPS: f64 = 299_792_458.0;
+
+/// A message in flight, or delivered.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Message {
+    pub from: u64,
+    pub to: u64,
+    /// Sim time it was sent.
+    pub sent: f64,
+    /// Sim time it arrives.
+    pub arrives: f64,
+    pub payload: Vec<u8>,
+}
+
+impl World {
+    /// Send `payload` from `from` to `to`, to arrive after the light-time
+    /// between them now. Returns the sim time it arrives.
+    pub fn send_message(&mut self, from: u64, to: u64, payload: Vec<u8>) -> error::Result<f64> {
+        let sender = self.body(from).ok_or(GgwError::UnknownBody(from))?;
+        let recipient = self.body(to).ok_or(GgwError::UnknownBody(to))?;
+        let sent = self.sim_time().0;
+        let arrives = sent + sender.position.distance(recipient.position) / SPEED_OF_LIGHT_MPS;
+        let at = self
+            .messages
+            .partition_point(|message| message.arrives <= arrives);
+        self.messages.insert(
+            at,
+            Message {
+                from,
+                to,
+                sent,
+                arrives,
+                payload,
+            },
+        );
+        Ok(arrives)
+    }
+
+    /// Messages still on their way, soonest to arrive first.
+    pub fn messages_in_flight(&self) -> &[Message] {
+        &self.messages
+    }
+
+    /// Hand over every message that has arrived by now.
+    pub(crate) fn deliver_messages(&mut self) {
+        let now = self.sim_time().0;
+        let due = self
+            .messages
+            .partition_point(|message| message.arrives <= now);
+        for message in self.messages.drain(..due) {
+            self.events.push(WorldEvent::MessageDelivered(message));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{BodyState, Seconds, DESPAWN_RADIUS_M};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn delivered(world: &mut World) -> Vec<Message> {
+        world
+            .drain_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                WorldEvent::MessageDelivered(message) => Some(message),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn messages_arrive_after_the_light_time() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let near = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let far = world
+            .add_body(BodyState::circular(1_400_000_000.0, 0.0))
+            .unwrap();
+        assert_eq!(
+            world.send_message(near, 99, Vec::new()),
+            Err(GgwError::UnknownBody(99))
+        );
+
+        let distance = world
+            .body(near)
+            .unwrap()
+            .position
+            .distance(world.body(far).unwrap().position);
+        let arrives = world.send_message(near, far, alloc::vec![1, 2, 3]).unwrap();
+        assert_eq!(arrives, distance / SPEED_OF_LIGHT_MPS);
+        assert!(arrives > 4.0 && arrives < 5.0, "{arrives}");
+        assert_eq!(world.messages_in_flight().len(), 1);
+
+        for _ in 0..4 {
+            world.step(Seconds(1.0));
+        }
+        assert!(delivered(&mut world).is_empty());
+        world.step(Seconds(1.0));
+        let messages = delivered(&mut world);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0],
+            Message {
+                from: near,
+                to: far,
+                sent: 0.0,
+                arrives,
+                payload: alloc::vec![1, 2, 3],
+            }
+        );
+        assert!(world.messages_in_flight().is_empty());
+
+        // Lost with its recipient.
+        world.send_message(far, near, Vec::new()).unwrap();
+        world.apply_damage(near, 1e9).unwrap();
+        assert!(world.messages_in_flight().is_empty());
+
+        // And with one that leaves the world.
+        let other = world
+            .add_body(BodyState::circular(1_400_000_000.0, 0.0))
+            .unwrap();
+        world.send_message(other, far, Vec::new()).unwrap();
+        world.get_body_mut(far).unwrap().orbit.semi_major_axis = 2.0 * DESPAWN_RADIUS_M;
+        for _ in 0..10 {
+            world.step(Seconds(1.0));
+        }
+        assert!(world.body(far).is_none());
+        assert!(world.messages_in_flight().is_empty());
+        assert!(delivered(&mut world).is_empty());
+    }
+}
@@ -1,31 +1,87 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
 use serde::Deserialize;
-use std::collections::HashMap;
+#[cfg(feature = "std")]
 use std::fs;
-use std::path::PathBuf;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "std")]
+use crate::error::GgwError;
 
 #[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct GameConfig {
     pub atmosphere: AtmosphereConfig,
     #[serde(default)]
-    pub items: HashMap<String, ItemConfig>,
+    pub items: BTreeMap<String, ItemConfig>,
     #[serde(default)]
-    pub resources: HashMap<String, ResourceConfig>,
+    pub resources: BTreeMap<String, ResourceConfig>,
     #[serde(default)]
     pub default_tank: TankContentsConfig,
     #[serde(default)]
     pub power: PowerConfig,
+    #[serde(default)]
+    pub autosave: AutosaveConfig,
+    #[serde(default)]
+    pub sleep: SleepConfig,
+    #[serde(default)]
+    pub interior: InteriorConfig,
+    #[serde(default)]
+    pub collision: CollisionConfig,
+    #[serde(default)]
+    pub attitude: AttitudeConfig,
+    #[serde(default)]
+    pub reentry: ReentryConfig,
+    #[serde(default)]
+    pub sun: SunConfig,
+    #[serde(default)]
+    pub propulsion: PropulsionConfig,
+    #[serde(default)]
+    pub docking: DockingConfig,
+    #[serde(default)]
+    pub autopilot: AutopilotConfig,
+    #[serde(default)]
+    pub flyby: FlybyConfig,
+    #[serde(default)]
+    pub conjunction: ConjunctionConfig,
+    #[serde(default)]
+    pub launch: LaunchConfig,
+    #[serde(default)]
+    pub blast: BlastConfig,
+    #[serde(default)]
+    pub damage: DamageConfig,
+    #[serde(default)]
+    pub debris: DebrisConfig,
 }
 
 #[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AtmosphereConfig {
     pub tile_size_m: f32,
     pub tile_height_m: f32,
     pub baseline_temp_c: f32,
     pub tick_interval_s: f32,
-    pub gases: HashMap<String, GasConfig>,
+    pub gases: BTreeMap<String, GasConfig>,
+    #[serde(default)]
+    pub solver: AtmosSolver,
+}
+
+/// How gas spreads through a ship; see `rooms` for the two-level solver.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(rename_all = "snake_case")]
+pub enum AtmosSolver {
+    /// Every tile diffuses into its neighbours every tick.
+    #[default]
+    Tiles,
+    /// Rooms exchange gas through open doors at a coarse rate, and tiles
+    /// diffuse only inside rooms that aren't evenly mixed.
+    Rooms,
 }
 
 #[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct GasConfig {
     pub display_name: String,
     pub molar_mass_kg_per_mol: f32,
@@ -33,6 +89,7 @@ pub struct GasConfig {
 }
 
 #[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ItemConfig {
     pub display_name: String,
     pub idle_power_kw: f32,
@@ -47,19 +104,27 @@ pub struct ItemConfig {
 }
 
 #[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ResourceConfig {
     pub density_kg_per_m3: f32,
+    /// Share of a typical asteroid's mass; see `mining`.
+    #[serde(default)]
+    pub abundance: f32,
 }
 
 #[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[serde(default)]
 pub struct TankContentsConfig {
     pub o2_mass_kg: f32,
     pub n2_mass_kg: f32,
     pub co2_mass_kg: f32,
+    pub fuel_mass_kg: f32,
+    pub xenon_mass_kg: f32,
 }
 
 #[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[serde(default)]
 pub struct PowerConfig {
     pub reactor_output_kw: f32,
@@ -73,12 +138,262 @@ pub struct PowerConfig {
     pub door_kw: f32,
 }
 
+/// Periodic world saves. Only honoured by builds with the `autosave` feature.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct AutosaveConfig {
+    pub enabled: bool,
+    pub directory: String,
+    pub interval_s: f64,
+    /// Number of rotating save files kept per session.
+    pub slots: u32,
+}
+
+/// Dormancy for asteroids and debris far from any ship; see `sleep`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct SleepConfig {
+    pub enabled: bool,
+    /// How close a ship or missile may come before a body stays awake.
+    pub distance_m: f64,
+    /// Ticks between position updates of a dormant body.
+    pub propagation_interval_ticks: u64,
+}
+
+/// Limits on how much interior work runs per world tick.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct InteriorConfig {
+    /// Work units, roughly tile visits, one `InteriorWorld::step` may
+    /// spend. Atmosphere ticks and room rebuilds that don't fit wait for a
+    /// later step. 0 means unlimited.
+    pub work_budget: u64,
+}
+
+/// How `World::resolve_collision` treats bodies that run into each other.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct CollisionConfig {
+    /// Share of the closing speed kept after a bounce: 0 stops the bodies
+    /// dead along the contact normal, 1 is perfectly elastic.
+    pub restitution: f64,
+    /// Closing speed below which the bodies stick together and merge
+    /// instead of bouncing.
+    pub merge_speed_mps: f64,
+    /// Impact speed above which `World::fragment_collision` breaks both
+    /// bodies up into debris.
+    pub fragmentation_speed_mps: f64,
+    /// Debris pieces made per breakup.
+    pub fragment_count: u32,
+    /// Speed of the pieces away from the wreck, as a share of the impact
+    /// speed.
+    pub fragment_spread: f64,
+    /// Fastest a body may hit the ground, relative to the turning surface,
+    /// and still land with `World::attempt_landing`.
+    pub max_landing_speed_mps: f64,
+}
+
+/// Limits and gains for RCS attitude control; see `attitude`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct AttitudeConfig {
+    /// Fastest spin RCS will run a body up to, in either direction.
+    pub max_angular_velocity_rad_s: f64,
+    /// Most RCS can change the spin by per second.
+    pub max_angular_acceleration_rad_s2: f64,
+    /// Angular acceleration asked for per radian off the target heading.
+    pub proportional_gain: f64,
+    /// Angular acceleration asked for against each rad/s of spin, damping
+    /// the turn.
+    pub derivative_gain: f64,
+}
+
+/// Heating of bodies dipping into the planet's atmosphere; see `reentry`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct ReentryConfig {
+    pub enabled: bool,
+    /// Height above the planet's surface where heating starts.
+    pub altitude_m: f64,
+    /// Heat damage taken per second at the surface, where 1 burns a body
+    /// up. It falls off with the square of the height, to nothing at
+    /// `altitude_m`.
+    pub heating_rate_per_s: f64,
+}
+
+/// How autopilots fly; see `autopilot`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct AutopilotConfig {
+    /// Time between corrections, and the time each one aims to close the
+    /// gap in.
+    pub interval_s: f64,
+    /// Drift left uncorrected, as long as the body would still be within it
+    /// of its station by the next correction.
+    pub deadband_m: f64,
+    /// Largest single RCS correction. A velocity match bigger than this
+    /// waits for closest approach and uses the main engine.
+    pub max_correction_mps: f64,
+    /// Relative speed a velocity match leaves alone.
+    pub match_tolerance_mps: f64,
+}
+
+/// How close gravity-assist plans pass a moon; see `flyby`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct FlybyConfig {
+    /// Height above the moon's surface at closest approach.
+    pub min_altitude_m: f64,
+}
+
+/// When close approaches are warned of; see `conjunction`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct ConjunctionConfig {
+    /// How far ahead to look.
+    pub horizon_s: f64,
+    /// Miss distance, centre to centre, worth a warning.
+    pub threshold_m: f64,
+    /// Time between looks.
+    pub interval_s: f64,
+}
+
+/// How ships climb off a surface; see `launch`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct LaunchConfig {
+    /// Thrust acceleration through the ascent.
+    pub acceleration_mps2: f64,
+    /// Time spent climbing straight up before pitching over.
+    pub vertical_rise_s: f64,
+}
+
+/// What a missile's warhead does when it goes off; see `blast`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct BlastConfig {
+    /// Reach of the blast, from where the missile went off to a body's
+    /// edge.
+    pub radius_m: f64,
+    /// Hit points taken at the heart of the blast.
+    pub damage: f64,
+    /// Impulse dealt at the heart of the blast, pushing bodies away.
+    pub impulse_ns: f64,
+}
+
+/// How much bodies can take and what is left of them; see `damage`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct DamageConfig {
+    /// Hit points of a body not given its own, per kilogram of its mass.
+    pub hp_per_kg: f64,
+    /// Hit points each body in a bouncing collision takes per
+    /// newton-second of impulse.
+    pub collision_hp_per_ns: f64,
+    /// Pieces of debris a destroyed body, or a missile going off, breaks
+    /// into; 0 for none.
+    pub debris_count: u32,
+    /// Speed the debris flies apart at.
+    pub debris_speed_mps: f64,
+    /// Lightest piece a destroyed asteroid splits into; one too light to
+    /// make two breaks into debris instead.
+    pub asteroid_split_min_kg: f64,
+    /// Speed the pieces of a split asteroid drift apart at.
+    pub asteroid_split_speed_mps: f64,
+}
+
+/// Debris breaking up further, and how much of it there can be; see
+/// `cascade`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct DebrisConfig {
+    /// Chance that debris hitting debris or an asteroid faster than
+    /// `CollisionConfig::fragmentation_speed_mps` breaks both up instead of
+    /// bouncing off. At 0, debris passes through debris by default; see
+    /// `collision::CollisionFilter::for_type`.
+    pub cascade_probability: f64,
+    /// Lightest piece of debris a breakup makes; a light wreck breaks into
+    /// fewer pieces instead.
+    pub min_fragment_kg: f64,
+    /// Most debris the world holds at once, or 0 for no limit.
+    pub max_debris: u32,
+}
+
+/// When ships can dock and how hard they part; see `docking`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct DockingConfig {
+    /// Widest gap between two ships' hulls they can still dock across.
+    pub capture_range_m: f64,
+    /// Fastest two ships can be drifting relative to each other and dock.
+    pub max_relative_speed_mps: f64,
+    /// Speed undocking pushes two ships apart at.
+    pub separation_speed_mps: f64,
+}
+
+/// Engines and what a burn costs; see `propellant`.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct PropulsionConfig {
+    /// Specific impulse of RCS thrusters, burning fuel.
+    pub rcs_isp_s: f64,
+    /// Specific impulse of the main engine, burning fuel.
+    pub chemical_isp_s: f64,
+    /// Specific impulse of ion engines, burning xenon.
+    pub ion_isp_s: f64,
+    /// What happens to a burn the body can't afford in full.
+    pub shortfall: BurnShortfall,
+}
+
+/// What to do with a burn that needs more propellant than is left.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(rename_all = "snake_case")]
+pub enum BurnShortfall {
+    /// Turn it down with `GgwError::InsufficientPropellant`.
+    #[default]
+    Reject,
+    /// Burn what is left, in the same direction.
+    Truncate,
+}
+
+/// Where sunlight comes from; see `sunlight`. The sun is far enough away
+/// that its light arrives as parallel rays from one direction.
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default)]
+pub struct SunConfig {
+    /// Direction of the sun from the planet at time zero, counterclockwise
+    /// from +x.
+    pub initial_angle_rad: f64,
+    /// How fast that direction turns, counterclockwise, as the planet goes
+    /// around the sun. 0 holds it fixed.
+    pub rotation_rate_rad_s: f64,
+}
+
 impl Default for TankContentsConfig {
     fn default() -> Self {
         Self {
             o2_mass_kg: 80.0,
             n2_mass_kg: 0.0,
             co2_mass_kg: 0.0,
+            fuel_mass_kg: 100.0,
+            xenon_mass_kg: 10.0,
         }
     }
 }
@@ -99,46 +414,211 @@ impl Default for PowerConfig {
     }
 }
 
+impl Default for AutosaveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: "saves".to_string(),
+            interval_s: 300.0,
+            slots: 3,
+        }
+    }
+}
+
+impl Default for CollisionConfig {
+    fn default() -> Self {
+        Self {
+            restitution: 0.5,
+            merge_speed_mps: 2.0,
+            fragmentation_speed_mps: 1_000.0,
+            fragment_count: 6,
+            fragment_spread: 0.1,
+            max_landing_speed_mps: 5.0,
+        }
+    }
+}
+
+impl Default for AttitudeConfig {
+    fn default() -> Self {
+        Self {
+            max_angular_velocity_rad_s: 0.5,
+            max_angular_acceleration_rad_s2: 0.2,
+            proportional_gain: 1.0,
+            derivative_gain: 2.0,
+        }
+    }
+}
+
+impl Default for ReentryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            altitude_m: 100_000.0,
+            heating_rate_per_s: 0.1,
+        }
+    }
+}
+
+impl Default for AutopilotConfig {
+    fn default() -> Self {
+        Self {
+            interval_s: 30.0,
+            deadband_m: 5.0,
+            max_correction_mps: 1.0,
+            match_tolerance_mps: 0.05,
+        }
+    }
+}
+
+impl Default for FlybyConfig {
+    fn default() -> Self {
+        Self {
+            min_altitude_m: 100_000.0,
+        }
+    }
+}
+
+impl Default for ConjunctionConfig {
+    fn default() -> Self {
+        Self {
+            horizon_s: 600.0,
+            threshold_m: 1_000.0,
+            interval_s: 10.0,
+        }
+    }
+}
+
+impl Default for LaunchConfig {
+    fn default() -> Self {
+        Self {
+            acceleration_mps2: 20.0,
+            vertical_rise_s: 10.0,
+        }
+    }
+}
+
+impl Default for BlastConfig {
+    fn default() -> Self {
+        Self {
+            radius_m: 200.0,
+            damage: 2_000.0,
+            impulse_ns: 20_000.0,
+        }
+    }
+}
+
+impl Default for DamageConfig {
+    fn default() -> Self {
+        Self {
+            hp_per_kg: 1.0,
+            collision_hp_per_ns: 0.1,
+            debris_count: 6,
+            debris_speed_mps: 30.0,
+            asteroid_split_min_kg: 1_000.0,
+            asteroid_split_speed_mps: 1.0,
+        }
+    }
+}
+
+impl Default for DebrisConfig {
+    fn default() -> Self {
+        Self {
+            cascade_probability: 0.5,
+            min_fragment_kg: 1.0,
+            max_debris: 0,
+        }
+    }
+}
+
+impl Default for DockingConfig {
+    fn default() -> Self {
+        Self {
+            capture_range_m: 20.0,
+            max_relative_speed_mps: 0.5,
+            separation_speed_mps: 0.2,
+        }
+    }
+}
+
+impl Default for PropulsionConfig {
+    fn default() -> Self {
+        Self {
+            rcs_isp_s: 70.0,
+            chemical_isp_s: 320.0,
+            ion_isp_s: 3_000.0,
+            shortfall: BurnShortfall::Reject,
+        }
+    }
+}
+
+impl Default for SunConfig {
+    fn default() -> Self {
+        Self {
+            initial_angle_rad: 0.0,
+            // Once around a year.
+            rotation_rate_rad_s: core::f64::consts::TAU / 31_557_600.0,
+        }
+    }
+}
+
+impl Default for SleepConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            distance_m: 200_000.0,
+            propagation_interval_ticks: 20,
+        }
+    }
+}
+
+/// File loading needs `std`; `no_std` embedders build a `GameConfig`
+/// themselves or start from `GameConfig::default()`.
+#[cfg(feature = "std")]
 impl GameConfig {
+    /// Load the first config found on the search path, falling back to
+    /// defaults (with a warning) if none can be read or parsed.
     pub fn load() -> Self {
+        Self::try_load().unwrap_or_else(|err| {
+            tracing::warn!(error = %err, "using default config");
+            Self::default()
+        })
+    }
+
+    /// Like `load`, but reports why no config could be used instead of
+    /// falling back. A file that exists but fails to parse is an error even
+    /// if a later search path would have worked.
+    pub fn try_load() -> Result<Self, GgwError> {
         let search_paths = [
             PathBuf::from("config/game_config.toml"),
             PathBuf::from("../config/game_config.toml"),
             PathBuf::from("../../config/game_config.toml"),
         ];
-        let mut tried = Vec::new();
         for path in &search_paths {
-            tried.push(path.display().to_string());
-            if !path.exists() {
-                continue;
-            }
-            match fs::read_to_string(path) {
-                Ok(contents) => {
-                    return toml::from_str(&contents).unwrap_or_else(|err| {
-                        eprintln!(
-                            "Failed to parse {} ({}), using defaults.",
-                            path.display(),
-                            err
-                        );
-                        Self::default()
-                    });
-                }
-                Err(_) => continue,
+            if path.exists() {
+                return Self::from_file(path);
             }
         }
-        if !tried.is_empty() {
-            eprintln!(
-                "Failed to read config from any of [{}], using defaults.",
-                tried.join(", ")
-            );
-        }
-        Self::default()
+        let tried: Vec<String> = search_paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        Err(GgwError::Config(format!(
+            "no config found in any of [{}]",
+            tried.join(", ")
+        )))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, GgwError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| GgwError::Config(format!("{}: {}", path.display(), err)))?;
+        toml::from_str(&contents)
+            .map_err(|err| GgwError::Config(format!("{}: {}", path.display(), err)))
     }
 }
 
 impl Default for GameConfig {
     fn default() -> Self {
-        let mut gases = HashMap::new();
+        let mut gases = BTreeMap::new();
         gases.insert(
             "O2".to_string(),
             GasConfig {
@@ -164,7 +644,7 @@ impl Default for GameConfig {
             },
         );
 
-        let mut items = HashMap::new();
+        let mut items = BTreeMap::new();
         items.insert(
             "reactor_uranium".to_string(),
             ItemConfig {
@@ -276,23 +756,26 @@ impl Default for GameConfig {
             },
         );
 
-        let mut resources = HashMap::new();
+        let mut resources = BTreeMap::new();
         resources.insert(
             "iron_ore".to_string(),
             ResourceConfig {
                 density_kg_per_m3: 5200.0,
+                abundance: 0.2,
             },
         );
         resources.insert(
             "gold_ore".to_string(),
             ResourceConfig {
                 density_kg_per_m3: 19_300.0,
+                abundance: 0.002,
             },
         );
         resources.insert(
             "silver_ore".to_string(),
             ResourceConfig {
                 density_kg_per_m3: 10_490.0,
+                abundance: 0.01,
             },
         );
 
@@ -303,11 +786,28 @@ impl Default for GameConfig {
                 baseline_temp_c: 20.0,
                 tick_interval_s: 0.25,
                 gases,
+                solver: AtmosSolver::default(),
             },
             items,
             resources,
             default_tank: TankContentsConfig::default(),
             power: PowerConfig::default(),
+            autosave: AutosaveConfig::default(),
+            sleep: SleepConfig::default(),
+            interior: InteriorConfig::default(),
+            collision: CollisionConfig::default(),
+            attitude: AttitudeConfig::default(),
+            reentry: ReentryConfig::default(),
+            sun: SunConfig::default(),
+            propulsion: PropulsionConfig::default(),
+            docking: DockingConfig::default(),
+            autopilot: AutopilotConfig::default(),
+            flyby: FlybyConfig::default(),
+            conjunction: ConjunctionConfig::default(),
+            launch: LaunchConfig::default(),
+            blast: BlastConfig::default(),
+            damage: DamageConfig::default(),
+            debris: DebrisConfig::default(),
         }
     }
 }
@@ -0,0 +1,219 @@
+//! Warnings of close approaches before they happen.
+//!
+//! Collisions are only found as they happen; a body marked with
+//! `World::track_conjunctions` is also watched for ones coming. Every
+//! `config::ConjunctionConfig::interval_s` each tracked body's orbit is
+//! run forward `horizon_s` against every other body's, and a pair whose
+//! centres will pass within `threshold_m` of each other raises a
+//! `WorldEvent::ConjunctionWarning` with when and how close. Untracked
+//! bodies, like debris, are still checked against tracked ones; they just
+//! aren't checked against each other.
+//!
+//! A pair is warned about once per encounter: it stays in
+//! `World::conjunctions` until its closest approach has passed, and is only
+//! warned about again if it is closing once more. Predictions follow each
+//! body's current orbit, so they miss burns and hand-overs to another
+//! primary. Pairs too far apart to close the gap within the horizon, at the
+//! fastest their orbits allow, aren't searched at all.
+
+use alloc::vec::Vec;
+
+use crate::collision::max_speed;
+use crate::{error, transfer, BodyState, GgwError, World, WorldEvent};
+
+/// A predicted close approach between two bodies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conjunction {
+    /// Lower id of the pair.
+    pub body_a: u64,
+    pub body_b: u64,
+    /// When they come closest.
+    pub time: f64,
+    /// Distance between their centres then.
+    pub miss_distance: f64,
+}
+
+impl World {
+    /// Watch `body_id` for close approaches from now on, or stop with
+    /// `false`.
+    pub fn track_conjunctions(&mut self, body_id: u64, track: bool) -> error::Result<()> {
+        if self.body(body_id).is_none() {
+            return Err(GgwError::UnknownBody(body_id));
+        }
+        if track {
+            self.conjunction_tracked.insert(body_id);
+        } else {
+            self.conjunction_tracked.remove(&body_id);
+        }
+        Ok(())
+    }
+
+    /// Whether `body_id` is watched for close approaches.
+    pub fn tracks_conjunctions(&self, body_id: u64) -> bool {
+        self.conjunction_tracked.contains(&body_id)
+    }
+
+    /// Close approaches warned about that are still to come, in the order
+    /// they were found.
+    pub fn conjunctions(&self) -> &[Conjunction] {
+        &self.conjunctions
+    }
+
+    /// Fastest `body` can move through the world frame.
+    fn speed_bound(&self, body: &BodyState) -> f64 {
+        let primary = self.primary_of(body);
+        max_speed(&body.orbit, primary.mu) + primary.max_speed
+    }
+
+    /// Time and distance of `a` and `b`'s closest approach within `horizon`
+    /// seconds of `now`, or `None` if they are drawing apart already.
+    fn closest_approach(
+        &self,
+        a: &BodyState,
+        b: &BodyState,
+        now: f64,
+        horizon: f64,
+    ) -> Option<(f64, f64)> {
+        let distance = |dt: f64| match (self.state_at(a, now + dt), self.state_at(b, now + dt)) {
+            (Ok((pa, _)), Ok((pb, _))) => pa.distance(pb),
+            _ => f64::INFINITY,
+        };
+        let dt = transfer::cheapest_flight_time(horizon, distance)?;
+        let miss = distance(dt);
+        (miss < distance(0.0)).then_some((now + dt, miss))
+    }
+
+    /// Look for close approaches, if a check is due, and warn of new ones.
+    pub(crate) fn check_conjunctions(&mut self) {
+        let now = self.sim_time().0;
+        self.conjunctions
+            .retain(|conjunction| conjunction.time >= now);
+        if self.conjunction_tracked.is_empty() || now < self.next_conjunction_check {
+            return;
+        }
+        let config = &self.config.conjunction;
+        self.next_conjunction_check = now + config.interval_s.max(0.0);
+        let (horizon, threshold) = (config.horizon_s.max(0.0), config.threshold_m);
+
+        let flying: Vec<&BodyState> = self
+            .bodies
+            .iter()
+            .filter(|body| !self.landed.contains_key(&body.id))
+            .collect();
+        let mut found = Vec::new();
+        for a in flying
+            .iter()
+            .filter(|a| self.conjunction_tracked.contains(&a.id))
+        {
+            let a_speed = self.speed_bound(a);
+            for b in &flying {
+                let (body_a, body_b) = (a.id.min(b.id), a.id.max(b.id));
+                // A pair of tracked bodies is checked once, from the lower
+                // id.
+                let twice = b.id < a.id && self.conjunction_tracked.contains(&b.id);
+                let warned = self
+                    .conjunctions
+                    .iter()
+                    .any(|c| (c.body_a, c.body_b) == (body_a, body_b));
+                if a.id == b.id || twice || warned {
+                    continue;
+                }
+                let reach = (a_speed + self.speed_bound(b)) * horizon + threshold;
+                if a.position.distance(b.position) > reach {
+                    continue;
+                }
+                let Some((time, miss_distance)) = self.closest_approach(a, b, now, horizon) else {
+                    continue;
+                };
+                if miss_distance < threshold {
+                    found.push(Conjunction {
+                        body_a,
+                        body_b,
+                        time,
+                        miss_distance,
+                    });
+                }
+            }
+        }
+        for conjunction in found {
+            tracing::debug!(
+                a = conjunction.body_a,
+                b = conjunction.body_b,
+                time = conjunction.time,
+                miss = conjunction.miss_distance,
+                "conjunction warning"
+            );
+            self.conjunctions.push(conjunction);
+            self.events
+                .push(WorldEvent::ConjunctionWarning(conjunction));
+        }
+    }
+
+    /// Stop watching `body_id` and drop warnings about it, as it leaves
+    /// the world.
+    pub(crate) fn forget_conjunctions(&mut self, body_id: u64) {
+        self.conjunction_tracked.remove(&body_id);
+        self.conjunctions
+            .retain(|c| c.body_a != body_id && c.body_b != body_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{BodyType, Seconds};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn body(semi_major_axis: f64, mean_anomaly: f64) -> BodyState {
+        BodyState {
+            body_type: BodyType::Debris,
+            ..BodyState::circular(semi_major_axis, mean_anomaly)
+        }
+    }
+
+    #[test]
+    fn close_approaches_are_warned_of_once() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world.add_body(body(7_000_000.0, 0.0)).unwrap();
+        // 5 km lower and 3.5 km behind: it passes under the ship in about
+        // 7 minutes.
+        let debris = world.add_body(body(6_995_000.0, -5e-4)).unwrap();
+        // Same orbit half a turn away never comes near.
+        world.add_body(body(7_000_000.0, 3.0)).unwrap();
+        assert_eq!(
+            world.track_conjunctions(99, true),
+            Err(GgwError::UnknownBody(99))
+        );
+
+        // Nothing is looked for until a body is tracked.
+        world.step(Seconds(1.0));
+        assert!(world.conjunctions().is_empty());
+        world.config.conjunction.threshold_m = 6_000.0;
+        world.track_conjunctions(ship, true).unwrap();
+        world.step(Seconds(1.0));
+        let warnings: Vec<Conjunction> = world
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                WorldEvent::ConjunctionWarning(conjunction) => Some(*conjunction),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(warnings.len(), 1);
+        let warning = warnings[0];
+        assert_eq!((warning.body_a, warning.body_b), (ship, debris));
+        assert!((warning.miss_distance - 5_000.0).abs() < 10.0);
+        assert!(warning.time > 300.0 && warning.time < 600.0);
+        assert_eq!(world.conjunctions(), [warning]);
+
+        // Not warned again while it is coming, and forgotten once past.
+        world.drain_events();
+        world.step(Seconds(60.0));
+        assert!(world.events().is_empty());
+        world.warp_to(Seconds(warning.time + 1.0));
+        assert!(world.conjunctions().is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! Hit points and destruction.
+//!
+//! Every body has `HitPoints`. Until it is given its own with
+//! `World::set_max_hp` it has `config::DamageConfig::hp_per_kg` for each
+//! kilogram of its mass, and only once hurt or given its own are they
+//! kept apart from the body. Damage comes from `World::apply_damage`, from
+//! missile blasts, see `blast`, and from bouncing collisions, which deal
+//! `collision_hp_per_ns` for each newton-second of the impulse between the
+//! two bodies. Each hit is reported with a `Damaged` event.
+//!
+//! A body brought down to 0 leaves the world with a
+//! `DespawnReason::Destroyed`, breaking into `debris_count` pieces of
+//! debris that fly apart at `debris_speed_mps`, or none if that is 0, as
+//! far as `config::DebrisConfig` allows; see `cascade`.
+//! An asteroid splits instead into as many smaller asteroids as it has
+//! `asteroid_split_min_kg` for, from 2 up to 4, sharing its mass equally
+//! and drifting apart at `asteroid_split_speed_mps`. They keep its
+//! composition and tags, and start with fresh hit points. One too light to
+//! split breaks into debris like anything else.
+
+use alloc::vec::Vec;
+
+use crate::math::Float;
+use crate::{error, BodyState, BodyType, DespawnReason, GgwError, Vec2, World, WorldEvent, Wreck};
+
+/// How much more damage a body can take, and how much it could when new.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HitPoints {
+    pub hp: f64,
+    pub max_hp: f64,
+}
+
+impl World {
+    /// `body_id`'s hit points, or `None` if there is no such body.
+    pub fn hit_points(&self, body_id: u64) -> Option<HitPoints> {
+        let body = self.body(body_id)?;
+        Some(self.hit_points.get(&body_id).copied().unwrap_or_else(|| {
+            let max_hp = body.mass * self.config.damage.hp_per_kg;
+            HitPoints { hp: max_hp, max_hp }
+        }))
+    }
+
+    /// Give `body_id` `max_hp` hit points of its own, repairing it fully.
+    /// Fails with `InvalidDamage` unless `max_hp` is positive and finite.
+    pub fn set_max_hp(&mut self, body_id: u64, max_hp: f64) -> error::Result<()> {
+        self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        if !(max_hp > 0.0 && max_hp.is_finite()) {
+            return Err(GgwError::InvalidDamage);
+        }
+        self.hit_points
+            .insert(body_id, HitPoints { hp: max_hp, max_hp });
+        Ok(())
+    }
+
+    /// Take `amount` hit points off `body_id`, destroying it if that leaves
+    /// none, and return whether it did. Fails with `InvalidDamage` unless
+    /// `amount` is finite and not negative.
+    pub fn apply_damage(&mut self, body_id: u64, amount: f64) -> error::Result<bool> {
+        self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        if !(amount >= 0.0 && amount.is_finite()) {
+            return Err(GgwError::InvalidDamage);
+        }
+        Ok(self.deal_damage(body_id, amount))
+    }
+
+    /// Take `amount` off `body_id`, a body that exists, destroying it if
+    /// that leaves none; returns whether it did.
+    pub(crate) fn deal_damage(&mut self, body_id: u64, amount: f64) -> bool {
+        let Some(mut hit_points) = self.hit_points(body_id) else {
+            return false;
+        };
+        hit_points.hp = (hit_points.hp - amount.max(0.0)).max(0.0);
+        self.hit_points.insert(body_id, hit_points);
+        self.events.push(WorldEvent::Damaged {
+            time: self.sim_time().0,
+            body_id,
+            amount,
+            hp: hit_points.hp,
+        });
+        if hit_points.hp > 0.0 {
+            return false;
+        }
+        self.destroy(body_id);
+        true
+    }
+
+    /// Break `body_id` up where it is now and take it out of the world.
+    fn destroy(&mut self, body_id: u64) {
+        let Some(body) = self.body(body_id) else {
+            return;
+        };
+        let now = self.sim_time().0;
+        let pieces = self
+            .tracked_state(body, now)
+            .and_then(|(position, velocity)| self.wreckage(body, position, velocity, now));
+        let composition = self.compositions.get(&body_id).cloned();
+        tracing::debug!(body = body_id, "body destroyed");
+        self.forget_body(body_id, DespawnReason::Destroyed);
+        let added = pieces.and_then(|pieces| {
+            pieces.into_iter().try_for_each(|piece| {
+                let asteroid = piece.body_type == BodyType::Asteroid;
+                let id = self.add_body(piece)?;
+                if let (true, Some(composition)) = (asteroid, &composition) {
+                    self.compositions.insert(id, composition.clone());
+                }
+                Ok(())
+            })
+        });
+        if let Err(err) = added {
+            tracing::warn!(body = body_id, error = %err, "wreckage dropped");
+        }
+    }
+
+    /// What `body` breaks into, at `position` and `velocity` in the world
+    /// frame at `time`: smaller asteroids if it is an asteroid heavy
+    /// enough, otherwise debris.
+    fn wreckage(
+        &self,
+        body: &BodyState,
+        position: Vec2,
+        velocity: Vec2,
+        time: f64,
+    ) -> error::Result<Vec<BodyState>> {
+        let config = &self.config.damage;
+        let wreck = Wreck {
+            position,
+            velocity,
+            mass: body.mass,
+            radius: body.radius,
+            parent: body.parent,
+        };
+        let heading = Float::atan2(velocity.y, velocity.x);
+        let splits =
+            body.body_type == BodyType::Asteroid && body.mass >= 2.0 * config.asteroid_split_min_kg;
+        if !splits {
+            let count = self.debris_pieces(config.debris_count, body.mass, &[body.id]);
+            if count == 0 {
+                return Ok(Vec::new());
+            }
+            return self.scatter(&wreck, count, config.debris_speed_mps, heading, time);
+        }
+
+        let count = (body.mass / config.asteroid_split_min_kg).clamp(2.0, 4.0) as u32;
+        // Same density, so size goes with the cube root of mass, as it
+        // does for mining.
+        let scale = Float::powf(1.0 / f64::from(count), 1.0 / 3.0);
+        let mut pieces = self.scatter(
+            &wreck,
+            count,
+            config.asteroid_split_speed_mps,
+            heading,
+            time,
+        )?;
+        for piece in &mut pieces {
+            piece.body_type = BodyType::Asteroid;
+            piece.radius = body.radius * scale;
+            piece.hull_shape = body.hull_shape.clone().map(|mut hull| {
+                for vertex in &mut hull.vertices {
+                    *vertex *= scale;
+                }
+                hull
+            });
+            piece.tags = body.tags.clone();
+        }
+        Ok(pieces)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::HullShape;
+    use alloc::collections::BTreeMap;
+    use alloc::string::String;
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn body(mass: f64, body_type: BodyType) -> BodyState {
+        BodyState {
+            mass,
+            radius: 10.0,
+            body_type,
+            hull_shape: Some(HullShape {
+                vertices: alloc::vec![
+                    Vec2::new(10.0, 0.0),
+                    Vec2::new(0.0, 10.0),
+                    Vec2::new(-10.0, 0.0),
+                ],
+            }),
+            ..BodyState::circular(7_000_000.0, 0.0)
+        }
+    }
+
+    #[test]
+    fn bodies_out_of_hit_points_break_up() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world.add_body(body(1_000.0, BodyType::Ship)).unwrap();
+        let full = HitPoints {
+            hp: 1_000.0,
+            max_hp: 1_000.0,
+        };
+        assert_eq!(world.hit_points(ship), Some(full));
+        assert_eq!(world.apply_damage(ship, -1.0), Err(GgwError::InvalidDamage));
+        assert_eq!(world.set_max_hp(ship, 0.0), Err(GgwError::InvalidDamage));
+
+        world.set_max_hp(ship, 100.0).unwrap();
+        assert_eq!(world.apply_damage(ship, 60.0), Ok(false));
+        assert_eq!(world.hit_points(ship).unwrap().hp, 40.0);
+        world.drain_events();
+
+        assert_eq!(world.apply_damage(ship, 60.0), Ok(true));
+        assert!(world.body(ship).is_none());
+        assert_eq!(world.hit_points(ship), None);
+        let events = world.drain_events();
+        assert_eq!(
+            events[0],
+            WorldEvent::Damaged {
+                time: 0.0,
+                body_id: ship,
+                amount: 60.0,
+                hp: 0.0
+            }
+        );
+        assert!(events.iter().any(|event| matches!(
+            event,
+            WorldEvent::Despawned(despawn)
+                if despawn.id == ship && despawn.reason == DespawnReason::Destroyed
+        )));
+        let debris: f64 = world
+            .bodies_of_type(BodyType::Debris)
+            .map(|piece| piece.mass)
+            .sum();
+        assert_eq!(world.bodies_of_type(BodyType::Debris).count(), 6);
+        assert!((debris - 1_000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn destroyed_asteroids_split_into_smaller_ones() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let rock = world.add_body(body(10_000.0, BodyType::Asteroid)).unwrap();
+        let mut composition = BTreeMap::new();
+        composition.insert(String::from("iron_ore"), 0.3);
+        world.set_composition(rock, composition.clone()).unwrap();
+        assert_eq!(world.apply_damage(rock, 1e9), Ok(true));
+
+        let pieces: Vec<BodyState> = world.bodies_of_type(BodyType::Asteroid).cloned().collect();
+        assert_eq!(pieces.len(), 4);
+        assert_eq!(world.bodies_of_type(BodyType::Debris).count(), 0);
+        let mass: f64 = pieces.iter().map(|piece| piece.mass).sum();
+        assert!((mass - 10_000.0).abs() < 1e-9);
+        for piece in &pieces {
+            assert_eq!(world.composition(piece.id), Some(composition.clone()));
+            assert_eq!(world.hit_points(piece.id).unwrap().hp, piece.mass);
+            assert!((piece.radius - 10.0 * Float::powf(0.25, 1.0 / 3.0)).abs() < 1e-9);
+            let drift = (piece.orbit.semi_major_axis - 7_000_000.0).abs();
+            assert!(drift > 0.0 && drift < 50_000.0, "{drift}");
+        }
+
+        // Too light to make two pieces worth keeping.
+        let pebble = world.add_body(body(1_500.0, BodyType::Asteroid)).unwrap();
+        assert_eq!(world.apply_damage(pebble, 1e9), Ok(true));
+        assert_eq!(world.bodies_of_type(BodyType::Asteroid).count(), 4);
+        assert_eq!(world.bodies_of_type(BodyType::Debris).count(), 6);
+    }
+}
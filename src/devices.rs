@@ -0,0 +1,287 @@
+//! Extension point for device types the built-in `DeviceType`/`DeviceData`
+//! enums don't cover. A downstream crate implements `DeviceBehavior`,
+//! registers a factory for its kind name on the ship's `DeviceRegistry`,
+//! and places devices with `ShipInterior::add_custom_device`.
+//!
+//! A custom device's state travels with the world as the bytes
+//! `DeviceBehavior::serialize` returns. After a load or a clone only those
+//! bytes are left, and the registered factory turns them back into a live
+//! behavior the next time the device is used. A device whose kind isn't
+//! registered sits inert but keeps its state.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::interior::{AtmosSample, GasType, Pawn};
+
+/// Behavior of one custom device instance.
+pub trait DeviceBehavior: fmt::Debug + Send + Sync {
+    /// Advance the device by `dt` seconds. Called every interior step,
+    /// online or not; check `ctx.online`.
+    fn step(&mut self, ctx: &mut DeviceContext, dt: f32);
+
+    /// The pawn used the device.
+    fn interact(&mut self, ctx: &mut DeviceContext, pawn: &mut Pawn) {
+        let _ = (ctx, pawn);
+    }
+
+    /// Encode the device's state. The factory registered for this kind must
+    /// accept the result.
+    fn serialize(&self) -> Vec<u8>;
+}
+
+/// Rebuilds a behavior from the bytes its `serialize` produced, or `None`
+/// if they can't be decoded.
+pub type DeviceFactory = fn(state: &[u8]) -> Option<Box<dyn DeviceBehavior>>;
+
+/// What a custom device can see and change while it runs.
+#[derive(Clone, Debug)]
+pub struct DeviceContext {
+    pub device_id: u64,
+    /// Whether the device is switched on. Clearing it switches it off.
+    pub online: bool,
+    /// Power drawn (positive) or supplied (negative) in kW. Counted in the
+    /// ship's balance while the device is online.
+    pub power_kw: f32,
+    /// Atmosphere on the tile the device vents into, if it has one.
+    pub atmosphere: Option<AtmosSample>,
+    emitted: Vec<(GasType, f32)>,
+}
+
+impl DeviceContext {
+    pub(crate) fn new(
+        device_id: u64,
+        online: bool,
+        power_kw: f32,
+        atmosphere: Option<AtmosSample>,
+    ) -> Self {
+        Self {
+            device_id,
+            online,
+            power_kw,
+            atmosphere,
+            emitted: Vec::new(),
+        }
+    }
+
+    /// Release `mass_kg` of `gas` into the device's output tile.
+    pub fn emit_gas(&mut self, gas: GasType, mass_kg: f32) {
+        if mass_kg > 0.0 {
+            self.emitted.push((gas, mass_kg));
+        }
+    }
+
+    pub(crate) fn take_emitted(&mut self) -> Vec<(GasType, f32)> {
+        core::mem::take(&mut self.emitted)
+    }
+}
+
+/// Factories for custom device kinds, keyed by kind name.
+#[derive(Clone, Debug, Default)]
+pub struct DeviceRegistry {
+    factories: BTreeMap<String, DeviceFactory>,
+}
+
+impl DeviceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `factory` for `kind`, returning the factory it replaced.
+    pub fn register(
+        &mut self,
+        kind: impl Into<String>,
+        factory: DeviceFactory,
+    ) -> Option<DeviceFactory> {
+        self.factories.insert(kind.into(), factory)
+    }
+
+    pub fn contains(&self, kind: &str) -> bool {
+        self.factories.contains_key(kind)
+    }
+
+    pub fn kinds(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    pub fn instantiate(&self, kind: &str, state: &[u8]) -> Option<Box<dyn DeviceBehavior>> {
+        self.factories.get(kind).and_then(|factory| factory(state))
+    }
+}
+
+/// `DeviceData` payload of a custom device.
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "CustomDeviceState", into = "CustomDeviceState")
+)]
+pub struct CustomDevice {
+    kind: String,
+    state: Vec<u8>,
+    behavior: Option<Box<dyn DeviceBehavior>>,
+}
+
+impl CustomDevice {
+    pub fn new(kind: impl Into<String>, behavior: Box<dyn DeviceBehavior>) -> Self {
+        Self {
+            kind: kind.into(),
+            state: Vec::new(),
+            behavior: Some(behavior),
+        }
+    }
+
+    /// A device known only by its saved state, bound to a behavior on
+    /// first use.
+    pub fn from_state(kind: impl Into<String>, state: Vec<u8>) -> Self {
+        Self {
+            kind: kind.into(),
+            state,
+            behavior: None,
+        }
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Current state as `DeviceBehavior::serialize` bytes.
+    pub fn state(&self) -> Vec<u8> {
+        match &self.behavior {
+            Some(behavior) => behavior.serialize(),
+            None => self.state.clone(),
+        }
+    }
+
+    pub fn behavior(&self) -> Option<&dyn DeviceBehavior> {
+        self.behavior.as_deref()
+    }
+
+    /// The live behavior, rebuilt from the saved state through `registry`
+    /// if this device hasn't been used since it was loaded or cloned.
+    pub(crate) fn bind(
+        &mut self,
+        registry: &DeviceRegistry,
+    ) -> Option<&mut (dyn DeviceBehavior + 'static)> {
+        if self.behavior.is_none() {
+            self.behavior = registry.instantiate(&self.kind, &self.state);
+        }
+        self.behavior.as_deref_mut()
+    }
+}
+
+/// Clones carry the serialized state only; see the module docs.
+impl Clone for CustomDevice {
+    fn clone(&self) -> Self {
+        Self::from_state(self.kind.clone(), self.state())
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CustomDeviceState {
+    kind: String,
+    state: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl From<CustomDeviceState> for CustomDevice {
+    fn from(saved: CustomDeviceState) -> Self {
+        Self::from_state(saved.kind, saved.state)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<CustomDevice> for CustomDeviceState {
+    fn from(device: CustomDevice) -> Self {
+        Self {
+            state: device.state(),
+            kind: device.kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::interior::{DeviceData, InteriorCommand, InteriorWorld};
+
+    /// Emits O2 while online and counts how often the pawn pressed it.
+    #[derive(Debug)]
+    struct OxygenCandle {
+        presses: u8,
+    }
+
+    impl DeviceBehavior for OxygenCandle {
+        fn step(&mut self, ctx: &mut DeviceContext, dt: f32) {
+            ctx.power_kw = if ctx.online { 0.5 } else { 0.0 };
+            if ctx.online {
+                ctx.emit_gas(GasType::O2, 0.01 * dt);
+            }
+        }
+
+        fn interact(&mut self, ctx: &mut DeviceContext, _pawn: &mut Pawn) {
+            self.presses += 1;
+            ctx.online = !ctx.online;
+        }
+
+        fn serialize(&self) -> Vec<u8> {
+            alloc::vec![self.presses]
+        }
+    }
+
+    fn oxygen_candle(state: &[u8]) -> Option<Box<dyn DeviceBehavior>> {
+        let presses = *state.first()?;
+        Some(Box::new(OxygenCandle { presses }))
+    }
+
+    #[test]
+    fn custom_device_steps_interacts_and_survives_clone() {
+        let config = GameConfig::default();
+        let mut interior = InteriorWorld::new_test_ship(&config);
+        let ship = &mut interior.ship;
+        ship.device_registry
+            .register("oxygen_candle", oxygen_candle);
+        let (x, y) = (5, 5);
+        let id = ship.add_custom_device(
+            CustomDevice::new("oxygen_candle", Box::new(OxygenCandle { presses: 0 })),
+            x,
+            y,
+            1,
+            1,
+        );
+
+        let o2_before = interior.ship.total_atmos().o2_kg;
+        interior.ship.step(1.0, &config);
+        interior.ship.step(1.0, &config);
+        assert!(interior.ship.total_atmos().o2_kg > o2_before);
+        let summary = &interior.ship.power_summary;
+        let status = summary.devices.iter().find(|d| d.id == id).unwrap();
+        assert_eq!(status.name, "oxygen_candle");
+        assert_eq!(status.draw_kw, 0.5);
+
+        interior.queue_command(InteriorCommand::InteractAt { x, y });
+        interior.step(0.1, &config);
+        let mut copy = interior.clone();
+        let device = copy.ship.devices.iter_mut().find(|d| d.id == id).unwrap();
+        assert!(!device.online);
+        let DeviceData::Custom(custom) = &device.data else {
+            panic!("expected a custom device");
+        };
+        assert!(custom.behavior().is_none());
+        assert_eq!(custom.state(), [1]);
+
+        copy.queue_command(InteriorCommand::InteractAt { x, y });
+        copy.step(0.1, &config);
+        let device = copy.ship.devices.iter().find(|d| d.id == id).unwrap();
+        assert!(device.online);
+        let DeviceData::Custom(custom) = &device.data else {
+            panic!("expected a custom device");
+        };
+        assert_eq!(custom.state(), [2]);
+    }
+}
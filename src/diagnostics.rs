@@ -0,0 +1,296 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    interior::{
+        GasType, HIGH_CO2_PARTIAL_PRESSURE_KPA, LOW_O2_PARTIAL_PRESSURE_KPA,
+        LOW_PRESSURE_THRESHOLD_KPA,
+    },
+    BodyType, World,
+};
+
+/// Snapshot of world health from `World::diagnostics`. The fields are for
+/// dashboards and soak tests; `Display` renders a short text report.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostics {
+    pub tick: u64,
+    pub sim_time: f64,
+    pub bodies: BodyCounts,
+    pub total_mass_kg: f64,
+    /// Specific orbital energy (J/kg) across all bodies; `None` when the
+    /// world is empty.
+    pub orbital_energy: Option<EnergyStats>,
+    pub life_support: LifeSupportStatus,
+    pub power: PowerBalance,
+    pub alerts: Vec<Alert>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BodyCounts {
+    pub ships: usize,
+    pub asteroids: usize,
+    pub debris: usize,
+    pub missiles: usize,
+}
+
+impl BodyCounts {
+    pub fn total(&self) -> usize {
+        self.ships + self.asteroids + self.debris + self.missiles
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnergyStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+/// Conditions at the pawn's tile plus ship-wide gas totals.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LifeSupportStatus {
+    pub pressure_kpa: f32,
+    pub o2_partial_kpa: f32,
+    pub co2_partial_kpa: f32,
+    pub total_o2_kg: f32,
+    pub total_co2_kg: f32,
+    pub suffocation_time_s: f32,
+    /// Remaining hit points over maximum, across all body parts.
+    pub pawn_health: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerBalance {
+    pub generation_kw: f32,
+    pub load_kw: f32,
+    pub net_kw: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Alert {
+    PowerDeficit { net_kw: f32 },
+    LowPressure { kpa: f32 },
+    LowOxygen { partial_kpa: f32 },
+    HighCo2 { partial_kpa: f32 },
+    Suffocating { seconds: f32 },
+    PawnInjured { health: f32 },
+    SolarStorm { until: f64 },
+}
+
+impl Diagnostics {
+    pub(crate) fn collect(world: &World) -> Self {
+        let mut bodies = BodyCounts::default();
+        let mut total_mass_kg = 0.0;
+        let mut energy: Option<(f64, f64, f64, usize)> = None;
+        for body in &world.bodies {
+            match body.body_type {
+                BodyType::Ship => bodies.ships += 1,
+                BodyType::Asteroid => bodies.asteroids += 1,
+                BodyType::Debris => bodies.debris += 1,
+                BodyType::Missile => bodies.missiles += 1,
+            }
+            total_mass_kg += body.mass;
+            let r = body.position.length();
+            if r <= 0.0 {
+                continue;
+            }
+            let specific = 0.5 * body.velocity.length_squared() - world.mu / r;
+            energy = Some(match energy {
+                Some((min, max, sum, n)) => {
+                    (min.min(specific), max.max(specific), sum + specific, n + 1)
+                }
+                None => (specific, specific, specific, 1),
+            });
+        }
+        let orbital_energy = energy.map(|(min, max, sum, n)| EnergyStats {
+            min,
+            max,
+            mean: sum / n as f64,
+        });
+
+        let interior = &world.interior;
+        let atmos_cfg = &world.config.atmosphere;
+        let pawn = &interior.pawn;
+        let (pressure_kpa, o2_partial_kpa, co2_partial_kpa) =
+            match interior.ship.tile_atmos_cell(pawn.x, pawn.y) {
+                Some(cell) => (
+                    cell.pressure_kpa(atmos_cfg),
+                    cell.partial_pressure_kpa(GasType::O2, atmos_cfg),
+                    cell.partial_pressure_kpa(GasType::CO2, atmos_cfg),
+                ),
+                None => (0.0, 0.0, 0.0),
+            };
+        let totals = interior.ship.total_atmos();
+        let (hp, max_hp) = pawn
+            .health
+            .body_parts
+            .iter()
+            .fold((0.0, 0.0), |(hp, max), part| {
+                (hp + part.hp, max + part.max_hp)
+            });
+        let life_support = LifeSupportStatus {
+            pressure_kpa,
+            o2_partial_kpa,
+            co2_partial_kpa,
+            total_o2_kg: totals.o2_kg,
+            total_co2_kg: totals.co2_kg,
+            suffocation_time_s: pawn.suffocation_time,
+            pawn_health: if max_hp > 0.0 { hp / max_hp } else { 0.0 },
+        };
+        let power = PowerBalance {
+            generation_kw: interior.ship.power.total_production_kw,
+            load_kw: interior.ship.power.total_consumption_kw,
+            net_kw: interior.ship.power.net_kw,
+        };
+
+        let mut alerts = Vec::new();
+        if power.net_kw < 0.0 {
+            alerts.push(Alert::PowerDeficit {
+                net_kw: power.net_kw,
+            });
+        }
+        if pressure_kpa < LOW_PRESSURE_THRESHOLD_KPA {
+            alerts.push(Alert::LowPressure { kpa: pressure_kpa });
+        }
+        if o2_partial_kpa < LOW_O2_PARTIAL_PRESSURE_KPA {
+            alerts.push(Alert::LowOxygen {
+                partial_kpa: o2_partial_kpa,
+            });
+        }
+        if co2_partial_kpa > HIGH_CO2_PARTIAL_PRESSURE_KPA {
+            alerts.push(Alert::HighCo2 {
+                partial_kpa: co2_partial_kpa,
+            });
+        }
+        if pawn.suffocation_time > 0.0 {
+            alerts.push(Alert::Suffocating {
+                seconds: pawn.suffocation_time,
+            });
+        }
+        if life_support.pawn_health < 1.0 {
+            alerts.push(Alert::PawnInjured {
+                health: life_support.pawn_health,
+            });
+        }
+        if let Some(until) = world.timeline.solar_storm_until() {
+            alerts.push(Alert::SolarStorm { until });
+        }
+
+        Self {
+            tick: world.tick_count,
+            sim_time: world.sim_time().0,
+            bodies,
+            total_mass_kg,
+            orbital_energy,
+            life_support,
+            power,
+            alerts,
+        }
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Alert::PowerDeficit { net_kw } => write!(f, "power deficit ({:.1} kW)", net_kw),
+            Alert::LowPressure { kpa } => write!(f, "low pressure ({:.1} kPa)", kpa),
+            Alert::LowOxygen { partial_kpa } => write!(f, "low O2 ({:.1} kPa)", partial_kpa),
+            Alert::HighCo2 { partial_kpa } => write!(f, "high CO2 ({:.1} kPa)", partial_kpa),
+            Alert::Suffocating { seconds } => write!(f, "pawn suffocating for {:.0} s", seconds),
+            Alert::PawnInjured { health } => write!(f, "pawn at {:.0}% health", health * 100.0),
+            Alert::SolarStorm { until } => write!(f, "solar storm until t={:.0} s", until),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tick {} at t={:.1} s", self.tick, self.sim_time)?;
+        writeln!(
+            f,
+            "bodies: {} ({} ships, {} asteroids, {} debris, {} missiles), {:.0} kg",
+            self.bodies.total(),
+            self.bodies.ships,
+            self.bodies.asteroids,
+            self.bodies.debris,
+            self.bodies.missiles,
+            self.total_mass_kg
+        )?;
+        if let Some(energy) = &self.orbital_energy {
+            writeln!(
+                f,
+                "orbital energy: min {:.3e} / mean {:.3e} / max {:.3e} J/kg",
+                energy.min, energy.mean, energy.max
+            )?;
+        }
+        let ls = &self.life_support;
+        writeln!(
+            f,
+            "life support: {:.1} kPa, O2 {:.1} kPa, CO2 {:.2} kPa, pawn health {:.0}%",
+            ls.pressure_kpa,
+            ls.o2_partial_kpa,
+            ls.co2_partial_kpa,
+            ls.pawn_health * 100.0
+        )?;
+        writeln!(
+            f,
+            "power: +{:.1} kW / -{:.1} kW = {:.1} kW",
+            self.power.generation_kw, self.power.load_kw, self.power.net_kw
+        )?;
+        if self.alerts.is_empty() {
+            write!(f, "alerts: none")
+        } else {
+            write!(f, "alerts:")?;
+            for alert in &self.alerts {
+                write!(f, "\n  - {}", alert)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::GameConfig, scenario::ScenarioAction, BodyState, Seconds};
+    use alloc::string::ToString;
+
+    fn body(semi_major_axis: f64, body_type: BodyType) -> BodyState {
+        BodyState {
+            mass: 500.0,
+            body_type,
+            ..BodyState::circular(semi_major_axis, 0.0)
+        }
+    }
+
+    #[test]
+    fn diagnostics_summarise_bodies_and_alerts() {
+        let mu = 3.986004418e14;
+        let mut world = World::new(mu, GameConfig::default());
+        world.add_body(body(7_000_000.0, BodyType::Ship)).unwrap();
+        world.add_body(body(8_000_000.0, BodyType::Debris)).unwrap();
+        world
+            .timeline
+            .schedule(0.0, ScenarioAction::SolarStorm { duration_s: 60.0 });
+        world.step(Seconds(1.0));
+
+        let report = world.diagnostics();
+        assert_eq!(report.bodies.total(), 2);
+        assert_eq!(report.bodies.ships, 1);
+        assert_eq!(report.total_mass_kg, 1_000.0);
+        let energy = report.orbital_energy.unwrap();
+        assert!((energy.min + mu / (2.0 * 7_000_000.0)).abs() < 1.0);
+        assert!((energy.max + mu / (2.0 * 8_000_000.0)).abs() < 1.0);
+        assert!(report
+            .alerts
+            .iter()
+            .any(|alert| matches!(alert, Alert::SolarStorm { until } if *until == 60.0)));
+        assert!(report.to_string().contains("solar storm until t=60 s"));
+    }
+}
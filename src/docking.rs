@@ -0,0 +1,336 @@
+//! Ships docked together.
+//!
+//! Two ships whose hulls are within `config::DockingConfig::capture_range_m`
+//! and drifting relative to each other no faster than
+//! `max_relative_speed_mps` can be joined with `World::dock`. The second
+//! ship, the passenger, leaves `bodies` and rides along with the first, the
+//! host: the host takes on the passenger's mass and the pair's
+//! momentum-weighted velocity, so the compound flies one orbit and burns on
+//! the host move both. A host docking with another ship in turn brings its
+//! own passengers along.
+//!
+//! `World::undock` lets a passenger go where it sits on the host, the two
+//! pushed apart at `separation_speed_mps` shared out by mass so momentum is
+//! kept. While docked the passenger isn't found by `World::body`, but its
+//! other per-body state, like tags, sensors and propellant, is kept for when
+//! it leaves. Its planned burns are dropped, since it can no longer steer.
+//! If the host is destroyed or leaves the world, its passengers go with it.
+
+use alloc::vec::Vec;
+
+use crate::{
+    cartesian_to_orbit, error, BodyState, BodyType, DespawnReason, GgwError, Vec2, World,
+    WorldEvent,
+};
+
+/// A ship riding along with another.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DockedShip {
+    pub host: u64,
+    /// The passenger as it was when it docked.
+    pub body: BodyState,
+    /// Where the passenger sits relative to the host, in the host's frame,
+    /// so it turns with the host's `orientation`.
+    pub offset: Vec2,
+    /// Passenger's orientation relative to the host's.
+    pub orientation: f64,
+}
+
+impl World {
+    /// Dock `passenger_id` with `host_id`. Fails with `NotAShip` unless both
+    /// are ships, and with `DockingOutOfRange` if they are too far apart,
+    /// drifting too fast, or orbiting different primaries.
+    pub fn dock(&mut self, host_id: u64, passenger_id: u64) -> error::Result<()> {
+        let out_of_range = GgwError::DockingOutOfRange {
+            host: host_id,
+            passenger: passenger_id,
+        };
+        let host = self.body(host_id).ok_or(GgwError::UnknownBody(host_id))?;
+        let passenger = self
+            .body(passenger_id)
+            .ok_or(GgwError::UnknownBody(passenger_id))?;
+        for body in [host, passenger] {
+            if body.body_type != BodyType::Ship {
+                return Err(GgwError::NotAShip(body.id));
+            }
+        }
+        let config = &self.config.docking;
+        let gap = host.position.distance(passenger.position) - host.radius - passenger.radius;
+        let drift = host.velocity.distance(passenger.velocity);
+        let same_primary = host.parent == passenger.parent;
+        if host_id == passenger_id
+            || gap > config.capture_range_m
+            || drift > config.max_relative_speed_mps
+            || !same_primary
+        {
+            return Err(out_of_range);
+        }
+
+        let mass = host.mass + passenger.mass;
+        let velocity = (host.velocity * host.mass + passenger.velocity * passenger.mass) / mass;
+        let primary = self.primary_of(host);
+        // A landed host stays put on the surface.
+        let orbit = match self.landed.contains_key(&host_id) {
+            true => None,
+            false => Some(cartesian_to_orbit(
+                host.position - primary.position,
+                velocity - primary.velocity,
+                primary.mu,
+                self.sim_time().0,
+            )?),
+        };
+        let offset = (passenger.position - host.position).rotate(-host.orientation);
+        let orientation = passenger.orientation - host.orientation;
+
+        let index = self
+            .bodies
+            .iter()
+            .position(|body| body.id == passenger_id)
+            .ok_or(GgwError::UnknownBody(passenger_id))?;
+        let passenger = self.bodies.remove(index);
+        self.docked.insert(
+            passenger_id,
+            DockedShip {
+                host: host_id,
+                body: passenger,
+                offset,
+                orientation,
+            },
+        );
+        let host = self
+            .get_body_mut(host_id)
+            .ok_or(GgwError::UnknownBody(host_id))?;
+        host.mass = mass;
+        if let Some(orbit) = orbit {
+            host.orbit = orbit;
+            host.velocity = velocity;
+        }
+        self.landed.remove(&passenger_id);
+        self.attitude_targets.remove(&passenger_id);
+        self.autopilots.remove(&passenger_id);
+        self.ascents.remove(&passenger_id);
+        self.maneuver_nodes
+            .retain(|node| node.body_id != passenger_id);
+        self.scheduled_thrusts
+            .retain(|event| event.body_id != passenger_id);
+        self.burn_programs
+            .retain(|program| program.body_id != passenger_id);
+        for id in [host_id, passenger_id] {
+            self.kepler_cache.remove(&id);
+            self.sleep_tracker.wake(id);
+        }
+        self.events.push(WorldEvent::Docked {
+            time: self.sim_time().0,
+            host: host_id,
+            passenger: passenger_id,
+        });
+        tracing::debug!(host = host_id, passenger = passenger_id, "ships docked");
+        Ok(())
+    }
+
+    /// Let `passenger_id` go from `host_id`, where it sits on the host.
+    /// Fails with `NotDocked` unless it is docked with that host.
+    pub fn undock(&mut self, host_id: u64, passenger_id: u64) -> error::Result<()> {
+        let host = self.body(host_id).ok_or(GgwError::UnknownBody(host_id))?;
+        let docked = match self.docked.get(&passenger_id) {
+            Some(docked) if docked.host == host_id => docked,
+            _ => {
+                return Err(GgwError::NotDocked {
+                    host: host_id,
+                    passenger: passenger_id,
+                })
+            }
+        };
+        let offset = docked.offset.rotate(host.orientation);
+        let direction = match offset.length_squared() > 0.0 {
+            true => offset.normalized(),
+            false => Vec2::new(1.0, 0.0).rotate(host.orientation),
+        };
+        let passenger_mass = docked.body.mass;
+        let host_mass = (host.mass - passenger_mass).max(0.0);
+        let separation = self.config.docking.separation_speed_mps / host.mass;
+        let position = host.position + offset;
+        let passenger_velocity = host.velocity + direction * (separation * host_mass);
+        let host_velocity = host.velocity - direction * (separation * passenger_mass);
+        let primary = self.primary_of(host);
+        let now = self.sim_time().0;
+        let passenger_orbit = cartesian_to_orbit(
+            position - primary.position,
+            passenger_velocity - primary.velocity,
+            primary.mu,
+            now,
+        )?;
+        // A landed host stays put on the surface.
+        let host_orbit = match self.landed.contains_key(&host_id) {
+            true => None,
+            false => Some(cartesian_to_orbit(
+                host.position - primary.position,
+                host_velocity - primary.velocity,
+                primary.mu,
+                now,
+            )?),
+        };
+        let (parent, orientation, angular_velocity) =
+            (host.parent, host.orientation, host.angular_velocity);
+
+        let docked = self
+            .docked
+            .remove(&passenger_id)
+            .ok_or(GgwError::UnknownBody(passenger_id))?;
+        let mut passenger = docked.body;
+        passenger.orbit = passenger_orbit;
+        passenger.position = position;
+        passenger.velocity = passenger_velocity;
+        passenger.parent = parent;
+        passenger.orientation = orientation + docked.orientation;
+        passenger.angular_velocity = angular_velocity;
+        self.bodies.push(passenger);
+        let host = self
+            .get_body_mut(host_id)
+            .ok_or(GgwError::UnknownBody(host_id))?;
+        host.mass = host_mass;
+        if let Some(orbit) = host_orbit {
+            host.orbit = orbit;
+            host.velocity = host_velocity;
+        }
+        for id in [host_id, passenger_id] {
+            self.kepler_cache.remove(&id);
+            self.sleep_tracker.wake(id);
+            if let Some(state) = self.numeric_bodies.get_mut(&id) {
+                *state = None;
+            }
+        }
+        self.events.push(WorldEvent::Undocked {
+            time: now,
+            host: host_id,
+            passenger: passenger_id,
+        });
+        tracing::debug!(host = host_id, passenger = passenger_id, "ships undocked");
+        Ok(())
+    }
+
+    /// Host `passenger_id` is docked with, if it is docked.
+    pub fn docked_host(&self, passenger_id: u64) -> Option<u64> {
+        self.docked.get(&passenger_id).map(|docked| docked.host)
+    }
+
+    /// Ships docked with `host_id` directly, in id order.
+    pub fn passengers(&self, host_id: u64) -> impl Iterator<Item = u64> + '_ {
+        self.docked
+            .iter()
+            .filter(move |(_, docked)| docked.host == host_id)
+            .map(|(&id, _)| id)
+    }
+
+    /// Destroy every passenger of `host_id` along with it, for `reason`.
+    pub(crate) fn forget_passengers(&mut self, host_id: u64, reason: DespawnReason) {
+        let passengers: Vec<u64> = self.passengers(host_id).collect();
+        for id in passengers {
+            if let Some(docked) = self.docked.remove(&id) {
+                self.bodies.push(docked.body);
+                self.forget_body(id, reason);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{Seconds, DESPAWN_RADIUS_M};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn ship(mean_anomaly: f64, mass: f64) -> BodyState {
+        BodyState {
+            mass,
+            ..BodyState::circular(7_000_000.0, mean_anomaly)
+        }
+    }
+
+    #[test]
+    fn docked_ships_fly_together_and_part_again() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let host = world.add_body(ship(0.0, 3_000.0)).unwrap();
+        // 14 m along the orbit: a 4 m gap between the hulls.
+        let passenger = world.add_body(ship(2e-6, 1_000.0)).unwrap();
+        let far = world.add_body(ship(1e-3, 1_000.0)).unwrap();
+        let rock = world
+            .add_body(BodyState {
+                body_type: BodyType::Asteroid,
+                ..ship(1e-6, 1_000.0)
+            })
+            .unwrap();
+        assert_eq!(world.dock(host, rock), Err(GgwError::NotAShip(rock)));
+        assert_eq!(
+            world.dock(host, far),
+            Err(GgwError::DockingOutOfRange {
+                host,
+                passenger: far,
+            })
+        );
+        assert_eq!(
+            world.undock(host, passenger),
+            Err(GgwError::NotDocked { host, passenger })
+        );
+
+        world.dock(host, passenger).unwrap();
+        assert!(world.body(passenger).is_none());
+        assert_eq!(world.docked_host(passenger), Some(host));
+        assert_eq!(world.passengers(host).collect::<Vec<_>>(), [passenger]);
+        assert_eq!(world.body(host).unwrap().mass, 4_000.0);
+        assert!(matches!(
+            world.events().last(),
+            Some(WorldEvent::Docked { .. })
+        ));
+
+        world.step(Seconds(10.0));
+        world.undock(host, passenger).unwrap();
+        let (a, b) = (world.body(host).unwrap(), world.body(passenger).unwrap());
+        assert_eq!((a.mass, b.mass), (3_000.0, 1_000.0));
+        assert!((a.position.distance(b.position) - 14.0).abs() < 0.1);
+        // Pushed apart at the separation speed, keeping momentum.
+        let separation = world.config.docking.separation_speed_mps;
+        assert!((a.velocity.distance(b.velocity) - separation).abs() < 1e-6);
+        assert!(matches!(
+            world.events().last(),
+            Some(WorldEvent::Undocked { .. })
+        ));
+
+        // A destroyed host takes its passengers with it.
+        world.dock(host, passenger).unwrap();
+        world.remove_body(host);
+        assert_eq!(world.docked_host(passenger), None);
+        assert!(world.body(passenger).is_none());
+        let gone: Vec<u64> = world.drain_despawn_events().iter().map(|e| e.id).collect();
+        assert_eq!(gone, [passenger, host]);
+    }
+
+    #[test]
+    fn a_host_leaving_the_world_takes_its_passengers_along() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let host = world.add_body(ship(0.0, 3_000.0)).unwrap();
+        let passenger = world.add_body(ship(2e-6, 1_000.0)).unwrap();
+        world.dock(host, passenger).unwrap();
+        world.get_body_mut(host).unwrap().orbit.semi_major_axis = 2.0 * DESPAWN_RADIUS_M;
+
+        world.step(Seconds(1.0));
+        assert!(world.body(host).is_none());
+        assert_eq!(world.docked_host(passenger), None);
+        assert_eq!(world.passengers(host).count(), 0);
+        let gone: Vec<(u64, DespawnReason)> = world
+            .drain_despawn_events()
+            .iter()
+            .map(|event| (event.id, event.reason))
+            .collect();
+        assert_eq!(
+            gone,
+            [
+                (passenger, DespawnReason::LeftDespawnRadius),
+                (host, DespawnReason::LeftDespawnRadius),
+            ]
+        );
+    }
+}
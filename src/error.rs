@@ -0,0 +1,146 @@
+use alloc::string::String;
+use core::fmt;
+
+/// Errors surfaced at the library boundary instead of panicking.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GgwError {
+    /// Semi-major axis is not a positive finite number.
+    InvalidSemiMajorAxis(f64),
+    /// Eccentricity outside `[0, 1)`; only closed orbits are supported.
+    InvalidEccentricity(f64),
+    /// State vector with zero angular momentum (purely radial motion).
+    DegenerateOrbit,
+    /// State vector on an escape trajectory (non-negative orbital energy).
+    UnboundOrbit,
+    UnknownBody(u64),
+    /// Body id that is already in use, belonged to a removed body, or is
+    /// `u64::MAX`.
+    InvalidBodyId(u64),
+    /// A body's `parent` names no moon in `World::central_bodies`.
+    UnknownCentralBody(u64),
+    /// Body exists but is not a missile, so it cannot be given a target.
+    NotAMissile(u64),
+    /// Body exists but is not an asteroid, so it cannot be mined.
+    NotAnAsteroid(u64),
+    /// Body exists but is not a ship, so it cannot dock.
+    NotAShip(u64),
+    /// Ships too far apart or drifting too fast to dock, or the same ship
+    /// twice.
+    DockingOutOfRange {
+        host: u64,
+        passenger: u64,
+    },
+    /// `passenger` isn't docked with `host`.
+    NotDocked {
+        host: u64,
+        passenger: u64,
+    },
+    /// Maneuver node scheduled before the current sim time.
+    ManeuverInPast(f64),
+    /// No single-revolution prograde transfer joins two points in the time
+    /// given, or they are lined up through the primary.
+    NoTransfer,
+    /// Time span that starts in the past or ends before it starts, or a
+    /// step through it that isn't positive.
+    InvalidTimeRange,
+    /// Burn program whose duration isn't positive or whose acceleration
+    /// isn't finite.
+    InvalidBurn,
+    /// Touchdown faster than `CollisionConfig::max_landing_speed_mps`, at
+    /// the speed given.
+    LandingTooFast(f64),
+    /// Launch of a body that isn't resting on a surface.
+    NotLanded(u64),
+    /// Launch heading outside `(0, π/2]` or target apoapsis that isn't
+    /// above the surface.
+    InvalidLaunch,
+    /// Damage that is negative or not finite, or hit points that aren't
+    /// positive and finite.
+    InvalidDamage,
+    /// Asteroid field band that isn't above the surface with its inner
+    /// radius first, or sizes that aren't positive and in order.
+    InvalidAsteroidField,
+    /// Resource name missing from `config.resources`.
+    UnknownResource(String),
+    /// Asteroid composition with a share outside `[0, 1]` or shares adding
+    /// up to more than 1.
+    InvalidComposition,
+    /// Save written by a different version of the save format.
+    UnsupportedSaveVersion(u32),
+    /// Save whose config doesn't match the hash it was saved with.
+    SaveConfigMismatch,
+    /// Snapshot delta built on the snapshot with this sequence number,
+    /// which the client never applied.
+    MissedSnapshot(u64),
+    /// Replay that ended with a different state hash from the recorded run.
+    ReplayDiverged {
+        expected: u64,
+        actual: u64,
+    },
+    /// Burn that needs more propellant than the body has left.
+    InsufficientPropellant {
+        body_id: u64,
+        needed_kg: f64,
+        available_kg: f64,
+    },
+    Config(String),
+}
+
+impl fmt::Display for GgwError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GgwError::InvalidSemiMajorAxis(a) => write!(f, "invalid semi-major axis {}", a),
+            GgwError::InvalidEccentricity(e) => write!(f, "eccentricity {} out of range", e),
+            GgwError::DegenerateOrbit => write!(f, "degenerate orbit (zero angular momentum)"),
+            GgwError::UnboundOrbit => write!(f, "orbit is not bound"),
+            GgwError::UnknownBody(id) => write!(f, "no body with id {}", id),
+            GgwError::InvalidBodyId(id) => write!(f, "body id {} is not available", id),
+            GgwError::UnknownCentralBody(id) => write!(f, "no central body with id {}", id),
+            GgwError::NotAMissile(id) => write!(f, "body {} is not a missile", id),
+            GgwError::NotAnAsteroid(id) => write!(f, "body {} is not an asteroid", id),
+            GgwError::NotAShip(id) => write!(f, "body {} is not a ship", id),
+            GgwError::DockingOutOfRange { host, passenger } => {
+                write!(f, "ship {} is out of docking range of {}", passenger, host)
+            }
+            GgwError::NotDocked { host, passenger } => {
+                write!(f, "ship {} is not docked with {}", passenger, host)
+            }
+            GgwError::ManeuverInPast(time) => write!(f, "maneuver node at {} is in the past", time),
+            GgwError::NoTransfer => write!(f, "no transfer orbit fits"),
+            GgwError::InvalidTimeRange => write!(f, "invalid time range"),
+            GgwError::InvalidBurn => write!(f, "invalid burn program"),
+            GgwError::LandingTooFast(speed) => write!(f, "touched down too fast at {} m/s", speed),
+            GgwError::NotLanded(id) => write!(f, "body {} is not landed", id),
+            GgwError::InvalidLaunch => write!(f, "invalid launch"),
+            GgwError::InvalidDamage => write!(f, "invalid damage"),
+            GgwError::InvalidAsteroidField => write!(f, "invalid asteroid field"),
+            GgwError::UnknownResource(name) => write!(f, "no resource named {}", name),
+            GgwError::InvalidComposition => write!(f, "invalid asteroid composition"),
+            GgwError::UnsupportedSaveVersion(version) => {
+                write!(f, "unsupported save version {}", version)
+            }
+            GgwError::SaveConfigMismatch => write!(f, "save config does not match its hash"),
+            GgwError::MissedSnapshot(seq) => write!(f, "missed snapshot {}", seq),
+            GgwError::ReplayDiverged { expected, actual } => write!(
+                f,
+                "replay diverged: state hash {:#x}, recorded {:#x}",
+                actual, expected
+            ),
+            GgwError::InsufficientPropellant {
+                body_id,
+                needed_kg,
+                available_kg,
+            } => write!(
+                f,
+                "body {} needs {} kg of propellant but has {} kg",
+                body_id, needed_kg, available_kg
+            ),
+            GgwError::Config(message) => write!(f, "config error: {}", message),
+        }
+    }
+}
+
+impl core::error::Error for GgwError {}
+
+pub type Result<T> = core::result::Result<T, GgwError>;
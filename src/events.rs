@@ -0,0 +1,154 @@
+//! One feed of everything that happens to the world.
+//!
+//! `World` records a `WorldEvent` as each change takes effect, whether
+//! during `step` or from a call like `apply_thrust_event`, and keeps them
+//! in order until `World::drain_events`. Clients can follow the feed
+//! instead of diffing snapshots to work out what changed. Hand-overs and
+//! despawns are still kept in their own queues as well, so draining one
+//! doesn't take them from the other.
+//!
+//! Collisions are only found when asked for with `detect_collisions`, so
+//! they reach the feed once resolved with `World::resolve_collision`.
+//! Fragmenting shows up as the two bodies despawning and their debris
+//! spawning.
+
+use alloc::vec::Vec;
+
+use crate::collision::CollisionOutcome;
+use crate::comms::Message;
+use crate::conjunction::Conjunction;
+use crate::preview::SoiEvent;
+use crate::{BodyDespawned, CollisionEvent, GgwError, ThrustEvent, World};
+
+/// Something that happened in the world.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WorldEvent {
+    /// Body `id` joined the world, by `add_body`, a scenario, a builder, an
+    /// asteroid field or a collision's debris.
+    BodySpawned {
+        time: f64,
+        id: u64,
+    },
+    Despawned(BodyDespawned),
+    SoiChanged(SoiEvent),
+    /// An impulse from `apply_thrust_event` or a maneuver node came due,
+    /// as burnt: cut short if the body ran low on propellant.
+    ThrustApplied(ThrustEvent),
+    /// A burn `apply_thrust_event` turned down, leaving the body as it was:
+    /// say one that would leave it on an escape trajectory or falling
+    /// straight down, or that it hasn't the propellant for.
+    ThrustRejected {
+        event: ThrustEvent,
+        error: GgwError,
+    },
+    Collision {
+        event: CollisionEvent,
+        outcome: CollisionOutcome,
+    },
+    /// Set down at `longitude` by `World::attempt_landing`.
+    Landed {
+        time: f64,
+        body_id: u64,
+        longitude: f64,
+    },
+    /// `amount` taken off `body_id`'s hit points, leaving `hp`; see
+    /// `damage`.
+    Damaged {
+        time: f64,
+        body_id: u64,
+        amount: f64,
+        hp: f64,
+    },
+    /// Lifted off by `World::launch`.
+    Launched {
+        time: f64,
+        body_id: u64,
+    },
+    /// Circularized at the top of an ascent from `World::launch`.
+    ReachedOrbit {
+        time: f64,
+        body_id: u64,
+    },
+    /// Two bodies, at least one of them tracked, will pass close; see
+    /// `conjunction`.
+    ConjunctionWarning(Conjunction),
+    /// `passenger` docked with `host` by `World::dock`.
+    Docked {
+        time: f64,
+        host: u64,
+        passenger: u64,
+    },
+    /// `passenger` let go of `host` by `World::undock`.
+    Undocked {
+        time: f64,
+        host: u64,
+        passenger: u64,
+    },
+    /// A message from `World::send_message` reached its recipient; see
+    /// `comms`.
+    MessageDelivered(Message),
+}
+
+impl World {
+    /// Everything recorded since the last `drain_events`, oldest first.
+    pub fn events(&self) -> &[WorldEvent] {
+        &self.events
+    }
+
+    /// Take everything recorded since the last call, oldest first.
+    pub fn drain_events(&mut self) -> Vec<WorldEvent> {
+        core::mem::take(&mut self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{BodyState, DespawnReason, Seconds, ThrustType, Vec2, DESPAWN_RADIUS_M};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn events_come_out_in_the_order_they_happened() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let other = world
+            .add_body(BodyState::circular(7_000_000.0, 1.0))
+            .unwrap();
+        world.step(Seconds(1.0));
+        let thrust = ThrustEvent {
+            body_id: ship,
+            time: world.sim_time().0,
+            delta_v: Vec2::new(0.0, 1.0),
+            thrust_type: ThrustType::Chemical,
+        };
+        world.apply_thrust_event(&thrust).unwrap();
+        world.get_body_mut(other).unwrap().position = Vec2::new(DESPAWN_RADIUS_M * 2.0, 0.0);
+        world.cull_despawned_bodies();
+
+        let despawned = world.despawn_events()[0];
+        assert_eq!(despawned.reason, DespawnReason::LeftDespawnRadius);
+        assert_eq!(
+            world.drain_events(),
+            [
+                WorldEvent::BodySpawned {
+                    time: 0.0,
+                    id: ship
+                },
+                WorldEvent::BodySpawned {
+                    time: 0.0,
+                    id: other
+                },
+                WorldEvent::ThrustApplied(thrust),
+                WorldEvent::Despawned(despawned),
+            ]
+        );
+        assert!(world.events().is_empty());
+        // The despawn queue is drained separately.
+        assert_eq!(world.drain_despawn_events(), [despawned]);
+    }
+}
@@ -0,0 +1,352 @@
+//! `extern "C"` API for embedding the simulation in C, C++ or C# engines.
+//! The world lives behind an opaque `GgwWorld` pointer; every call returns a
+//! `GgwStatus` and writes results through caller-provided pointers. The C
+//! header is checked in as `include/ggw_world.h`; after changing this API,
+//! regenerate it with
+//! `cbindgen --config cbindgen.toml --output include/ggw_world.h`. Build the
+//! shared library with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+
+use alloc::collections::BTreeMap;
+use core::ptr;
+use core::slice;
+
+use crate::{
+    config::GameConfig, BodyState, BodyType, GgwError, OrbitState, Seconds, ThrustEvent,
+    ThrustType, Vec2, World,
+};
+
+/// Opaque handle owned by the caller between `ggw_world_new` and
+/// `ggw_world_free`.
+pub struct GgwWorld {
+    inner: World,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GgwStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidBodyType = 2,
+    InvalidOrbit = 3,
+    UnknownBody = 4,
+    /// The output buffer is too short; the required length was written to
+    /// `out_len`.
+    BufferTooSmall = 5,
+    /// The requested body id is taken, was used by a removed body, or is
+    /// `u64::MAX`.
+    InvalidBodyId = 6,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GgwOrbit {
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub arg_of_periapsis: f64,
+    pub mean_anomaly_at_epoch: f64,
+    pub epoch: f64,
+}
+
+/// Flattened body snapshot. `body_type` uses the same codes as the wasm
+/// bindings: 0 ship, 1 asteroid, 2 debris, 3 missile.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GgwBodyState {
+    pub id: u64,
+    pub body_type: u8,
+    pub mass: f64,
+    pub radius: f64,
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+}
+
+impl From<&BodyState> for GgwBodyState {
+    fn from(body: &BodyState) -> Self {
+        Self {
+            id: body.id,
+            body_type: body_type_code(body.body_type),
+            mass: body.mass,
+            radius: body.radius,
+            x: body.position.x,
+            y: body.position.y,
+            vx: body.velocity.x,
+            vy: body.velocity.y,
+        }
+    }
+}
+
+impl From<&GgwOrbit> for OrbitState {
+    fn from(orbit: &GgwOrbit) -> Self {
+        Self {
+            semi_major_axis: orbit.semi_major_axis,
+            eccentricity: orbit.eccentricity,
+            arg_of_periapsis: orbit.arg_of_periapsis,
+            mean_anomaly_at_epoch: orbit.mean_anomaly_at_epoch,
+            epoch: orbit.epoch,
+        }
+    }
+}
+
+impl From<GgwError> for GgwStatus {
+    fn from(err: GgwError) -> Self {
+        match err {
+            GgwError::UnknownBody(_) | GgwError::UnknownCentralBody(_) => GgwStatus::UnknownBody,
+            GgwError::InvalidBodyId(_) => GgwStatus::InvalidBodyId,
+            _ => GgwStatus::InvalidOrbit,
+        }
+    }
+}
+
+/// Create a world around a planet with gravitational parameter `mu`, using
+/// the default game config. Release it with `ggw_world_free`.
+#[no_mangle]
+pub extern "C" fn ggw_world_new(mu: f64) -> *mut GgwWorld {
+    Box::into_raw(Box::new(GgwWorld {
+        inner: World::new(mu, GameConfig::default()),
+    }))
+}
+
+/// # Safety
+/// `world` must be null or a pointer returned by `ggw_world_new` that has
+/// not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ggw_world_free(world: *mut GgwWorld) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+/// # Safety
+/// `world` must be a live handle from `ggw_world_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ggw_world_step(world: *mut GgwWorld, dt: f64) -> GgwStatus {
+    let Some(world) = world.as_mut() else {
+        return GgwStatus::NullPointer;
+    };
+    world.inner.step(Seconds(dt));
+    GgwStatus::Ok
+}
+
+/// # Safety
+/// `world` must be a live handle from `ggw_world_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ggw_world_warp_to(world: *mut GgwWorld, target_time: f64) -> GgwStatus {
+    let Some(world) = world.as_mut() else {
+        return GgwStatus::NullPointer;
+    };
+    world.inner.warp_to(Seconds(target_time));
+    GgwStatus::Ok
+}
+
+/// Current simulation time in seconds, or NaN for a null handle.
+///
+/// # Safety
+/// `world` must be null or a live handle from `ggw_world_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ggw_world_sim_time(world: *const GgwWorld) -> f64 {
+    world
+        .as_ref()
+        .map_or(f64::NAN, |world| world.inner.sim_time().0)
+}
+
+/// # Safety
+/// `world` must be null or a live handle from `ggw_world_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ggw_world_state_hash(world: *const GgwWorld) -> u64 {
+    world.as_ref().map_or(0, |world| world.inner.state_hash())
+}
+
+/// Spawn a body on `orbit`. `id` 0 lets the world assign one; the id used
+/// is written to `out_id` when it is non-null.
+///
+/// # Safety
+/// `world` must be a live handle, `orbit` must point to a valid
+/// `GgwOrbit`, and `out_id` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn ggw_world_spawn_body(
+    world: *mut GgwWorld,
+    id: u64,
+    body_type: u8,
+    mass: f64,
+    radius: f64,
+    orbit: *const GgwOrbit,
+    out_id: *mut u64,
+) -> GgwStatus {
+    let (Some(world), Some(orbit)) = (world.as_mut(), orbit.as_ref()) else {
+        return GgwStatus::NullPointer;
+    };
+    let Some(body_type) = body_type_from_code(body_type) else {
+        return GgwStatus::InvalidBodyType;
+    };
+    let body = BodyState {
+        id,
+        mass,
+        radius,
+        orbit: orbit.into(),
+        position: Vec2::zero(),
+        velocity: Vec2::zero(),
+        body_type,
+        hull_shape: None,
+        parent: None,
+        orientation: 0.0,
+        angular_velocity: 0.0,
+        tags: BTreeMap::new(),
+    };
+    match world.inner.add_body(body) {
+        Ok(id) => {
+            if !out_id.is_null() {
+                *out_id = id;
+            }
+            GgwStatus::Ok
+        }
+        Err(err) => err.into(),
+    }
+}
+
+/// # Safety
+/// `world` must be a live handle from `ggw_world_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ggw_world_remove_body(world: *mut GgwWorld, id: u64) -> GgwStatus {
+    let Some(world) = world.as_mut() else {
+        return GgwStatus::NullPointer;
+    };
+    match world.inner.remove_body(id) {
+        Some(_) => GgwStatus::Ok,
+        None => GgwStatus::UnknownBody,
+    }
+}
+
+/// Apply an instantaneous chemical burn to `body_id`.
+///
+/// # Safety
+/// `world` must be a live handle from `ggw_world_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ggw_world_apply_thrust(
+    world: *mut GgwWorld,
+    body_id: u64,
+    time: f64,
+    delta_v_x: f64,
+    delta_v_y: f64,
+) -> GgwStatus {
+    let Some(world) = world.as_mut() else {
+        return GgwStatus::NullPointer;
+    };
+    let event = ThrustEvent {
+        body_id,
+        time,
+        delta_v: Vec2::new(delta_v_x, delta_v_y),
+        thrust_type: ThrustType::Chemical,
+    };
+    match world.inner.apply_thrust_event(&event) {
+        Ok(()) => GgwStatus::Ok,
+        Err(err) => err.into(),
+    }
+}
+
+/// Copy every body into `out`, which holds `capacity` entries. The number
+/// of bodies is always written to `out_len`; if it exceeds `capacity`
+/// nothing is copied and `BufferTooSmall` is returned, so callers can size
+/// the buffer with a first call passing `capacity` 0.
+///
+/// # Safety
+/// `world` must be a live handle, `out_len` must be writable, and `out`
+/// must be valid for `capacity` writes (it may be null when `capacity` is
+/// 0).
+#[no_mangle]
+pub unsafe extern "C" fn ggw_world_body_states(
+    world: *const GgwWorld,
+    out: *mut GgwBodyState,
+    capacity: usize,
+    out_len: *mut usize,
+) -> GgwStatus {
+    let Some(world) = world.as_ref() else {
+        return GgwStatus::NullPointer;
+    };
+    if out_len.is_null() || (out.is_null() && capacity > 0) {
+        return GgwStatus::NullPointer;
+    }
+    let bodies = &world.inner.bodies;
+    ptr::write(out_len, bodies.len());
+    if bodies.len() > capacity {
+        return GgwStatus::BufferTooSmall;
+    }
+    if bodies.is_empty() {
+        return GgwStatus::Ok;
+    }
+    let out = slice::from_raw_parts_mut(out, bodies.len());
+    for (slot, body) in out.iter_mut().zip(bodies) {
+        *slot = body.into();
+    }
+    GgwStatus::Ok
+}
+
+fn body_type_code(body_type: BodyType) -> u8 {
+    match body_type {
+        BodyType::Ship => 0,
+        BodyType::Asteroid => 1,
+        BodyType::Debris => 2,
+        BodyType::Missile => 3,
+    }
+}
+
+fn body_type_from_code(code: u8) -> Option<BodyType> {
+    match code {
+        0 => Some(BodyType::Ship),
+        1 => Some(BodyType::Asteroid),
+        2 => Some(BodyType::Debris),
+        3 => Some(BodyType::Missile),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_step_and_query_through_handle() {
+        unsafe {
+            let world = ggw_world_new(3.986004418e14);
+            let orbit = GgwOrbit {
+                semi_major_axis: 7_000_000.0,
+                eccentricity: 0.01,
+                arg_of_periapsis: 0.0,
+                mean_anomaly_at_epoch: 0.0,
+                epoch: 0.0,
+            };
+            let mut id = 0;
+            assert_eq!(
+                ggw_world_spawn_body(world, 0, 1, 1_000.0, 5.0, &orbit, &mut id),
+                GgwStatus::Ok
+            );
+            assert_eq!(ggw_world_step(world, 1.0), GgwStatus::Ok);
+            assert_eq!(ggw_world_sim_time(world), 1.0);
+
+            let mut len = 0;
+            assert_eq!(
+                ggw_world_body_states(world, ptr::null_mut(), 0, &mut len),
+                GgwStatus::BufferTooSmall
+            );
+            assert_eq!(len, 1);
+            let mut states = vec![GgwBodyState::default(); len];
+            assert_eq!(
+                ggw_world_body_states(world, states.as_mut_ptr(), states.len(), &mut len),
+                GgwStatus::Ok
+            );
+            assert_eq!(states[0].id, id);
+            assert_eq!(states[0].body_type, 1);
+
+            assert_eq!(
+                ggw_world_apply_thrust(world, id + 1, 1.0, 0.0, 1.0),
+                GgwStatus::UnknownBody
+            );
+            assert_eq!(ggw_world_remove_body(world, id), GgwStatus::Ok);
+            assert_eq!((*world).inner.despawn_events()[0].id, id);
+            assert_eq!(ggw_world_remove_body(world, id), GgwStatus::UnknownBody);
+            ggw_world_free(world);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Procedural asteroid fields.
+//!
+//! `World::spawn_asteroid_field` fills a ring around the planet with
+//! asteroids on orbits that stay inside the ring, each with its own convex
+//! hull, tumble, size and mix of resources (see `mining`). Everything is drawn from the seed passed in, so
+//! the same call on the same world always spawns the same field, and
+//! lockstep peers that make it agree without sending the bodies over.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::f64::consts::PI;
+
+use crate::config::ResourceConfig;
+use crate::hull::convex_hull;
+use crate::lockstep::StateHasher;
+use crate::math::Float;
+use crate::{
+    error, mining, BodyState, BodyType, GgwError, HullShape, Meters, OrbitState, Vec2, World,
+};
+
+/// Richest an asteroid gets, as a multiple of each resource's
+/// `abundance`.
+const MAX_RICHNESS: f64 = 2.0;
+/// Fastest an asteroid is set tumbling, either way.
+const MAX_TUMBLE_RAD_S: f64 = 0.05;
+/// Fewest and most corners on a hull before it is made convex.
+const HULL_CORNERS: (usize, usize) = (5, 9);
+
+/// How asteroid sizes, as hull radii, are spread.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SizeDistribution {
+    /// Every size from `min_m` to `max_m` equally likely.
+    Uniform { min_m: f64, max_m: f64 },
+    /// Sizes from `min_m` to `max_m` where the number bigger than `r`
+    /// falls off as `r^-exponent`, like a real belt: plenty of small rocks
+    /// and a few big ones.
+    PowerLaw {
+        min_m: f64,
+        max_m: f64,
+        exponent: f64,
+    },
+}
+
+impl SizeDistribution {
+    fn is_valid(&self) -> bool {
+        let (min_m, max_m) = match *self {
+            SizeDistribution::Uniform { min_m, max_m } => (min_m, max_m),
+            SizeDistribution::PowerLaw {
+                min_m,
+                max_m,
+                exponent,
+            } => {
+                if !(exponent.is_finite() && exponent > 0.0) {
+                    return false;
+                }
+                (min_m, max_m)
+            }
+        };
+        min_m > 0.0 && max_m.is_finite() && min_m <= max_m
+    }
+
+    /// The size at `u` in `[0, 1)` along the distribution.
+    fn sample(&self, u: f64) -> f64 {
+        match *self {
+            SizeDistribution::Uniform { min_m, max_m } => min_m + (max_m - min_m) * u,
+            SizeDistribution::PowerLaw {
+                min_m,
+                max_m,
+                exponent,
+            } => {
+                let (low, high) = (Float::powf(min_m, -exponent), Float::powf(max_m, -exponent));
+                Float::powf(low - u * (low - high), -1.0 / exponent)
+            }
+        }
+    }
+}
+
+/// Numbers in `[0, 1)` drawn one after another from a seed.
+struct Draws {
+    seed: u64,
+    index: u64,
+}
+
+impl Draws {
+    fn next(&mut self) -> f64 {
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(self.seed);
+        hasher.write_u64(self.index);
+        self.index += 1;
+        (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn between(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next()
+    }
+}
+
+/// One asteroid from `draws`, on an orbit between `inner` and `outer` from
+/// the planet's centre, and what it is made of.
+fn asteroid(
+    draws: &mut Draws,
+    inner: f64,
+    outer: f64,
+    sizes: &SizeDistribution,
+    resources: &BTreeMap<String, ResourceConfig>,
+) -> (BodyState, BTreeMap<String, f64>) {
+    let semi_major_axis = draws.between(inner, outer);
+    // Periapsis and apoapsis both stay in the ring.
+    let max_eccentricity = ((semi_major_axis - inner) / semi_major_axis)
+        .min((outer - semi_major_axis) / semi_major_axis);
+    let orbit = OrbitState {
+        semi_major_axis,
+        eccentricity: draws.next() * max_eccentricity,
+        arg_of_periapsis: draws.between(-PI, PI),
+        mean_anomaly_at_epoch: draws.between(-PI, PI),
+        epoch: 0.0,
+    };
+
+    let size = sizes.sample(draws.next());
+    let (fewest, most) = HULL_CORNERS;
+    let corners = fewest + (draws.next() * (most - fewest + 1) as f64) as usize;
+    let spacing = 2.0 * PI / corners as f64;
+    let outline: Vec<Vec2> = (0..corners)
+        .map(|corner| {
+            let angle = spacing * (corner as f64 + draws.between(-0.3, 0.3));
+            Vec2::new(1.0, 0.0).rotate(angle) * (size * draws.between(0.6, 1.0))
+        })
+        .collect();
+    let hull_shape = HullShape {
+        vertices: convex_hull(&outline),
+    };
+    let radius = hull_shape.bounding_radius();
+
+    let mut composition: BTreeMap<String, f64> = mining::typical_composition(resources)
+        .into_iter()
+        .map(|(name, share)| (name, share * draws.between(0.0, MAX_RICHNESS)))
+        .collect();
+    let total: f64 = composition.values().sum();
+    if total > 1.0 {
+        composition.values_mut().for_each(|share| *share /= total);
+    }
+    let density = mining::bulk_density(&composition, resources);
+
+    let body = BodyState {
+        id: 0,
+        mass: density * 4.0 / 3.0 * PI * radius * radius * radius,
+        radius,
+        orbit,
+        position: Vec2::zero(),
+        velocity: Vec2::zero(),
+        body_type: BodyType::Asteroid,
+        hull_shape: Some(hull_shape),
+        parent: None,
+        orientation: draws.between(-PI, PI),
+        angular_velocity: draws.between(-MAX_TUMBLE_RAD_S, MAX_TUMBLE_RAD_S),
+        tags: BTreeMap::new(),
+    };
+    (body, composition)
+}
+
+impl World {
+    /// Add `count` asteroids orbiting the planet between the two radii of
+    /// `radius_band`, measured from its centre, with sizes spread by
+    /// `size_distribution`, returning their ids. The field depends only on
+    /// `seed` and the arguments, not on the world's own seed. Fails with
+    /// `InvalidAsteroidField` if the band isn't above the surface and in
+    /// order or the sizes don't make sense, in which case nothing is added.
+    pub fn spawn_asteroid_field(
+        &mut self,
+        seed: u64,
+        count: usize,
+        radius_band: (Meters, Meters),
+        size_distribution: SizeDistribution,
+    ) -> error::Result<Vec<u64>> {
+        let (Meters(inner), Meters(outer)) = radius_band;
+        let band_is_valid = inner > self.planet_radius && outer.is_finite() && inner <= outer;
+        if !band_is_valid || !size_distribution.is_valid() {
+            return Err(GgwError::InvalidAsteroidField);
+        }
+        let mut draws = Draws { seed, index: 0 };
+        let resources = &self.config.resources;
+        let asteroids: Vec<_> = (0..count)
+            .map(|_| asteroid(&mut draws, inner, outer, &size_distribution, resources))
+            .collect();
+        tracing::debug!(seed, count, inner, outer, "spawning asteroid field");
+        asteroids
+            .into_iter()
+            .map(|(asteroid, composition)| {
+                let id = self.add_body(asteroid)?;
+                self.compositions.insert(id, composition);
+                Ok(id)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::PLANET_RADIUS_M;
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn fields_are_reproducible_and_stay_in_their_ring() {
+        let inner = PLANET_RADIUS_M + 2_000_000.0;
+        let outer = PLANET_RADIUS_M + 2_500_000.0;
+        let band = (Meters(inner), Meters(outer));
+        let sizes = SizeDistribution::PowerLaw {
+            min_m: 10.0,
+            max_m: 500.0,
+            exponent: 2.5,
+        };
+        let spawn = |seed| {
+            let mut world = World::new(MU_EARTH, GameConfig::default());
+            let ids = world.spawn_asteroid_field(seed, 40, band, sizes).unwrap();
+            assert_eq!(ids.len(), 40);
+            world
+        };
+
+        let world = spawn(7);
+        assert_eq!(world.state_hash(), spawn(7).state_hash());
+        assert_ne!(world.state_hash(), spawn(8).state_hash());
+        let mut small = 0;
+        for body in &world.bodies {
+            assert_eq!(body.body_type, BodyType::Asteroid);
+            let mix = world.composition(body.id).unwrap();
+            assert!(mix["iron_ore"] > 0.0 && mix.values().sum::<f64>() <= 1.0);
+            let orbit = &body.orbit;
+            assert!(orbit.semi_major_axis * (1.0 - orbit.eccentricity) >= inner - 1e-6);
+            assert!(orbit.semi_major_axis * (1.0 + orbit.eccentricity) <= outer + 1e-6);
+            assert!(body.radius > 0.6 * 10.0 - 1e-9 && body.radius <= 500.0 + 1e-9);
+            let hull = &body.hull_shape.as_ref().unwrap().vertices;
+            assert!(hull.len() >= 3 && hull.len() <= HULL_CORNERS.1);
+            assert_eq!(convex_hull(hull).len(), hull.len());
+            if body.radius < 50.0 {
+                small += 1;
+            }
+        }
+        // The power law leaves most of the field small.
+        assert!(small > 20, "{small}");
+    }
+
+    #[test]
+    fn bad_bands_and_sizes_add_nothing() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let sizes = SizeDistribution::Uniform {
+            min_m: 10.0,
+            max_m: 20.0,
+        };
+        let low = (Meters(1_000.0), Meters(PLANET_RADIUS_M + 1e6));
+        let backwards = (Meters(PLANET_RADIUS_M + 2e6), Meters(PLANET_RADIUS_M + 1e6));
+        let good = (Meters(PLANET_RADIUS_M + 1e6), Meters(PLANET_RADIUS_M + 2e6));
+        for (band, sizes) in [
+            (low, sizes),
+            (backwards, sizes),
+            (
+                good,
+                SizeDistribution::Uniform {
+                    min_m: 0.0,
+                    max_m: 20.0,
+                },
+            ),
+            (
+                good,
+                SizeDistribution::PowerLaw {
+                    min_m: 1.0,
+                    max_m: 20.0,
+                    exponent: 0.0,
+                },
+            ),
+        ] {
+            assert_eq!(
+                world.spawn_asteroid_field(1, 5, band, sizes),
+                Err(GgwError::InvalidAsteroidField)
+            );
+        }
+        assert!(world.bodies.is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+//! Gravity-assist planning.
+//!
+//! `World::plan_flybys` looks for slingshots past a moon orbiting the same
+//! primary as a body. For departure times spread over a window it finds the
+//! cheapest transfer that reaches the moon, by `transfer::lambert` as
+//! `World::compute_intercept` does, and swings the arrival past the moon
+//! on either side at `config::FlybyConfig::min_altitude_m`, the closest
+//! and so the hardest turn. Each flyby that leaves on a closed prograde
+//! orbit is a `FlybyCandidate`, with that orbit to plan the next leg from.
+//!
+//! The encounter is patched-conic: the moon turns the velocity relative to
+//! it without changing its size, all at once at the moon's centre. That is
+//! close enough to choose a window by, but the burn a candidate suggests
+//! will want correcting on the way, as the moon's pull bends the approach.
+
+use alloc::vec::Vec;
+
+use crate::math::Float;
+use crate::{
+    cartesian_to_orbit, error, maneuver, orbit_to_cartesian, transfer, GgwError, OrbitState,
+    Seconds, ThrustEvent, ThrustType, Vec2, World,
+};
+
+/// Departure times tried across the window given `World::plan_flybys`.
+pub const FLYBY_DEPARTURE_STEPS: usize = 32;
+
+/// A slingshot past a moon, and the burn that sets it up.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlybyCandidate {
+    /// Burn onto the transfer to the moon; schedule it or plan it as a
+    /// node to fly it.
+    pub departure: ThrustEvent,
+    /// When the body reaches the moon.
+    pub encounter_time: f64,
+    /// Speed relative to the moon, the same before and after.
+    pub v_infinity: f64,
+    /// Whether the body swings round the moon anticlockwise, turning its
+    /// velocity relative to the moon that way, or clockwise.
+    pub anticlockwise: bool,
+    /// Orbit around the primary once the moon is left behind.
+    pub orbit_after: OrbitState,
+    /// Burn at the encounter that would have left on `orbit_after` without
+    /// the moon's help.
+    pub delta_v_saved: f64,
+}
+
+/// Velocity relative to a moon of `mu` after passing it at
+/// `periapsis_radius` from its centre, arriving at `v_infinity`, turned
+/// anticlockwise or not.
+pub fn flyby_turn(v_infinity: Vec2, periapsis_radius: f64, mu: f64, anticlockwise: bool) -> Vec2 {
+    let speed_sq = v_infinity.length_squared();
+    // Sine of half the angle between the hyperbola's asymptotes.
+    let sin_half = 1.0 / (1.0 + periapsis_radius * speed_sq / mu);
+    let cos_half = Float::sqrt((1.0 - sin_half * sin_half).max(0.0));
+    let turn = 2.0 * Float::atan2(sin_half, cos_half);
+    v_infinity.rotate(if anticlockwise { turn } else { -turn })
+}
+
+impl World {
+    /// Slingshots for `body_id` past `moon_id` departing within `window`
+    /// seconds of now, in departure order, skipping departures dearer than
+    /// `max_delta_v`. Transfers take at most half the moon's period. Fails
+    /// with `UnknownCentralBody` if there is no such moon, and with
+    /// `NoTransfer` if it doesn't orbit the body's primary.
+    pub fn plan_flybys(
+        &self,
+        body_id: u64,
+        moon_id: u64,
+        window: Seconds,
+        max_delta_v: f64,
+    ) -> error::Result<Vec<FlybyCandidate>> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        let moon = self
+            .central_body(moon_id)
+            .ok_or(GgwError::UnknownCentralBody(moon_id))?;
+        if moon.parent != body.parent {
+            return Err(GgwError::NoTransfer);
+        }
+        let primary = self.primary_of(body);
+        let mu = primary.mu;
+        let orbit = self.flight_orbit(body)?;
+        let max_tof = 0.5 * moon.orbit.period(mu);
+        let periapsis_radius = moon.radius + self.config.flyby.min_altitude_m.max(0.0);
+        // Moon's state relative to the body's primary.
+        let moon_at = |time: f64| {
+            let primaries = self.primaries_at(time);
+            let moon = primaries.find(Some(moon_id))?;
+            let primary = primaries.get(body.parent);
+            Some((
+                moon.position - primary.position,
+                moon.velocity - primary.velocity,
+            ))
+        };
+
+        let step = window.0.max(0.0) / FLYBY_DEPARTURE_STEPS as f64;
+        let mut candidates = Vec::new();
+        for i in 0..FLYBY_DEPARTURE_STEPS {
+            let departure_time = self.sim_time().0 + step * i as f64;
+            let (r1, velocity) = orbit_to_cartesian(&orbit, mu, departure_time)?;
+            // Departure and arrival velocities of the transfer taking
+            // `tof`, if it stays clear of the primary's surface.
+            let transfer = |tof: f64| {
+                let (r2, _) = moon_at(departure_time + tof)?;
+                let (v1, v2) = transfer::lambert(r1, r2, tof, mu).ok()?;
+                let after =
+                    maneuver::orbit_after_burn(&orbit, mu, departure_time, v1 - velocity).ok()?;
+                let clear = after.periapsis() > primary.radius
+                    || after.time_to_periapsis(mu, Seconds(departure_time)).0 >= tof;
+                clear.then_some((v1, v2))
+            };
+            let cost =
+                |tof| transfer(tof).map_or(f64::INFINITY, |(v1, _)| (v1 - velocity).length());
+            let Some(tof) = transfer::cheapest_flight_time(max_tof, cost) else {
+                continue;
+            };
+            let Some((v1, v2)) = transfer(tof) else {
+                continue;
+            };
+            if (v1 - velocity).length() > max_delta_v {
+                continue;
+            }
+            let encounter_time = departure_time + tof;
+            let Some((r2, moon_velocity)) = moon_at(encounter_time) else {
+                continue;
+            };
+            let v_infinity = v2 - moon_velocity;
+            for anticlockwise in [true, false] {
+                let turned = flyby_turn(v_infinity, periapsis_radius, moon.mu, anticlockwise);
+                let v_out = moon_velocity + turned;
+                // `OrbitState` only describes prograde orbits.
+                if r2.cross(v_out) <= 0.0 {
+                    continue;
+                }
+                let Ok(orbit_after) = cartesian_to_orbit(r2, v_out, mu, encounter_time) else {
+                    continue;
+                };
+                candidates.push(FlybyCandidate {
+                    departure: ThrustEvent {
+                        body_id,
+                        time: departure_time,
+                        delta_v: v1 - velocity,
+                        thrust_type: ThrustType::Chemical,
+                    },
+                    encounter_time,
+                    v_infinity: v_infinity.length(),
+                    anticlockwise,
+                    orbit_after,
+                    delta_v_saved: (v_out - v2).length(),
+                });
+            }
+        }
+        Ok(candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::BodyState;
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+    const MU_MOON: f64 = 4.904_869_5e12;
+    const MOON_RADIUS: f64 = 1_737_400.0;
+
+    fn circular(a: f64) -> OrbitState {
+        OrbitState {
+            semi_major_axis: a,
+            eccentricity: 0.0,
+            arg_of_periapsis: 0.0,
+            mean_anomaly_at_epoch: 0.0,
+            epoch: 0.0,
+        }
+    }
+
+    #[test]
+    fn flybys_turn_without_speeding_up() {
+        let v = Vec2::new(1_000.0, 0.0);
+        let left = flyby_turn(v, MOON_RADIUS, MU_MOON, true);
+        let right = flyby_turn(v, MOON_RADIUS, MU_MOON, false);
+        assert!((left.length() - 1_000.0).abs() < 1e-9);
+        assert!(left.y > 0.0 && right.y < 0.0);
+        assert!((left.x - right.x).abs() < 1e-9);
+        // Passing farther out turns less.
+        assert!(flyby_turn(v, 10.0 * MOON_RADIUS, MU_MOON, true).y < left.y);
+    }
+
+    #[test]
+    fn slingshots_past_the_moon_are_found() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let moon = world
+            .add_central_body(MU_MOON, MOON_RADIUS, circular(384_400_000.0))
+            .unwrap();
+        let ship = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        assert_eq!(
+            world.plan_flybys(ship, moon + 100, Seconds(1.0), 1e9),
+            Err(GgwError::UnknownCentralBody(moon + 100))
+        );
+
+        let period = circular(7_000_000.0).period(MU_EARTH);
+        let candidates = world
+            .plan_flybys(ship, moon, Seconds(period), 4_000.0)
+            .unwrap();
+        assert!(!candidates.is_empty());
+        for candidate in &candidates {
+            assert!(candidate.departure.delta_v.length() <= 4_000.0);
+            assert!(candidate.delta_v_saved > 0.0);
+        }
+        // The right side of the moon throws the ship out past it.
+        let thrown = candidates
+            .iter()
+            .any(|candidate| candidate.orbit_after.apoapsis() > 384_400_000.0);
+        assert!(thrown);
+        assert!(world
+            .plan_flybys(ship, moon, Seconds(period), 1.0)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+//! Transforms between the three frames positions show up in:
+//!
+//! - the inertial world frame bodies orbit in, centred on the planet;
+//! - a body's local frame, centred on the body and rotated with it;
+//! - a ship interior's tile grid, `(0, 0)` at the top-left tile with `y`
+//!   growing downwards.
+//!
+//! The local frame has the ship's centre at the origin, `x` to the right
+//! and `y` up, which is also the frame `HullShape` vertices are given in.
+//!
+//! For rendering, `floating_origin_offset` gives positions in single
+//! precision relative to a nearby origin instead of the planet.
+//!
+//! For docking, `World::relative_state` gives one body's position and
+//! velocity as seen from another, either along the world axes or in the
+//! observer's rotating LVLH frame.
+
+use crate::central::Primary;
+use crate::interior::ShipInterior;
+use crate::math::Float;
+use crate::{error, BodyState, GgwError, Vec2, Vec2f, World, TILE_SIZE_METERS};
+
+/// A body-centred frame: where its origin is in the world frame, how fast
+/// that origin is moving, and how far its axes are rotated counter-clockwise
+/// from the world axes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocalFrame {
+    pub origin: Vec2,
+    pub velocity: Vec2,
+    pub rotation: f64,
+}
+
+impl LocalFrame {
+    pub fn new(origin: Vec2, velocity: Vec2, rotation: f64) -> Self {
+        Self {
+            origin,
+            velocity,
+            rotation,
+        }
+    }
+
+    /// Frame of `body` with its axes turned by `attitude`.
+    pub fn of_body(body: &BodyState, attitude: f64) -> Self {
+        Self::new(body.position, body.velocity, attitude)
+    }
+
+    /// Frame of `body` turned to its own `orientation`.
+    pub fn oriented(body: &BodyState) -> Self {
+        Self::of_body(body, body.orientation)
+    }
+
+    /// Frame of `body` with `+x` pointing prograde, so it turns with the
+    /// orbit. A body at rest falls back to the world axes.
+    pub fn prograde(body: &BodyState) -> Self {
+        let attitude = if body.velocity.length_squared() > 0.0 {
+            Float::atan2(body.velocity.y, body.velocity.x)
+        } else {
+            0.0
+        };
+        Self::of_body(body, attitude)
+    }
+
+    pub fn to_local(&self, world_point: Vec2) -> Vec2 {
+        world_point.sub(self.origin).rotate(-self.rotation)
+    }
+
+    pub fn to_world(&self, local_point: Vec2) -> Vec2 {
+        local_point.rotate(self.rotation).add(self.origin)
+    }
+
+    /// Rotate a direction (or any free vector) into the local frame.
+    pub fn direction_to_local(&self, world_direction: Vec2) -> Vec2 {
+        world_direction.rotate(-self.rotation)
+    }
+
+    pub fn direction_to_world(&self, local_direction: Vec2) -> Vec2 {
+        local_direction.rotate(self.rotation)
+    }
+
+    /// Velocity relative to the frame's origin, in local axes. The frame's
+    /// own spin is not accounted for.
+    pub fn velocity_to_local(&self, world_velocity: Vec2) -> Vec2 {
+        self.direction_to_local(world_velocity.sub(self.velocity))
+    }
+
+    pub fn velocity_to_world(&self, local_velocity: Vec2) -> Vec2 {
+        self.direction_to_world(local_velocity).add(self.velocity)
+    }
+}
+
+/// Axes a relative state is given in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RelativeFrame {
+    /// The world axes, with velocity simply differenced.
+    #[default]
+    Inertial,
+    /// Local vertical, local horizontal: `+x` radially out from the
+    /// observer's primary and `+y` a quarter turn anticlockwise from it,
+    /// along track for an anticlockwise orbit. The axes turn with the
+    /// observer's orbit, and velocities are as seen from the turning frame,
+    /// so a target holding station ahead on the same circular orbit sits
+    /// still.
+    Lvlh,
+}
+
+/// The observer's LVLH frame around `primary`, and how fast it turns
+/// anticlockwise. An observer at the primary's centre falls back to the
+/// world axes.
+pub fn lvlh(observer: &BodyState, primary: &Primary) -> (LocalFrame, f64) {
+    let radial = observer.position - primary.position;
+    let r_sq = radial.length_squared();
+    let frame = LocalFrame::new(observer.position, observer.velocity, 0.0);
+    if r_sq == 0.0 {
+        return (frame, 0.0);
+    }
+    let rate = radial.cross(observer.velocity - primary.velocity) / r_sq;
+    let rotation = Float::atan2(radial.y, radial.x);
+    (LocalFrame { rotation, ..frame }, rate)
+}
+
+/// `target`'s position and velocity relative to `observer` in `frame`,
+/// with `primary` the observer's primary.
+pub fn relative_state(
+    observer: &BodyState,
+    target: &BodyState,
+    primary: &Primary,
+    frame: RelativeFrame,
+) -> (Vec2, Vec2) {
+    match frame {
+        RelativeFrame::Inertial => (
+            target.position - observer.position,
+            target.velocity - observer.velocity,
+        ),
+        RelativeFrame::Lvlh => {
+            let (frame, rate) = lvlh(observer, primary);
+            let offset = target.position - observer.position;
+            // Take out the frame's own spin carrying the offset round.
+            let velocity = target.velocity - observer.velocity - offset.perp() * rate;
+            (
+                frame.to_local(target.position),
+                frame.direction_to_local(velocity),
+            )
+        }
+    }
+}
+
+impl World {
+    /// `target_id`'s position and velocity relative to `observer_id`, along
+    /// the world axes.
+    pub fn relative_state(&self, observer_id: u64, target_id: u64) -> error::Result<(Vec2, Vec2)> {
+        self.relative_state_in(observer_id, target_id, RelativeFrame::Inertial)
+    }
+
+    /// `target_id`'s position and velocity relative to `observer_id` in
+    /// `frame`; see `RelativeFrame`.
+    pub fn relative_state_in(
+        &self,
+        observer_id: u64,
+        target_id: u64,
+        frame: RelativeFrame,
+    ) -> error::Result<(Vec2, Vec2)> {
+        let observer = self
+            .body(observer_id)
+            .ok_or(GgwError::UnknownBody(observer_id))?;
+        let target = self
+            .body(target_id)
+            .ok_or(GgwError::UnknownBody(target_id))?;
+        Ok(relative_state(
+            observer,
+            target,
+            &self.primary_of(observer),
+            frame,
+        ))
+    }
+}
+
+/// Local-frame position of grid coordinates `(gx, gy)` on a `width` by
+/// `height` tile grid. Whole numbers are tile corners; add `0.5` for a
+/// tile's centre.
+pub fn grid_to_local(gx: f64, gy: f64, width: u32, height: u32) -> Vec2 {
+    let center_x = width as f64 * TILE_SIZE_METERS / 2.0;
+    let center_y = height as f64 * TILE_SIZE_METERS / 2.0;
+    Vec2::new(
+        gx * TILE_SIZE_METERS - center_x,
+        center_y - gy * TILE_SIZE_METERS,
+    )
+}
+
+/// Inverse of `grid_to_local`.
+pub fn local_to_grid(local_point: Vec2, width: u32, height: u32) -> (f64, f64) {
+    let center_x = width as f64 * TILE_SIZE_METERS / 2.0;
+    let center_y = height as f64 * TILE_SIZE_METERS / 2.0;
+    (
+        (local_point.x + center_x) / TILE_SIZE_METERS,
+        (center_y - local_point.y) / TILE_SIZE_METERS,
+    )
+}
+
+/// Centre of tile `(x, y)` in the ship's local frame.
+pub fn tile_to_local(ship: &ShipInterior, x: u32, y: u32) -> Vec2 {
+    grid_to_local(x as f64 + 0.5, y as f64 + 0.5, ship.width, ship.height)
+}
+
+/// Tile containing `local_point`, or `None` if it lies outside the grid.
+pub fn local_to_tile(ship: &ShipInterior, local_point: Vec2) -> Option<(u32, u32)> {
+    let (gx, gy) = local_to_grid(local_point, ship.width, ship.height);
+    let (x, y) = (Float::floor(gx), Float::floor(gy));
+    ship.in_bounds(x as i32, y as i32)
+        .then_some((x as u32, y as u32))
+}
+
+/// Centre of tile `(x, y)` in the world frame, for a ship in `frame`.
+pub fn tile_to_world(frame: &LocalFrame, ship: &ShipInterior, x: u32, y: u32) -> Vec2 {
+    frame.to_world(tile_to_local(ship, x, y))
+}
+
+/// Tile under `world_point` for a ship in `frame`, or `None` if the point
+/// is off the grid.
+pub fn world_to_tile(
+    frame: &LocalFrame,
+    ship: &ShipInterior,
+    world_point: Vec2,
+) -> Option<(u32, u32)> {
+    local_to_tile(ship, frame.to_local(world_point))
+}
+
+/// `world_point` relative to `origin`, rounded to `f32`. The difference is
+/// taken in `f64` first: at 1.5e9 m from the planet an `f32` world position
+/// is only good to about 128 m, but an offset from a nearby origin keeps
+/// sub-millimetre precision.
+pub fn floating_origin_offset(world_point: Vec2, origin: Vec2) -> Vec2f {
+    (world_point - origin).cast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use core::f64::consts::FRAC_PI_2;
+
+    fn approx_vec(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn local_frame_round_trips() {
+        let frame = LocalFrame::new(Vec2::new(100.0, -50.0), Vec2::new(0.0, 7.0), FRAC_PI_2);
+        let local = Vec2::new(2.0, 1.0);
+        let world = frame.to_world(local);
+        approx_vec(world, Vec2::new(99.0, -48.0));
+        approx_vec(frame.to_local(world), local);
+        approx_vec(
+            frame.velocity_to_local(Vec2::new(0.0, 10.0)),
+            Vec2::new(3.0, 0.0),
+        );
+        approx_vec(
+            frame.velocity_to_world(frame.velocity_to_local(Vec2::new(4.0, -2.0))),
+            Vec2::new(4.0, -2.0),
+        );
+    }
+
+    #[test]
+    fn tiles_map_through_the_world_frame() {
+        let ship = ShipInterior::new_test_layout(&GameConfig::default());
+        let frame = LocalFrame::new(Vec2::new(7_000_000.0, 0.0), Vec2::zero(), 0.3);
+        for (x, y) in [(0, 0), (2, 3), (ship.width - 1, ship.height - 1)] {
+            let world = tile_to_world(&frame, &ship, x, y);
+            assert_eq!(world_to_tile(&frame, &ship, world), Some((x, y)));
+        }
+        let top_left = grid_to_local(0.0, 0.0, ship.width, ship.height);
+        assert!(top_left.x < 0.0 && top_left.y > 0.0);
+        let outside = frame.to_world(top_left.add(Vec2::new(-0.5, 0.5)));
+        assert_eq!(world_to_tile(&frame, &ship, outside), None);
+    }
+
+    #[test]
+    fn lvlh_holds_a_co_orbiting_target_still() {
+        let mut world = World::new(3.986_004_418e14, GameConfig::default());
+        let mut ids = [0.0, 1e-4].map(|mean_anomaly_at_epoch| {
+            world
+                .add_body(BodyState::circular(7_000_000.0, mean_anomaly_at_epoch))
+                .unwrap()
+        });
+        world.step(crate::Seconds(1_000.0));
+        assert!(world.relative_state(ids[0], 99).is_err());
+
+        // 700 m ahead, a touch below the observer's horizon.
+        let (position, velocity) = world
+            .relative_state_in(ids[0], ids[1], RelativeFrame::Lvlh)
+            .unwrap();
+        assert!((position.y - 700.0).abs() < 1e-2, "{:?}", position);
+        assert!(position.x < 0.0 && position.x > -0.1);
+        assert!(velocity.length() < 1e-6, "{:?}", velocity);
+        let (position, velocity) = world.relative_state(ids[0], ids[1]).unwrap();
+        assert!((position.length() - 700.0).abs() < 1e-2);
+        assert!(velocity.length() > 0.5);
+
+        // Seen the other way round.
+        ids.reverse();
+        let (position, _) = world
+            .relative_state_in(ids[0], ids[1], RelativeFrame::Lvlh)
+            .unwrap();
+        assert!((position.y + 700.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn floating_origin_keeps_precision_far_out() {
+        let origin = Vec2::new(1.5e9, -2.0e8);
+        let point = origin.add(Vec2::new(12.345, -0.5));
+        let offset = floating_origin_offset(point, origin);
+        assert!((offset.x as f64 - 12.345).abs() < 1e-5);
+        assert!((offset.y as f64 + 0.5).abs() < 1e-5);
+        // What a client casting the world position itself would see.
+        let cast: Vec2f = point.cast();
+        assert!((cast.x as f64 - point.x).abs() > 1.0);
+    }
+}
@@ -0,0 +1,652 @@
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+use tonic::{Request, Response, Status};
+
+use crate::{
+    collision::CollisionOutcome,
+    interior::{GasType, InteriorCommand},
+    preview::SoiTransition,
+    BodyState, BodyType, DespawnReason, OrbitState, Seconds, ThrustEvent, ThrustType, Vec2, World,
+    WorldEvent, MAX_WARP_DURATION_S,
+};
+
+pub mod proto {
+    tonic::include_proto!("ggw.v1");
+}
+
+use proto::ggw_world_server::{GgwWorld, GgwWorldServer};
+
+const DEFAULT_STREAM_INTERVAL_MS: u32 = 50;
+const MIN_STREAM_INTERVAL_MS: u32 = 10;
+const STREAM_BUFFER: usize = 4;
+/// World events kept for event streams that fall behind before the oldest
+/// are dropped.
+const EVENT_BUFFER: usize = 1024;
+
+/// gRPC front end over a world shared with whatever loop steps it. The
+/// service itself never advances time except through explicit `WarpTo`,
+/// which goes at most `MAX_WARP_DURATION_S` ahead and never back. The
+/// world is only ever locked off the async workers, so a long warp doesn't
+/// stall the other calls' tasks. The stepping loop hands each step's events
+/// to `publish_events` for `StreamEvents`.
+#[derive(Clone)]
+pub struct WorldService {
+    world: Arc<Mutex<World>>,
+    events: broadcast::Sender<proto::Event>,
+}
+
+impl WorldService {
+    pub fn new(world: Arc<Mutex<World>>) -> Self {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        Self { world, events }
+    }
+
+    pub fn into_server(self) -> GgwWorldServer<Self> {
+        GgwWorldServer::new(self)
+    }
+
+    /// Drain `world`'s events and send them to every event stream.
+    pub fn publish_events(&self, world: &mut World) {
+        for event in world.drain_events() {
+            // No one streaming events is no reason to fail.
+            let _ = self.events.send(proto::Event::from(&event));
+        }
+    }
+
+    /// Run `f` on the world on a blocking thread, once the lock is free.
+    async fn with_world<T, F>(&self, f: F) -> Result<T, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut World) -> T + Send + 'static,
+    {
+        with_world(Arc::clone(&self.world), f).await
+    }
+}
+
+async fn with_world<T, F>(world: Arc<Mutex<World>>, f: F) -> Result<T, Status>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut World) -> T + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || match world.lock() {
+        Ok(mut world) => Ok(f(&mut world)),
+        Err(_) => Err(Status::internal("world lock poisoned")),
+    })
+    .await
+    .map_err(|_| Status::internal("world task failed"))?
+}
+
+#[tonic::async_trait]
+impl GgwWorld for WorldService {
+    type StreamSnapshotsStream =
+        Pin<Box<dyn Stream<Item = Result<proto::Snapshot, Status>> + Send + 'static>>;
+    type StreamEventsStream =
+        Pin<Box<dyn Stream<Item = Result<proto::Event, Status>> + Send + 'static>>;
+
+    async fn send_command(
+        &self,
+        request: Request<proto::Command>,
+    ) -> Result<Response<proto::CommandReply>, Status> {
+        let kind = request
+            .into_inner()
+            .kind
+            .ok_or_else(|| Status::invalid_argument("command kind missing"))?;
+        let reply = self
+            .with_world(move |world| apply_command(world, kind))
+            .await??;
+        Ok(Response::new(reply))
+    }
+
+    async fn get_snapshot(
+        &self,
+        _request: Request<proto::SnapshotRequest>,
+    ) -> Result<Response<proto::Snapshot>, Status> {
+        let snapshot = self.with_world(|world| snapshot(world)).await?;
+        Ok(Response::new(snapshot))
+    }
+
+    async fn get_body(
+        &self,
+        request: Request<proto::BodyQuery>,
+    ) -> Result<Response<proto::Body>, Status> {
+        let id = request.into_inner().id;
+        self.with_world(move |world| world.body(id).map(proto::Body::from))
+            .await?
+            .map(Response::new)
+            .ok_or_else(|| Status::not_found(format!("no body with id {}", id)))
+    }
+
+    async fn stream_snapshots(
+        &self,
+        request: Request<proto::StreamRequest>,
+    ) -> Result<Response<Self::StreamSnapshotsStream>, Status> {
+        let interval_ms = match request.into_inner().interval_ms {
+            0 => DEFAULT_STREAM_INTERVAL_MS,
+            ms => ms.max(MIN_STREAM_INTERVAL_MS),
+        };
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        let world = Arc::clone(&self.world);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_millis(interval_ms as u64));
+            loop {
+                ticker.tick().await;
+                let item = with_world(Arc::clone(&world), |world| snapshot(world)).await;
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn stream_events(
+        &self,
+        _request: Request<proto::EventStreamRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        let mut events = self.events.subscribe();
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "event stream fell behind");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if tx.send(Ok(event)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+/// Apply `kind` to `world`, replying whether the world took it. A command
+/// naming no known thrust type or gas is refused with `invalid_argument`.
+fn apply_command(
+    world: &mut World,
+    kind: proto::command::Kind,
+) -> Result<proto::CommandReply, Status> {
+    use proto::command::Kind;
+
+    let result = match kind {
+        Kind::MovePawn(cmd) => {
+            world.interior.queue_command(InteriorCommand::MovePawn {
+                dx: cmd.dx,
+                dy: cmd.dy,
+            });
+            Ok(())
+        }
+        Kind::ToggleSleep(_) => {
+            world.interior.queue_command(InteriorCommand::ToggleSleep);
+            Ok(())
+        }
+        Kind::InteractAt(cmd) => {
+            world
+                .interior
+                .queue_command(InteriorCommand::InteractAt { x: cmd.x, y: cmd.y });
+            Ok(())
+        }
+        Kind::SetDevice(cmd) => {
+            world.interior.queue_command(InteriorCommand::SetDevice {
+                device_id: cmd.device_id,
+                online: cmd.online,
+                setpoint: cmd.setpoint,
+            });
+            Ok(())
+        }
+        Kind::SetDispenserGas(cmd) => {
+            let gas = gas_type(cmd.gas)?;
+            world
+                .interior
+                .queue_command(InteriorCommand::SetDispenserGas {
+                    device_id: cmd.device_id,
+                    gas,
+                });
+            Ok(())
+        }
+        Kind::ReactorThrottle(cmd) => {
+            world
+                .interior
+                .queue_command(InteriorCommand::ReactorThrottle {
+                    device_id: cmd.device_id,
+                    throttle: cmd.throttle,
+                });
+            Ok(())
+        }
+        Kind::WarpTo(cmd) => warp_to(world, cmd.sim_time),
+        Kind::ApplyThrust(cmd) => {
+            let thrust_type = thrust_type(cmd.thrust_type)?;
+            match cmd.delta_v {
+                Some(delta_v) => world
+                    .apply_thrust_event(&ThrustEvent {
+                        body_id: cmd.body_id,
+                        time: cmd.time,
+                        delta_v: delta_v.into(),
+                        thrust_type,
+                    })
+                    .map_err(|err| err.to_string()),
+                None => Err("delta_v missing".to_string()),
+            }
+        }
+    };
+    Ok(match result {
+        Ok(()) => proto::CommandReply {
+            accepted: true,
+            error: String::new(),
+        },
+        Err(error) => proto::CommandReply {
+            accepted: false,
+            error,
+        },
+    })
+}
+
+/// Warp to `sim_time`, but at most `MAX_WARP_DURATION_S` ahead.
+fn warp_to(world: &mut World, sim_time: f64) -> Result<(), String> {
+    if !sim_time.is_finite() {
+        return Err("sim_time must be finite".to_string());
+    }
+    let now = world.sim_time();
+    if sim_time < now.0 {
+        return Err(format!("sim_time {} is before now ({})", sim_time, now.0));
+    }
+    world.warp_to(Seconds(sim_time).min(now + Seconds(MAX_WARP_DURATION_S)));
+    Ok(())
+}
+
+fn thrust_type(value: i32) -> Result<ThrustType, Status> {
+    match proto::ThrustType::try_from(value) {
+        Ok(proto::ThrustType::Rcs) => Ok(ThrustType::Rcs),
+        Ok(proto::ThrustType::Chemical) => Ok(ThrustType::Chemical),
+        Ok(proto::ThrustType::Ion) => Ok(ThrustType::Ion),
+        Ok(proto::ThrustType::Unspecified) | Err(_) => Err(Status::invalid_argument(format!(
+            "unknown thrust type {value}"
+        ))),
+    }
+}
+
+fn gas_type(value: i32) -> Result<GasType, Status> {
+    match proto::GasType::try_from(value) {
+        Ok(proto::GasType::O2) => Ok(GasType::O2),
+        Ok(proto::GasType::N2) => Ok(GasType::N2),
+        Ok(proto::GasType::Co2) => Ok(GasType::CO2),
+        Ok(proto::GasType::Xenon) => Ok(GasType::Xenon),
+        Ok(proto::GasType::Unspecified) | Err(_) => Err(Status::invalid_argument(format!(
+            "unknown gas type {value}"
+        ))),
+    }
+}
+
+/// Build the protobuf snapshot of `world`.
+pub fn snapshot(world: &World) -> proto::Snapshot {
+    let ship = &world.interior.ship;
+    let pawn = &world.interior.pawn;
+    let totals = ship.total_atmos();
+    proto::Snapshot {
+        tick: world.tick_count,
+        sim_time: world.sim_time().0,
+        mu: world.mu,
+        planet_radius: world.planet_radius,
+        bodies: world.bodies.iter().map(proto::Body::from).collect(),
+        interior: Some(proto::Interior {
+            width: ship.width,
+            height: ship.height,
+            pawn: Some(proto::Pawn {
+                x: pawn.x,
+                y: pawn.y,
+                status: pawn.status.as_str().to_string(),
+                hunger: pawn.needs.hunger,
+                thirst: pawn.needs.thirst,
+                rest: pawn.needs.rest,
+            }),
+            power: Some(proto::Power {
+                net_kw: ship.power.net_kw,
+                total_production_kw: ship.power.total_production_kw,
+                total_consumption_kw: ship.power.total_consumption_kw,
+            }),
+            o2_kg: totals.o2_kg,
+            n2_kg: totals.n2_kg,
+            co2_kg: totals.co2_kg,
+        }),
+    }
+}
+
+impl From<Vec2> for proto::Vec2 {
+    fn from(v: Vec2) -> Self {
+        Self { x: v.x, y: v.y }
+    }
+}
+
+impl From<proto::Vec2> for Vec2 {
+    fn from(v: proto::Vec2) -> Self {
+        Vec2::new(v.x, v.y)
+    }
+}
+
+impl From<&OrbitState> for proto::Orbit {
+    fn from(orbit: &OrbitState) -> Self {
+        Self {
+            semi_major_axis: orbit.semi_major_axis,
+            eccentricity: orbit.eccentricity,
+            arg_of_periapsis: orbit.arg_of_periapsis,
+            mean_anomaly_at_epoch: orbit.mean_anomaly_at_epoch,
+            epoch: orbit.epoch,
+        }
+    }
+}
+
+impl From<BodyType> for proto::BodyType {
+    fn from(body_type: BodyType) -> Self {
+        match body_type {
+            BodyType::Ship => proto::BodyType::Ship,
+            BodyType::Asteroid => proto::BodyType::Asteroid,
+            BodyType::Debris => proto::BodyType::Debris,
+            BodyType::Missile => proto::BodyType::Missile,
+        }
+    }
+}
+
+impl From<&BodyState> for proto::Body {
+    fn from(body: &BodyState) -> Self {
+        Self {
+            id: body.id,
+            body_type: proto::BodyType::from(body.body_type) as i32,
+            mass: body.mass,
+            radius: body.radius,
+            orbit: Some((&body.orbit).into()),
+            position: Some(body.position.into()),
+            velocity: Some(body.velocity.into()),
+            hull_vertices: body
+                .hull_shape
+                .as_ref()
+                .map(|hull| hull.vertices.iter().map(|v| (*v).into()).collect())
+                .unwrap_or_default(),
+            tags: body
+                .tags
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl From<ThrustType> for proto::ThrustType {
+    fn from(thrust_type: ThrustType) -> Self {
+        match thrust_type {
+            ThrustType::Rcs => proto::ThrustType::Rcs,
+            ThrustType::Chemical => proto::ThrustType::Chemical,
+            ThrustType::Ion => proto::ThrustType::Ion,
+        }
+    }
+}
+
+impl From<&ThrustEvent> for proto::ApplyThrust {
+    fn from(event: &ThrustEvent) -> Self {
+        Self {
+            body_id: event.body_id,
+            time: event.time,
+            delta_v: Some(event.delta_v.into()),
+            thrust_type: proto::ThrustType::from(event.thrust_type) as i32,
+        }
+    }
+}
+
+impl From<&WorldEvent> for proto::Event {
+    fn from(event: &WorldEvent) -> Self {
+        use proto::event::Kind;
+
+        let body = |body_id| proto::BodyEvent { body_id };
+        let docking = |host, passenger| proto::Docking { host, passenger };
+        let (time, kind) = match event {
+            WorldEvent::BodySpawned { time, id } => (*time, Kind::BodySpawned(body(*id))),
+            WorldEvent::Despawned(event) => (
+                event.time,
+                Kind::Despawned(proto::Despawned {
+                    body_id: event.id,
+                    reason: despawn_reason_name(event.reason).to_string(),
+                }),
+            ),
+            WorldEvent::SoiChanged(event) => (
+                event.time,
+                Kind::SoiChanged(proto::SoiChanged {
+                    body_id: event.body_id,
+                    transition: soi_transition_name(event.transition).to_string(),
+                }),
+            ),
+            WorldEvent::ThrustApplied(event) => (event.time, Kind::ThrustApplied(event.into())),
+            WorldEvent::ThrustRejected { event, error } => (
+                event.time,
+                Kind::ThrustRejected(proto::ThrustRejected {
+                    thrust: Some(event.into()),
+                    error: error.to_string(),
+                }),
+            ),
+            WorldEvent::Collision { event, outcome } => (
+                event.time,
+                Kind::Collision(proto::Collision {
+                    body_a: event.body_a,
+                    body_b: event.body_b,
+                    outcome: collision_outcome_name(*outcome).to_string(),
+                }),
+            ),
+            WorldEvent::Landed {
+                time,
+                body_id,
+                longitude,
+            } => (
+                *time,
+                Kind::Landed(proto::Landed {
+                    body_id: *body_id,
+                    longitude: *longitude,
+                }),
+            ),
+            WorldEvent::Damaged {
+                time,
+                body_id,
+                amount,
+                hp,
+            } => (
+                *time,
+                Kind::Damaged(proto::Damaged {
+                    body_id: *body_id,
+                    amount: *amount,
+                    hp: *hp,
+                }),
+            ),
+            WorldEvent::Launched { time, body_id } => (*time, Kind::Launched(body(*body_id))),
+            WorldEvent::ReachedOrbit { time, body_id } => {
+                (*time, Kind::ReachedOrbit(body(*body_id)))
+            }
+            WorldEvent::ConjunctionWarning(conjunction) => (
+                conjunction.time,
+                Kind::ConjunctionWarning(proto::Conjunction {
+                    body_a: conjunction.body_a,
+                    body_b: conjunction.body_b,
+                    miss_distance: conjunction.miss_distance,
+                }),
+            ),
+            WorldEvent::Docked {
+                time,
+                host,
+                passenger,
+            } => (*time, Kind::Docked(docking(*host, *passenger))),
+            WorldEvent::Undocked {
+                time,
+                host,
+                passenger,
+            } => (*time, Kind::Undocked(docking(*host, *passenger))),
+            WorldEvent::MessageDelivered(message) => (
+                message.arrives,
+                Kind::MessageDelivered(proto::MessageDelivered {
+                    from: message.from,
+                    to: message.to,
+                    sent: message.sent,
+                    payload: message.payload.clone(),
+                }),
+            ),
+        };
+        Self {
+            time,
+            kind: Some(kind),
+        }
+    }
+}
+
+/// The names the TCP protocol's events use.
+fn despawn_reason_name(reason: DespawnReason) -> &'static str {
+    match reason {
+        DespawnReason::LeftDespawnRadius => "left_despawn_radius",
+        DespawnReason::Merged { .. } => "merged",
+        DespawnReason::Fragmented => "fragmented",
+        DespawnReason::Removed => "removed",
+        DespawnReason::ReentryBurnup => "reentry_burnup",
+        DespawnReason::Detonated { .. } => "detonated",
+        DespawnReason::MinedOut => "mined_out",
+        DespawnReason::Destroyed => "destroyed",
+    }
+}
+
+fn soi_transition_name(transition: SoiTransition) -> &'static str {
+    match transition {
+        SoiTransition::EnteredGravityWell => "entered_gravity_well",
+        SoiTransition::LeftGravityWell => "left_gravity_well",
+        SoiTransition::Despawned => "despawned",
+        SoiTransition::EnteredSoi { .. } => "entered_soi",
+        SoiTransition::LeftSoi { .. } => "left_soi",
+    }
+}
+
+fn collision_outcome_name(outcome: CollisionOutcome) -> &'static str {
+    match outcome {
+        CollisionOutcome::Separating => "separating",
+        CollisionOutcome::Bounced => "bounced",
+        CollisionOutcome::Merged { .. } => "merged",
+        CollisionOutcome::Fragmented => "fragmented",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+
+    #[test]
+    fn snapshot_carries_bodies_and_interior() {
+        let mut world = World::new(3.986004418e14, GameConfig::default());
+        let id = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let snap = snapshot(&world);
+        assert_eq!(snap.bodies.len(), 1);
+        assert_eq!(snap.bodies[0].id, id);
+        assert_eq!(snap.bodies[0].body_type, proto::BodyType::Ship as i32);
+        assert!(snap.interior.and_then(|i| i.pawn).is_some());
+    }
+
+    fn accepted(world: &mut World, kind: proto::command::Kind) -> bool {
+        apply_command(world, kind).unwrap().accepted
+    }
+
+    #[test]
+    fn bad_thrusts_are_rejected() {
+        let mut world = World::new(3.986004418e14, GameConfig::default());
+        let thrust = |thrust_type: i32| {
+            proto::command::Kind::ApplyThrust(proto::ApplyThrust {
+                body_id: 42,
+                time: 0.0,
+                delta_v: Some(proto::Vec2 { x: 1.0, y: 0.0 }),
+                thrust_type,
+            })
+        };
+        // An unknown body is the world's call.
+        assert!(!accepted(
+            &mut world,
+            thrust(proto::ThrustType::Chemical as i32)
+        ));
+        // An unknown thrust type is a malformed command.
+        for thrust_type in [proto::ThrustType::Unspecified as i32, 99] {
+            let status = apply_command(&mut world, thrust(thrust_type)).unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        }
+    }
+
+    #[test]
+    fn interior_commands_reach_the_interior() {
+        let mut world = World::new(3.986004418e14, GameConfig::default());
+        let gas = |gas| {
+            proto::command::Kind::SetDispenserGas(proto::SetDispenserGas { device_id: 1, gas })
+        };
+        assert!(accepted(&mut world, gas(proto::GasType::N2 as i32)));
+        let status =
+            apply_command(&mut world, gas(proto::GasType::Unspecified as i32)).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        let throttle = proto::command::Kind::ReactorThrottle(proto::ReactorThrottle {
+            device_id: 1,
+            throttle: 0.5,
+        });
+        assert!(accepted(&mut world, throttle));
+    }
+
+    #[test]
+    fn warps_are_bounded() {
+        let mut world = World::new(3.986004418e14, GameConfig::default());
+        let warp = |sim_time| proto::command::Kind::WarpTo(proto::WarpTo { sim_time });
+        assert!(!accepted(&mut world, warp(-1.0)));
+        assert!(!accepted(&mut world, warp(f64::NAN)));
+        assert_eq!(world.sim_time(), Seconds::ZERO);
+
+        assert!(accepted(&mut world, warp(1e12)));
+        assert_eq!(world.sim_time(), Seconds(MAX_WARP_DURATION_S));
+        assert!(!accepted(&mut world, warp(60.0)));
+    }
+
+    #[test]
+    fn published_events_reach_event_streams() {
+        use tokio_stream::StreamExt;
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let world = Arc::new(Mutex::new(World::new(
+            3.986004418e14,
+            GameConfig::default(),
+        )));
+        let service = WorldService::new(Arc::clone(&world));
+        runtime.block_on(async {
+            let mut stream = service
+                .stream_events(Request::new(proto::EventStreamRequest {}))
+                .await
+                .unwrap()
+                .into_inner();
+            let id = {
+                let mut world = world.lock().unwrap();
+                let id = world
+                    .add_body(BodyState::circular(7_000_000.0, 0.0))
+                    .unwrap();
+                world.remove_body(id);
+                service.publish_events(&mut world);
+                assert!(world.events().is_empty());
+                id
+            };
+            let kinds = [stream.next().await, stream.next().await]
+                .map(|event| event.unwrap().unwrap().kind.unwrap());
+            assert_eq!(
+                kinds,
+                [
+                    proto::event::Kind::BodySpawned(proto::BodyEvent { body_id: id }),
+                    proto::event::Kind::Despawned(proto::Despawned {
+                        body_id: id,
+                        reason: "removed".to_string(),
+                    }),
+                ]
+            );
+        });
+    }
+}
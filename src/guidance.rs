@@ -0,0 +1,282 @@
+//! Homing missiles.
+//!
+//! A missile given a `Guidance` with `World::set_missile_guidance` steers
+//! for its `World::missile_target` every step by proportional navigation:
+//! it accelerates across the line of sight in proportion to how fast that
+//! line is turning and how fast the gap is closing, which puts it on a
+//! collision course without having to predict where the target is going.
+//! While the gap is opening it flies straight at the target instead.
+//! Thrust is capped at `Guidance::max_acceleration_mps2` and paid for out
+//! of `Guidance::fuel_mps`; once that runs dry the missile coasts.
+//!
+//! A missile that passes within `Guidance::proximity_radius_m` of its
+//! target's edge detonates where it came closest, leaving the world with a
+//! `DespawnReason::Detonated` and setting off a blast; see `blast`.
+//! Steering and the fuze both work in slices of at most `GUIDANCE_STEP_S`,
+//! so a missile under time warp flies the same way it does stepped and
+//! can't skip past its target between ticks. A missile with no target, or
+//! whose target is gone, coasts with its fuze idle.
+
+use alloc::vec::Vec;
+
+use crate::blast::Detonation;
+use crate::landing::surface_point;
+use crate::{error, maneuver, BodyState, BodyType, GgwError, Vec2, World};
+
+/// Longest slice a missile steers or watches its fuze over at once.
+pub const GUIDANCE_STEP_S: f64 = 1.0;
+/// Proportional navigation gain: commanded acceleration per unit of
+/// closing speed times line-of-sight rate.
+pub const NAVIGATION_CONSTANT: f64 = 4.0;
+
+/// A missile's seeker, motor and fuze.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Guidance {
+    /// Hardest the motor can push.
+    pub max_acceleration_mps2: f64,
+    /// Delta-v left to steer with.
+    pub fuel_mps: f64,
+    /// How close to the target's edge sets the warhead off.
+    pub proximity_radius_m: f64,
+}
+
+/// Acceleration commanded for a missile `offset` from its target (target
+/// minus missile) and moving at `relative_velocity` relative to it (target
+/// minus missile), at most `max_acceleration` long.
+pub fn steering(offset: Vec2, relative_velocity: Vec2, max_acceleration: f64) -> Vec2 {
+    let range_sq = offset.length_squared();
+    if range_sq <= 0.0 {
+        return Vec2::zero();
+    }
+    let line_of_sight = offset.normalized();
+    let closing_speed = -relative_velocity.dot(line_of_sight);
+    let command = if closing_speed > 0.0 {
+        let turn_rate = offset.cross(relative_velocity) / range_sq;
+        line_of_sight.perp() * (NAVIGATION_CONSTANT * closing_speed * turn_rate)
+    } else {
+        line_of_sight * max_acceleration
+    };
+    let max_acceleration = max_acceleration.max(0.0);
+    if command.length() > max_acceleration {
+        command.normalized() * max_acceleration
+    } else {
+        command
+    }
+}
+
+/// Closest two bodies come while the gap between them moves in a straight
+/// line from `start` to `end`, and how far along that line it comes.
+fn closest_approach(start: Vec2, end: Vec2) -> (f64, f64) {
+    let travel = end - start;
+    let length_sq = travel.length_squared();
+    let t = if length_sq > 0.0 {
+        (-start.dot(travel) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    ((start + travel * t).length(), t)
+}
+
+impl World {
+    /// Fit missile `missile_id` with `guidance`, or strip it with `None`.
+    pub fn set_missile_guidance(
+        &mut self,
+        missile_id: u64,
+        guidance: Option<Guidance>,
+    ) -> error::Result<()> {
+        match self.body(missile_id) {
+            Some(body) if body.body_type == BodyType::Missile => {}
+            Some(_) => return Err(GgwError::NotAMissile(missile_id)),
+            None => return Err(GgwError::UnknownBody(missile_id)),
+        }
+        match guidance {
+            Some(guidance) => self.missile_guidance.insert(missile_id, guidance),
+            None => self.missile_guidance.remove(&missile_id),
+        };
+        Ok(())
+    }
+
+    /// `missile_id`'s guidance as it stands, fuel spent so far included.
+    pub fn missile_guidance(&self, missile_id: u64) -> Option<Guidance> {
+        self.missile_guidance.get(&missile_id).copied()
+    }
+
+    /// Steer every guided missile through the tick of `dt` seconds just
+    /// ended, and detonate those that reached their targets.
+    pub(crate) fn guide_missiles(&mut self, dt: f64) {
+        if self.missile_guidance.is_empty() {
+            return;
+        }
+        let now = self.sim_time().0;
+        let guided: Vec<u64> = self.missile_guidance.keys().copied().collect();
+        let mut detonated = Vec::new();
+        for missile_id in guided {
+            let Some(target_id) = self.missile_target(missile_id) else {
+                continue;
+            };
+            if self.landed.contains_key(&missile_id) {
+                continue;
+            }
+            match self.guide(missile_id, target_id, now - dt, now) {
+                Ok(Some(detonation)) => detonated.push((missile_id, target_id, detonation)),
+                Ok(None) => {}
+                Err(err) => {
+                    tracing::warn!(missile = missile_id, error = %err, "missile guidance failed")
+                }
+            }
+        }
+        for (missile_id, target, detonation) in detonated {
+            tracing::debug!(missile = missile_id, target, "missile detonated");
+            self.detonate(missile_id, target, detonation);
+        }
+    }
+
+    /// Fly `missile_id` at `target_id` from `from` to `to`, returning
+    /// where its fuze went off, if it did. On error nothing changes.
+    fn guide(
+        &mut self,
+        missile_id: u64,
+        target_id: u64,
+        from: f64,
+        to: f64,
+    ) -> error::Result<Option<Detonation>> {
+        let mut guidance = self.missile_guidance[&missile_id];
+        let missile = self
+            .body(missile_id)
+            .ok_or(GgwError::UnknownBody(missile_id))?;
+        let target = self
+            .body(target_id)
+            .ok_or(GgwError::UnknownBody(target_id))?;
+        let fuze = guidance.proximity_radius_m.max(0.0) + target.radius;
+        let parent = missile.parent;
+        let mut orbit = missile.orbit;
+        let mut time = from;
+        while time < to {
+            let step = (to - time).min(GUIDANCE_STEP_S);
+            let primary = *self.primaries_at(time).get(parent);
+            let (position, velocity) = primary.state_at(&orbit, time)?;
+            let (target_position, target_velocity) = self.tracked_state(target, time)?;
+            let offset = target_position - position;
+            let command = steering(
+                offset,
+                target_velocity - velocity,
+                guidance.max_acceleration_mps2,
+            );
+            let delta_v = (command.length() * step).min(guidance.fuel_mps.max(0.0));
+            if delta_v > 0.0 {
+                let kick = command.normalized() * delta_v;
+                orbit = maneuver::orbit_after_burn(&orbit, primary.mu, time, kick)?;
+                guidance.fuel_mps -= delta_v;
+            }
+
+            let end = time + step;
+            let (position, _) = self.primaries_at(end).get(parent).state_at(&orbit, end)?;
+            let (target_position, _) = self.tracked_state(target, end)?;
+            let (miss, along) = closest_approach(offset, target_position - position);
+            if miss <= fuze {
+                let time = time + step * along;
+                let (position, velocity) =
+                    self.primaries_at(time).get(parent).state_at(&orbit, time)?;
+                return Ok(Some(Detonation {
+                    time,
+                    position,
+                    velocity,
+                }));
+            }
+            time = end;
+        }
+
+        let (position, velocity) = self.primaries_at(to).get(parent).state_at(&orbit, to)?;
+        self.missile_guidance.insert(missile_id, guidance);
+        if let Some(missile) = self.get_body_mut(missile_id) {
+            missile.orbit = orbit;
+            missile.position = position;
+            missile.velocity = velocity;
+        }
+        self.sleep_tracker.wake(missile_id);
+        Ok(None)
+    }
+
+    /// World-frame position and velocity of `body` at `time`, on the
+    /// surface if it has landed.
+    pub(crate) fn tracked_state(&self, body: &BodyState, time: f64) -> error::Result<(Vec2, Vec2)> {
+        match self.landed.get(&body.id) {
+            Some(&longitude) => {
+                let primary = *self.primaries_at(time).get(body.parent);
+                Ok(surface_point(&primary, body.radius, longitude, time))
+            }
+            None => self.state_at(body, time),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{DespawnReason, Seconds};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn body_at(mean_anomaly: f64, body_type: BodyType) -> BodyState {
+        BodyState {
+            body_type,
+            ..BodyState::circular(7_000_000.0, mean_anomaly)
+        }
+    }
+
+    #[test]
+    fn steering_turns_with_the_line_of_sight() {
+        // Closing head on with the target drifting counter-clockwise.
+        let command = steering(Vec2::new(1_000.0, 0.0), Vec2::new(-100.0, 10.0), 1e6);
+        assert!(command.x.abs() < 1e-9 && command.y > 0.0);
+        // Opening, so straight at it, capped.
+        let command = steering(Vec2::new(0.0, 1_000.0), Vec2::new(0.0, 50.0), 20.0);
+        assert!((command - Vec2::new(0.0, 20.0)).length() < 1e-9);
+        assert_eq!(
+            steering(Vec2::zero(), Vec2::new(1.0, 0.0), 20.0),
+            Vec2::zero()
+        );
+    }
+
+    #[test]
+    fn guided_missiles_run_down_their_target() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world.add_body(body_at(0.0, BodyType::Ship)).unwrap();
+        // 7 km behind on the same orbit, which on its own it never closes.
+        let guided = world.add_body(body_at(-0.001, BodyType::Missile)).unwrap();
+        let ballistic = world.add_body(body_at(-0.002, BodyType::Missile)).unwrap();
+        for missile in [guided, ballistic] {
+            world.set_missile_target(missile, ship).unwrap();
+        }
+        let guidance = Guidance {
+            max_acceleration_mps2: 30.0,
+            fuel_mps: 1_000.0,
+            proximity_radius_m: 10.0,
+        };
+        assert_eq!(
+            world.set_missile_guidance(ship, Some(guidance)),
+            Err(GgwError::NotAMissile(ship))
+        );
+        world.set_missile_guidance(guided, Some(guidance)).unwrap();
+
+        world.step(Seconds(10.0));
+        let left = world.missile_guidance(guided).unwrap().fuel_mps;
+        assert!(left < guidance.fuel_mps && left > 0.0, "{left}");
+
+        // Warped in big jumps, it still can't fly through without
+        // noticing.
+        world.warp_to(Seconds(400.0));
+        assert!(world.body(guided).is_none());
+        assert_eq!(world.missile_guidance(guided), None);
+        let event = world.drain_despawn_events()[0];
+        assert_eq!(
+            (event.id, event.reason),
+            (guided, DespawnReason::Detonated { target: ship })
+        );
+        assert!(world.body(ballistic).is_some());
+        // Taken out by the blast; see `blast`.
+        assert!(world.body(ship).is_none());
+    }
+}
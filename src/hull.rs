@@ -0,0 +1,286 @@
+//! Narrow phase for bodies with a `HullShape`.
+//!
+//! `BodyState::radius` bounds the hull and serves as the broad phase; once
+//! two bounding circles touch, the shapes themselves are tested against
+//! each other with the separating axis theorem. SAT needs convex shapes, so
+//! a hull is tested as the convex hull of its vertices, and a notch in a
+//! ship's outline counts as solid.
+//!
+//! A hull is placed at the body's `orientation`, turning with its spin
+//! through a swept interval.
+
+use alloc::vec::Vec;
+
+use crate::math::Float;
+use crate::Vec2;
+
+/// Where two shapes touch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    pub point: Vec2,
+    /// Unit vector from the first shape towards the second, along which
+    /// they overlap least.
+    pub normal: Vec2,
+    /// Overlap along `normal`; 0 when the shapes only just touch.
+    pub depth: f64,
+}
+
+/// A shape in the world frame.
+#[derive(Clone, Copy, Debug)]
+pub enum Shape<'a> {
+    Circle {
+        center: Vec2,
+        radius: f64,
+    },
+    /// Convex, anticlockwise, as produced by `convex_hull`.
+    Polygon(&'a [Vec2]),
+}
+
+/// Contact between `a` and `b`, if they overlap or touch.
+pub fn contact(a: Shape, b: Shape) -> Option<Contact> {
+    match (a, b) {
+        (Shape::Polygon(a), Shape::Polygon(b)) => polygon_contact(a, b),
+        (Shape::Polygon(polygon), Shape::Circle { center, radius }) => {
+            polygon_circle_contact(polygon, center, radius)
+        }
+        (Shape::Circle { center, radius }, Shape::Polygon(polygon)) => {
+            polygon_circle_contact(polygon, center, radius).map(|contact| Contact {
+                normal: -contact.normal,
+                ..contact
+            })
+        }
+        (
+            Shape::Circle {
+                center: center_a,
+                radius: radius_a,
+            },
+            Shape::Circle {
+                center: center_b,
+                radius: radius_b,
+            },
+        ) => (center_a.distance(center_b) <= radius_a + radius_b)
+            .then(|| circle_contact(center_a, radius_a, center_b, radius_b)),
+    }
+}
+
+/// Convex hull of `points`, anticlockwise with no repeated or collinear
+/// vertices. Fewer than three points come back as they are, minus
+/// duplicates.
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_unstable_by(|p, q| p.x.total_cmp(&q.x).then(p.y.total_cmp(&q.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    // Andrew's monotone chain: lower hull left to right, then upper hull
+    // back again without disturbing the lower one.
+    let mut hull: Vec<Vec2> = Vec::with_capacity(sorted.len() + 1);
+    let mut floor = 2;
+    let upper = sorted.iter().rev().skip(1);
+    for (index, &point) in sorted.iter().chain(upper).enumerate() {
+        if index == sorted.len() {
+            floor = hull.len() + 1;
+        }
+        while hull.len() >= floor {
+            let (p, q) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+            if (q - p).cross(point - q) > 0.0 {
+                break;
+            }
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    hull.pop();
+    hull
+}
+
+/// `hull`'s vertices moved into the world frame for a body at `position`
+/// turned to `orientation`.
+pub fn place(hull: &[Vec2], position: Vec2, orientation: f64, out: &mut Vec<Vec2>) {
+    let (sin, cos) = Float::sin_cos(orientation);
+    out.clear();
+    out.extend(
+        hull.iter()
+            .map(|v| Vec2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos) + position),
+    );
+}
+
+/// Contact for two circles already known to touch. Coincident centres get
+/// an arbitrary normal.
+pub(crate) fn circle_contact(
+    center_a: Vec2,
+    radius_a: f64,
+    center_b: Vec2,
+    radius_b: f64,
+) -> Contact {
+    let offset = center_b - center_a;
+    let dist = offset.length();
+    let normal = if dist > 1e-9 {
+        offset / dist
+    } else {
+        Vec2::new(1.0, 0.0)
+    };
+    let depth = (radius_a + radius_b - dist).max(0.0);
+    Contact {
+        point: center_a + normal * (radius_a - 0.5 * depth),
+        normal,
+        depth,
+    }
+}
+
+fn polygon_contact(a: &[Vec2], b: &[Vec2]) -> Option<Contact> {
+    let (mut normal, mut depth) = (Vec2::new(1.0, 0.0), f64::INFINITY);
+    for axis in edge_normals(a).chain(edge_normals(b)) {
+        let overlap = overlap(project(a, axis), project(b, axis))?;
+        if overlap < depth {
+            (normal, depth) = (axis, overlap);
+        }
+    }
+    if (centroid(b) - centroid(a)).dot(normal) < 0.0 {
+        normal = -normal;
+    }
+
+    // Average of the corners poking into the other shape; when none do,
+    // as with two bars crossed like a plus sign, halfway between the
+    // deepest point of each.
+    let (mut sum, mut count) = (Vec2::zero(), 0.0);
+    for &vertex in b.iter().filter(|v| contains(a, **v)) {
+        sum += vertex;
+        count += 1.0;
+    }
+    for &vertex in a.iter().filter(|v| contains(b, **v)) {
+        sum += vertex;
+        count += 1.0;
+    }
+    let point = if count > 0.0 {
+        sum / count
+    } else {
+        (support(a, normal) + support(b, -normal)) * 0.5
+    };
+    Some(Contact {
+        point,
+        normal,
+        depth,
+    })
+}
+
+/// Contact with the normal pointing from `polygon` towards the circle.
+fn polygon_circle_contact(polygon: &[Vec2], center: Vec2, radius: f64) -> Option<Contact> {
+    let closest = polygon
+        .iter()
+        .copied()
+        .min_by(|p, q| p.distance(center).total_cmp(&q.distance(center)))?;
+    let to_center = center - closest;
+    let vertex_axis = (to_center.length() > 1e-9).then(|| to_center.normalized());
+
+    let (mut normal, mut depth) = (Vec2::new(1.0, 0.0), f64::INFINITY);
+    for axis in edge_normals(polygon).chain(vertex_axis) {
+        let reach = center.dot(axis);
+        let overlap = overlap(project(polygon, axis), (reach - radius, reach + radius))?;
+        if overlap < depth {
+            (normal, depth) = (axis, overlap);
+        }
+    }
+    if (center - centroid(polygon)).dot(normal) < 0.0 {
+        normal = -normal;
+    }
+    Some(Contact {
+        point: center - normal * (radius - 0.5 * depth),
+        normal,
+        depth,
+    })
+}
+
+/// Unit normals of an anticlockwise polygon's edges.
+fn edge_normals(polygon: &[Vec2]) -> impl Iterator<Item = Vec2> + '_ {
+    let next = polygon.iter().cycle().skip(1);
+    polygon
+        .iter()
+        .zip(next)
+        .map(|(&p, &q)| -(q - p).perp())
+        .filter(|edge| edge.length_squared() > 0.0)
+        .map(Vec2::normalized)
+}
+
+fn project(polygon: &[Vec2], axis: Vec2) -> (f64, f64) {
+    polygon
+        .iter()
+        .map(|v| v.dot(axis))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), p| {
+            (min.min(p), max.max(p))
+        })
+}
+
+/// Overlap of two intervals, or `None` if there's a gap between them.
+fn overlap((min_a, max_a): (f64, f64), (min_b, max_b): (f64, f64)) -> Option<f64> {
+    let overlap = max_a.min(max_b) - min_a.max(min_b);
+    (overlap >= 0.0).then_some(overlap)
+}
+
+fn centroid(polygon: &[Vec2]) -> Vec2 {
+    let sum = polygon.iter().fold(Vec2::zero(), |sum, &v| sum + v);
+    sum / polygon.len() as f64
+}
+
+fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let next = polygon.iter().cycle().skip(1);
+    polygon
+        .iter()
+        .zip(next)
+        .all(|(&p, &q)| (q - p).cross(point - p) >= 0.0)
+}
+
+/// Vertex of `polygon` furthest along `direction`.
+fn support(polygon: &[Vec2], direction: Vec2) -> Vec2 {
+    polygon
+        .iter()
+        .copied()
+        .max_by(|p, q| p.dot(direction).total_cmp(&q.dot(direction)))
+        .unwrap_or_else(Vec2::zero)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(center: Vec2, half: f64) -> Vec<Vec2> {
+        convex_hull(&[
+            center + Vec2::new(-half, -half),
+            center + Vec2::new(half, -half),
+            center + Vec2::new(half, half),
+            center + Vec2::new(-half, half),
+            // Interior and repeated points drop out.
+            center,
+            center + Vec2::new(half, half),
+        ])
+    }
+
+    #[test]
+    fn sat_finds_depth_normal_and_point() {
+        let a = square(Vec2::zero(), 1.0);
+        assert_eq!(a.len(), 4);
+        let b = square(Vec2::new(1.5, 0.2), 1.0);
+        let hit = contact(Shape::Polygon(&a), Shape::Polygon(&b)).unwrap();
+        assert!((hit.depth - 0.5).abs() < 1e-12);
+        assert_eq!(hit.normal, Vec2::new(1.0, 0.0));
+        assert!(contains(&a, hit.point) && contains(&b, hit.point));
+
+        let apart = square(Vec2::new(2.5, 0.0), 1.0);
+        assert_eq!(contact(Shape::Polygon(&a), Shape::Polygon(&apart)), None);
+
+        // Near a corner the bounding circles overlap but the shapes don't.
+        let circle = Shape::Circle {
+            center: Vec2::new(1.6, 1.6),
+            radius: 0.8,
+        };
+        assert_eq!(contact(Shape::Polygon(&a), circle), None);
+        let circle = Shape::Circle {
+            center: Vec2::new(0.0, -1.5),
+            radius: 0.8,
+        };
+        let hit = contact(circle, Shape::Polygon(&a)).unwrap();
+        assert_eq!(hit.normal, Vec2::new(0.0, 1.0));
+        assert!((hit.depth - 0.3).abs() < 1e-12);
+    }
+}
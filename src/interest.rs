@@ -0,0 +1,118 @@
+use alloc::vec::Vec;
+
+use crate::{BodyState, BodyType, Vec2, World};
+
+/// Where a client's view is anchored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ViewCenter {
+    Point(Vec2),
+    Body(u64),
+}
+
+/// A client's region of interest: bodies farther than `radius_m` from the
+/// view center are left out of its snapshots, except mission-critical ones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ViewFilter {
+    pub center: ViewCenter,
+    pub radius_m: f64,
+}
+
+impl ViewFilter {
+    pub fn around_point(center: Vec2, radius_m: f64) -> Self {
+        Self {
+            center: ViewCenter::Point(center),
+            radius_m,
+        }
+    }
+
+    pub fn around_body(body_id: u64, radius_m: f64) -> Self {
+        Self {
+            center: ViewCenter::Body(body_id),
+            radius_m,
+        }
+    }
+
+    /// Current world-space center, or `None` if the focused body is gone.
+    pub fn resolve_center(&self, world: &World) -> Option<Vec2> {
+        match self.center {
+            ViewCenter::Point(point) => Some(point),
+            ViewCenter::Body(id) => world
+                .bodies
+                .iter()
+                .find(|body| body.id == id)
+                .map(|body| body.position),
+        }
+    }
+
+    /// Bodies this view should receive, in world order. If the focused body
+    /// no longer exists, nothing is culled.
+    pub fn visible_bodies<'a>(&self, world: &'a World) -> Vec<&'a BodyState> {
+        self.iter_visible(world).collect()
+    }
+
+    /// `visible_bodies` without collecting, for per-frame snapshot writers.
+    pub fn iter_visible<'a>(&self, world: &'a World) -> impl Iterator<Item = &'a BodyState> {
+        let center = self.resolve_center(world);
+        let radius_m = self.radius_m;
+        world.bodies.iter().filter(move |body| match center {
+            Some(center) => {
+                is_mission_critical(body)
+                    || body.position.distance(center) <= radius_m + body.radius
+            }
+            None => true,
+        })
+    }
+}
+
+/// Ships and missiles are always streamed regardless of view.
+pub fn is_mission_critical(body: &BodyState) -> bool {
+    matches!(body.body_type, BodyType::Ship | BodyType::Missile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use alloc::vec;
+
+    fn body_at(a: f64, mean_anomaly: f64, body_type: BodyType) -> BodyState {
+        BodyState {
+            radius: 10.0,
+            body_type,
+            ..BodyState::circular(a, mean_anomaly)
+        }
+    }
+
+    #[test]
+    fn culls_distant_rocks_but_keeps_ships() {
+        let mut world = World::new(3.986004418e14, GameConfig::default());
+        let ship = world
+            .add_body(body_at(7_000_000.0, 0.0, BodyType::Ship))
+            .unwrap();
+        let near = world
+            .add_body(body_at(7_000_500.0, 0.0, BodyType::Asteroid))
+            .unwrap();
+        let far_rock = world
+            .add_body(body_at(7_000_000.0, 2.0, BodyType::Asteroid))
+            .unwrap();
+        let far_ship = world
+            .add_body(body_at(9_000_000.0, 2.0, BodyType::Ship))
+            .unwrap();
+        let view = ViewFilter::around_body(ship, 10_000.0);
+        let ids: Vec<u64> = view.visible_bodies(&world).iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![ship, near, far_ship]);
+        assert!(!ids.contains(&far_rock));
+    }
+
+    #[test]
+    fn missing_focus_body_disables_culling() {
+        let mut world = World::new(3.986004418e14, GameConfig::default());
+        world
+            .add_body(body_at(7_000_000.0, 0.0, BodyType::Debris))
+            .unwrap();
+        let view = ViewFilter::around_body(999, 1.0);
+        assert_eq!(view.visible_bodies(&world).len(), 1);
+    }
+}
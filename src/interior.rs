@@ -1,8 +1,18 @@
-use std::collections::VecDeque;
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
 
+use crate::math::Float;
 use crate::{
-    config::{AtmosphereConfig, GameConfig},
-    HullShape, Vec2, TILE_SIZE_METERS,
+    budget::WorkBudget,
+    config::{AtmosSolver, AtmosphereConfig, GameConfig},
+    devices::{CustomDevice, DeviceBehavior, DeviceContext, DeviceRegistry},
+    frame,
+    lockstep::StateHasher,
+    propellant::Propellant,
+    rooms::RoomSolver,
+    HullShape,
 };
 
 const IDEAL_GAS_R: f64 = 8.314_462_618;
@@ -10,13 +20,17 @@ const ATMOS_DIFFUSION_COEFF: f32 = 0.4;
 const ATMOS_DIFFUSION_MAX_FRACTION: f32 = 0.5;
 const O2_CONSUMPTION_KG_PER_SEC: f32 = 0.0003;
 const CO2_PRODUCTION_KG_PER_SEC: f32 = 0.0003;
-const LOW_PRESSURE_THRESHOLD_KPA: f32 = 70.0;
-const LOW_O2_PARTIAL_PRESSURE_KPA: f32 = 16.0;
-const HIGH_CO2_PARTIAL_PRESSURE_KPA: f32 = 8.0;
+pub(crate) const LOW_PRESSURE_THRESHOLD_KPA: f32 = 70.0;
+pub(crate) const LOW_O2_PARTIAL_PRESSURE_KPA: f32 = 16.0;
+pub(crate) const HIGH_CO2_PARTIAL_PRESSURE_KPA: f32 = 8.0;
 const SUFFOCATION_DAMAGE_PER_SEC: f32 = 2.0;
 const VACUUM_DAMAGE_PER_SEC: f32 = 8.0;
+const MAX_DISPENSER_RATE_KG_PER_S: f32 = 1.0;
+/// Cap on atmosphere ticks per coarse (warp) step; longer spans use longer ticks.
+const COARSE_MAX_ATMOS_TICKS: u32 = 8;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TileType {
     Empty,
     Floor,
@@ -27,6 +41,7 @@ pub enum TileType {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tile {
     pub tile_type: TileType,
 }
@@ -38,6 +53,7 @@ impl Tile {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GasType {
     O2,
     N2,
@@ -66,7 +82,8 @@ impl GasType {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AtmosSample {
     pub pressure_kpa: f32,
     pub o2_kg: f32,
@@ -75,6 +92,7 @@ pub struct AtmosSample {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileAtmosphere {
     pub o2_kg: f32,
     pub n2_kg: f32,
@@ -89,7 +107,11 @@ struct GasDelta {
     co2_kg: f32,
 }
 
+/// Gas released by a device this tick: (device rect, gas, mass in kg).
+type PendingInjection = ((u32, u32, u32, u32), GasType, f32);
+
 #[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GasTotals {
     pub o2_kg: f32,
     pub n2_kg: f32,
@@ -234,6 +256,7 @@ impl Default for TileAtmosphere {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PowerState {
     pub net_kw: f32,
     pub total_production_kw: f32,
@@ -241,6 +264,7 @@ pub struct PowerState {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShipPowerSummary {
     pub generation_kw: f32,
     pub load_kw: f32,
@@ -249,6 +273,7 @@ pub struct ShipPowerSummary {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DevicePowerStatus {
     pub id: u64,
     pub name: String,
@@ -259,6 +284,7 @@ pub struct DevicePowerStatus {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DevicePowerGroup {
     Reactor,
     LifeSupport,
@@ -278,6 +304,7 @@ impl DevicePowerGroup {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DeviceType {
     Tank,
     ReactorUranium,
@@ -293,9 +320,12 @@ pub enum DeviceType {
     DoorDevice,
     PowerLine,
     GasLine,
+    /// Implemented outside this crate; see `devices`.
+    Custom,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Device {
     pub id: u64,
     pub device_type: DeviceType,
@@ -309,6 +339,7 @@ pub struct Device {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DeviceData {
     Tank(TankData),
     Reactor(ReactorData),
@@ -324,27 +355,36 @@ pub enum DeviceData {
     DoorDevice(DoorDeviceData),
     PowerLine(PowerLineData),
     GasLine(GasLineData),
+    Custom(CustomDevice),
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TankData {
     pub capacity_kg: f32,
     pub o2_kg: f32,
     pub n2_kg: f32,
     pub co2_kg: f32,
     pub xenon_kg: f32,
+    /// Propellant for RCS and the main engine; see `propellant`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fuel_kg: f32,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReactorData {
     pub fuel_kg: f32,
     pub max_fuel_kg: f32,
     pub fuel_burn_rate_kg_per_s: f32,
     pub power_output_kw: f32,
+    /// Fraction of rated output in `[0, 1]`; scales power and fuel burn.
+    pub throttle: f32,
     pub online: bool,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DispenserData {
     pub active: bool,
     pub rate_kg_per_s: f32,
@@ -353,11 +393,13 @@ pub struct DispenserData {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NavStationData {
     pub online: bool,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransponderData {
     pub callsign: String,
     pub online: bool,
@@ -365,17 +407,21 @@ pub struct TransponderData {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShipComputerData {
     pub online: bool,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BedDeviceData {}
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ToiletData {}
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FoodGeneratorData {
     pub food_units: f32,
     pub max_food_units: f32,
@@ -383,6 +429,7 @@ pub struct FoodGeneratorData {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RCSThrusterData {
     pub uses_any_gas: bool,
     pub preferred_gas: GasType,
@@ -390,35 +437,47 @@ pub struct RCSThrusterData {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LightData {
     pub intensity: f32,
     pub online: bool,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DoorDeviceData {
     pub open: bool,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PowerLineData {}
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GasLineData {}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PawnStatus {
     Awake,
     Sleeping,
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NeedsState {
     pub hunger: f32,
     pub thirst: f32,
     pub rest: f32,
 }
 
+impl Default for NeedsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NeedsState {
     pub fn new() -> Self {
         Self {
@@ -436,6 +495,7 @@ impl NeedsState {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pawn {
     pub id: u64,
     pub name: String,
@@ -448,6 +508,7 @@ pub struct Pawn {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BodyPart {
     pub name: String,
     pub hp: f32,
@@ -456,6 +517,7 @@ pub struct BodyPart {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HealthState {
     pub body_parts: Vec<BodyPart>,
 }
@@ -484,6 +546,7 @@ impl HealthState {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShipInterior {
     pub width: u32,
     pub height: u32,
@@ -493,6 +556,29 @@ pub struct ShipInterior {
     pub power_summary: ShipPowerSummary,
     pub devices: Vec<Device>,
     pub hull_shape: HullShape,
+    /// Factories for `DeviceType::Custom` devices. Not saved; register
+    /// again after loading a world.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub device_registry: DeviceRegistry,
+    /// Room layout for `AtmosSolver::Rooms`, invalidated whenever the
+    /// adjacency changes.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub room_solver: RoomSolver,
+    /// Rebuilt on the first atmosphere tick after loading.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    adjacency: AtmosAdjacency,
+}
+
+/// Forward neighbour offsets, so every neighbouring pair of tiles is
+/// visited once.
+const ATMOS_NEIGHBORS: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (-1, 1)];
+
+/// Which neighbours each tile exchanges gas with: bit `i` of `links[idx]`
+/// is set when tile `idx` links to its `ATMOS_NEIGHBORS[i]` neighbour.
+/// Sealing a tile off is clearing bits.
+#[derive(Clone, Debug, Default)]
+struct AtmosAdjacency {
+    links: Vec<u8>,
 }
 
 impl ShipInterior {
@@ -567,6 +653,7 @@ impl ShipInterior {
                 max_fuel_kg: 100.0,
                 fuel_burn_rate_kg_per_s: 0.0005,
                 power_output_kw: power_cfg.reactor_output_kw,
+                throttle: 1.0,
                 online: true,
             }),
         });
@@ -587,7 +674,8 @@ impl ShipInterior {
                 o2_kg: tank_defaults.o2_mass_kg,
                 n2_kg: tank_defaults.n2_mass_kg,
                 co2_kg: tank_defaults.co2_mass_kg,
-                xenon_kg: 10.0,
+                xenon_kg: tank_defaults.xenon_mass_kg,
+                fuel_kg: tank_defaults.fuel_mass_kg,
             }),
         });
         let tank_id = next_id;
@@ -723,7 +811,11 @@ impl ShipInterior {
             hull_shape: HullShape {
                 vertices: Vec::new(),
             },
+            device_registry: DeviceRegistry::new(),
+            room_solver: RoomSolver::new(),
+            adjacency: AtmosAdjacency::default(),
         };
+        ship.rebuild_adjacency();
         ship.rebuild_hull_shape();
         ship.rebuild_power_summary(config);
         ship
@@ -751,7 +843,7 @@ impl ShipInterior {
             .unwrap_or(TileType::Empty)
     }
 
-    fn tile_supports_atmos(tile_type: TileType) -> bool {
+    pub(crate) fn tile_supports_atmos(tile_type: TileType) -> bool {
         matches!(
             tile_type,
             TileType::Floor | TileType::Bed | TileType::DoorOpen | TileType::DoorClosed
@@ -801,10 +893,10 @@ impl ShipInterior {
         if !self.in_bounds(x, y) {
             return false;
         }
-        match self.tile_type(x as u32, y as u32) {
-            TileType::Floor | TileType::Bed | TileType::DoorOpen => true,
-            _ => false,
-        }
+        matches!(
+            self.tile_type(x as u32, y as u32),
+            TileType::Floor | TileType::Bed | TileType::DoorOpen
+        )
     }
 
     pub fn set_tile_type(
@@ -817,6 +909,8 @@ impl ShipInterior {
         if x < self.width && y < self.height {
             let idx = Self::idx(x, y, self.width);
             self.tiles[idx].tile_type = tile_type;
+            self.update_adjacency(x, y);
+            self.room_solver.invalidate();
             if !Self::tile_supports_atmos(tile_type) {
                 self.tile_atmos[idx] = TileAtmosphere::vacuum(atmos_cfg.baseline_temp_c);
             } else if self.tile_atmos[idx].total_mass() <= f32::EPSILON {
@@ -933,28 +1027,21 @@ impl ShipInterior {
             }
         }
 
-        let center_x = (self.width as f64 * TILE_SIZE_METERS) / 2.0;
-        let center_y = (self.height as f64 * TILE_SIZE_METERS) / 2.0;
         let vertices = polygon_points
             .into_iter()
-            .map(|(px, py)| {
-                let x = px as f64 * TILE_SIZE_METERS - center_x;
-                let y = center_y - py as f64 * TILE_SIZE_METERS;
-                Vec2::new(x, y)
-            })
+            .map(|(px, py)| frame::grid_to_local(px as f64, py as f64, self.width, self.height))
             .collect();
         self.hull_shape = HullShape { vertices };
     }
 
     fn rectangular_hull(width: u32, height: u32) -> HullShape {
-        let w = width as f64 * TILE_SIZE_METERS / 2.0;
-        let h = height as f64 * TILE_SIZE_METERS / 2.0;
+        let (w, h) = (width as f64, height as f64);
         HullShape {
             vertices: vec![
-                Vec2::new(-w, h),
-                Vec2::new(w, h),
-                Vec2::new(w, -h),
-                Vec2::new(-w, -h),
+                frame::grid_to_local(0.0, 0.0, width, height),
+                frame::grid_to_local(w, 0.0, width, height),
+                frame::grid_to_local(w, h, width, height),
+                frame::grid_to_local(0.0, h, width, height),
             ],
         }
     }
@@ -976,7 +1063,7 @@ impl ShipInterior {
             let (before, rest) = self.devices.split_at_mut(idx);
             let (device, after) = rest.split_first_mut().expect("split_first");
             let device_rect = (device.x, device.y, device.w, device.h);
-            let mut pending_injection: Option<((u32, u32, u32, u32), GasType, f32)> = None;
+            let mut pending_injection: Option<PendingInjection> = None;
 
             if device.online && device.power_kw > 0.0 {
                 self.power.total_consumption_kw += device.power_kw;
@@ -985,15 +1072,14 @@ impl ShipInterior {
             }
 
             match &mut device.data {
-                DeviceData::Reactor(data) => {
-                    if data.online && data.fuel_kg > 0.0 {
-                        self.power.total_production_kw += data.power_output_kw;
-                        let burn = (data.fuel_burn_rate_kg_per_s * dt_f32).min(data.fuel_kg);
-                        data.fuel_kg -= burn;
-                        if data.fuel_kg <= 0.0 {
-                            data.fuel_kg = 0.0;
-                            data.online = false;
-                        }
+                DeviceData::Reactor(data) if data.online && data.fuel_kg > 0.0 => {
+                    self.power.total_production_kw += data.power_output_kw * data.throttle;
+                    let burn =
+                        (data.fuel_burn_rate_kg_per_s * data.throttle * dt_f32).min(data.fuel_kg);
+                    data.fuel_kg -= burn;
+                    if data.fuel_kg <= 0.0 {
+                        data.fuel_kg = 0.0;
+                        data.online = false;
                     }
                 }
                 DeviceData::Dispenser(data) => {
@@ -1047,22 +1133,92 @@ impl ShipInterior {
             }
         }
 
+        self.step_custom_devices(dt_f32, &config.atmosphere);
         self.power.net_kw = self.power.total_production_kw - self.power.total_consumption_kw;
         self.rebuild_power_summary(config);
     }
 
+    /// Place a custom device at `(x, y)` covering `w` by `h` tiles, switched
+    /// on. Returns its id.
+    pub fn add_custom_device(
+        &mut self,
+        device: CustomDevice,
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+    ) -> u64 {
+        let id = self.devices.iter().map(|d| d.id).max().unwrap_or(0) + 1;
+        self.devices.push(Device {
+            id,
+            device_type: DeviceType::Custom,
+            x,
+            y,
+            w,
+            h,
+            power_kw: 0.0,
+            online: true,
+            data: DeviceData::Custom(device),
+        });
+        id
+    }
+
+    fn step_custom_devices(&mut self, dt: f32, atmos_cfg: &AtmosphereConfig) {
+        for idx in 0..self.devices.len() {
+            if matches!(self.devices[idx].data, DeviceData::Custom(_)) {
+                self.run_custom_device(idx, atmos_cfg, |behavior, ctx| behavior.step(ctx, dt));
+            }
+        }
+    }
+
+    /// Bind the custom device at `idx` to its behavior, run `f` on it and
+    /// apply whatever it changed in the context.
+    fn run_custom_device(
+        &mut self,
+        idx: usize,
+        atmos_cfg: &AtmosphereConfig,
+        f: impl FnOnce(&mut dyn DeviceBehavior, &mut DeviceContext),
+    ) {
+        let device = &self.devices[idx];
+        let output_tile = self.pick_device_output_tile((device.x, device.y, device.w, device.h));
+        let atmosphere = output_tile.and_then(|(x, y)| self.tile_atmos_sample(x, y, atmos_cfg));
+        let device = &mut self.devices[idx];
+        let DeviceData::Custom(custom) = &mut device.data else {
+            return;
+        };
+        let Some(behavior) = custom.bind(&self.device_registry) else {
+            return;
+        };
+        let mut ctx = DeviceContext::new(device.id, device.online, device.power_kw, atmosphere);
+        f(behavior, &mut ctx);
+        device.online = ctx.online;
+        device.power_kw = if ctx.power_kw.is_finite() {
+            ctx.power_kw
+        } else {
+            0.0
+        };
+        if let Some((x, y)) = output_tile {
+            for (gas, mass) in ctx.take_emitted() {
+                self.inject_gas_into_tile(x, y, gas, mass);
+            }
+        }
+    }
+
     fn rebuild_power_summary(&mut self, config: &GameConfig) {
-        let mut summary = ShipPowerSummary::default();
-        summary.generation_kw = self.power.total_production_kw;
-        summary.load_kw = self.power.total_consumption_kw;
-        summary.net_kw = self.power.net_kw;
-        summary.devices.reserve(self.devices.len());
+        let mut summary = ShipPowerSummary {
+            generation_kw: self.power.total_production_kw,
+            load_kw: self.power.total_consumption_kw,
+            net_kw: self.power.net_kw,
+            devices: Vec::with_capacity(self.devices.len()),
+        };
         for device in &self.devices {
             let Some(group) = device_power_group(device.device_type) else {
                 continue;
             };
             let draw_kw = device.power_kw.abs();
-            let name = if let Some(key) = device.device_type.config_key() {
+            let name = if let DeviceData::Custom(custom) = &device.data {
+                custom.kind().to_string()
+            } else if let Some(key) = device.device_type.config_key() {
                 config
                     .items
                     .get(key)
@@ -1083,14 +1239,49 @@ impl ShipInterior {
         self.power_summary = summary;
     }
 
+    /// Fuel and xenon left across every tank.
+    pub fn tank_propellant(&self) -> Propellant {
+        let mut total = Propellant::default();
+        for device in &self.devices {
+            if let DeviceData::Tank(data) = &device.data {
+                total.fuel_kg += data.fuel_kg as f64;
+                total.xenon_kg += data.xenon_kg as f64;
+            }
+        }
+        total
+    }
+
+    /// Take `used` out of the tanks, emptying each in turn.
+    pub fn drain_tanks(&mut self, used: Propellant) {
+        let mut fuel = used.fuel_kg as f32;
+        let mut xenon = used.xenon_kg as f32;
+        for device in &mut self.devices {
+            if let DeviceData::Tank(data) = &mut device.data {
+                let moved = data.fuel_kg.min(fuel);
+                data.fuel_kg -= moved;
+                fuel -= moved;
+                let moved = data.xenon_kg.min(xenon);
+                data.xenon_kg -= moved;
+                xenon -= moved;
+            }
+        }
+    }
+
+    /// The ship's transponder, if it has one that is switched on and
+    /// broadcasting.
+    pub fn broadcasting_transponder(&self) -> Option<&TransponderData> {
+        self.devices.iter().find_map(|device| match &device.data {
+            DeviceData::Transponder(data) if device.online && data.online => Some(data),
+            _ => None,
+        })
+    }
+
     pub fn handle_device_action(&mut self, device_id: u64, action: DeviceAction) {
         if let Some(device) = self.devices.iter_mut().find(|d| d.id == device_id) {
             match (&mut device.data, action) {
-                (DeviceData::Reactor(data), DeviceAction::Toggle) => {
-                    if data.fuel_kg > 0.0 {
-                        data.online = !data.online;
-                        device.online = data.online;
-                    }
+                (DeviceData::Reactor(data), DeviceAction::Toggle) if data.fuel_kg > 0.0 => {
+                    data.online = !data.online;
+                    device.online = data.online;
                 }
                 (DeviceData::Dispenser(data), DeviceAction::Toggle) => {
                     data.active = !data.active;
@@ -1101,6 +1292,60 @@ impl ShipInterior {
         }
     }
 
+    /// Force a device on or off remotely. Reactors without fuel stay off.
+    pub fn set_device_online(&mut self, device_id: u64, online: bool) {
+        let Some(device) = self.devices.iter_mut().find(|d| d.id == device_id) else {
+            return;
+        };
+        match &mut device.data {
+            DeviceData::Reactor(data) => {
+                if online && data.fuel_kg <= 0.0 {
+                    return;
+                }
+                data.online = online;
+            }
+            DeviceData::Dispenser(data) => data.active = online,
+            DeviceData::NavStation(data) => data.online = online,
+            DeviceData::Transponder(data) => data.online = online,
+            DeviceData::ShipComputer(data) => data.online = online,
+            DeviceData::FoodGenerator(data) => data.online = online,
+            DeviceData::RCSThruster(data) => data.online = online,
+            DeviceData::Light(data) => data.online = online,
+            _ => {}
+        }
+        device.online = online;
+    }
+
+    /// Apply a device-specific setpoint: dispenser flow (kg/s), light
+    /// intensity, or reactor throttle. Other devices ignore it.
+    pub fn set_device_setpoint(&mut self, device_id: u64, setpoint: f32) {
+        if !setpoint.is_finite() {
+            return;
+        }
+        let Some(device) = self.devices.iter_mut().find(|d| d.id == device_id) else {
+            return;
+        };
+        match &mut device.data {
+            DeviceData::Dispenser(data) => {
+                data.rate_kg_per_s = setpoint.clamp(0.0, MAX_DISPENSER_RATE_KG_PER_S);
+            }
+            DeviceData::Light(data) => data.intensity = setpoint.clamp(0.0, 1.0),
+            DeviceData::Reactor(data) => {
+                data.throttle = setpoint.clamp(0.0, 1.0);
+                device.power_kw = -data.power_output_kw * data.throttle;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn set_dispenser_gas(&mut self, device_id: u64, gas: GasType) {
+        if let Some(device) = self.devices.iter_mut().find(|d| d.id == device_id) {
+            if let DeviceData::Dispenser(data) = &mut device.data {
+                data.gas_type = gas;
+            }
+        }
+    }
+
     pub fn toggle_device_from_computer(&mut self, device_id: u64) {
         if let Some(device) = self.devices.iter_mut().find(|d| d.id == device_id) {
             if !ship_computer_controllable(device.device_type) {
@@ -1132,49 +1377,133 @@ impl ShipInterior {
         }
     }
 
+    /// One atmosphere tick of `solver`.
+    pub fn step_atmosphere_with(&mut self, solver: AtmosSolver, dt: f32) {
+        match solver {
+            AtmosSolver::Tiles => self.step_atmosphere(dt),
+            AtmosSolver::Rooms => self.step_atmosphere_rooms(dt),
+        }
+    }
+
     pub fn step_atmosphere(&mut self, dt: f32) {
         if dt <= 0.0 {
             return;
         }
-        let width = self.width as i32;
-        let height = self.height as i32;
-        let factor = (ATMOS_DIFFUSION_COEFF * dt).min(ATMOS_DIFFUSION_MAX_FRACTION);
+        let _span = tracing::trace_span!("atmosphere", dt).entered();
+        if self.adjacency.links.len() != self.tiles.len() {
+            self.rebuild_adjacency();
+        }
+        let factor = Self::diffusion_factor(dt);
         if factor <= 0.0 {
             return;
         }
-        let mut deltas = vec![GasDelta::default(); self.tile_atmos.len()];
-        const NEIGHBORS: &[(i32, i32)] = &[(1, 0), (0, 1), (1, 1), (-1, 1)];
-        for y in 0..height {
-            for x in 0..width {
-                let idx_a = Self::idx(x as u32, y as u32, self.width);
-                if !Self::tile_supports_atmos(self.tiles[idx_a].tile_type) {
-                    continue;
-                }
-                for (dx, dy) in NEIGHBORS {
-                    let nx = x + dx;
-                    let ny = y + dy;
-                    if nx < 0 || ny < 0 || nx >= width || ny >= height {
-                        continue;
-                    }
-                    let idx_b = Self::idx(nx as u32, ny as u32, self.width);
-                    if !Self::tile_supports_atmos(self.tiles[idx_b].tile_type) {
-                        continue;
-                    }
-                    let cell_a = self.tile_atmos[idx_a].clone();
-                    let cell_b = self.tile_atmos[idx_b].clone();
-                    let delta_o2 = (cell_b.o2_kg - cell_a.o2_kg) * factor;
-                    let delta_n2 = (cell_b.n2_kg - cell_a.n2_kg) * factor;
-                    let delta_co2 = (cell_b.co2_kg - cell_a.co2_kg) * factor;
-                    deltas[idx_a].o2_kg += delta_o2;
-                    deltas[idx_b].o2_kg -= delta_o2;
-                    deltas[idx_a].n2_kg += delta_n2;
-                    deltas[idx_b].n2_kg -= delta_n2;
-                    deltas[idx_a].co2_kg += delta_co2;
-                    deltas[idx_b].co2_kg -= delta_co2;
+        self.diffuse(factor, |_, _| true);
+    }
+
+    /// Fraction of the difference between two neighbouring tiles that
+    /// evens out over `dt` seconds.
+    pub(crate) fn diffusion_factor(dt: f32) -> f32 {
+        (ATMOS_DIFFUSION_COEFF * dt).min(ATMOS_DIFFUSION_MAX_FRACTION)
+    }
+
+    /// Call `f` once for each pair of neighbouring tile indices that
+    /// exchange gas. Uses the cached adjacency unless the tiles were resized
+    /// since it was built.
+    pub(crate) fn for_each_atmos_pair(&self, mut f: impl FnMut(usize, usize)) {
+        let cached = self.adjacency.links.len() == self.tiles.len();
+        for idx_a in 0..self.tiles.len() {
+            let links = if cached {
+                self.adjacency.links[idx_a]
+            } else {
+                self.atmos_links(idx_a)
+            };
+            if links == 0 {
+                continue;
+            }
+            for (bit, (dx, dy)) in ATMOS_NEIGHBORS.iter().enumerate() {
+                if links & (1 << bit) != 0 {
+                    let idx_b = (idx_a as isize + (dy * self.width as i32 + dx) as isize) as usize;
+                    f(idx_a, idx_b);
                 }
             }
         }
-        for (cell, delta) in self.tile_atmos.iter_mut().zip(deltas.into_iter()) {
+    }
+
+    /// Whether gas moves between a tile of this type and its neighbours.
+    /// Closed doors hold gas but seal it in.
+    fn passes_gas(tile_type: TileType) -> bool {
+        Self::tile_supports_atmos(tile_type) && tile_type != TileType::DoorClosed
+    }
+
+    /// The `ATMOS_NEIGHBORS` bits of tile `idx`, worked out from the tiles.
+    fn atmos_links(&self, idx: usize) -> u8 {
+        if !Self::passes_gas(self.tiles[idx].tile_type) {
+            return 0;
+        }
+        let width = self.width as usize;
+        let (x, y) = ((idx % width) as i32, (idx / width) as i32);
+        let mut links = 0;
+        for (bit, (dx, dy)) in ATMOS_NEIGHBORS.iter().enumerate() {
+            let (nx, ny) = (x + dx, y + dy);
+            if !self.in_bounds(nx, ny) {
+                continue;
+            }
+            let neighbor = Self::idx(nx as u32, ny as u32, self.width);
+            if Self::passes_gas(self.tiles[neighbor].tile_type) {
+                links |= 1 << bit;
+            }
+        }
+        links
+    }
+
+    /// Rebuild the cached adjacency from scratch. Call after editing
+    /// `tiles` directly; `set_tile_type` keeps it up to date by itself.
+    pub fn rebuild_adjacency(&mut self) {
+        self.adjacency.links = (0..self.tiles.len())
+            .map(|idx| self.atmos_links(idx))
+            .collect();
+        self.room_solver.invalidate();
+    }
+
+    /// Update the cached links of the tile at `(x, y)` and of the tiles
+    /// that link to it.
+    fn update_adjacency(&mut self, x: u32, y: u32) {
+        if self.adjacency.links.len() != self.tiles.len() {
+            self.rebuild_adjacency();
+            return;
+        }
+        let idx = Self::idx(x, y, self.width);
+        self.adjacency.links[idx] = self.atmos_links(idx);
+        for (dx, dy) in ATMOS_NEIGHBORS {
+            let (nx, ny) = (x as i32 - dx, y as i32 - dy);
+            if self.in_bounds(nx, ny) {
+                let neighbor = Self::idx(nx as u32, ny as u32, self.width);
+                self.adjacency.links[neighbor] = self.atmos_links(neighbor);
+            }
+        }
+    }
+
+    /// Diffuse gas by `factor` between the neighbouring tiles for which
+    /// `include` holds.
+    pub(crate) fn diffuse(&mut self, factor: f32, include: impl Fn(usize, usize) -> bool) {
+        let mut deltas = vec![GasDelta::default(); self.tile_atmos.len()];
+        self.for_each_atmos_pair(|idx_a, idx_b| {
+            if !include(idx_a, idx_b) {
+                return;
+            }
+            let cell_a = &self.tile_atmos[idx_a];
+            let cell_b = &self.tile_atmos[idx_b];
+            let delta_o2 = (cell_b.o2_kg - cell_a.o2_kg) * factor;
+            let delta_n2 = (cell_b.n2_kg - cell_a.n2_kg) * factor;
+            let delta_co2 = (cell_b.co2_kg - cell_a.co2_kg) * factor;
+            deltas[idx_a].o2_kg += delta_o2;
+            deltas[idx_b].o2_kg -= delta_o2;
+            deltas[idx_a].n2_kg += delta_n2;
+            deltas[idx_b].n2_kg -= delta_n2;
+            deltas[idx_a].co2_kg += delta_co2;
+            deltas[idx_b].co2_kg -= delta_co2;
+        });
+        for (cell, delta) in self.tile_atmos.iter_mut().zip(deltas) {
             cell.o2_kg += delta.o2_kg;
             cell.n2_kg += delta.n2_kg;
             cell.co2_kg += delta.co2_kg;
@@ -1184,6 +1513,7 @@ impl ShipInterior {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InteriorWorld {
     pub ship: ShipInterior,
     pub pawn: Pawn,
@@ -1216,7 +1546,75 @@ impl InteriorWorld {
         self.command_queue.push_back(command);
     }
 
+    /// Feed the interior state that affects future simulation into `hasher`.
+    pub fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_f64(self.atmos_accumulator);
+        for (tile, atmos) in self.ship.tiles.iter().zip(&self.ship.tile_atmos) {
+            hasher.write_bytes(tile.tile_type.as_str().as_bytes());
+            hasher.write_f32(atmos.o2_kg);
+            hasher.write_f32(atmos.n2_kg);
+            hasher.write_f32(atmos.co2_kg);
+            hasher.write_f32(atmos.temp_c);
+        }
+        for device in &self.ship.devices {
+            hasher.write_u64(device.id);
+            hasher.write_bool(device.online);
+            hasher.write_f32(device.power_kw);
+            match &device.data {
+                DeviceData::Tank(data) => {
+                    hasher.write_f32(data.o2_kg);
+                    hasher.write_f32(data.n2_kg);
+                    hasher.write_f32(data.co2_kg);
+                    hasher.write_f32(data.xenon_kg);
+                    hasher.write_f32(data.fuel_kg);
+                }
+                DeviceData::Reactor(data) => {
+                    hasher.write_f32(data.fuel_kg);
+                    hasher.write_f32(data.throttle);
+                    hasher.write_bool(data.online);
+                }
+                DeviceData::Dispenser(data) => {
+                    hasher.write_bool(data.active);
+                    hasher.write_f32(data.rate_kg_per_s);
+                    hasher.write_bytes(data.gas_type.config_key().as_bytes());
+                }
+                DeviceData::Light(data) => hasher.write_f32(data.intensity),
+                DeviceData::DoorDevice(data) => hasher.write_bool(data.open),
+                DeviceData::FoodGenerator(data) => hasher.write_f32(data.food_units),
+                DeviceData::Custom(data) => {
+                    hasher.write_bytes(data.kind().as_bytes());
+                    hasher.write_bytes(&data.state());
+                }
+                _ => {}
+            }
+        }
+        let pawn = &self.pawn;
+        hasher.write_u32(pawn.x);
+        hasher.write_u32(pawn.y);
+        hasher.write_bytes(pawn.status.as_str().as_bytes());
+        hasher.write_f32(pawn.needs.hunger);
+        hasher.write_f32(pawn.needs.thirst);
+        hasher.write_f32(pawn.needs.rest);
+        hasher.write_f32(pawn.suffocation_time);
+        for part in &pawn.health.body_parts {
+            hasher.write_f32(part.hp);
+        }
+    }
+
+    /// Advance by `dt`, spending at most `config.interior.work_budget` work
+    /// units on the atmosphere and room layout; see `step_with_budget`.
     pub fn step(&mut self, dt: f64, config: &GameConfig) {
+        let mut budget = WorkBudget::new(config.interior.work_budget);
+        self.step_with_budget(dt, config, &mut budget);
+    }
+
+    /// Advance by `dt` within `budget`. A stale room layout is rebuilt a
+    /// piece at a time, with the tile solver standing in until it is ready,
+    /// and atmosphere ticks that don't fit stay in the accumulator for a
+    /// later step. Once the atmosphere falls more than
+    /// `COARSE_MAX_ATMOS_TICKS` ticks behind, the backlog runs as one
+    /// stretched tick so it can't grow without bound.
+    pub fn step_with_budget(&mut self, dt: f64, config: &GameConfig, budget: &mut WorkBudget) {
         self.process_commands(config);
         self.ship.step(dt, config);
         self.update_pawn_needs(dt);
@@ -1225,14 +1623,62 @@ impl InteriorWorld {
         if tick <= f64::EPSILON {
             return;
         }
+        let mut solver = config.atmosphere.solver;
+        if solver == AtmosSolver::Rooms && !self.ship.rebuild_rooms(budget) {
+            solver = AtmosSolver::Tiles;
+        }
+        let cost = self.ship.tiles.len() as u64;
         while self.atmos_accumulator >= tick {
+            if !budget.try_spend(cost) {
+                let backlog = Float::floor(self.atmos_accumulator / tick);
+                if backlog > COARSE_MAX_ATMOS_TICKS as f64 {
+                    let span = backlog * tick;
+                    self.ship.step_atmosphere_with(solver, span as f32);
+                    self.apply_pawn_atmos_effects(span as f32, &config.atmosphere);
+                    self.atmos_accumulator -= span;
+                }
+                break;
+            }
             let dt_f32 = tick as f32;
-            self.ship.step_atmosphere(dt_f32);
+            self.ship.step_atmosphere_with(solver, dt_f32);
             self.apply_pawn_atmos_effects(dt_f32, &config.atmosphere);
             self.atmos_accumulator -= tick;
         }
     }
 
+    /// Whether an atmosphere tick is overdue, because an earlier step ran
+    /// out of budget.
+    pub fn atmosphere_behind(&self, config: &GameConfig) -> bool {
+        self.atmos_accumulator >= config.atmosphere.tick_interval_s as f64
+    }
+
+    /// Abstracted step used during time warp: devices and needs advance by
+    /// the full `dt`, but the atmosphere runs at most
+    /// `COARSE_MAX_ATMOS_TICKS` stretched ticks instead of one per interval.
+    pub fn step_coarse(&mut self, dt: f64, config: &GameConfig) {
+        self.process_commands(config);
+        self.ship.step(dt, config);
+        self.update_pawn_needs(dt);
+        self.atmos_accumulator += dt;
+        let tick = config.atmosphere.tick_interval_s as f64;
+        if tick <= f64::EPSILON {
+            return;
+        }
+        let ticks = Float::floor(self.atmos_accumulator / tick);
+        if ticks < 1.0 {
+            return;
+        }
+        let span = ticks * tick;
+        let coarse_ticks = (ticks as u32).min(COARSE_MAX_ATMOS_TICKS);
+        let coarse_dt = (span / coarse_ticks as f64) as f32;
+        for _ in 0..coarse_ticks {
+            self.ship
+                .step_atmosphere_with(config.atmosphere.solver, coarse_dt);
+            self.apply_pawn_atmos_effects(coarse_dt, &config.atmosphere);
+        }
+        self.atmos_accumulator -= span;
+    }
+
     fn process_commands(&mut self, config: &GameConfig) {
         while let Some(command) = self.command_queue.pop_front() {
             match command {
@@ -1251,6 +1697,34 @@ impl InteriorWorld {
                 InteriorCommand::ShipComputerToggle { device_id } => {
                     self.ship.toggle_device_from_computer(device_id);
                 }
+                InteriorCommand::SetDevice {
+                    device_id,
+                    online,
+                    setpoint,
+                } => {
+                    if let Some(online) = online {
+                        self.ship.set_device_online(device_id, online);
+                    }
+                    if let Some(setpoint) = setpoint {
+                        self.ship.set_device_setpoint(device_id, setpoint);
+                    }
+                }
+                InteriorCommand::SetDispenserGas { device_id, gas } => {
+                    self.ship.set_dispenser_gas(device_id, gas);
+                }
+                InteriorCommand::ReactorThrottle {
+                    device_id,
+                    throttle,
+                } => {
+                    let is_reactor = self
+                        .ship
+                        .devices
+                        .iter()
+                        .any(|d| d.id == device_id && matches!(d.data, DeviceData::Reactor(_)));
+                    if is_reactor {
+                        self.ship.set_device_setpoint(device_id, throttle);
+                    }
+                }
             }
         }
     }
@@ -1280,15 +1754,14 @@ impl InteriorWorld {
             return;
         }
         let mut door_update: Option<(TileType, Vec<(u32, u32)>)> = None;
-        for device in &mut self.ship.devices {
+        let mut custom_device = None;
+        for (idx, device) in self.ship.devices.iter_mut().enumerate() {
             if !device_contains(device, x, y) {
                 continue;
             }
             match &mut device.data {
-                DeviceData::BedDevice(_) => {
-                    if self.pawn.x == x && self.pawn.y == y {
-                        self.toggle_sleep();
-                    }
+                DeviceData::BedDevice(_) if self.pawn.x == x && self.pawn.y == y => {
+                    self.toggle_sleep();
                 }
                 DeviceData::DoorDevice(data) => {
                     data.open = !data.open;
@@ -1322,10 +1795,17 @@ impl InteriorWorld {
                     self.pawn.needs.thirst = (self.pawn.needs.thirst + 0.05).min(1.0);
                     self.pawn.needs.clamp();
                 }
+                DeviceData::Custom(_) => custom_device = Some(idx),
                 _ => {}
             }
             break;
         }
+        if let Some(idx) = custom_device {
+            let pawn = &mut self.pawn;
+            self.ship
+                .run_custom_device(idx, atmos_cfg, |behavior, ctx| behavior.interact(ctx, pawn));
+            pawn.needs.clamp();
+        }
         if let Some((tile_type, tiles)) = door_update {
             for (tx, ty) in tiles {
                 self.ship.set_tile_type(tx, ty, tile_type, atmos_cfg);
@@ -1407,15 +1887,24 @@ impl InteriorWorld {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InteriorCommand {
     MovePawn { dx: i32, dy: i32 },
     ToggleSleep,
     InteractAt { x: u32, y: u32 },
     DeviceAction { device_id: u64, action: DeviceAction },
     ShipComputerToggle { device_id: u64 },
+    SetDevice {
+        device_id: u64,
+        online: Option<bool>,
+        setpoint: Option<f32>,
+    },
+    SetDispenserGas { device_id: u64, gas: GasType },
+    ReactorThrottle { device_id: u64, throttle: f32 },
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DeviceAction {
     Toggle,
 }
@@ -1431,7 +1920,7 @@ fn device_power_group(device_type: DeviceType) -> Option<DevicePowerGroup> {
         DeviceType::NavStation | DeviceType::Transponder | DeviceType::ShipComputer => {
             Some(DevicePowerGroup::NavComms)
         }
-        DeviceType::Light => Some(DevicePowerGroup::Misc),
+        DeviceType::Light | DeviceType::Custom => Some(DevicePowerGroup::Misc),
         _ => None,
     }
 }
@@ -1478,6 +1967,7 @@ impl DeviceType {
             DeviceType::DoorDevice => "DoorDevice",
             DeviceType::PowerLine => "PowerLine",
             DeviceType::GasLine => "GasLine",
+            DeviceType::Custom => "Custom",
         }
     }
 
@@ -1511,6 +2001,7 @@ impl PawnStatus {
 mod tests {
     use super::*;
     use crate::config::GameConfig;
+    use crate::rooms::RoomLayout;
 
     fn make_interior() -> (InteriorWorld, GameConfig) {
         let config = GameConfig::default();
@@ -1571,6 +2062,31 @@ mod tests {
         assert!(spread > 1);
     }
 
+    #[test]
+    fn adjacency_tracks_tile_edits_and_closed_doors_seal() {
+        let (mut interior, config) = make_interior();
+        let ship = &mut interior.ship;
+        ship.set_tile_type(5, 4, TileType::DoorClosed, &config.atmosphere);
+        ship.set_tile_type(8, 3, TileType::Wall, &config.atmosphere);
+        ship.set_tile_type(8, 3, TileType::Floor, &config.atmosphere);
+        let fresh: Vec<u8> = (0..ship.tiles.len())
+            .map(|idx| ship.atmos_links(idx))
+            .collect();
+        assert_eq!(ship.adjacency.links, fresh);
+
+        let door = ShipInterior::idx(5, 4, ship.width);
+        ship.tile_atmos[door].co2_kg += 0.01;
+        let sealed = ship.tile_atmos[door].co2_kg;
+        ship.step_atmosphere(config.atmosphere.tick_interval_s);
+        assert_eq!(ship.tile_atmos[door].co2_kg, sealed);
+        let mut pairs = 0;
+        ship.for_each_atmos_pair(|a, b| {
+            assert!(a != door && b != door);
+            pairs += 1;
+        });
+        assert!(pairs > 0);
+    }
+
     #[test]
     fn pawn_breathing_consumes_o2() {
         let (mut interior, config) = make_interior();
@@ -1603,6 +2119,52 @@ mod tests {
         assert!(after.1 > initial.1);
     }
 
+    #[test]
+    fn coarse_step_tracks_needs_like_fine_step() {
+        let (mut fine, config) = make_interior();
+        let (mut coarse, _) = make_interior();
+        for _ in 0..60 {
+            fine.step(60.0, &config);
+            coarse.step_coarse(60.0, &config);
+        }
+        assert!((fine.pawn.needs.hunger - coarse.pawn.needs.hunger).abs() < 1e-5);
+        assert!(coarse.atmos_accumulator < config.atmosphere.tick_interval_s as f64);
+    }
+
+    #[test]
+    fn work_budget_defers_and_catches_up() {
+        let (mut interior, mut config) = make_interior();
+        let tick = config.atmosphere.tick_interval_s as f64;
+        // Room for one atmosphere tick per step, while two fall due.
+        config.interior.work_budget = interior.ship.tiles.len() as u64;
+        interior.step(2.0 * tick, &config);
+        assert!(interior.atmosphere_behind(&config));
+        for _ in 0..COARSE_MAX_ATMOS_TICKS {
+            interior.step(2.0 * tick, &config);
+        }
+        // The backlog passed the limit and ran as one stretched tick.
+        assert!(!interior.atmosphere_behind(&config));
+
+        // A small budget rebuilds the room layout over several steps, with
+        // the tile solver covering the gap.
+        config.atmosphere.solver = AtmosSolver::Rooms;
+        config.interior.work_budget = 20;
+        interior.step(tick, &config);
+        assert!(!interior.ship.room_solver.is_ready(&interior.ship));
+        let mut steps = 1;
+        while !interior.ship.room_solver.is_ready(&interior.ship) {
+            interior.step(tick, &config);
+            steps += 1;
+        }
+        assert!(steps > 2);
+        let full = RoomLayout::build(&interior.ship);
+        let layout = interior.ship.room_solver.layout().unwrap();
+        assert_eq!(layout.room_count(), full.room_count());
+        for idx in 0..interior.ship.tiles.len() {
+            assert_eq!(layout.room_of(idx), full.room_of(idx));
+        }
+    }
+
     #[test]
     fn pawn_health_initialized_full() {
         let (interior, _) = make_interior();
@@ -1701,6 +2263,87 @@ mod tests {
         assert!(reactor_online, "Reactor should ignore ship-computer toggles");
     }
 
+    fn device_id_of(interior: &InteriorWorld, device_type: DeviceType) -> u64 {
+        interior
+            .ship
+            .devices
+            .iter()
+            .find(|device| device.device_type == device_type)
+            .map(|device| device.id)
+            .expect("device present")
+    }
+
+    #[test]
+    fn set_device_commands_reach_devices() {
+        let (mut interior, config) = make_interior();
+        let light_id = device_id_of(&interior, DeviceType::Light);
+        let dispenser_id = device_id_of(&interior, DeviceType::Dispenser);
+        interior.queue_command(InteriorCommand::SetDevice {
+            device_id: light_id,
+            online: Some(false),
+            setpoint: Some(0.25),
+        });
+        interior.queue_command(InteriorCommand::SetDispenserGas {
+            device_id: dispenser_id,
+            gas: GasType::N2,
+        });
+        interior.step(0.0, &config);
+        let light = interior.ship.devices.iter().find(|d| d.id == light_id);
+        match light.map(|d| (d.online, &d.data)) {
+            Some((false, DeviceData::Light(data))) => {
+                assert!(!data.online);
+                assert!((data.intensity - 0.25).abs() < f32::EPSILON);
+            }
+            other => panic!("unexpected light state {:?}", other),
+        }
+        let dispenser = interior.ship.devices.iter().find(|d| d.id == dispenser_id);
+        match dispenser.map(|d| &d.data) {
+            Some(DeviceData::Dispenser(data)) => assert_eq!(data.gas_type, GasType::N2),
+            other => panic!("unexpected dispenser state {:?}", other),
+        }
+    }
+
+    #[test]
+    fn device_settings_reach_the_state_hash() {
+        let (mut interior, _) = make_interior();
+        let light_id = device_id_of(&interior, DeviceType::Light);
+        let dispenser_id = device_id_of(&interior, DeviceType::Dispenser);
+        let reactor_id = device_id_of(&interior, DeviceType::ReactorUranium);
+        let hash = |interior: &InteriorWorld| {
+            let mut hasher = StateHasher::new();
+            interior.hash_state(&mut hasher);
+            hasher.finish()
+        };
+        let mut last = hash(&interior);
+        let changes: [&dyn Fn(&mut ShipInterior); 4] = [
+            &|ship| ship.set_device_setpoint(light_id, 0.25),
+            &|ship| ship.set_device_setpoint(dispenser_id, 0.5),
+            &|ship| ship.set_dispenser_gas(dispenser_id, GasType::N2),
+            &|ship| ship.set_device_setpoint(reactor_id, 0.5),
+        ];
+        for change in changes {
+            change(&mut interior.ship);
+            let next = hash(&interior);
+            assert_ne!(next, last);
+            last = next;
+        }
+    }
+
+    #[test]
+    fn reactor_throttle_scales_output() {
+        let (mut interior, config) = make_interior();
+        let reactor_id = device_id_of(&interior, DeviceType::ReactorUranium);
+        interior.step(1.0, &config);
+        let full = interior.ship.power.total_production_kw;
+        interior.queue_command(InteriorCommand::ReactorThrottle {
+            device_id: reactor_id,
+            throttle: 0.5,
+        });
+        interior.step(1.0, &config);
+        let half = interior.ship.power.total_production_kw;
+        assert!((half - full * 0.5).abs() < 1e-3);
+    }
+
     #[test]
     fn bed_and_nav_use_two_tiles() {
         let (interior, _) = make_interior();
@@ -0,0 +1,142 @@
+//! Where two orbits around the same primary cross.
+//!
+//! Orbits here are coplanar ellipses sharing a focus, so their paths meet
+//! at most twice. `orbit_crossings` only compares the paths, not where the
+//! bodies are on them; whether two bodies actually reach a crossing
+//! together is for `World::next_warp_time` with `WarpTarget::Encounter`
+//! or for transfer planning to work out.
+
+use alloc::vec::Vec;
+
+use crate::math::Float;
+use crate::{angle, error, GgwError, OrbitState, Vec2, World};
+
+/// A point both orbits pass through.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrbitCrossing {
+    /// Relative to the primary.
+    pub position: Vec2,
+    pub radius: f64,
+    /// True anomaly of the crossing on the first orbit, in `[-π, π)`.
+    pub true_anomaly_a: f64,
+    /// True anomaly of the crossing on the second orbit, in `[-π, π)`.
+    pub true_anomaly_b: f64,
+}
+
+/// Points where the paths of `a` and `b` cross, in order of increasing
+/// true anomaly on `a`: none, one where they just touch, or two. Paths
+/// that coincide have no isolated crossings and give none.
+pub fn orbit_crossings(a: &OrbitState, b: &OrbitState) -> Vec<OrbitCrossing> {
+    let (p_a, e_a, w_a) = conic(a);
+    let (p_b, e_b, w_b) = conic(b);
+    // r = p / (1 + e cos(θ - ω)) on both gives
+    // x cos θ + y sin θ = p_b - p_a, with θ measured from the world x axis.
+    let (sin_a, cos_a) = Float::sin_cos(w_a);
+    let (sin_b, cos_b) = Float::sin_cos(w_b);
+    let x = p_a * e_b * cos_b - p_b * e_a * cos_a;
+    let y = p_a * e_b * sin_b - p_b * e_a * sin_a;
+    let reach = Float::sqrt(x * x + y * y);
+    let ratio = (p_b - p_a) / reach;
+    // Coinciding paths, or none that meet.
+    if reach.is_nan() || reach <= 1e-9 * p_a.max(p_b) || ratio.abs() > 1.0 + 1e-12 {
+        return Vec::new();
+    }
+    let ratio = ratio.clamp(-1.0, 1.0);
+    let centre = Float::atan2(y, x);
+    let spread = Float::atan2(Float::sqrt(1.0 - ratio * ratio), ratio);
+    let mut angles = Vec::with_capacity(2);
+    angles.push(centre - spread);
+    if spread > 1e-9 {
+        angles.push(centre + spread);
+    }
+
+    let mut crossings: Vec<OrbitCrossing> = angles
+        .into_iter()
+        .map(|theta| {
+            let true_anomaly_a = angle::wrap_pi(theta - w_a);
+            let radius = p_a / (1.0 + e_a * Float::cos(true_anomaly_a));
+            OrbitCrossing {
+                position: Vec2::new(1.0, 0.0).rotate(theta) * radius,
+                radius,
+                true_anomaly_a,
+                true_anomaly_b: angle::wrap_pi(theta - w_b),
+            }
+        })
+        .collect();
+    crossings.sort_by(|p, q| p.true_anomaly_a.total_cmp(&q.true_anomaly_a));
+    crossings
+}
+
+/// Semi-latus rectum, eccentricity and argument of periapsis.
+fn conic(orbit: &OrbitState) -> (f64, f64, f64) {
+    let e = orbit.eccentricity;
+    (
+        orbit.semi_major_axis * (1.0 - e * e),
+        e,
+        orbit.arg_of_periapsis,
+    )
+}
+
+impl World {
+    /// Where the paths of `body_a` and `body_b` cross, relative to their
+    /// primary; see `orbit_crossings`. Bodies around different primaries
+    /// have no crossings here.
+    pub fn orbit_crossings(&self, body_a: u64, body_b: u64) -> error::Result<Vec<OrbitCrossing>> {
+        let a = self.body(body_a).ok_or(GgwError::UnknownBody(body_a))?;
+        let b = self.body(body_b).ok_or(GgwError::UnknownBody(body_b))?;
+        if a.parent != b.parent {
+            return Ok(Vec::new());
+        }
+        Ok(orbit_crossings(&a.orbit, &b.orbit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orbit(periapsis: f64, apoapsis: f64, arg_of_periapsis: f64) -> OrbitState {
+        let a = 0.5 * (periapsis + apoapsis);
+        OrbitState {
+            semi_major_axis: a,
+            eccentricity: (apoapsis - periapsis) / (apoapsis + periapsis),
+            arg_of_periapsis,
+            mean_anomaly_at_epoch: 0.0,
+            epoch: 0.0,
+        }
+    }
+
+    #[test]
+    fn crossings_lie_on_both_orbits() {
+        let circle = orbit(7_000_000.0, 7_000_000.0, 0.0);
+        let transfer = orbit(6_500_000.0, 8_000_000.0, 1.0);
+        let crossings = orbit_crossings(&circle, &transfer);
+        assert_eq!(crossings.len(), 2);
+        for crossing in &crossings {
+            assert!((crossing.radius - 7_000_000.0).abs() < 1e-3);
+            assert!((crossing.position.length() - crossing.radius).abs() < 1e-3);
+            // Each orbit reaches that radius at that anomaly, in that
+            // direction.
+            for (orbit, nu) in [
+                (circle, crossing.true_anomaly_a),
+                (transfer, crossing.true_anomaly_b),
+            ] {
+                let (p, e, w) = conic(&orbit);
+                assert!((p / (1.0 + e * nu.cos()) - crossing.radius).abs() < 1e-3);
+                let direction = Vec2::new(1.0, 0.0).rotate(nu + w);
+                assert!(direction.distance(crossing.position.normalized()) < 1e-9);
+            }
+        }
+        assert!(crossings[0].true_anomaly_a < crossings[1].true_anomaly_a);
+
+        // Nested, touching at periapsis, and the same path twice.
+        let outer = orbit(7_500_000.0, 9_000_000.0, 2.0);
+        assert!(orbit_crossings(&circle, &outer).is_empty());
+        let grazing = orbit(7_000_000.0, 9_000_000.0, 0.5);
+        let touch = orbit_crossings(&circle, &grazing);
+        assert_eq!(touch.len(), 1);
+        assert!(touch[0].true_anomaly_b.abs() < 1e-4);
+        assert!(orbit_crossings(&transfer, &transfer).is_empty());
+    }
+}
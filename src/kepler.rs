@@ -0,0 +1,113 @@
+//! Per-body Kepler propagation state kept between `World::step` calls.
+//!
+//! A body's elements only change on a burn or an SOI change, so the mean
+//! motion and other element-derived constants are computed once per orbit.
+//! The eccentric anomaly from the previous step, advanced by the change in
+//! mean anomaly, seeds Newton's method, which then converges in one or two
+//! iterations instead of starting cold.
+//!
+//! A warm start converges to the same root as a cold one, but not always to
+//! the same last bit. Every peer steps through the same sequence of times, so
+//! lockstep stays in agreement.
+
+use crate::math::Float;
+use crate::{
+    angle, error, kepler_seed, solve_kepler, state_from_eccentric_anomaly, validate_orbit,
+};
+use crate::{OrbitState, Vec2};
+
+#[derive(Clone, Debug)]
+pub(crate) struct KeplerCache {
+    /// Elements and `mu` the constants below were derived from.
+    orbit: OrbitState,
+    mu: f64,
+    n: f64,
+    sqrt_one_minus_e2: f64,
+    /// Mean and eccentric anomaly at the last propagated time.
+    last_solution: Option<(f64, f64)>,
+}
+
+impl KeplerCache {
+    pub(crate) fn new(orbit: &OrbitState, mu: f64) -> error::Result<Self> {
+        validate_orbit(orbit)?;
+        let a = orbit.semi_major_axis;
+        let e = orbit.eccentricity;
+        Ok(Self {
+            orbit: *orbit,
+            mu,
+            n: Float::sqrt(mu / (a * a * a)),
+            sqrt_one_minus_e2: Float::sqrt((1.0 - e * e).max(0.0)),
+            last_solution: None,
+        })
+    }
+
+    /// Whether this cache was built for `orbit` around a body of `mu`.
+    pub(crate) fn is_valid_for(&self, orbit: &OrbitState, mu: f64) -> bool {
+        self.orbit == *orbit && self.mu == mu
+    }
+
+    /// Position and velocity at `t`, warm-starting from the last solution.
+    pub(crate) fn propagate(&mut self, t: f64) -> (Vec2, Vec2) {
+        let orbit = &self.orbit;
+        let e = orbit.eccentricity;
+        let m = angle::wrap_pi(orbit.mean_anomaly_at_epoch + self.n * (t - orbit.epoch));
+        let cold_seed = kepler_seed(m, e);
+        let e_anom = match self.last_solution {
+            Some((last_m, last_e_anom)) => {
+                // One Newton step's worth of extrapolation from the last root.
+                let slope = 1.0 - e * Float::cos(last_e_anom);
+                let seed =
+                    angle::wrap_pi(last_e_anom + angle::shortest_difference(last_m, m) / slope);
+                match solve_kepler(m, e, seed) {
+                    (e_anom, true) => e_anom,
+                    (_, false) => solve_kepler(m, e, cold_seed).0,
+                }
+            }
+            None => solve_kepler(m, e, cold_seed).0,
+        };
+        self.last_solution = Some((m, e_anom));
+        state_from_eccentric_anomaly(
+            orbit.semi_major_axis,
+            e,
+            self.n,
+            self.sqrt_one_minus_e2,
+            orbit.arg_of_periapsis,
+            e_anom,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbit_to_cartesian;
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn warm_start_tracks_cold_solve() {
+        let orbit = OrbitState {
+            semi_major_axis: 12_000_000.0,
+            eccentricity: 0.85,
+            arg_of_periapsis: -0.7,
+            mean_anomaly_at_epoch: 3.0,
+            epoch: 0.0,
+        };
+        let mut cache = KeplerCache::new(&orbit, MU_EARTH).unwrap();
+        assert!(cache.is_valid_for(&orbit, MU_EARTH));
+        let mut t = 0.0;
+        // Several laps, including each wrap of the mean anomaly at ±π.
+        while t < 60_000.0 {
+            t += 37.0;
+            let (pos, vel) = cache.propagate(t);
+            let (expected_pos, expected_vel) = orbit_to_cartesian(&orbit, MU_EARTH, t).unwrap();
+            assert!(pos.distance(expected_pos) < 1e-3, "t = {}", t);
+            assert!(vel.distance(expected_vel) < 1e-6, "t = {}", t);
+        }
+
+        let mut burned = orbit;
+        burned.eccentricity = 0.2;
+        assert!(!cache.is_valid_for(&burned, MU_EARTH));
+        assert!(!cache.is_valid_for(&orbit, MU_EARTH * 2.0));
+    }
+}
@@ -0,0 +1,288 @@
+//! Bodies resting on a surface.
+//!
+//! A body that hits its primary slower than
+//! `config::CollisionConfig::max_landing_speed_mps` can be set down with
+//! `World::attempt_landing`. From then on it rides the surface at a fixed
+//! longitude, turning with the planet or moon, instead of following its
+//! orbit back into the ground every tick. A landed body sits out collision
+//! checks and reentry heating, and its `orbit` is left as it was at
+//! touchdown. Thrust, whether an impulse or a burn program, lifts it off
+//! again from wherever the surface has carried it.
+//!
+//! Longitudes are counter-clockwise in the primary's turning frame, from
+//! where its `+x` axis pointed at time 0. `ground_track` gives the
+//! longitudes a flying body passes over in the same terms.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::central::Primary;
+use crate::math::Float;
+use crate::{
+    angle, cartesian_to_orbit, error, orbit_to_cartesian, BodyState, CollisionEvent, GgwError,
+    OrbitState, Seconds, Vec2, World, WorldEvent,
+};
+
+/// Whether a body is flying or sitting on the surface of what it orbits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SurfaceState {
+    Flying,
+    /// Resting at `longitude`, in radians in `[-π, π)`.
+    Landed {
+        longitude: f64,
+    },
+}
+
+/// World-frame position and velocity of a point `height` above `primary`'s
+/// surface at `longitude`, at `time`. `primary` should be placed at `time`.
+pub fn surface_point(primary: &Primary, height: f64, longitude: f64, time: f64) -> (Vec2, Vec2) {
+    let offset = Vec2::new(1.0, 0.0).rotate(longitude + primary.rotation_rate * time)
+        * (primary.radius + height);
+    (
+        primary.position + offset,
+        primary.velocity + offset.perp() * primary.rotation_rate,
+    )
+}
+
+/// Longitude under world-frame `point` at `time`, with `primary` placed at
+/// `time`.
+pub fn longitude_at(primary: &Primary, point: Vec2, time: f64) -> f64 {
+    let offset = point - primary.position;
+    angle::wrap_pi(Float::atan2(offset.y, offset.x) - primary.rotation_rate * time)
+}
+
+/// Longitudes passed over by a body on `orbit` around a primary of `mu`
+/// whose surface turns at `rotation_rate`, at `samples` times spread
+/// evenly from `t0` to `t1`, both ends included. Each is in `[-π, π)`, so
+/// a track drawn from them wraps at the antimeridian.
+pub fn ground_track(
+    orbit: &OrbitState,
+    mu: f64,
+    rotation_rate: f64,
+    t0: f64,
+    t1: f64,
+    samples: usize,
+) -> error::Result<Vec<f64>> {
+    let step = if samples > 1 {
+        (t1 - t0) / (samples - 1) as f64
+    } else {
+        0.0
+    };
+    (0..samples)
+        .map(|i| {
+            let time = t0 + step * i as f64;
+            let (offset, _) = orbit_to_cartesian(orbit, mu, time)?;
+            Ok(angle::wrap_pi(
+                Float::atan2(offset.y, offset.x) - rotation_rate * time,
+            ))
+        })
+        .collect()
+}
+
+impl World {
+    /// Ground track of `body_id` over the next `duration`, in `samples`
+    /// points from now; see `ground_track`. A landed body's track stays at
+    /// its longitude.
+    pub fn ground_track(
+        &self,
+        body_id: u64,
+        duration: Seconds,
+        samples: usize,
+    ) -> error::Result<Vec<f64>> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        if let Some(&longitude) = self.landed.get(&body_id) {
+            return Ok(vec![longitude; samples]);
+        }
+        let primary = self.primary_of(body);
+        let now = self.sim_time().0;
+        ground_track(
+            &body.orbit,
+            primary.mu,
+            primary.rotation_rate,
+            now,
+            now + duration.0,
+            samples,
+        )
+    }
+
+    /// Set `event`'s first body down where it hit the surface of its
+    /// primary, returning the longitude it landed at. Fails with
+    /// `UnknownCentralBody` if `event` isn't a hit on that surface, and
+    /// with `LandingTooFast` if the body hit faster than
+    /// `config.collision.max_landing_speed_mps`, measured against the
+    /// turning ground. A body already down stays where it is.
+    pub fn attempt_landing(&mut self, event: &CollisionEvent) -> error::Result<f64> {
+        let body = self
+            .body(event.body_a)
+            .ok_or(GgwError::UnknownBody(event.body_a))?;
+        if let Some(&longitude) = self.landed.get(&body.id) {
+            return Ok(longitude);
+        }
+        let primary = *self.primaries_at(event.time).get(body.parent);
+        if event.body_b != primary.collision_id() {
+            return Err(GgwError::UnknownCentralBody(event.body_b));
+        }
+        let ground = (event.contact_point - primary.position).perp() * primary.rotation_rate;
+        let speed = (event.relative_velocity - ground).length();
+        if speed > self.config.collision.max_landing_speed_mps {
+            return Err(GgwError::LandingTooFast(speed));
+        }
+
+        let longitude = longitude_at(&primary, event.contact_point, event.time);
+        let (position, velocity) = surface_point(
+            &self.primary_of(body),
+            body.radius,
+            longitude,
+            self.sim_time().0,
+        );
+        let id = body.id;
+        if let Some(body) = self.get_body_mut(id) {
+            body.position = position;
+            body.velocity = velocity;
+        }
+        self.landed.insert(id, longitude);
+        self.kepler_cache.remove(&id);
+        self.sleep_tracker.wake(id);
+        self.events.push(WorldEvent::Landed {
+            time: self.sim_time().0,
+            body_id: id,
+            longitude,
+        });
+        tracing::debug!(body = id, longitude, speed, "body landed");
+        Ok(longitude)
+    }
+
+    /// Whether `body_id` is flying or landed, or `None` if there is no such
+    /// body.
+    pub fn surface_state(&self, body_id: u64) -> Option<SurfaceState> {
+        self.body(body_id)?;
+        Some(match self.landed.get(&body_id) {
+            Some(&longitude) => SurfaceState::Landed { longitude },
+            None => SurfaceState::Flying,
+        })
+    }
+
+    /// Orbit `body` is on, or for a landed body the one it would be on if
+    /// let go of the surface now.
+    pub(crate) fn flight_orbit(&self, body: &BodyState) -> error::Result<OrbitState> {
+        if !self.landed.contains_key(&body.id) {
+            return Ok(body.orbit);
+        }
+        let primary = self.primary_of(body);
+        cartesian_to_orbit(
+            body.position - primary.position,
+            body.velocity - primary.velocity,
+            primary.mu,
+            self.sim_time().0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{ThrustEvent, ThrustType, PLANET_RADIUS_M, PLANET_ROTATION_RAD_S};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn slow_touchdowns_stick_to_the_turning_surface() {
+        // Falling in from low orbit is far too fast for any real landing
+        // gear, so the limit is raised once the strict one has been seen
+        // to turn it away.
+        let mut config = GameConfig::default();
+        config.reentry.enabled = false;
+        let mut world = World::new(MU_EARTH, config);
+        let periapsis = PLANET_RADIUS_M - 100_000.0;
+        let apoapsis = PLANET_RADIUS_M + 200_000.0;
+        let ship = world
+            .add_body(BodyState::orbiting(OrbitState {
+                semi_major_axis: 0.5 * (periapsis + apoapsis),
+                eccentricity: (apoapsis - periapsis) / (apoapsis + periapsis),
+                arg_of_periapsis: 0.0,
+                mean_anomaly_at_epoch: core::f64::consts::PI,
+                epoch: 0.0,
+            }))
+            .unwrap();
+        assert_eq!(world.surface_state(ship), Some(SurfaceState::Flying));
+
+        // Step until the ground comes up, then land.
+        let event = loop {
+            if let Some(event) = world.detect_collisions(Seconds(10.0)).pop() {
+                break event;
+            }
+            world.step(Seconds(10.0));
+        };
+        assert_eq!(event.body_b, 0);
+        world.config.collision.max_landing_speed_mps = 5.0;
+        assert!(matches!(
+            world.attempt_landing(&event),
+            Err(GgwError::LandingTooFast(_))
+        ));
+        world.config.collision.max_landing_speed_mps = 10_000.0;
+        let longitude = world.attempt_landing(&event).unwrap();
+        assert_eq!(
+            world.surface_state(ship),
+            Some(SurfaceState::Landed { longitude })
+        );
+
+        // Carried round with the planet, at the same height and no more
+        // collisions.
+        world.step(Seconds(3_600.0));
+        let body = world.body(ship).unwrap();
+        assert!((body.position.length() - PLANET_RADIUS_M - 5.0).abs() < 1e-6);
+        let primary = world.primary_of(body);
+        assert!(
+            (longitude_at(&primary, body.position, world.sim_time().0) - longitude).abs() < 1e-9
+        );
+        assert!((body.velocity.length() - 7.292_115e-5 * (PLANET_RADIUS_M + 5.0)).abs() < 1e-6);
+        assert!(world.detect_collisions(Seconds(60.0)).is_empty());
+
+        // A burn straight up lifts it off.
+        let up = body.position.normalized() * 100.0;
+        world
+            .apply_thrust_event(&ThrustEvent {
+                body_id: ship,
+                time: world.sim_time().0,
+                delta_v: up,
+                thrust_type: ThrustType::Chemical,
+            })
+            .unwrap();
+        assert_eq!(world.surface_state(ship), Some(SurfaceState::Flying));
+        world.step(Seconds(5.0));
+        assert!(world.altitude(world.body(ship).unwrap()).0 > 100.0);
+    }
+
+    #[test]
+    fn ground_track_drifts_west_with_the_turning_planet() {
+        // A circular orbit whose period is exactly two days' rotation.
+        let period = 4.0 * core::f64::consts::PI / PLANET_ROTATION_RAD_S;
+        let n = 2.0 * core::f64::consts::PI / period;
+        let orbit = OrbitState {
+            semi_major_axis: (MU_EARTH / (n * n)).cbrt(),
+            eccentricity: 0.0,
+            arg_of_periapsis: 0.0,
+            mean_anomaly_at_epoch: 0.0,
+            epoch: 0.0,
+        };
+        let track = ground_track(&orbit, MU_EARTH, PLANET_ROTATION_RAD_S, 0.0, period, 9).unwrap();
+        assert_eq!(track.len(), 9);
+        // The body goes round once while the ground goes round twice, so
+        // the track runs backwards at the orbital rate.
+        for (i, longitude) in track.iter().enumerate() {
+            let expected = angle::wrap_pi(-(i as f64) * core::f64::consts::PI / 4.0);
+            assert!(angle::shortest_difference(*longitude, expected).abs() < 1e-9);
+        }
+        assert_eq!(
+            ground_track(&orbit, MU_EARTH, 0.0, 5.0, 10.0, 1)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(ground_track(&orbit, MU_EARTH, 0.0, 0.0, 1.0, 0)
+            .unwrap()
+            .is_empty());
+    }
+}
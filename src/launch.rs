@@ -0,0 +1,268 @@
+//! Ascents from the surface to orbit.
+//!
+//! `World::launch` lifts a landed body off and flies it up to a circular
+//! orbit over the steps that follow, in three parts. It climbs straight up
+//! for `config::LaunchConfig::vertical_rise_s`, then pitches over to the
+//! heading given and on towards the horizon as its apoapsis rises, until
+//! that reaches the target. It coasts up to the apoapsis and, once there,
+//! circularizes with a single impulse. A shallow ascent can get there
+//! only after passing its apoapsis, flying level, and then circularizes
+//! straight away, a little lower.
+//!
+//! Thrust is a steady `acceleration_mps2` along the pitch, applied in
+//! slices of at most `BURN_STEP_S` and refit into an orbit after each, as
+//! burn programs are; like them it costs no propellant and records no
+//! thrust events, only `Launched` at liftoff and `ReachedOrbit` at the
+//! end. There is no drag and no reentry heating on the way up. Ascents
+//! only turn the way the surface does, as `OrbitState` only describes
+//! prograde orbits.
+//!
+//! An ascent is dropped if the body lands or docks before it is done, and
+//! any thrust applied meanwhile adds to the trajectory it is flying.
+
+use core::f64::consts::FRAC_PI_2;
+
+use crate::maneuver::{self, BURN_STEP_S};
+use crate::math::Float;
+use crate::{
+    cartesian_to_orbit, error, orbit_to_cartesian, GgwError, Meters, Seconds, World, WorldEvent,
+};
+
+/// Where an ascent has got to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AscentPhase {
+    /// Climbing straight up off the pad.
+    Rise,
+    /// Pitching over towards the horizon until the apoapsis is high enough.
+    Turn,
+    /// Engines off, until circularizing at `apoapsis_time`.
+    Coast { apoapsis_time: f64 },
+}
+
+/// A body on its way up to orbit.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Ascent {
+    /// Angle from vertical to pitch over to at the end of the rise.
+    pub(crate) heading: f64,
+    /// Apoapsis to reach, from the primary's centre.
+    pub(crate) target_radius: f64,
+    pub(crate) liftoff: f64,
+    pub(crate) phase: AscentPhase,
+}
+
+impl World {
+    /// Launch landed `body_id` towards a circular orbit about
+    /// `target_apoapsis` above its primary's surface.
+    /// `heading` is the angle from vertical, in radians in `(0, π/2]`, the
+    /// body pitches over to once clear of the pad, turning the way the
+    /// surface does; a small one climbs steeply, a large one stays low.
+    /// Fails with `NotLanded` if the body isn't resting on a surface, and
+    /// with `InvalidLaunch` for a heading outside that range or a target
+    /// that isn't above the surface.
+    pub fn launch(
+        &mut self,
+        body_id: u64,
+        heading: f64,
+        target_apoapsis: Meters,
+    ) -> error::Result<()> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        if !self.landed.contains_key(&body_id) {
+            return Err(GgwError::NotLanded(body_id));
+        }
+        let valid = heading > 0.0
+            && heading <= FRAC_PI_2
+            && target_apoapsis.0 > 0.0
+            && target_apoapsis.0.is_finite();
+        if !valid {
+            return Err(GgwError::InvalidLaunch);
+        }
+        let primary = self.primary_of(body);
+        let orbit = self.flight_orbit(body)?;
+        let now = self.sim_time().0;
+        self.landed.remove(&body_id);
+        if let Some(body) = self.get_body_mut(body_id) {
+            body.orbit = orbit;
+        }
+        self.ascents.insert(
+            body_id,
+            Ascent {
+                heading,
+                target_radius: primary.radius + target_apoapsis.0,
+                liftoff: now,
+                phase: AscentPhase::Rise,
+            },
+        );
+        self.kepler_cache.remove(&body_id);
+        self.sleep_tracker.wake(body_id);
+        self.events
+            .push(WorldEvent::Launched { time: now, body_id });
+        tracing::debug!(body = body_id, heading, "body launched");
+        Ok(())
+    }
+
+    /// Where `body_id`'s ascent has got to, or `None` if it isn't on one.
+    pub fn ascent(&self, body_id: u64) -> Option<AscentPhase> {
+        self.ascents.get(&body_id).map(|ascent| ascent.phase)
+    }
+
+    /// Fly every ascent through the tick of `dt` seconds just ended. One
+    /// whose body has landed again, or whose trajectory no longer works
+    /// out, is dropped.
+    pub(crate) fn fly_ascents(&mut self, dt: f64) {
+        if self.ascents.is_empty() {
+            return;
+        }
+        let now = self.sim_time().0;
+        let ascents = core::mem::take(&mut self.ascents);
+        for (body_id, mut ascent) in ascents {
+            if self.landed.contains_key(&body_id) {
+                continue;
+            }
+            let from = (now - dt).max(ascent.liftoff);
+            match self.ascend(body_id, &mut ascent, from, now) {
+                Ok(None) => {
+                    self.ascents.insert(body_id, ascent);
+                }
+                Ok(Some(time)) => {
+                    self.events.push(WorldEvent::ReachedOrbit { time, body_id });
+                    tracing::debug!(body = body_id, "ascent finished");
+                }
+                Err(err) => {
+                    tracing::warn!(body = body_id, error = %err, "ascent dropped")
+                }
+            }
+        }
+    }
+
+    /// Fly `ascent` between `from` and `to`, leaving the body where its new
+    /// orbit puts it at `to`. Returns when it circularized, if it did.
+    fn ascend(
+        &mut self,
+        body_id: u64,
+        ascent: &mut Ascent,
+        from: f64,
+        to: f64,
+    ) -> error::Result<Option<f64>> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        let primary = *self.primaries().get(body.parent);
+        let (mu, radius) = (primary.mu, primary.radius);
+        let acceleration = self.config.launch.acceleration_mps2;
+        let mut orbit = body.orbit;
+        let mut circularized = None;
+        // Ceiling, as slices are at most `BURN_STEP_S` long.
+        let steps = (-Float::floor((from - to) / BURN_STEP_S)).max(1.0);
+        let slice = (to - from) / steps;
+        for i in 0..steps as usize {
+            let end = from + slice * (i + 1) as f64;
+            let pitch = match ascent.phase {
+                AscentPhase::Rise => 0.0,
+                AscentPhase::Turn => {
+                    let climbed = (orbit.apoapsis() - radius) / (ascent.target_radius - radius);
+                    ascent.heading + (FRAC_PI_2 - ascent.heading) * climbed.clamp(0.0, 1.0)
+                }
+                AscentPhase::Coast { apoapsis_time } => {
+                    if apoapsis_time > end {
+                        continue;
+                    }
+                    let (position, velocity) = orbit_to_cartesian(&orbit, mu, apoapsis_time)?;
+                    let circular =
+                        position.normalized().perp() * Float::sqrt(mu / position.length());
+                    orbit =
+                        maneuver::orbit_after_burn(&orbit, mu, apoapsis_time, circular - velocity)?;
+                    circularized = Some(apoapsis_time);
+                    break;
+                }
+            };
+            let (position, velocity) = orbit_to_cartesian(&orbit, mu, end - 0.5 * slice)?;
+            let up = position.normalized();
+            let (sin, cos) = (Float::sin(pitch), Float::cos(pitch));
+            let thrust = (up * cos + up.perp() * sin) * (acceleration * slice);
+            orbit = cartesian_to_orbit(position, velocity + thrust, mu, end - 0.5 * slice)?;
+            ascent.phase = match ascent.phase {
+                AscentPhase::Rise if end - ascent.liftoff >= self.config.launch.vertical_rise_s => {
+                    AscentPhase::Turn
+                }
+                AscentPhase::Turn if orbit.apoapsis() >= ascent.target_radius => {
+                    // Already on the way down, circularize straight away.
+                    let wait = orbit.time_to_apoapsis(mu, Seconds(end)).0;
+                    let wait = if wait > 0.5 * orbit.period(mu) {
+                        0.0
+                    } else {
+                        wait
+                    };
+                    AscentPhase::Coast {
+                        apoapsis_time: end + wait,
+                    }
+                }
+                phase => phase,
+            };
+        }
+        let (position, velocity) = primary.state_at(&orbit, to)?;
+        if let Some(body) = self.get_body_mut(body_id) {
+            body.orbit = orbit;
+            body.position = position;
+            body.velocity = velocity;
+        }
+        self.sleep_tracker.wake(body_id);
+        Ok(circularized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::landing::{surface_point, SurfaceState};
+    use crate::{BodyState, Seconds, PLANET_RADIUS_M};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn landed_ships_climb_to_a_circular_orbit() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        assert_eq!(
+            world.launch(ship, 0.3, Meters(200_000.0)),
+            Err(GgwError::NotLanded(ship))
+        );
+        let primary = world.primary_of(world.body(ship).unwrap());
+        let (position, velocity) = surface_point(&primary, 5.0, 0.0, world.sim_time().0);
+        let body = world.get_body_mut(ship).unwrap();
+        body.position = position;
+        body.velocity = velocity;
+        world.landed.insert(ship, 0.0);
+        assert_eq!(
+            world.launch(ship, 2.0, Meters(200_000.0)),
+            Err(GgwError::InvalidLaunch)
+        );
+
+        world.launch(ship, 0.3, Meters(200_000.0)).unwrap();
+        assert_eq!(world.surface_state(ship), Some(SurfaceState::Flying));
+        assert_eq!(world.ascent(ship), Some(AscentPhase::Rise));
+        let mut steps = 0;
+        while world.ascent(ship).is_some() {
+            world.step(Seconds(1.0));
+            steps += 1;
+            assert!(steps < 3_600, "ascent never finished");
+            let altitude = world.body(ship).unwrap().position.length() - PLANET_RADIUS_M;
+            assert!(altitude > 0.0);
+        }
+        let events = world.drain_events();
+        assert!(events.contains(&WorldEvent::Launched {
+            time: 0.0,
+            body_id: ship
+        }));
+        assert!(events.iter().any(
+            |event| matches!(event, WorldEvent::ReachedOrbit { body_id, .. } if *body_id == ship)
+        ));
+
+        let orbit = world.body(ship).unwrap().orbit;
+        assert!(orbit.eccentricity < 1e-6);
+        assert!(orbit.periapsis() > PLANET_RADIUS_M + 190_000.0);
+        assert!(orbit.apoapsis() < PLANET_RADIUS_M + 210_000.0);
+    }
+}
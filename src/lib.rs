@@ -1,19 +1,194 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::f64::consts::PI;
 
+pub mod angle;
+
+pub mod autopilot;
+
+pub mod attitude;
+
+pub mod batch;
+
+pub mod blast;
+
+pub mod budget;
+
+pub mod builder;
+
+pub mod cascade;
+
+pub mod central;
+
+pub mod collision;
+
+pub mod comms;
+
+pub mod clock;
+
 pub mod config;
 
+pub mod conjunction;
+
+pub mod damage;
+
+pub mod devices;
+
+pub mod diagnostics;
+
+pub mod docking;
+
+pub mod error;
+
+pub mod events;
+
+pub mod export;
+
+pub mod field;
+
+pub mod flyby;
+
+pub mod frame;
+
+pub mod guidance;
+
+pub mod hull;
+
+#[cfg(feature = "autosave")]
+pub mod autosave;
+
 pub mod interior;
 
-use config::GameConfig;
+mod kepler;
+
+#[cfg(feature = "std")]
+pub mod compression;
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+pub mod interest;
+
+pub mod intersect;
+
+pub mod landing;
+
+pub mod launch;
+
+pub mod lockstep;
+
+pub mod maneuver;
+
+pub mod mining;
+
+pub mod nbody;
+
+pub mod preview;
+
+pub mod propellant;
+
+pub mod query;
+
+pub mod replay;
+
+pub mod reentry;
+
+pub mod rooms;
+
+pub mod save;
+
+pub mod scenario;
+
+pub mod sensors;
+
+#[cfg(feature = "shm")]
+pub mod shm;
+
+pub mod sleep;
+
+pub mod snapshot;
+
+pub mod sunlight;
+
+pub mod transfer;
+
+pub mod units;
+
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "bevy")]
+pub mod bevy;
+
+pub mod math;
+
+#[cfg(test)]
+mod test_support;
+
+use autopilot::{Autopilot, AutopilotState};
+use batch::OrbitBatch;
+pub use builder::WorldBuilder;
+use central::{CentralBody, Primaries, Primary};
+use clock::SimClock;
+use collision::{
+    CollisionFilter, CollisionOutcome, CollisionScheduler, CollisionScratch, CollisionStats,
+};
+use comms::Message;
+use config::{AttitudeConfig, GameConfig, SleepConfig};
+use conjunction::Conjunction;
+use damage::HitPoints;
+use diagnostics::Diagnostics;
+use docking::DockedShip;
+pub use error::GgwError;
+pub use events::WorldEvent;
+use guidance::Guidance;
 use interior::InteriorWorld;
+use kepler::KeplerCache;
+use launch::{Ascent, AscentPhase};
+use lockstep::StateHasher;
+use maneuver::{BurnProgram, ManeuverNode};
+use math::Float;
+use nbody::NumericState;
+use preview::{PredictedBody, SoiChange, SoiEvent, SoiTransition, StepPreview, TrajectoryPoint};
+use propellant::Propellant;
+use query::BodyIterExt;
+use scenario::{ScenarioAction, ScenarioEvent, Timeline};
+use sensors::SensorSuite;
+use sleep::SleepTracker;
+pub use units::{Meters, MetersPerSecond, Radians, Seconds};
 
 pub const PLANET_RADIUS_M: f64 = 6_371_000.0;
+/// Sidereal spin of the planet, counter-clockwise.
+pub const PLANET_ROTATION_RAD_S: f64 = 7.292_115e-5;
 pub const GRAVITY_WELL_RADIUS_M: f64 = 1_500_000_000.0;
 pub const GRAVITY_WELL_ALTITUDE_M: f64 = GRAVITY_WELL_RADIUS_M - PLANET_RADIUS_M;
 pub const DESPAWN_RADIUS_M: f64 = PLANET_RADIUS_M + 3.0 * GRAVITY_WELL_ALTITUDE_M;
 pub const TILE_SIZE_METERS: f64 = 1.0;
+/// Largest exterior jump taken in one go while warping.
+pub const WARP_SUBSTEP_S: f64 = 60.0;
+/// Furthest past the current sim time a client may ask to warp in one
+/// request, so no one request holds the world for long.
+pub const MAX_WARP_DURATION_S: f64 = 30.0 * 86_400.0;
+/// Separation below which two bodies count as an encounter for warp purposes.
+pub const ENCOUNTER_DISTANCE_M: f64 = 100_000.0;
+const ENCOUNTER_SCAN_STEP_S: f64 = 10.0;
+/// Sim time an orbit's epoch may fall behind before `step` moves it up to
+/// now, so the phase since epoch stays small enough to keep its precision.
+pub const EPOCH_REBASE_S: f64 = 86_400.0;
+/// Furthest a rebased orbit may put a body from where the old one did;
+/// past this the old elements are kept.
+const EPOCH_REBASE_TOLERANCE_M: f64 = 1e-3;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HullShape {
     pub vertices: Vec<Vec2>,
 }
@@ -27,38 +202,47 @@ impl HullShape {
     }
 }
 
+/// 2D vector, `f64` unless a client opts into `Vec2f` for rendering-grade
+/// precision.
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Vec2 {
-    pub x: f64,
-    pub y: f64,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vec2<T = f64> {
+    pub x: T,
+    pub y: T,
 }
 
-impl Vec2 {
+pub type Vec2f = Vec2<f32>;
+
+impl<T: Float> Vec2<T> {
     pub fn zero() -> Self {
-        Self { x: 0.0, y: 0.0 }
+        Self {
+            x: T::ZERO,
+            y: T::ZERO,
+        }
     }
 
-    pub fn new(x: f64, y: f64) -> Self {
+    pub fn new(x: T, y: T) -> Self {
         Self { x, y }
     }
 
-    pub fn length_squared(self) -> f64 {
+    pub fn length_squared(self) -> T {
         self.x * self.x + self.y * self.y
     }
 
-    pub fn length(self) -> f64 {
+    pub fn length(self) -> T {
         self.length_squared().sqrt()
     }
 
     pub fn normalized(self) -> Self {
         let len = self.length();
-        if len <= 1e-12 {
+        if len <= T::from_f64(1e-12) {
             Self::zero()
         } else {
-            self.scale(1.0 / len)
+            self.scale(T::ONE / len)
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn add(self, other: Self) -> Self {
         Self {
             x: self.x + other.x,
@@ -66,6 +250,7 @@ impl Vec2 {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn sub(self, other: Self) -> Self {
         Self {
             x: self.x - other.x,
@@ -73,43 +258,154 @@ impl Vec2 {
         }
     }
 
-    pub fn scale(self, k: f64) -> Self {
+    pub fn scale(self, k: T) -> Self {
         Self {
             x: self.x * k,
             y: self.y * k,
         }
     }
 
-    pub fn dot(self, other: Self) -> f64 {
+    pub fn dot(self, other: Self) -> T {
         self.x * other.x + self.y * other.y
     }
+
+    /// z component of the 3D cross product; positive when `other` is
+    /// counter-clockwise of `self`.
+    pub fn cross(self, other: Self) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// `self` rotated 90 degrees counter-clockwise.
+    pub fn perp(self) -> Self {
+        Self {
+            x: -self.y,
+            y: self.x,
+        }
+    }
+
+    /// `self` rotated counter-clockwise by `angle` radians.
+    pub fn rotate(self, angle: T) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            x: cos * self.x - sin * self.y,
+            y: sin * self.x + cos * self.y,
+        }
+    }
+
+    /// Linear interpolation: `self` at `t = 0`, `other` at `t = 1`.
+    pub fn lerp(self, other: Self, t: T) -> Self {
+        Self {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+        }
+    }
+
+    pub fn distance(self, other: Self) -> T {
+        self.sub(other).length()
+    }
+
+    /// Signed angle in radians from `self` to `other`, in `(-PI, PI]`.
+    pub fn angle_to(self, other: Self) -> T {
+        self.cross(other).atan2(self.dot(other))
+    }
+
+    /// Convert to another precision, e.g. `Vec2f` for rendering.
+    pub fn cast<U: Float>(self) -> Vec2<U> {
+        Vec2 {
+            x: U::from_f64(self.x.to_f64()),
+            y: U::from_f64(self.y.to_f64()),
+        }
+    }
 }
 
-impl core::ops::Add for Vec2 {
-    type Output = Vec2;
+impl<T: Float> core::ops::Add for Vec2<T> {
+    type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
         self.add(rhs)
     }
 }
 
-impl core::ops::Sub for Vec2 {
-    type Output = Vec2;
+impl<T: Float> core::ops::Sub for Vec2<T> {
+    type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
         self.sub(rhs)
     }
 }
 
-impl core::ops::Mul<f64> for Vec2 {
-    type Output = Vec2;
+impl<T: Float> core::ops::Mul<T> for Vec2<T> {
+    type Output = Self;
 
-    fn mul(self, rhs: f64) -> Self::Output {
+    fn mul(self, rhs: T) -> Self::Output {
         self.scale(rhs)
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+impl<T: Float> core::ops::Div<T> for Vec2<T> {
+    type Output = Self;
+
+    fn div(self, rhs: T) -> Self::Output {
+        Self {
+            x: self.x / rhs,
+            y: self.y / rhs,
+        }
+    }
+}
+
+impl<T: Float> core::ops::Neg for Vec2<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl<T: Float> core::ops::AddAssign for Vec2<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = self.add(rhs);
+    }
+}
+
+impl<T: Float> core::ops::SubAssign for Vec2<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = self.sub(rhs);
+    }
+}
+
+impl<T: Float> core::ops::MulAssign<T> for Vec2<T> {
+    fn mul_assign(&mut self, rhs: T) {
+        *self = self.scale(rhs);
+    }
+}
+
+impl<T: Float> core::ops::DivAssign<T> for Vec2<T> {
+    fn div_assign(&mut self, rhs: T) {
+        *self = *self / rhs;
+    }
+}
+
+impl core::ops::Mul<Vec2<f64>> for f64 {
+    type Output = Vec2<f64>;
+
+    fn mul(self, rhs: Vec2<f64>) -> Self::Output {
+        rhs.scale(self)
+    }
+}
+
+impl core::ops::Mul<Vec2<f32>> for f32 {
+    type Output = Vec2<f32>;
+
+    fn mul(self, rhs: Vec2<f32>) -> Self::Output {
+        rhs.scale(self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrbitState {
     pub semi_major_axis: f64,
     pub eccentricity: f64,
@@ -118,7 +414,132 @@ pub struct OrbitState {
     pub epoch: f64,
 }
 
+impl OrbitState {
+    /// Closest approach to the primary's centre.
+    pub fn periapsis(&self) -> f64 {
+        self.semi_major_axis * (1.0 - self.eccentricity)
+    }
+
+    /// Farthest distance from the primary's centre.
+    pub fn apoapsis(&self) -> f64 {
+        self.semi_major_axis * (1.0 + self.eccentricity)
+    }
+
+    /// Mean anomaly swept per second around a primary of gravitational
+    /// parameter `mu`.
+    pub fn mean_motion(&self, mu: f64) -> f64 {
+        let a = self.semi_major_axis;
+        Float::sqrt(mu / (a * a * a))
+    }
+
+    /// Seconds for one whole orbit around a primary of gravitational
+    /// parameter `mu`.
+    pub fn period(&self, mu: f64) -> f64 {
+        2.0 * PI / self.mean_motion(mu)
+    }
+
+    /// Orbital energy per kilogram, kinetic plus potential; negative for
+    /// every bound orbit.
+    pub fn specific_energy(&self, mu: f64) -> f64 {
+        -mu / (2.0 * self.semi_major_axis)
+    }
+
+    /// `n` points around the whole orbit for drawing it, starting where a
+    /// body on it is at `epoch`; see `sample_points_at`.
+    pub fn sample_points(&self, mu: f64, n: usize) -> error::Result<Vec<Vec2>> {
+        self.sample_points_at(mu, Seconds(self.epoch), n)
+    }
+
+    /// `n` points around the whole orbit, relative to the primary, starting
+    /// where a body on it is at time `t` and going the way it moves. They
+    /// are spaced evenly in eccentric anomaly rather than time, which
+    /// crowds them around periapsis where the curve is tightest and needs
+    /// no Kepler solve past the first. Join the last point to the first to
+    /// close the loop.
+    pub fn sample_points_at(&self, mu: f64, t: Seconds, n: usize) -> error::Result<Vec<Vec2>> {
+        validate_orbit(self)?;
+        let a = self.semi_major_axis;
+        let e = self.eccentricity;
+        let mean_motion = Float::sqrt(mu / (a * a * a));
+        let m = angle::wrap_pi(self.mean_anomaly_at_epoch + mean_motion * (t.0 - self.epoch));
+        let (start, _) = solve_kepler(m, e, kepler_seed(m, e));
+        let sqrt_one_minus_e2 = Float::sqrt((1.0 - e * e).max(0.0));
+        let step = 2.0 * PI / n as f64;
+        Ok((0..n)
+            .map(|i| {
+                let e_anom = start + step * i as f64;
+                let (position, _) = state_from_eccentric_anomaly(
+                    a,
+                    e,
+                    mean_motion,
+                    sqrt_one_minus_e2,
+                    self.arg_of_periapsis,
+                    e_anom,
+                );
+                position
+            })
+            .collect())
+    }
+
+    /// The same orbit with its epoch moved to `time`, around a primary of
+    /// gravitational parameter `mu`. The phase gone by since the old epoch
+    /// is folded into `mean_anomaly_at_epoch`, wrapped to `[-π, π)`.
+    pub fn rebased(&self, mu: f64, time: f64) -> OrbitState {
+        let n = self.mean_motion(mu);
+        OrbitState {
+            mean_anomaly_at_epoch: angle::wrap_pi(
+                self.mean_anomaly_at_epoch + n * (time - self.epoch),
+            ),
+            epoch: time,
+            ..*self
+        }
+    }
+
+    /// Time from `now` until the next periapsis passage around a primary
+    /// of gravitational parameter `mu`. A body passing periapsis at `now`
+    /// waits a whole orbit, so the result is always positive.
+    pub fn time_to_periapsis(&self, mu: f64, now: Seconds) -> Seconds {
+        Seconds(self.time_to_mean_anomaly(mu, now.0, 0.0))
+    }
+
+    /// Time from `now` until the next apoapsis passage; see
+    /// `time_to_periapsis`.
+    pub fn time_to_apoapsis(&self, mu: f64, now: Seconds) -> Seconds {
+        Seconds(self.time_to_mean_anomaly(mu, now.0, PI))
+    }
+
+    /// Angle from periapsis of a body on this orbit at time `t`, around a
+    /// primary of gravitational parameter `mu`, in `[-π, π]`.
+    pub fn true_anomaly_at(&self, mu: f64, t: f64) -> error::Result<f64> {
+        validate_orbit(self)?;
+        let n = self.mean_motion(mu);
+        let m = self.mean_anomaly_at_epoch + n * (t - self.epoch);
+        let e_anom = mean_to_eccentric(m, self.eccentricity);
+        Ok(eccentric_to_true(e_anom, self.eccentricity))
+    }
+
+    /// Time from `now` until the body next reaches true anomaly `target`;
+    /// like `time_to_periapsis`, always positive.
+    pub fn time_to_true_anomaly(&self, mu: f64, now: Seconds, target: f64) -> Seconds {
+        let target = true_to_mean(target, self.eccentricity);
+        Seconds(self.time_to_mean_anomaly(mu, now.0, target))
+    }
+
+    /// Seconds from `now` until the mean anomaly next reaches `target`,
+    /// counting from however many orbits have passed since `epoch`.
+    fn time_to_mean_anomaly(&self, mu: f64, now: f64, target: f64) -> f64 {
+        let n = self.mean_motion(mu);
+        let m = self.mean_anomaly_at_epoch + n * (now - self.epoch);
+        let mut remaining = angle::wrap_two_pi(target - m) / n;
+        if remaining <= 1e-9 {
+            remaining += 2.0 * PI / n;
+        }
+        remaining
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BodyType {
     Ship,
     Asteroid,
@@ -127,6 +548,7 @@ pub enum BodyType {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BodyState {
     pub id: u64,
     pub mass: f64,
@@ -136,16 +558,45 @@ pub struct BodyState {
     pub velocity: Vec2,
     pub body_type: BodyType,
     pub hull_shape: Option<HullShape>,
+    /// Moon this body orbits, by `CentralBody::id`; `None` for the planet.
+    /// `orbit` is relative to the parent, `position` and `velocity` are not.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub parent: Option<u64>,
+    /// Direction the body's local `+x` axis points, in radians
+    /// counter-clockwise from the world `+x` axis.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub orientation: f64,
+    /// Spin in radians per second, counter-clockwise positive.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub angular_velocity: f64,
+    /// Free-form labels for gameplay layers to hang names, factions, cargo
+    /// manifests and the like on. The simulation never reads them, but they
+    /// go wherever the body does: saves, snapshots and the state hash.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tags: BTreeMap<String, String>,
+}
+
+impl BodyState {
+    /// Value of tag `key`, if the body has one.
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags.get(key).map(String::as_str)
+    }
+
+    pub fn speed(&self) -> MetersPerSecond {
+        MetersPerSecond(self.velocity.length())
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ThrustType {
     Rcs,
     Chemical,
     Ion,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ThrustEvent {
     pub body_id: u64,
     pub time: f64,
@@ -153,23 +604,81 @@ pub struct ThrustEvent {
     pub thrust_type: ThrustType,
 }
 
+/// An RCS kick to a body's spin, in radians per second.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TorqueEvent {
+    pub body_id: u64,
+    pub delta_angular_velocity: f64,
+}
+
+/// Milestones `World::warp_to_next` can fast-forward to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WarpTarget {
+    Maneuver,
+    Periapsis,
+    Encounter,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CollisionEvent {
     pub time: f64,
     pub body_a: u64,
     pub body_b: u64,
     pub relative_velocity: Vec2,
     pub contact_point: Vec2,
+    /// Unit vector from `body_a` towards `body_b` along which they overlap
+    /// least.
+    pub normal: Vec2,
+    /// Overlap along `normal`; 0 when they only just touch.
+    pub penetration_depth: f64,
 }
 
-fn normalize_angle(mut angle: f64) -> f64 {
-    while angle <= -PI {
-        angle += 2.0 * PI;
-    }
-    while angle > PI {
-        angle -= 2.0 * PI;
-    }
-    angle
+/// Why a body left the world.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DespawnReason {
+    /// Drifted past `DESPAWN_RADIUS_M`.
+    LeftDespawnRadius,
+    /// Absorbed by body `survivor` in a slow collision.
+    Merged { survivor: u64 },
+    /// Broken up into debris by a fast impact.
+    Fragmented,
+    /// Taken out with `World::remove_body`.
+    Removed,
+    /// Burnt up by heating on a low pass through the atmosphere; see
+    /// `reentry`.
+    ReentryBurnup,
+    /// A missile that came close enough to body `target` to set off its
+    /// warhead; see `guidance`.
+    Detonated { target: u64 },
+    /// An asteroid with nothing left after `World::extract`.
+    MinedOut,
+    /// Brought down to 0 hit points; see `damage`.
+    Destroyed,
+}
+
+/// A body removed from the world, kept until drained with
+/// `World::drain_despawn_events`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BodyDespawned {
+    pub time: f64,
+    pub id: u64,
+    pub reason: DespawnReason,
+}
+
+/// What is left of bodies broken up at one instant, about to fly apart as
+/// debris; see `World::scatter`.
+pub(crate) struct Wreck {
+    /// World-frame centre of mass.
+    pub(crate) position: Vec2,
+    pub(crate) velocity: Vec2,
+    pub(crate) mass: f64,
+    pub(crate) radius: f64,
+    pub(crate) parent: Option<u64>,
 }
 
 fn clamp(value: f64, min: f64, max: f64) -> f64 {
@@ -182,42 +691,143 @@ fn clamp(value: f64, min: f64, max: f64) -> f64 {
     }
 }
 
+/// Sim time of the first inbound crossing of `radius` at or after `now`,
+/// or `None` if the orbit never dips that low or never rises above it.
+pub(crate) fn next_descent_time(orbit: &OrbitState, mu: f64, radius: f64, now: f64) -> Option<f64> {
+    let (a, e) = (orbit.semi_major_axis, orbit.eccentricity);
+    if a * (1.0 - e) > radius || a * (1.0 + e) <= radius {
+        return None;
+    }
+    // r = a(1 - e cos E), on the inbound half where E is negative.
+    let cos_e = clamp((1.0 - radius / a) / e, -1.0, 1.0);
+    let e_anom = -Float::atan2(Float::sqrt(1.0 - cos_e * cos_e), cos_e);
+    let m_hit = e_anom - e * Float::sin(e_anom);
+    let n = Float::sqrt(mu / (a * a * a));
+    let m_now = orbit.mean_anomaly_at_epoch + n * (now - orbit.epoch);
+    Some(now + Float::rem_euclid(m_hit - m_now, 2.0 * PI) / n)
+}
+
 /// Convert an OrbitState into Cartesian position/velocity at time `t`.
-pub fn orbit_to_cartesian(orbit: &OrbitState, mu: f64, t: f64) -> (Vec2, Vec2) {
-    assert!(
-        orbit.semi_major_axis > 0.0,
-        "semi-major axis must be positive"
-    );
-    assert!(
-        orbit.eccentricity >= 0.0 && orbit.eccentricity < 1.0,
-        "eccentricity out of range"
-    );
+pub fn orbit_to_cartesian(orbit: &OrbitState, mu: f64, t: f64) -> error::Result<(Vec2, Vec2)> {
+    orbit_to_cartesian_in(orbit, mu, t)
+}
 
-    let a = orbit.semi_major_axis;
-    let e = orbit.eccentricity;
-    let n = (mu / (a * a * a)).sqrt();
+/// `orbit_to_cartesian` evaluated in `T` precision, e.g. `f32` for clients
+/// that only draw the result. The orbital phase is still accumulated in
+/// `f64`, so late sim times don't smear bodies along their orbits.
+pub fn orbit_to_cartesian_in<T: Float>(
+    orbit: &OrbitState,
+    mu: f64,
+    t: f64,
+) -> error::Result<(Vec2<T>, Vec2<T>)> {
+    validate_orbit(orbit)?;
+
+    let a_cubed = orbit.semi_major_axis * orbit.semi_major_axis * orbit.semi_major_axis;
+    let n = Float::sqrt(mu / a_cubed);
     let dt = t - orbit.epoch;
-    let mut m = orbit.mean_anomaly_at_epoch + n * dt;
-    m = normalize_angle(m);
+    let m = T::from_f64(angle::wrap_pi(orbit.mean_anomaly_at_epoch + n * dt));
+
+    let e = T::from_f64(orbit.eccentricity);
+    let (e_anom, _) = solve_kepler(m, e, kepler_seed(m, e));
+    let sqrt_one_minus_e2 = (T::ONE - e * e).max(T::ZERO).sqrt();
+
+    Ok(state_from_eccentric_anomaly(
+        T::from_f64(orbit.semi_major_axis),
+        e,
+        T::from_f64(n),
+        sqrt_one_minus_e2,
+        T::from_f64(orbit.arg_of_periapsis),
+        e_anom,
+    ))
+}
+
+fn validate_orbit(orbit: &OrbitState) -> error::Result<()> {
+    if !(orbit.semi_major_axis.is_finite() && orbit.semi_major_axis > 0.0) {
+        return Err(GgwError::InvalidSemiMajorAxis(orbit.semi_major_axis));
+    }
+    if !(orbit.eccentricity >= 0.0 && orbit.eccentricity < 1.0) {
+        return Err(GgwError::InvalidEccentricity(orbit.eccentricity));
+    }
+    Ok(())
+}
+
+/// Starting guess for `solve_kepler` with nothing better to go on. Past
+/// `e = 0.8` Newton from `M` can overshoot, so start from apoapsis on the
+/// same side as `M`, where the iteration converges monotonically.
+fn kepler_seed<T: Float>(m: T, e: T) -> T {
+    if e < T::from_f64(0.8) {
+        m
+    } else if m < T::ZERO {
+        -T::PI
+    } else {
+        T::PI
+    }
+}
 
-    let mut e_anom = if e < 0.8 { m } else { PI };
+/// Newton iteration for Kepler's equation `E - e sin E = M`, starting from
+/// `seed`. Also reports whether it converged within the iteration budget.
+fn solve_kepler<T: Float>(m: T, e: T, seed: T) -> (T, bool) {
+    let tolerance = T::from_f64(1e-12).max(T::EPSILON * T::from_f64(4.0));
+    let mut e_anom = seed;
     for _ in 0..32 {
         let f = e_anom - e * e_anom.sin() - m;
-        let f_prime = 1.0 - e * e_anom.cos();
-        if f_prime.abs() < 1e-12 {
+        let f_prime = T::ONE - e * e_anom.cos();
+        if f_prime.abs() < T::from_f64(1e-12) {
             break;
         }
         let delta = f / f_prime;
         e_anom -= delta;
-        if delta.abs() < 1e-12 {
-            break;
+        if delta.abs() < tolerance {
+            return (e_anom, true);
         }
     }
+    (e_anom, false)
+}
+
+/// Eccentric anomaly for `mean_anomaly` on an orbit of eccentricity `e`,
+/// in `[-π, π]`: Kepler's equation `E - e sin E = M` solved for `E`.
+pub fn mean_to_eccentric(mean_anomaly: f64, e: f64) -> f64 {
+    let m = angle::wrap_pi(mean_anomaly);
+    solve_kepler(m, e, kepler_seed(m, e)).0
+}
+
+/// True anomaly, the angle from periapsis, for eccentric anomaly
+/// `eccentric_anomaly` on an orbit of eccentricity `e`.
+pub fn eccentric_to_true(eccentric_anomaly: f64, e: f64) -> f64 {
+    let (sin_half, cos_half) = Float::sin_cos(eccentric_anomaly / 2.0);
+    2.0 * Float::atan2(
+        Float::sqrt(1.0 + e) * sin_half,
+        Float::sqrt((1.0 - e).max(0.0)) * cos_half,
+    )
+}
+
+/// Mean anomaly, in `[-π, π]`, at true anomaly `true_anomaly` on an orbit
+/// of eccentricity `e`.
+pub fn true_to_mean(true_anomaly: f64, e: f64) -> f64 {
+    let (sin_nu, cos_nu) = Float::sin_cos(angle::wrap_pi(true_anomaly));
+    let cos_e = clamp((e + cos_nu) / (1.0 + e * cos_nu), -1.0, 1.0);
+    let sin_e = clamp(
+        Float::sqrt((1.0 - e * e).max(0.0)) * sin_nu / (1.0 + e * cos_nu),
+        -1.0,
+        1.0,
+    );
+    let e_anom = Float::atan2(sin_e, cos_e);
+    e_anom - e * Float::sin(e_anom)
+}
 
+/// Position and velocity for eccentric anomaly `e_anom`, rotated into the
+/// world frame.
+fn state_from_eccentric_anomaly<T: Float>(
+    a: T,
+    e: T,
+    n: T,
+    sqrt_one_minus_e2: T,
+    arg_of_periapsis: T,
+    e_anom: T,
+) -> (Vec2<T>, Vec2<T>) {
     let cos_e = e_anom.cos();
     let sin_e = e_anom.sin();
-    let factor = 1.0 - e * cos_e;
-    let sqrt_one_minus_e2 = (1.0 - e * e).max(0.0).sqrt();
+    let factor = T::ONE - e * cos_e;
 
     let x_orb = a * (cos_e - e);
     let y_orb = a * sqrt_one_minus_e2 * sin_e;
@@ -225,28 +835,31 @@ pub fn orbit_to_cartesian(orbit: &OrbitState, mu: f64, t: f64) -> (Vec2, Vec2) {
     let vx_orb = -a * sin_e * n / factor;
     let vy_orb = a * sqrt_one_minus_e2 * cos_e * n / factor;
 
-    let cos_w = orbit.arg_of_periapsis.cos();
-    let sin_w = orbit.arg_of_periapsis.sin();
-
-    let position = Vec2::new(cos_w * x_orb - sin_w * y_orb, sin_w * x_orb + cos_w * y_orb);
-    let velocity = Vec2::new(
-        cos_w * vx_orb - sin_w * vy_orb,
-        sin_w * vx_orb + cos_w * vy_orb,
-    );
+    let position = Vec2::new(x_orb, y_orb).rotate(arg_of_periapsis);
+    let velocity = Vec2::new(vx_orb, vy_orb).rotate(arg_of_periapsis);
 
     (position, velocity)
 }
 
 /// Convert Cartesian state to OrbitState at epoch `t`.
-pub fn cartesian_to_orbit(position: Vec2, velocity: Vec2, mu: f64, t: f64) -> OrbitState {
+pub fn cartesian_to_orbit(
+    position: Vec2,
+    velocity: Vec2,
+    mu: f64,
+    t: f64,
+) -> error::Result<OrbitState> {
     let r = position.length();
     let v = velocity.length();
-    let h = position.x * velocity.y - position.y * velocity.x;
-    assert!(h.abs() > 0.0, "degenerate orbit (zero angular momentum)");
+    let h = position.cross(velocity);
+    if !h.is_finite() || h == 0.0 {
+        return Err(GgwError::DegenerateOrbit);
+    }
 
     let energy = 0.5 * v * v - mu / r;
     let a = -mu / (2.0 * energy);
-    assert!(a.is_finite() && a > 0.0, "invalid semi-major axis");
+    if !(a.is_finite() && a > 0.0) {
+        return Err(GgwError::UnboundOrbit);
+    }
 
     let v_sq = v * v;
     let r_vec = position;
@@ -262,7 +875,7 @@ pub fn cartesian_to_orbit(position: Vec2, velocity: Vec2, mu: f64, t: f64) -> Or
         e = 0.0;
     }
 
-    let mut omega = e_vec.y.atan2(e_vec.x);
+    let mut omega = Float::atan2(e_vec.y, e_vec.x);
     if e == 0.0 {
         omega = 0.0;
     }
@@ -272,162 +885,2098 @@ pub fn cartesian_to_orbit(position: Vec2, velocity: Vec2, mu: f64, t: f64) -> Or
     } else {
         Vec2::zero()
     };
-    let mut true_anomaly = r_hat.y.atan2(r_hat.x) - omega;
-    true_anomaly = normalize_angle(true_anomaly);
-
-    let cos_nu = true_anomaly.cos();
-    let sin_nu = true_anomaly.sin();
-    let cos_e = clamp((e + cos_nu) / (1.0 + e * cos_nu), -1.0, 1.0);
-    let sin_e = clamp(
-        (1.0 - e * e).max(0.0).sqrt() * sin_nu / (1.0 + e * cos_nu),
-        -1.0,
-        1.0,
-    );
-    let e_anom = sin_e.atan2(cos_e);
-    let mean_anomaly = e_anom - e * e_anom.sin();
+    let true_anomaly = Float::atan2(r_hat.y, r_hat.x) - omega;
+    let mean_anomaly = true_to_mean(true_anomaly, e);
 
-    OrbitState {
+    Ok(OrbitState {
         semi_major_axis: a,
         eccentricity: e,
         arg_of_periapsis: omega,
         mean_anomaly_at_epoch: mean_anomaly,
         epoch: t,
-    }
+    })
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct World {
     pub mu: f64,
-    pub sim_time: f64,
+    /// Elapsed sim time in whole ticks; see `sim_time` for seconds.
+    pub clock: SimClock,
+    /// Number of `step` calls (and warp substeps) taken so far.
+    pub tick_count: u64,
     pub bodies: Vec<BodyState>,
     pub planet_radius: f64,
+    /// Moons orbiting the planet; see `central`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub central_bodies: Vec<CentralBody>,
     pub interior: InteriorWorld,
     pub config: GameConfig,
+    /// Scripted scenario events, fired from `step` and `warp_to`.
+    pub timeline: Timeline,
+    /// Seed for scenario randomness, set through `WorldBuilder::with_seed`.
+    /// Part of the state hash so lockstep peers must agree on it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: u64,
+    /// Missile id to the id of the body it is homing on.
+    #[cfg_attr(feature = "serde", serde(default))]
+    missile_targets: BTreeMap<u64, u64>,
+    /// Seeker, motor and fuze of each guided missile; see `guidance`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    missile_guidance: BTreeMap<u64, Guidance>,
+    /// Warm-start state for `propagate_bodies`, rebuilt on demand.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    kepler_cache: BTreeMap<u64, KeplerCache>,
+    /// Pair schedule for `detect_collisions_tiered`, rebuilt on demand.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    collision_scheduler: CollisionScheduler,
+    /// Buffers for `detect_collisions_into`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    collision_scratch: CollisionScratch,
+    /// Collision filter of each body given its own; see `collision`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    collision_filters: BTreeMap<u64, CollisionFilter>,
+    /// Dormant asteroids and debris; see `sleep`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    sleep_tracker: SleepTracker,
+    /// Hand-overs between primaries since the last `drain_soi_events`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    soi_events: Vec<SoiEvent>,
+    /// Bodies removed since the last `drain_despawn_events`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    despawn_events: Vec<BodyDespawned>,
+    /// Everything that happened since the last `drain_events`; see
+    /// `events`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    events: Vec<WorldEvent>,
+    /// Planned burns in time order; see `maneuver`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    maneuver_nodes: Vec<ManeuverNode>,
+    /// Thrust events waiting for their time, soonest first; see
+    /// `schedule_thrust`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    scheduled_thrusts: Vec<ThrustEvent>,
+    /// Heading each attitude-controlled body is turning to; see `attitude`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    attitude_targets: BTreeMap<u64, f64>,
+    /// Heat damage taken so far by bodies that have dipped into the
+    /// atmosphere; see `reentry`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    reentry_damage: BTreeMap<u64, f64>,
+    /// Longitude of each body resting on its primary's surface; see
+    /// `landing`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    landed: BTreeMap<u64, f64>,
+    /// Sensors fitted to each body that has them; see `sensors`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    sensor_suites: BTreeMap<u64, SensorSuite>,
+    /// Mass share of each resource in asteroids given their own mix; see
+    /// `mining`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    compositions: BTreeMap<u64, BTreeMap<String, f64>>,
+    /// Autopilot flying each body that has one; see `autopilot`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    autopilots: BTreeMap<u64, AutopilotState>,
+    /// Bodies watched for close approaches; see `conjunction`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    conjunction_tracked: BTreeSet<u64>,
+    /// Close approaches warned about and still to come.
+    #[cfg_attr(feature = "serde", serde(default))]
+    conjunctions: Vec<Conjunction>,
+    /// Sim time of the next look for close approaches.
+    #[cfg_attr(feature = "serde", serde(default))]
+    next_conjunction_check: f64,
+    /// Ascent each body launched and not yet in orbit is flying; see
+    /// `launch`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    ascents: BTreeMap<u64, Ascent>,
+    /// Hit points of each body that has been hurt or given its own; see
+    /// `damage`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    hit_points: BTreeMap<u64, HitPoints>,
+    /// Messages on their way, soonest to arrive first; see `comms`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    messages: Vec<Message>,
+    /// Ships riding along with another, by id; see `docking`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    docked: BTreeMap<u64, DockedShip>,
+    /// Propellant reserve of each body that pays for its burns; see
+    /// `propellant`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    propellant: BTreeMap<u64, Propellant>,
+    /// Exterior body the ship `interior` is aboard, if any.
+    #[cfg_attr(feature = "serde", serde(default))]
+    interior_body: Option<u64>,
+    /// Bodies integrated numerically, with where each left off; see
+    /// `nbody`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    numeric_bodies: BTreeMap<u64, Option<NumericState>>,
+    /// Finite burns planned or under way, by start time; see `maneuver`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    burn_programs: Vec<BurnProgram>,
+    /// Id for the next body or moon. Only ever grows, past every id handed
+    /// out or supplied, so a removed body's id is never reused.
     next_id: u64,
 }
 
-impl World {
-    pub fn new(mu: f64, config: GameConfig) -> Self {
-        let interior = InteriorWorld::new_test_ship(&config);
-        Self {
-            mu,
-            sim_time: 0.0,
-            bodies: Vec::new(),
-            planet_radius: PLANET_RADIUS_M,
-            interior,
-            config,
-            next_id: 1,
+/// The orbital half of a `World`: everything `step` touches apart from the
+/// interior, borrowed field by field.
+struct Exterior<'a> {
+    mu: f64,
+    planet_radius: f64,
+    /// Sim time after the clock has advanced.
+    now: f64,
+    tick: u64,
+    bodies: &'a mut Vec<BodyState>,
+    central_bodies: &'a mut Vec<CentralBody>,
+    timeline: &'a mut Timeline,
+    kepler_cache: &'a mut BTreeMap<u64, KeplerCache>,
+    next_id: &'a mut u64,
+    sleep: &'a mut SleepTracker,
+    sleep_config: &'a SleepConfig,
+    soi_events: &'a mut Vec<SoiEvent>,
+    events: &'a mut Vec<WorldEvent>,
+    attitude_targets: &'a BTreeMap<u64, f64>,
+    attitude_config: &'a AttitudeConfig,
+    numeric_bodies: &'a mut BTreeMap<u64, Option<NumericState>>,
+    landed: &'a BTreeMap<u64, f64>,
+}
+
+impl Exterior<'_> {
+    /// Advance everything orbital to `now`, `dt` seconds on from the last
+    /// tick. Bodies that have left are culled, and the sleepers settled,
+    /// once the interior has caught up; see `World::finish_exterior_step`.
+    fn step(&mut self, dt: f64) {
+        self.run_timeline();
+        if self.sleep_config.enabled {
+            self.sleep.wake_due(self.bodies, self.now);
+        } else {
+            self.sleep.wake_all();
         }
+        self.propagate_central_bodies();
+        self.propagate_bodies(dt);
+        self.rotate_bodies(dt);
+        self.patch_conics();
+    }
+
+    fn primaries(&self) -> Primaries {
+        Primaries::current(self.mu, self.planet_radius, self.central_bodies)
     }
 
-    pub fn add_body(&mut self, mut body: BodyState) -> u64 {
+    fn add_body(&mut self, mut body: BodyState) -> error::Result<u64> {
+        let primaries = self.primaries();
+        let primary = match primaries.find(body.parent) {
+            Some(primary) => primary,
+            None => return Err(GgwError::UnknownCentralBody(body.parent.unwrap_or(0))),
+        };
+        if body.id != 0 && (body.id < *self.next_id || body.id == u64::MAX) {
+            return Err(GgwError::InvalidBodyId(body.id));
+        }
+        let (pos, vel) = primary.state_at(&body.orbit, self.now)?;
         if body.id == 0 {
-            body.id = self.next_id;
-            self.next_id += 1;
+            body.id = *self.next_id;
         }
+        *self.next_id = body.id + 1;
         if let Some(shape) = &body.hull_shape {
             body.radius = shape.bounding_radius();
         }
-        let (pos, vel) = orbit_to_cartesian(&body.orbit, self.mu, self.sim_time);
         body.position = pos;
         body.velocity = vel;
         let id = body.id;
         self.bodies.push(body);
-        id
-    }
-
-    pub fn get_body_mut(&mut self, id: u64) -> Option<&mut BodyState> {
-        self.bodies.iter_mut().find(|b| b.id == id)
+        self.events
+            .push(WorldEvent::BodySpawned { time: self.now, id });
+        Ok(id)
     }
 
-    pub fn step(&mut self, dt: f64) {
-        self.sim_time += dt;
-        for body in &mut self.bodies {
-            let (pos, vel) = orbit_to_cartesian(&body.orbit, self.mu, self.sim_time);
-            body.position = pos;
-            body.velocity = vel;
+    /// Move every moon to its position at `now`, ahead of the bodies that
+    /// orbit them.
+    fn propagate_central_bodies(&mut self) {
+        let states = central::states_at(self.mu, self.central_bodies, self.now);
+        for (moon, state) in self.central_bodies.iter_mut().zip(states) {
+            if let Some((position, velocity)) = state {
+                moon.position = position;
+                moon.velocity = velocity;
+            }
         }
-        self.cull_despawned_bodies();
-        self.interior.step(dt, &self.config);
     }
 
-    pub fn is_inside_gravity_well(&self, body: &BodyState) -> bool {
-        body.position.length() <= GRAVITY_WELL_RADIUS_M
+    /// Move every body to its position at `now`, dormant ones only every
+    /// few ticks, numeric ones across the whole tick of `dt`, and landed
+    /// ones round with their primary's surface. A body whose orbit can no
+    /// longer be propagated keeps its last state rather than taking the
+    /// whole world down.
+    fn propagate_bodies(&mut self, dt: f64) {
+        let mut stale = core::mem::take(self.kepler_cache);
+        let interval = self.sleep_config.propagation_interval_ticks;
+        let primaries = self.primaries();
+        let field = (!self.numeric_bodies.is_empty()).then(|| {
+            nbody::Field::new(
+                self.mu,
+                self.planet_radius,
+                self.central_bodies,
+                self.now - dt,
+                self.now,
+            )
+        });
+        for body in self.bodies.iter_mut() {
+            if let Some(&longitude) = self.landed.get(&body.id) {
+                let primary = primaries.get(body.parent);
+                (body.position, body.velocity) =
+                    landing::surface_point(primary, body.radius, longitude, self.now);
+                continue;
+            }
+            if let (Some(state), Some(field)) = (self.numeric_bodies.get_mut(&body.id), &field) {
+                if let Err(err) = nbody::propagate(body, state, field) {
+                    tracing::warn!(body = body.id, error = %err, "failed to integrate body");
+                }
+                continue;
+            }
+            if !self.sleep.should_propagate(body.id, self.tick, interval) {
+                if let Some(cache) = stale.remove(&body.id) {
+                    self.kepler_cache.insert(body.id, cache);
+                }
+                continue;
+            }
+            let primary = primaries.get(body.parent);
+            let cached = stale
+                .remove(&body.id)
+                .filter(|cache| cache.is_valid_for(&body.orbit, primary.mu));
+            let cache = match cached.map_or_else(|| KeplerCache::new(&body.orbit, primary.mu), Ok) {
+                Ok(cache) => self.kepler_cache.entry(body.id).or_insert(cache),
+                Err(err) => {
+                    tracing::warn!(body = body.id, error = %err, "failed to propagate body");
+                    continue;
+                }
+            };
+            let (position, velocity) = cache.propagate(self.now);
+            body.position = primary.position + position;
+            body.velocity = primary.velocity + velocity;
+        }
     }
 
-    pub fn cull_despawned_bodies(&mut self) {
-        self.bodies
-            .retain(|body| body.position.length() <= DESPAWN_RADIUS_M);
+    /// Turn every body by its spin over `dt`, dormant ones included, since
+    /// a tumbling asteroid can sleep. Bodies with an attitude target steer
+    /// towards it instead.
+    fn rotate_bodies(&mut self, dt: f64) {
+        for body in self.bodies.iter_mut() {
+            if let Some(&target) = self.attitude_targets.get(&body.id) {
+                (body.orientation, body.angular_velocity) = attitude::steer(
+                    body.orientation,
+                    body.angular_velocity,
+                    target,
+                    self.attitude_config,
+                    dt,
+                );
+            } else if body.angular_velocity != 0.0 {
+                body.orientation = angle::wrap_pi(body.orientation + body.angular_velocity * dt);
+            }
+        }
     }
 
-    pub fn apply_thrust_event(&mut self, event: &ThrustEvent) {
-        let mu = self.mu;
-        let sim_time = self.sim_time;
-        if let Some(body) = self.get_body_mut(event.body_id) {
-            let (pos_at_burn, vel_at_burn) = orbit_to_cartesian(&body.orbit, mu, event.time);
-            let new_velocity = vel_at_burn.add(event.delta_v);
-            let new_orbit = cartesian_to_orbit(pos_at_burn, new_velocity, mu, event.time);
-            body.orbit = new_orbit;
-            let (pos_now, vel_now) = orbit_to_cartesian(&body.orbit, mu, sim_time);
-            body.position = pos_now;
-            body.velocity = vel_now;
+    /// Hand each awake body over to the moon whose sphere of influence it
+    /// has just entered, or back to the planet once it has left, recording
+    /// a `SoiEvent` for each. Run after propagation, so positions are those
+    /// at `now`.
+    fn patch_conics(&mut self) {
+        if self.central_bodies.is_empty() && self.bodies.iter().all(|b| b.parent.is_none()) {
+            return;
+        }
+        let primaries = self.primaries();
+        for body in self.bodies.iter_mut() {
+            if self.sleep.is_dormant(body.id) || self.landed.contains_key(&body.id) {
+                continue;
+            }
+            let state = (body.position, body.velocity);
+            let Some(handover) = central::handover(body.parent, state, &primaries, self.now) else {
+                continue;
+            };
+            body.orbit = handover.orbit;
+            body.parent = handover.parent;
+            let event = SoiEvent {
+                time: self.now,
+                body_id: body.id,
+                transition: handover.transition,
+            };
+            self.soi_events.push(event);
+            self.events.push(WorldEvent::SoiChanged(event));
+            tracing::debug!(
+                body = body.id,
+                transition = ?handover.transition,
+                "sphere of influence changed"
+            );
         }
     }
 
-    pub fn detect_collisions(&self, dt: f64) -> Vec<CollisionEvent> {
-        let target_time = self.sim_time + dt;
-        let mut events = Vec::new();
-        let mut future_states = Vec::with_capacity(self.bodies.len());
-        for body in &self.bodies {
-            future_states.push(orbit_to_cartesian(&body.orbit, self.mu, target_time));
-        }
-
-        for i in 0..self.bodies.len() {
-            for j in (i + 1)..self.bodies.len() {
-                let body_a = &self.bodies[i];
-                let body_b = &self.bodies[j];
-                let (pos_a, vel_a) = future_states[i];
-                let (pos_b, vel_b) = future_states[j];
-                let dist = pos_a.sub(pos_b).length();
-                if dist <= body_a.radius + body_b.radius {
-                    let relative_velocity = vel_b.sub(vel_a);
-                    let contact_point = pos_a.add(pos_b).scale(0.5);
-                    events.push(CollisionEvent {
-                        time: target_time,
-                        body_a: body_a.id,
-                        body_b: body_b.id,
-                        relative_velocity,
-                        contact_point,
+    /// Execute every scenario action that has come due.
+    fn run_timeline(&mut self) {
+        let now = self.now;
+        self.timeline.expire_solar_storm(now);
+        for (time, action) in self.timeline.take_due(now) {
+            match action {
+                ScenarioAction::SpawnBodies(bodies) => {
+                    let body_ids = bodies
+                        .into_iter()
+                        .filter_map(|body| match self.add_body(body) {
+                            Ok(id) => Some(id),
+                            Err(err) => {
+                                tracing::warn!(error = %err, "scenario body rejected");
+                                None
+                            }
+                        })
+                        .collect();
+                    self.timeline
+                        .record(ScenarioEvent::BodiesSpawned { time, body_ids });
+                }
+                ScenarioAction::SolarStorm { duration_s } => {
+                    self.timeline.start_solar_storm(time, duration_s.max(0.0));
+                }
+                ScenarioAction::DeliverContract {
+                    contract_id,
+                    description,
+                } => {
+                    self.timeline.record(ScenarioEvent::ContractDelivered {
+                        time,
+                        contract_id,
+                        description,
                     });
                 }
             }
+            tracing::debug!(time, "scenario action fired");
         }
-
-        for (body, &(position, velocity)) in self.bodies.iter().zip(future_states.iter()) {
-            let altitude = position.length();
-            if altitude <= self.planet_radius + body.radius {
-                let contact_point = if altitude > 1e-6 {
-                    position.normalized().scale(self.planet_radius)
-                } else {
-                    Vec2::zero()
-                };
-                events.push(CollisionEvent {
-                    time: target_time,
-                    body_a: body.id,
-                    body_b: 0,
-                    relative_velocity: velocity,
-                    contact_point,
-                });
-            }
-        }
-
-        events
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
+impl World {
+    pub fn builder() -> WorldBuilder {
+        WorldBuilder::new()
+    }
+
+    pub fn new(mu: f64, config: GameConfig) -> Self {
+        let interior = InteriorWorld::new_test_ship(&config);
+        Self {
+            mu,
+            clock: SimClock::default(),
+            tick_count: 0,
+            bodies: Vec::new(),
+            planet_radius: PLANET_RADIUS_M,
+            central_bodies: Vec::new(),
+            interior,
+            config,
+            timeline: Timeline::new(),
+            seed: 0,
+            missile_targets: BTreeMap::new(),
+            missile_guidance: BTreeMap::new(),
+            kepler_cache: BTreeMap::new(),
+            collision_scheduler: CollisionScheduler::new(),
+            collision_scratch: CollisionScratch::new(),
+            collision_filters: BTreeMap::new(),
+            sleep_tracker: SleepTracker::new(),
+            soi_events: Vec::new(),
+            despawn_events: Vec::new(),
+            events: Vec::new(),
+            maneuver_nodes: Vec::new(),
+            scheduled_thrusts: Vec::new(),
+            attitude_targets: BTreeMap::new(),
+            reentry_damage: BTreeMap::new(),
+            landed: BTreeMap::new(),
+            sensor_suites: BTreeMap::new(),
+            compositions: BTreeMap::new(),
+            autopilots: BTreeMap::new(),
+            conjunction_tracked: BTreeSet::new(),
+            conjunctions: Vec::new(),
+            next_conjunction_check: 0.0,
+            ascents: BTreeMap::new(),
+            hit_points: BTreeMap::new(),
+            messages: Vec::new(),
+            docked: BTreeMap::new(),
+            propellant: BTreeMap::new(),
+            interior_body: None,
+            numeric_bodies: BTreeMap::new(),
+            burn_programs: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Add `body`, assigning a fresh id when `body.id == 0`. Fails without
+    /// touching the world if the orbit cannot be propagated, or if an
+    /// explicit id is one the world has already handed out or `u64::MAX`.
+    pub fn add_body(&mut self, body: BodyState) -> error::Result<u64> {
+        self.split().0.add_body(body)
+    }
+
+    /// Take `id` out of the world, along with its missile target and
+    /// planned burns, returning its last state. Records a `BodyDespawned`.
+    /// Ids are never handed out again, so one kept after removal can't
+    /// come to name a different body.
+    pub fn remove_body(&mut self, id: u64) -> Option<BodyState> {
+        self.forget_body(id, DespawnReason::Removed)
+    }
+
+    /// Borrow the orbital side of the world apart from the interior and the
+    /// config it steps with, so the two can advance concurrently.
+    fn split(&mut self) -> (Exterior<'_>, &mut InteriorWorld, &GameConfig) {
+        let exterior = Exterior {
+            mu: self.mu,
+            planet_radius: self.planet_radius,
+            now: self.clock.seconds().0,
+            tick: self.tick_count,
+            bodies: &mut self.bodies,
+            central_bodies: &mut self.central_bodies,
+            timeline: &mut self.timeline,
+            kepler_cache: &mut self.kepler_cache,
+            next_id: &mut self.next_id,
+            sleep: &mut self.sleep_tracker,
+            sleep_config: &self.config.sleep,
+            soi_events: &mut self.soi_events,
+            events: &mut self.events,
+            attitude_targets: &self.attitude_targets,
+            attitude_config: &self.config.attitude,
+            numeric_bodies: &mut self.numeric_bodies,
+            landed: &self.landed,
+        };
+        (exterior, &mut self.interior, &self.config)
+    }
+
+    /// Add a moon of gravitational parameter `mu` and surface `radius` on
+    /// `orbit` around the planet, returning its id. Bodies join it by
+    /// setting `BodyState::parent` to that id.
+    pub fn add_central_body(
+        &mut self,
+        mu: f64,
+        radius: f64,
+        orbit: OrbitState,
+    ) -> error::Result<u64> {
+        self.insert_central_body(None, mu, radius, orbit)
+    }
+
+    /// Add a moon like `add_central_body`, but on `orbit` around moon
+    /// `parent`. Fails with `UnknownCentralBody` if there is no such moon.
+    pub fn add_central_body_orbiting(
+        &mut self,
+        parent: u64,
+        mu: f64,
+        radius: f64,
+        orbit: OrbitState,
+    ) -> error::Result<u64> {
+        self.insert_central_body(Some(parent), mu, radius, orbit)
+    }
+
+    fn insert_central_body(
+        &mut self,
+        parent: Option<u64>,
+        mu: f64,
+        radius: f64,
+        orbit: OrbitState,
+    ) -> error::Result<u64> {
+        let origin = self
+            .primaries()
+            .find(parent)
+            .copied()
+            .ok_or(GgwError::UnknownCentralBody(parent.unwrap_or(0)))?;
+        let (position, velocity) = origin.state_at(&orbit, self.sim_time().0)?;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.central_bodies.push(CentralBody {
+            id,
+            mu,
+            radius,
+            parent,
+            orbit,
+            position,
+            velocity,
+        });
+        Ok(id)
+    }
+
+    pub fn central_body(&self, id: u64) -> Option<&CentralBody> {
+        self.central_bodies.iter().find(|moon| moon.id == id)
+    }
+
+    /// The planet and every moon where they are now.
+    pub fn primaries(&self) -> Primaries {
+        Primaries::current(self.mu, self.planet_radius, &self.central_bodies)
+    }
+
+    /// The planet and every moon where they will be at `time`.
+    pub fn primaries_at(&self, time: f64) -> Primaries {
+        Primaries::at(self.mu, self.planet_radius, &self.central_bodies, time)
+    }
+
+    /// What `body` orbits, where it is now. A body whose moon is gone falls
+    /// back to the planet.
+    pub fn primary_of(&self, body: &BodyState) -> Primary {
+        *self.primaries().get(body.parent)
+    }
+
+    /// World-frame position and velocity of `body` at `time`.
+    fn state_at(&self, body: &BodyState, time: f64) -> error::Result<(Vec2, Vec2)> {
+        self.primaries_at(time)
+            .get(body.parent)
+            .state_at(&body.orbit, time)
+    }
+
+    pub fn body(&self, id: u64) -> Option<&BodyState> {
+        self.bodies.iter().find(|b| b.id == id)
+    }
+
+    /// `n` points around `body_id`'s orbit in the world frame, starting at
+    /// its current position, for drawing its path; see
+    /// `OrbitState::sample_points_at`. A moon's satellite is drawn around
+    /// where the moon is now.
+    pub fn orbit_path(&self, body_id: u64, n: usize) -> error::Result<Vec<Vec2>> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        let primary = self.primary_of(body);
+        let mut points = body
+            .orbit
+            .sample_points_at(primary.mu, self.sim_time(), n)?;
+        for point in &mut points {
+            *point += primary.position;
+        }
+        Ok(points)
+    }
+
+    pub fn get_body_mut(&mut self, id: u64) -> Option<&mut BodyState> {
+        self.bodies.iter_mut().find(|b| b.id == id)
+    }
+
+    pub fn bodies_of_type(&self, body_type: BodyType) -> impl Iterator<Item = &BodyState> {
+        self.bodies.iter().of_type(body_type)
+    }
+
+    pub fn ships(&self) -> impl Iterator<Item = &BodyState> {
+        self.bodies_of_type(BodyType::Ship)
+    }
+
+    pub fn missiles(&self) -> impl Iterator<Item = &BodyState> {
+        self.bodies_of_type(BodyType::Missile)
+    }
+
+    /// Put the ship `interior` aboard `body_id`, so its devices act for that
+    /// body outside, or aboard nothing with `None`.
+    pub fn set_interior_body(&mut self, body_id: Option<u64>) -> error::Result<()> {
+        if let Some(id) = body_id {
+            self.body(id).ok_or(GgwError::UnknownBody(id))?;
+        }
+        self.interior_body = body_id;
+        Ok(())
+    }
+
+    pub fn interior_body(&self) -> Option<u64> {
+        self.interior_body
+    }
+
+    /// Point missile `missile_id` at `target_id`, replacing any previous
+    /// target.
+    pub fn set_missile_target(&mut self, missile_id: u64, target_id: u64) -> error::Result<()> {
+        match self.body(missile_id) {
+            Some(body) if body.body_type == BodyType::Missile => {}
+            Some(_) => return Err(GgwError::NotAMissile(missile_id)),
+            None => return Err(GgwError::UnknownBody(missile_id)),
+        }
+        if self.body(target_id).is_none() {
+            return Err(GgwError::UnknownBody(target_id));
+        }
+        self.missile_targets.insert(missile_id, target_id);
+        Ok(())
+    }
+
+    pub fn missile_target(&self, missile_id: u64) -> Option<u64> {
+        self.missile_targets.get(&missile_id).copied()
+    }
+
+    /// Live missiles currently homing on `target_id`.
+    pub fn missiles_targeting(&self, target_id: u64) -> impl Iterator<Item = &BodyState> {
+        self.missiles()
+            .filter(move |missile| self.missile_target(missile.id) == Some(target_id))
+    }
+
+    /// Elapsed sim time, derived from `clock`.
+    pub fn sim_time(&self) -> Seconds {
+        self.clock.seconds()
+    }
+
+    /// Advance by `dt`, rounded to whole clock ticks. A step spanning a
+    /// scheduled thrust is split at its tick, so the rest of the step runs
+    /// on the orbit after the burn.
+    pub fn step(&mut self, dt: Seconds) {
+        let _span = tracing::trace_span!("step", tick = self.tick_count, dt = dt.0).entered();
+        let end = self
+            .clock
+            .ticks()
+            .saturating_add(clock::ticks_from_seconds(dt));
+        loop {
+            let now = self.clock.ticks();
+            let stop = match self.next_thrust_tick() {
+                Some(tick) if tick > now && tick < end => tick,
+                _ => end,
+            };
+            self.clock.advance(stop - now);
+            self.advance(clock::seconds_from_ticks(stop - now).0, false);
+            if stop == end {
+                break;
+            }
+        }
+    }
+
+    /// Fast-forward to `target_time` in jumps of at most `WARP_SUBSTEP_S`,
+    /// stepping the interior at coarse resolution. Does nothing if the
+    /// target is not in the future.
+    pub fn warp_to(&mut self, target_time: Seconds) {
+        let _span =
+            tracing::debug_span!("warp", from = self.sim_time().0, to = target_time.0).entered();
+        let target = clock::ticks_from_seconds(target_time);
+        let substep = clock::ticks_from_seconds(Seconds(WARP_SUBSTEP_S));
+        while self.clock.ticks() < target {
+            let now = self.clock.ticks();
+            let mut ticks = (target - now).min(substep);
+            if let Some(next_event) = self.timeline.next_time() {
+                let next_event = clock::ticks_from_seconds_ceil(Seconds(next_event));
+                if next_event > now {
+                    ticks = ticks.min(next_event - now);
+                }
+            }
+            if let Some(next_thrust) = self.next_thrust_tick() {
+                if next_thrust > now {
+                    ticks = ticks.min(next_thrust - now);
+                }
+            }
+            self.clock.advance(ticks);
+            let dt = clock::seconds_from_ticks(ticks).0;
+            self.advance(dt, true);
+        }
+    }
+
+    /// Run one tick of `dt` seconds once the clock has moved. The orbital
+    /// pass and the interior share no state within a tick, so with the
+    /// `parallel` feature the interior steps on a scoped thread while this
+    /// thread propagates; both finish before `advance` returns. Thrust and
+    /// damage cross between the two only outside `step`, through
+    /// `apply_thrust_event` and interior commands, so the result is the
+    /// same with or without the feature.
+    fn advance(&mut self, dt: f64, coarse: bool) {
+        self.tick_count += 1;
+        self.execute_due_nodes();
+        self.execute_due_thrusts();
+        self.fly_autopilots();
+        self.execute_burns(dt);
+        self.fly_ascents(dt);
+        self.guide_missiles(dt);
+        let (mut exterior, interior, config) = self.split();
+        let step_interior = |interior: &mut InteriorWorld| {
+            if coarse {
+                interior.step_coarse(dt, config);
+            } else {
+                interior.step(dt, config);
+            }
+        };
+        #[cfg(feature = "parallel")]
+        std::thread::scope(|scope| {
+            scope.spawn(|| step_interior(interior));
+            exterior.step(dt);
+        });
+        #[cfg(not(feature = "parallel"))]
+        {
+            exterior.step(dt);
+            step_interior(interior);
+        }
+        self.finish_exterior_step();
+        self.heat_reentering_bodies(dt);
+        self.rebase_epochs();
+        self.check_conjunctions();
+        self.deliver_messages();
+    }
+
+    /// Time of the next `target` milestone for `body_id` within `horizon`
+    /// seconds of now, if any.
+    pub fn next_warp_time(
+        &self,
+        body_id: u64,
+        target: WarpTarget,
+        horizon: Seconds,
+    ) -> Option<Seconds> {
+        let horizon = horizon.0;
+        let body = self.body(body_id)?;
+        let now = self.sim_time();
+        let time = match target {
+            WarpTarget::Maneuver => self.next_node(body_id)?.time,
+            WarpTarget::Periapsis => {
+                now + body.orbit.time_to_periapsis(self.primary_of(body).mu, now)
+            }
+            WarpTarget::Encounter => Seconds(self.next_encounter_time(body, horizon)?),
+        };
+        ((time - now).0 <= horizon).then_some(time)
+    }
+
+    /// Warp to the next `target` milestone for `body_id`, returning the
+    /// sim time reached, or `None` (without advancing) if there is none
+    /// within `horizon`.
+    pub fn warp_to_next(
+        &mut self,
+        body_id: u64,
+        target: WarpTarget,
+        horizon: Seconds,
+    ) -> Option<Seconds> {
+        let time = self.next_warp_time(body_id, target, horizon)?;
+        self.warp_to(time);
+        Some(self.sim_time())
+    }
+
+    fn next_encounter_time(&self, body: &BodyState, horizon: f64) -> Option<f64> {
+        let others: Vec<&BodyState> = self.bodies.iter().excluding(body.id).collect();
+        if others.is_empty() {
+            return None;
+        }
+        let within_range = |t: f64| {
+            let primaries = self.primaries_at(t);
+            let state_at = |body: &BodyState| primaries.get(body.parent).state_at(&body.orbit, t);
+            let Ok((pos, _)) = state_at(body) else {
+                return false;
+            };
+            others.iter().any(|other| {
+                state_at(other).is_ok_and(|(other_pos, _)| {
+                    pos.distance(other_pos) <= ENCOUNTER_DISTANCE_M + other.radius
+                })
+            })
+        };
+        // An encounter already in progress doesn't count; wait for the next one.
+        let mut was_close = within_range(self.sim_time().0);
+        let mut t = self.sim_time().0;
+        let end = self.sim_time().0 + horizon;
+        while t < end {
+            t = (t + ENCOUNTER_SCAN_STEP_S).min(end);
+            let close = within_range(t);
+            if close && !was_close {
+                return Some(t);
+            }
+            was_close = close;
+        }
+        None
+    }
+
+    /// Deterministic digest of the simulation state, compared between peers
+    /// each lockstep tick to detect desyncs.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(self.clock.ticks());
+        hasher.write_u64(self.tick_count);
+        hasher.write_u64(self.next_id);
+        hasher.write_u64(self.seed);
+        hasher.write_u64(self.bodies.len() as u64);
+        for body in &self.bodies {
+            hasher.write_u64(body.id);
+            hasher.write_u64(body.parent.unwrap_or(0));
+            hasher.write_f64(body.mass);
+            hasher.write_f64(body.radius);
+            hasher.write_f64(body.orbit.semi_major_axis);
+            hasher.write_f64(body.orbit.eccentricity);
+            hasher.write_f64(body.orbit.arg_of_periapsis);
+            hasher.write_f64(body.orbit.mean_anomaly_at_epoch);
+            hasher.write_f64(body.orbit.epoch);
+            hasher.write_f64(body.position.x);
+            hasher.write_f64(body.position.y);
+            hasher.write_f64(body.velocity.x);
+            hasher.write_f64(body.velocity.y);
+            hasher.write_f64(body.orientation);
+            hasher.write_f64(body.angular_velocity);
+            hasher.write_u64(body.tags.len() as u64);
+            for (key, value) in &body.tags {
+                for text in [key, value] {
+                    hasher.write_u64(text.len() as u64);
+                    hasher.write_bytes(text.as_bytes());
+                }
+            }
+        }
+        hasher.write_u64(self.central_bodies.len() as u64);
+        for moon in &self.central_bodies {
+            hasher.write_u64(moon.id);
+            hasher.write_u64(moon.parent.unwrap_or(0));
+            hasher.write_f64(moon.mu);
+            hasher.write_f64(moon.radius);
+            hasher.write_f64(moon.orbit.semi_major_axis);
+            hasher.write_f64(moon.orbit.eccentricity);
+            hasher.write_f64(moon.orbit.arg_of_periapsis);
+            hasher.write_f64(moon.orbit.mean_anomaly_at_epoch);
+            hasher.write_f64(moon.orbit.epoch);
+        }
+        hasher.write_u64(self.maneuver_nodes.len() as u64);
+        for node in &self.maneuver_nodes {
+            hasher.write_u64(node.body_id);
+            hasher.write_f64(node.time.0);
+            hasher.write_f64(node.delta_v.x);
+            hasher.write_f64(node.delta_v.y);
+        }
+        hasher.write_u64(self.scheduled_thrusts.len() as u64);
+        for event in &self.scheduled_thrusts {
+            hasher.write_u64(event.body_id);
+            hasher.write_f64(event.time);
+            hasher.write_f64(event.delta_v.x);
+            hasher.write_f64(event.delta_v.y);
+            hasher.write_u64(event.thrust_type as u64);
+        }
+        hasher.write_u64(self.burn_programs.len() as u64);
+        for program in &self.burn_programs {
+            hasher.write_u64(program.body_id);
+            hasher.write_f64(program.start);
+            hasher.write_f64(program.duration);
+            hasher.write_f64(program.acceleration.x);
+            hasher.write_f64(program.acceleration.y);
+        }
+        hasher.write_u64(self.attitude_targets.len() as u64);
+        for (body_id, heading) in &self.attitude_targets {
+            hasher.write_u64(*body_id);
+            hasher.write_f64(*heading);
+        }
+        hasher.write_u64(self.numeric_bodies.len() as u64);
+        for (body_id, state) in &self.numeric_bodies {
+            hasher.write_u64(*body_id);
+            hasher.write_bool(state.is_some());
+            if let Some(state) = state {
+                state.hash_state(&mut hasher);
+            }
+        }
+        hasher.write_u64(self.reentry_damage.len() as u64);
+        for (body_id, damage) in &self.reentry_damage {
+            hasher.write_u64(*body_id);
+            hasher.write_f64(*damage);
+        }
+        hasher.write_u64(self.landed.len() as u64);
+        for (body_id, longitude) in &self.landed {
+            hasher.write_u64(*body_id);
+            hasher.write_f64(*longitude);
+        }
+        hasher.write_u64(self.sensor_suites.len() as u64);
+        for (body_id, suite) in &self.sensor_suites {
+            hasher.write_u64(*body_id);
+            hasher.write_f64(suite.range_m);
+            hasher.write_f64(suite.half_angle.unwrap_or(-1.0));
+            hasher.write_f64(suite.noise_per_m);
+        }
+        hasher.write_u64(self.timeline.pending_len() as u64);
+        hasher.write_f64(self.timeline.solar_storm_until().unwrap_or(-1.0));
+        hasher.write_u64(self.missile_targets.len() as u64);
+        for (missile, target) in &self.missile_targets {
+            hasher.write_u64(*missile);
+            hasher.write_u64(*target);
+        }
+        hasher.write_u64(self.missile_guidance.len() as u64);
+        for (missile, guidance) in &self.missile_guidance {
+            hasher.write_u64(*missile);
+            hasher.write_f64(guidance.max_acceleration_mps2);
+            hasher.write_f64(guidance.fuel_mps);
+            hasher.write_f64(guidance.proximity_radius_m);
+        }
+        hasher.write_u64(self.compositions.len() as u64);
+        for (asteroid, composition) in &self.compositions {
+            hasher.write_u64(*asteroid);
+            hasher.write_u64(composition.len() as u64);
+            for (name, share) in composition {
+                hasher.write_bytes(name.as_bytes());
+                hasher.write_f64(*share);
+            }
+        }
+        hasher.write_u64(self.autopilots.len() as u64);
+        for (body_id, state) in &self.autopilots {
+            hasher.write_u64(*body_id);
+            hasher.write_f64(state.next_correction);
+            hasher.write_bool(state.planned.is_some());
+            match state.autopilot {
+                Autopilot::Orbit(orbit) => {
+                    hasher.write_u64(0);
+                    hasher.write_f64(orbit.semi_major_axis);
+                    hasher.write_f64(orbit.eccentricity);
+                    hasher.write_f64(orbit.arg_of_periapsis);
+                    hasher.write_f64(orbit.mean_anomaly_at_epoch);
+                    hasher.write_f64(orbit.epoch);
+                }
+                Autopilot::Offset { body_id, offset } => {
+                    hasher.write_u64(1);
+                    hasher.write_u64(body_id);
+                    hasher.write_f64(offset.x);
+                    hasher.write_f64(offset.y);
+                }
+                Autopilot::MatchVelocity { target_id } => {
+                    hasher.write_u64(2);
+                    hasher.write_u64(target_id);
+                }
+            }
+        }
+        hasher.write_u64(self.conjunction_tracked.len() as u64);
+        for body_id in &self.conjunction_tracked {
+            hasher.write_u64(*body_id);
+        }
+        hasher.write_u64(self.conjunctions.len() as u64);
+        for conjunction in &self.conjunctions {
+            hasher.write_u64(conjunction.body_a);
+            hasher.write_u64(conjunction.body_b);
+            hasher.write_f64(conjunction.time);
+            hasher.write_f64(conjunction.miss_distance);
+        }
+        hasher.write_f64(self.next_conjunction_check);
+        hasher.write_u64(self.ascents.len() as u64);
+        for (body_id, ascent) in &self.ascents {
+            hasher.write_u64(*body_id);
+            hasher.write_f64(ascent.heading);
+            hasher.write_f64(ascent.target_radius);
+            hasher.write_f64(ascent.liftoff);
+            match ascent.phase {
+                AscentPhase::Rise => hasher.write_u64(0),
+                AscentPhase::Turn => hasher.write_u64(1),
+                AscentPhase::Coast { apoapsis_time } => {
+                    hasher.write_u64(2);
+                    hasher.write_f64(apoapsis_time);
+                }
+            }
+        }
+        hasher.write_u64(self.collision_filters.len() as u64);
+        for (body_id, filter) in &self.collision_filters {
+            hasher.write_u64(*body_id);
+            hasher.write_u64(u64::from(filter.layer));
+            hasher.write_u64(u64::from(filter.mask));
+        }
+        hasher.write_u64(self.hit_points.len() as u64);
+        for (body_id, hit_points) in &self.hit_points {
+            hasher.write_u64(*body_id);
+            hasher.write_f64(hit_points.hp);
+            hasher.write_f64(hit_points.max_hp);
+        }
+        hasher.write_u64(self.messages.len() as u64);
+        for message in &self.messages {
+            hasher.write_u64(message.from);
+            hasher.write_u64(message.to);
+            hasher.write_f64(message.sent);
+            hasher.write_f64(message.arrives);
+            hasher.write_u64(message.payload.len() as u64);
+            for byte in &message.payload {
+                hasher.write_u64(u64::from(*byte));
+            }
+        }
+        hasher.write_u64(self.docked.len() as u64);
+        for (passenger, docked) in &self.docked {
+            hasher.write_u64(*passenger);
+            hasher.write_u64(docked.host);
+            hasher.write_f64(docked.body.mass);
+            hasher.write_f64(docked.offset.x);
+            hasher.write_f64(docked.offset.y);
+            hasher.write_f64(docked.orientation);
+        }
+        hasher.write_u64(self.propellant.len() as u64);
+        for (body_id, propellant) in &self.propellant {
+            hasher.write_u64(*body_id);
+            hasher.write_f64(propellant.fuel_kg);
+            hasher.write_f64(propellant.xenon_kg);
+        }
+        self.sleep_tracker.hash_state(&mut hasher);
+        self.interior.hash_state(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Body, life-support and power summary for admin tooling and soak
+    /// tests.
+    pub fn diagnostics(&self) -> Diagnostics {
+        Diagnostics::collect(self)
+    }
+
+    /// Height of `body` above the surface of whatever it orbits.
+    pub fn altitude(&self, body: &BodyState) -> Meters {
+        let primary = self.primary_of(body);
+        Meters(body.position.distance(primary.position) - primary.radius)
+    }
+
+    /// Whether `body` is inside its primary's gravity well: within
+    /// `GRAVITY_WELL_RADIUS_M` of the planet, or inside a moon's sphere of
+    /// influence.
+    pub fn is_inside_gravity_well(&self, body: &BodyState) -> bool {
+        let primary = self.primary_of(body);
+        body.position.distance(primary.position) <= primary.gravity_well_radius
+    }
+
+    /// Drop every body past `DESPAWN_RADIUS_M`, and everything that refers
+    /// to it, as `DespawnReason::LeftDespawnRadius`.
+    pub fn cull_despawned_bodies(&mut self) {
+        let culled: Vec<u64> = self
+            .bodies
+            .iter()
+            .filter(|body| body.position.length() > DESPAWN_RADIUS_M)
+            .map(|body| body.id)
+            .collect();
+        for id in culled {
+            tracing::debug!(body = id, "body left the despawn radius");
+            self.forget_body(id, DespawnReason::LeftDespawnRadius);
+        }
+    }
+
+    /// The rest of the orbital step: cull the bodies that have left, then
+    /// let the ones left settle into sleep.
+    fn finish_exterior_step(&mut self) {
+        self.cull_despawned_bodies();
+        if self.config.sleep.enabled {
+            let primaries = self.primaries();
+            self.sleep_tracker.settle(
+                &self.bodies,
+                &primaries,
+                &self.config.sleep,
+                self.clock.seconds().0,
+                self.tick_count,
+            );
+        }
+    }
+
+    /// Apply an impulsive burn, lifting the body off if it has landed and
+    /// paying for it in propellant; see `propellant`. On error the body's
+    /// orbit and propellant are left as they were and a
+    /// `WorldEvent::ThrustRejected` is recorded.
+    pub fn apply_thrust_event(&mut self, event: &ThrustEvent) -> error::Result<()> {
+        let result = self.apply_impulse(event);
+        if let Err(err) = &result {
+            self.events.push(WorldEvent::ThrustRejected {
+                event: event.clone(),
+                error: err.clone(),
+            });
+        }
+        result
+    }
+
+    fn apply_impulse(&mut self, event: &ThrustEvent) -> error::Result<()> {
+        let sim_time = self.sim_time().0;
+        let body = self
+            .body(event.body_id)
+            .ok_or(GgwError::UnknownBody(event.body_id))?;
+        let (event, used) = self.afford_burn(event)?;
+        let primary = self.primary_of(body);
+        let orbit = self.flight_orbit(body)?;
+        // Orbits are relative to the primary, which doesn't accelerate
+        // during the burn, so the burn can be worked in its frame.
+        let new_orbit = maneuver::orbit_after_burn(&orbit, primary.mu, event.time, event.delta_v)?;
+        let (pos_now, vel_now) = primary.state_at(&new_orbit, sim_time)?;
+        self.landed.remove(&event.body_id);
+        let body = self
+            .get_body_mut(event.body_id)
+            .ok_or(GgwError::UnknownBody(event.body_id))?;
+        body.orbit = new_orbit;
+        body.position = pos_now;
+        body.velocity = vel_now;
+        body.mass -= used.total_kg();
+        self.spend_propellant(event.body_id, used);
+        self.sleep_tracker.wake(event.body_id);
+        self.events.push(WorldEvent::ThrustApplied(event));
+        Ok(())
+    }
+
+    /// Add `event`'s kick to the body's spin, capped at
+    /// `config.attitude.max_angular_velocity_rad_s`. A body with an
+    /// attitude target steers back towards it afterwards.
+    pub fn apply_torque_event(&mut self, event: &TorqueEvent) -> error::Result<()> {
+        let max_rate = self.config.attitude.max_angular_velocity_rad_s.max(0.0);
+        let body = self
+            .get_body_mut(event.body_id)
+            .ok_or(GgwError::UnknownBody(event.body_id))?;
+        body.angular_velocity =
+            (body.angular_velocity + event.delta_angular_velocity).clamp(-max_rate, max_rate);
+        self.sleep_tracker.wake(event.body_id);
+        Ok(())
+    }
+
+    /// Have RCS turn `body_id` to `heading` and hold it there, or let it
+    /// spin freely again with `None`.
+    pub fn set_attitude_target(
+        &mut self,
+        body_id: u64,
+        heading: Option<Radians>,
+    ) -> error::Result<()> {
+        if self.body(body_id).is_none() {
+            return Err(GgwError::UnknownBody(body_id));
+        }
+        match heading {
+            Some(heading) => self
+                .attitude_targets
+                .insert(body_id, angle::wrap_pi(heading.0)),
+            None => self.attitude_targets.remove(&body_id),
+        };
+        Ok(())
+    }
+
+    /// Integrate `body_id` numerically under the planet and every moon from
+    /// the next step on, or put it back on its Kepler orbit; see `nbody`.
+    pub fn set_numeric_propagation(&mut self, body_id: u64, enabled: bool) -> error::Result<()> {
+        if self.body(body_id).is_none() {
+            return Err(GgwError::UnknownBody(body_id));
+        }
+        if enabled {
+            self.numeric_bodies.entry(body_id).or_insert(None);
+        } else {
+            self.numeric_bodies.remove(&body_id);
+        }
+        Ok(())
+    }
+
+    pub fn uses_numeric_propagation(&self, body_id: u64) -> bool {
+        self.numeric_bodies.contains_key(&body_id)
+    }
+
+    /// Heading `body_id` is turning to or holding, if it has one.
+    pub fn attitude_target(&self, body_id: u64) -> Option<Radians> {
+        self.attitude_targets.get(&body_id).copied().map(Radians)
+    }
+
+    /// Heat damage `body_id` has taken on reentry, from 0 up to the 1 that
+    /// burns it up.
+    pub fn reentry_damage(&self, body_id: u64) -> f64 {
+        self.reentry_damage.get(&body_id).copied().unwrap_or(0.0)
+    }
+
+    /// Respond to a collision between two bodies reported by
+    /// `detect_collisions`. Bodies closing along `event.normal` bounce
+    /// apart with equal and opposite impulses, keeping
+    /// `config.collision.restitution` of their closing speed and taking
+    /// damage for it, or merge into the heavier one if they close slower
+    /// than `merge_speed_mps`. Orbits
+    /// are refit from the bodies' states at `event.time`. Debris may break
+    /// up instead, see `cascade`. Hitting a planet
+    /// or moon isn't resolved here and fails with `UnknownBody`. On error
+    /// nothing changes.
+    pub fn resolve_collision(&mut self, event: &CollisionEvent) -> error::Result<CollisionOutcome> {
+        let outcome = self.settle_collision(event)?;
+        self.events.push(WorldEvent::Collision {
+            event: event.clone(),
+            outcome,
+        });
+        Ok(outcome)
+    }
+
+    fn settle_collision(&mut self, event: &CollisionEvent) -> error::Result<CollisionOutcome> {
+        let body_a = self
+            .body(event.body_a)
+            .ok_or(GgwError::UnknownBody(event.body_a))?;
+        let body_b = self
+            .body(event.body_b)
+            .ok_or(GgwError::UnknownBody(event.body_b))?;
+        let (pos_a, vel_a) = self.state_at(body_a, event.time)?;
+        let (pos_b, vel_b) = self.state_at(body_b, event.time)?;
+        let closing = (vel_a - vel_b).dot(event.normal);
+        if closing <= 0.0 {
+            return Ok(CollisionOutcome::Separating);
+        }
+        if self.cascades(body_a, body_b, event.relative_velocity.length()) {
+            self.fragment_collision(event)?;
+            return Ok(CollisionOutcome::Fragmented);
+        }
+        let (mass_a, mass_b) = (body_a.mass, body_b.mass);
+        let config = &self.config.collision;
+
+        if closing < config.merge_speed_mps {
+            let (survivor, absorbed) = if mass_a >= mass_b {
+                (body_a, body_b)
+            } else {
+                (body_b, body_a)
+            };
+            let (survivor, absorbed) = (survivor.id, absorbed.id);
+            let total = mass_a + mass_b;
+            let position = (pos_a * mass_a + pos_b * mass_b) / total;
+            let velocity = (vel_a * mass_a + vel_b * mass_b) / total;
+            let radius = Float::sqrt(body_a.radius * body_a.radius + body_b.radius * body_b.radius);
+            let orbit = self.refit_orbit(survivor, position, velocity, event.time)?;
+            self.set_orbit(survivor, orbit)?;
+            if let Some(body) = self.get_body_mut(survivor) {
+                body.mass = total;
+                body.radius = body.radius.max(radius);
+            }
+            self.forget_body(absorbed, DespawnReason::Merged { survivor });
+            return Ok(CollisionOutcome::Merged { survivor, absorbed });
+        }
+
+        let impulse = (1.0 + config.restitution) * closing / (1.0 / mass_a + 1.0 / mass_b);
+        let (id_a, id_b) = (event.body_a, event.body_b);
+        let kick = event.normal * impulse;
+        let orbit_a = self.refit_orbit(id_a, pos_a, vel_a - kick / mass_a, event.time)?;
+        let orbit_b = self.refit_orbit(id_b, pos_b, vel_b + kick / mass_b, event.time)?;
+        self.set_orbit(id_a, orbit_a)?;
+        self.set_orbit(id_b, orbit_b)?;
+        let damage = impulse * self.config.damage.collision_hp_per_ns.max(0.0);
+        if damage > 0.0 {
+            self.deal_damage(id_a, damage);
+            self.deal_damage(id_b, damage);
+        }
+        Ok(CollisionOutcome::Bounced)
+    }
+
+    /// Break both bodies of a collision into `config.collision.fragment_count`
+    /// pieces of debris if they hit faster than `fragmentation_speed_mps`,
+    /// returning the new ids; slower impacts are left alone and return
+    /// none. The pieces split the bodies' total mass evenly and fly apart
+    /// in a ring from the wreck's centre of mass, so momentum is kept too,
+    /// on orbits around the heavier body's primary. There may be fewer of
+    /// them, or none, to keep to `config.debris`; see `cascade`. Hitting a
+    /// planet or moon fails with `UnknownBody`. On error nothing changes.
+    pub fn fragment_collision(&mut self, event: &CollisionEvent) -> error::Result<Vec<u64>> {
+        let body_a = self
+            .body(event.body_a)
+            .ok_or(GgwError::UnknownBody(event.body_a))?;
+        let body_b = self
+            .body(event.body_b)
+            .ok_or(GgwError::UnknownBody(event.body_b))?;
+        let config = &self.config.collision;
+        let impact_speed = event.relative_velocity.length();
+        if impact_speed <= config.fragmentation_speed_mps {
+            return Ok(Vec::new());
+        }
+        let total = body_a.mass + body_b.mass;
+        let count = self.debris_pieces(config.fragment_count, total, &[body_a.id, body_b.id]);
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let (pos_a, vel_a) = self.state_at(body_a, event.time)?;
+        let (pos_b, vel_b) = self.state_at(body_b, event.time)?;
+        let center = (pos_a * body_a.mass + pos_b * body_b.mass) / total;
+        let velocity = (vel_a * body_a.mass + vel_b * body_b.mass) / total;
+        let wreck_radius =
+            Float::sqrt(body_a.radius * body_a.radius + body_b.radius * body_b.radius);
+        let parent = if body_a.mass >= body_b.mass {
+            body_a.parent
+        } else {
+            body_b.parent
+        };
+        let wreck = Wreck {
+            position: center,
+            velocity,
+            mass: total,
+            radius: wreck_radius,
+            parent,
+        };
+        let heading = Float::atan2(event.relative_velocity.y, event.relative_velocity.x);
+        let fragments = self.scatter(
+            &wreck,
+            count,
+            impact_speed * config.fragment_spread,
+            heading,
+            event.time,
+        )?;
+
+        let (id_a, id_b) = (event.body_a, event.body_b);
+        self.forget_body(id_a, DespawnReason::Fragmented);
+        self.forget_body(id_b, DespawnReason::Fragmented);
+        fragments
+            .into_iter()
+            .map(|fragment| self.add_body(fragment))
+            .collect()
+    }
+
+    /// `wreck` broken into `count` pieces of debris, at least 2, that split
+    /// its mass evenly and fly apart at `speed` in a ring starting at
+    /// `heading`, so the kicks cancel out, on orbits around `wreck.parent`
+    /// as it is at `time`. Nothing is added to the world.
+    pub(crate) fn scatter(
+        &self,
+        wreck: &Wreck,
+        count: u32,
+        speed: f64,
+        heading: f64,
+        time: f64,
+    ) -> error::Result<Vec<BodyState>> {
+        let primary = *self.primaries_at(time).get(wreck.parent);
+        let count = count.max(2);
+        (0..count)
+            .map(|index| {
+                // Evenly spaced directions, so the kicks cancel out.
+                let angle = heading + 2.0 * PI * f64::from(index) / f64::from(count);
+                let direction = Vec2::new(1.0, 0.0).rotate(angle);
+                let orbit = cartesian_to_orbit(
+                    wreck.position + direction * wreck.radius - primary.position,
+                    wreck.velocity + direction * speed - primary.velocity,
+                    primary.mu,
+                    time,
+                )?;
+                Ok(BodyState {
+                    id: 0,
+                    mass: wreck.mass / f64::from(count),
+                    radius: wreck.radius / Float::sqrt(f64::from(count)),
+                    orbit,
+                    position: Vec2::zero(),
+                    velocity: Vec2::zero(),
+                    body_type: BodyType::Debris,
+                    hull_shape: None,
+                    parent: wreck.parent,
+                    orientation: 0.0,
+                    angular_velocity: 0.0,
+                    tags: BTreeMap::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// Drop `body_id` and everything that refers to it, recording a
+    /// `BodyDespawned` if it was there.
+    fn forget_body(&mut self, body_id: u64, reason: DespawnReason) -> Option<BodyState> {
+        let index = self.bodies.iter().position(|body| body.id == body_id)?;
+        let body = self.bodies.remove(index);
+        self.forget_passengers(body_id, reason);
+        let event = BodyDespawned {
+            time: self.sim_time().0,
+            id: body_id,
+            reason,
+        };
+        self.despawn_events.push(event);
+        self.events.push(WorldEvent::Despawned(event));
+        self.missile_targets
+            .retain(|missile, target| *missile != body_id && *target != body_id);
+        self.missile_guidance.remove(&body_id);
+        self.maneuver_nodes.retain(|node| node.body_id != body_id);
+        self.scheduled_thrusts
+            .retain(|event| event.body_id != body_id);
+        self.burn_programs
+            .retain(|program| program.body_id != body_id);
+        self.attitude_targets.remove(&body_id);
+        self.reentry_damage.remove(&body_id);
+        self.landed.remove(&body_id);
+        self.sensor_suites.remove(&body_id);
+        self.compositions.remove(&body_id);
+        self.propellant.remove(&body_id);
+        self.forget_autopilots(body_id);
+        self.forget_conjunctions(body_id);
+        self.ascents.remove(&body_id);
+        self.hit_points.remove(&body_id);
+        self.collision_filters.remove(&body_id);
+        self.messages.retain(|message| message.to != body_id);
+        if self.interior_body == Some(body_id) {
+            self.interior_body = None;
+        }
+        self.numeric_bodies.remove(&body_id);
+        self.kepler_cache.remove(&body_id);
+        self.sleep_tracker.wake(body_id);
+        Some(body)
+    }
+
+    /// Orbit for `body_id` around its primary passing through the world-frame
+    /// `position` and `velocity` at `time`.
+    fn refit_orbit(
+        &self,
+        body_id: u64,
+        position: Vec2,
+        velocity: Vec2,
+        time: f64,
+    ) -> error::Result<OrbitState> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        let primary = *self.primaries_at(time).get(body.parent);
+        cartesian_to_orbit(
+            position - primary.position,
+            velocity - primary.velocity,
+            primary.mu,
+            time,
+        )
+    }
+
+    /// Put `body_id` on `orbit`, moving it to where that has it now.
+    fn set_orbit(&mut self, body_id: u64, orbit: OrbitState) -> error::Result<()> {
+        let sim_time = self.sim_time().0;
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        let (position, velocity) = self.primary_of(body).state_at(&orbit, sim_time)?;
+        let body = self
+            .get_body_mut(body_id)
+            .ok_or(GgwError::UnknownBody(body_id))?;
+        body.orbit = orbit;
+        body.position = position;
+        body.velocity = velocity;
+        self.sleep_tracker.wake(body_id);
+        Ok(())
+    }
+
+    /// Schedule `node`, to be executed by the first step that reaches its
+    /// time. Fails if the body doesn't exist, the time has passed, or the
+    /// burn would leave no closed orbit.
+    pub fn plan_node(&mut self, node: ManeuverNode) -> error::Result<()> {
+        if node.time < self.sim_time() {
+            return Err(GgwError::ManeuverInPast(node.time.0));
+        }
+        self.predict_orbit_after_node(&node)?;
+        let at = self
+            .maneuver_nodes
+            .partition_point(|planned| planned.time <= node.time);
+        self.maneuver_nodes.insert(at, node);
+        Ok(())
+    }
+
+    /// The orbit `node`'s body would be on after it, counting the body's
+    /// planned nodes up to the same time first. Relative to the body's
+    /// current primary; a sphere-of-influence change in between isn't
+    /// foreseen.
+    pub fn predict_orbit_after_node(&self, node: &ManeuverNode) -> error::Result<OrbitState> {
+        let body = self
+            .body(node.body_id)
+            .ok_or(GgwError::UnknownBody(node.body_id))?;
+        let mu = self.primary_of(body).mu;
+        let mut orbit = body.orbit;
+        for planned in self.planned_nodes(node.body_id) {
+            if planned.time > node.time || planned == node {
+                break;
+            }
+            orbit = maneuver::orbit_after_burn(&orbit, mu, planned.time.0, planned.delta_v)?;
+        }
+        maneuver::orbit_after_burn(&orbit, mu, node.time.0, node.delta_v)
+    }
+
+    /// `samples` points along the path `body_id` will fly over the next
+    /// `horizon`, evenly spaced from now, for drawing its future rather than
+    /// just its current ellipse. Planned nodes and scheduled thrusts are
+    /// burned at their times, as planned, and one that wouldn't work out is
+    /// skipped as `step` would. Sphere-of-influence hand-overs are checked
+    /// at each sample. The path ends early at the first sample inside a
+    /// primary's surface.
+    pub fn predict_trajectory(
+        &self,
+        body_id: u64,
+        horizon: Seconds,
+        samples: usize,
+    ) -> error::Result<Vec<TrajectoryPoint>> {
+        let now = self.sim_time().0;
+        let horizon = horizon.0.max(0.0);
+        let interval = if samples > 1 {
+            horizon / (samples - 1) as f64
+        } else {
+            0.0
+        };
+        let times = (0..samples).map(|sample| now + interval * sample as f64);
+        self.trace_trajectory(body_id, times)
+    }
+
+    /// `predict_trajectory` sampled at `times`, which run forward from no
+    /// earlier than now.
+    pub(crate) fn trace_trajectory(
+        &self,
+        body_id: u64,
+        times: impl ExactSizeIterator<Item = f64> + Clone,
+    ) -> error::Result<Vec<TrajectoryPoint>> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        let end = times.clone().last().unwrap_or(f64::NEG_INFINITY);
+        let mut orbit = self.flight_orbit(body)?;
+        let mut parent = body.parent;
+        let nodes = self
+            .planned_nodes(body_id)
+            .map(|node| (node.time.0, node.delta_v));
+        let thrusts = self
+            .scheduled_thrusts
+            .iter()
+            .filter(|event| event.body_id == body_id)
+            .map(|event| (event.time, event.delta_v));
+        let mut burns: Vec<(f64, Vec2)> = nodes
+            .chain(thrusts)
+            .filter(|(time, _)| *time <= end)
+            .collect();
+        // Stable, so nodes go before thrusts at the same time as in `step`.
+        burns.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut burns = burns.into_iter().peekable();
+        let mut points = Vec::with_capacity(times.len());
+        for time in times {
+            while let Some((burn_time, delta_v)) =
+                burns.next_if(|(burn_time, _)| *burn_time <= time)
+            {
+                let mu = self.primaries().get(parent).mu;
+                if let Ok(after) = maneuver::orbit_after_burn(&orbit, mu, burn_time, delta_v) {
+                    orbit = after;
+                }
+            }
+            let primaries = self.primaries_at(time);
+            let primary = primaries.get(parent);
+            let (position, velocity) = primary.state_at(&orbit, time)?;
+            let crashed = position.distance(primary.position) < primary.radius;
+            if let Some(handover) =
+                central::handover(parent, (position, velocity), &primaries, time)
+            {
+                orbit = handover.orbit;
+                parent = handover.parent;
+            }
+            points.push(TrajectoryPoint {
+                time,
+                position,
+                velocity,
+                parent,
+            });
+            if crashed {
+                break;
+            }
+        }
+        Ok(points)
+    }
+
+    /// Burn for `chaser_id`, starting now, that brings it to where
+    /// `target_id` will be `tof` later, found with `transfer::lambert` in
+    /// the frame of the chaser's primary. Nothing is applied; pass the
+    /// event to `apply_thrust_event`. Arrival velocities aren't matched.
+    pub fn plan_intercept(
+        &self,
+        chaser_id: u64,
+        target_id: u64,
+        tof: Seconds,
+    ) -> error::Result<ThrustEvent> {
+        let now = self.sim_time().0;
+        let chaser = self
+            .body(chaser_id)
+            .ok_or(GgwError::UnknownBody(chaser_id))?;
+        let target = self
+            .body(target_id)
+            .ok_or(GgwError::UnknownBody(target_id))?;
+        let mu = self.primary_of(chaser).mu;
+        let (departure, velocity) = orbit_to_cartesian(&chaser.orbit, mu, now)?;
+        let arrival_time = now + tof.0;
+        let (target_pos, _) = self.state_at(target, arrival_time)?;
+        let arrival = target_pos - self.primaries_at(arrival_time).get(chaser.parent).position;
+        let (transfer_velocity, _) = transfer::lambert(departure, arrival, tof.0, mu)?;
+        Ok(ThrustEvent {
+            body_id: chaser_id,
+            time: now,
+            delta_v: transfer_velocity - velocity,
+            thrust_type: ThrustType::Chemical,
+        })
+    }
+
+    /// Cheapest burn, starting now, that puts `shooter_id` on a collision
+    /// course with `target_id`, or `None` if every intercept costs more
+    /// than `max_delta_v`. `plan_intercept` is tried for flight times up to
+    /// one orbit of the shooter; see `transfer::cheapest_flight_time`.
+    /// Transfers that would leave no closed orbit or dip below the
+    /// primary's surface before arriving are passed over.
+    pub fn compute_intercept(
+        &self,
+        shooter_id: u64,
+        target_id: u64,
+        max_delta_v: f64,
+    ) -> error::Result<Option<ThrustEvent>> {
+        let shooter = self
+            .body(shooter_id)
+            .ok_or(GgwError::UnknownBody(shooter_id))?;
+        if self.body(target_id).is_none() {
+            return Err(GgwError::UnknownBody(target_id));
+        }
+        let primary = self.primary_of(shooter);
+        let (orbit, mu) = (&shooter.orbit, primary.mu);
+        let intercept = |tof: f64| {
+            let burn = self
+                .plan_intercept(shooter_id, target_id, Seconds(tof))
+                .ok()?;
+            let after = maneuver::orbit_after_burn(orbit, mu, burn.time, burn.delta_v).ok()?;
+            // Only a transfer passing its periapsis on the way can hit the
+            // surface.
+            let clear = after.periapsis() > primary.radius
+                || after.time_to_periapsis(mu, Seconds(burn.time)).0 >= tof;
+            clear.then_some(burn)
+        };
+        let delta_v = |tof| intercept(tof).map_or(f64::INFINITY, |burn| burn.delta_v.length());
+        Ok(transfer::cheapest_flight_time(orbit.period(mu), delta_v)
+            .and_then(intercept)
+            .filter(|burn| burn.delta_v.length() <= max_delta_v))
+    }
+
+    /// `transfer::plan_hohmann` for `body_id` around its primary, starting
+    /// now. Nothing is applied; plan the burns with `plan_node` to have
+    /// them executed on time.
+    pub fn plan_hohmann(
+        &self,
+        body_id: u64,
+        to_radius: f64,
+    ) -> error::Result<(ThrustEvent, ThrustEvent)> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        let mu = self.primary_of(body).mu;
+        let (mut first, mut second) =
+            transfer::plan_hohmann(&body.orbit, to_radius, mu, self.sim_time().0)?;
+        first.body_id = body_id;
+        second.body_id = body_id;
+        Ok((first, second))
+    }
+
+    /// Every planned node, soonest first.
+    pub fn maneuver_nodes(&self) -> &[ManeuverNode] {
+        &self.maneuver_nodes
+    }
+
+    /// `body_id`'s planned nodes, soonest first.
+    pub fn planned_nodes(&self, body_id: u64) -> impl Iterator<Item = &ManeuverNode> {
+        self.maneuver_nodes
+            .iter()
+            .filter(move |node| node.body_id == body_id)
+    }
+
+    fn next_node(&self, body_id: u64) -> Option<&ManeuverNode> {
+        self.planned_nodes(body_id).next()
+    }
+
+    /// Drop every node planned for `body_id`, returning how many there were.
+    pub fn cancel_nodes(&mut self, body_id: u64) -> usize {
+        let before = self.maneuver_nodes.len();
+        self.maneuver_nodes.retain(|node| node.body_id != body_id);
+        before - self.maneuver_nodes.len()
+    }
+
+    /// Schedule `event`, to be applied at exactly `event.time` by the step
+    /// or warp that reaches it. Fails if the body doesn't exist or the time
+    /// has passed. Whether the burn works out, and whether there is the
+    /// propellant for it, is only known then; a burn that doesn't is
+    /// recorded as a `WorldEvent::ThrustRejected`.
+    pub fn schedule_thrust(&mut self, event: ThrustEvent) -> error::Result<()> {
+        if self.body(event.body_id).is_none() {
+            return Err(GgwError::UnknownBody(event.body_id));
+        }
+        if event.time < self.sim_time().0 {
+            return Err(GgwError::ManeuverInPast(event.time));
+        }
+        let at = self
+            .scheduled_thrusts
+            .partition_point(|scheduled| scheduled.time <= event.time);
+        self.scheduled_thrusts.insert(at, event);
+        Ok(())
+    }
+
+    /// Every scheduled thrust event, soonest first.
+    pub fn scheduled_thrusts(&self) -> &[ThrustEvent] {
+        &self.scheduled_thrusts
+    }
+
+    /// Drop every thrust event scheduled for `body_id`, returning how many
+    /// there were.
+    pub fn cancel_scheduled_thrusts(&mut self, body_id: u64) -> usize {
+        let before = self.scheduled_thrusts.len();
+        self.scheduled_thrusts
+            .retain(|event| event.body_id != body_id);
+        before - self.scheduled_thrusts.len()
+    }
+
+    /// Clock tick of the next scheduled thrust event, if any.
+    fn next_thrust_tick(&self) -> Option<u64> {
+        self.scheduled_thrusts
+            .first()
+            .map(|event| clock::ticks_from_seconds_ceil(Seconds(event.time)))
+    }
+
+    /// Schedule `program`, to be burned by the steps that cover it. Fails if
+    /// the body doesn't exist, the start has passed, or the duration isn't
+    /// positive.
+    pub fn plan_burn(&mut self, program: BurnProgram) -> error::Result<()> {
+        if self.body(program.body_id).is_none() {
+            return Err(GgwError::UnknownBody(program.body_id));
+        }
+        if program.start < self.sim_time().0 {
+            return Err(GgwError::ManeuverInPast(program.start));
+        }
+        let valid = program.duration > 0.0
+            && program.end().is_finite()
+            && program.acceleration.x.is_finite()
+            && program.acceleration.y.is_finite();
+        if !valid {
+            return Err(GgwError::InvalidBurn);
+        }
+        let at = self
+            .burn_programs
+            .partition_point(|planned| planned.start <= program.start);
+        self.burn_programs.insert(at, program);
+        Ok(())
+    }
+
+    /// Every planned or running burn program, by start time.
+    pub fn burn_programs(&self) -> &[BurnProgram] {
+        &self.burn_programs
+    }
+
+    /// Drop every burn program for `body_id`, running or not, returning how
+    /// many there were.
+    pub fn cancel_burns(&mut self, body_id: u64) -> usize {
+        let before = self.burn_programs.len();
+        self.burn_programs
+            .retain(|program| program.body_id != body_id);
+        before - self.burn_programs.len()
+    }
+
+    /// Burn the part of each program that falls in the tick of `dt` seconds
+    /// just ended, and drop the programs that have finished. A program
+    /// whose body is gone or whose burn no longer works out is dropped.
+    fn execute_burns(&mut self, dt: f64) {
+        if self.burn_programs.is_empty() {
+            return;
+        }
+        let now = self.sim_time().0;
+        let from = now - dt;
+        let programs = core::mem::take(&mut self.burn_programs);
+        for program in programs {
+            if program.start >= now {
+                self.burn_programs.push(program);
+                continue;
+            }
+            match self.burn(&program, from, now) {
+                Ok(()) if program.end() > now => self.burn_programs.push(program),
+                Ok(()) => {
+                    tracing::debug!(body = program.body_id, "burn program finished")
+                }
+                Err(err) => {
+                    tracing::warn!(body = program.body_id, error = %err, "burn program dropped")
+                }
+            }
+        }
+    }
+
+    /// Move up the epoch of every orbit more than `EPOCH_REBASE_S` old, of
+    /// bodies and moons alike. Only the elements change: positions and
+    /// velocities stay as this tick left them, and an orbit whose rebased
+    /// elements don't put its body back in the same place is left alone.
+    fn rebase_epochs(&mut self) {
+        let now = self.sim_time().0;
+        let stale = |orbit: &OrbitState| now - orbit.epoch > EPOCH_REBASE_S;
+        let rebase = |orbit: &mut OrbitState, mu: f64| {
+            let rebased = orbit.rebased(mu, now);
+            let moved = match (
+                orbit_to_cartesian(orbit, mu, now),
+                orbit_to_cartesian(&rebased, mu, now),
+            ) {
+                (Ok((before, _)), Ok((after, _))) => before.distance(after),
+                _ => return,
+            };
+            if moved <= EPOCH_REBASE_TOLERANCE_M {
+                *orbit = rebased;
+            } else {
+                tracing::warn!(moved, "orbit epoch not rebased");
+            }
+        };
+        let moons = &self.central_bodies;
+        let due: Vec<(usize, f64)> = moons
+            .iter()
+            .enumerate()
+            .filter(|(_, moon)| stale(&moon.orbit))
+            .map(|(index, moon)| (index, central::parent_mu(self.mu, moons, moon)))
+            .collect();
+        for (index, mu) in due {
+            rebase(&mut self.central_bodies[index].orbit, mu);
+        }
+        let due: Vec<(usize, f64)> = self
+            .bodies
+            .iter()
+            .enumerate()
+            .filter(|(_, body)| stale(&body.orbit))
+            .map(|(index, body)| (index, self.primary_of(body).mu))
+            .collect();
+        for (index, mu) in due {
+            rebase(&mut self.bodies[index].orbit, mu);
+        }
+    }
+
+    /// Heat every body that dipped into the atmosphere during the tick of
+    /// `dt` seconds just ended, and burn up those that have had enough.
+    fn heat_reentering_bodies(&mut self, dt: f64) {
+        let config = &self.config.reentry;
+        let now = self.sim_time().0;
+        let mut burnt_up = Vec::new();
+        for body in &self.bodies {
+            if body.parent.is_some()
+                || self.landed.contains_key(&body.id)
+                || self.ascents.contains_key(&body.id)
+                || !reentry::dips_into_atmosphere(&body.orbit, self.planet_radius, config)
+            {
+                continue;
+            }
+            let (from, radius) = (now - dt, self.planet_radius);
+            let heat = reentry::heating(&body.orbit, self.mu, radius, config, from, now);
+            if heat <= 0.0 {
+                continue;
+            }
+            let damage = self.reentry_damage.entry(body.id).or_insert(0.0);
+            *damage += heat;
+            if *damage >= 1.0 {
+                burnt_up.push(body.id);
+            }
+        }
+        for id in burnt_up {
+            tracing::debug!(body = id, "body burnt up on reentry");
+            self.forget_body(id, DespawnReason::ReentryBurnup);
+        }
+    }
+
+    /// Apply `program` between `from` and `to`, leaving the body where its
+    /// new orbit puts it at `to`.
+    fn burn(&mut self, program: &BurnProgram, from: f64, to: f64) -> error::Result<()> {
+        let body = self
+            .body(program.body_id)
+            .ok_or(GgwError::UnknownBody(program.body_id))?;
+        let primary = *self.primaries().get(body.parent);
+        let orbit = self.flight_orbit(body)?;
+        let orbit = maneuver::orbit_after_program(&orbit, primary.mu, program, from, to)?;
+        let (position, velocity) = primary.state_at(&orbit, to)?;
+        self.landed.remove(&program.body_id);
+        if let Some(body) = self.get_body_mut(program.body_id) {
+            body.orbit = orbit;
+            body.position = position;
+            body.velocity = velocity;
+        }
+        self.sleep_tracker.wake(program.body_id);
+        Ok(())
+    }
+
+    /// Burn every node the clock has reached, at its own time. A node whose
+    /// body is gone or whose burn no longer works out is dropped.
+    fn execute_due_nodes(&mut self) {
+        let now = self.sim_time();
+        let due = self.maneuver_nodes.partition_point(|node| node.time <= now);
+        for node in self.maneuver_nodes.drain(..due).collect::<Vec<_>>() {
+            let event = ThrustEvent {
+                body_id: node.body_id,
+                time: node.time.0,
+                delta_v: node.delta_v,
+                thrust_type: ThrustType::Chemical,
+            };
+            match self.apply_thrust_event(&event) {
+                Ok(()) => {
+                    tracing::debug!(body = node.body_id, time = node.time.0, "maneuver executed")
+                }
+                Err(err) => {
+                    tracing::warn!(body = node.body_id, error = %err, "maneuver node dropped")
+                }
+            }
+        }
+    }
+
+    /// Apply every scheduled thrust event the clock has reached, at its own
+    /// time.
+    fn execute_due_thrusts(&mut self) {
+        let now = self.sim_time().0;
+        let due = self
+            .scheduled_thrusts
+            .partition_point(|event| event.time <= now);
+        for event in self.scheduled_thrusts.drain(..due).collect::<Vec<_>>() {
+            if let Err(err) = self.apply_thrust_event(&event) {
+                tracing::warn!(body = event.body_id, error = %err, "scheduled thrust dropped");
+            }
+        }
+    }
+
+    /// Sphere-of-influence hand-overs since the last `drain_soi_events`,
+    /// oldest first.
+    pub fn soi_events(&self) -> &[SoiEvent] {
+        &self.soi_events
+    }
+
+    /// Take the sphere-of-influence hand-overs since the last call, oldest
+    /// first.
+    pub fn drain_soi_events(&mut self) -> Vec<SoiEvent> {
+        core::mem::take(&mut self.soi_events)
+    }
+
+    /// Bodies removed since the last `drain_despawn_events`, oldest first.
+    pub fn despawn_events(&self) -> &[BodyDespawned] {
+        &self.despawn_events
+    }
+
+    /// Take the bodies removed since the last call, oldest first, so
+    /// anything tracking ids can let go of them.
+    pub fn drain_despawn_events(&mut self) -> Vec<BodyDespawned> {
+        core::mem::take(&mut self.despawn_events)
+    }
+
+    /// Dormancy state of every body; see `sleep`.
+    pub fn sleep_tracker(&self) -> &SleepTracker {
+        &self.sleep_tracker
+    }
+
+    pub fn is_dormant(&self, body_id: u64) -> bool {
+        self.sleep_tracker.is_dormant(body_id)
+    }
+
+    /// Wake a dormant body and bring its position up to date, returning
+    /// whether it was dormant. It may go back to sleep at the end of the next
+    /// step if nothing is near it.
+    pub fn wake_body(&mut self, body_id: u64) -> bool {
+        if !self.sleep_tracker.wake(body_id) {
+            return false;
+        }
+        let state = self
+            .body(body_id)
+            .and_then(|body| self.state_at(body, self.sim_time().0).ok());
+        if let (Some((position, velocity)), Some(body)) = (state, self.get_body_mut(body_id)) {
+            body.position = position;
+            body.velocity = velocity;
+        }
+        true
+    }
+
+    /// Sensor ping: wake every dormant body within `radius_m` of `center`,
+    /// returning how many woke.
+    pub fn ping(&mut self, center: Vec2, radius_m: Meters) -> usize {
+        let now = self.sim_time().0;
+        let in_range: Vec<u64> = self
+            .bodies
+            .iter()
+            .filter(|body| self.sleep_tracker.is_dormant(body.id))
+            .filter(|body| {
+                self.state_at(body, now).is_ok_and(|(position, _)| {
+                    position.distance(center) <= radius_m.0 + body.radius
+                })
+            })
+            .map(|body| body.id)
+            .collect();
+        for &id in &in_range {
+            self.wake_body(id);
+        }
+        in_range.len()
+    }
+
+    /// Dry run of `step(dt)`: where every body will be, which collisions
+    /// `detect_collisions` would report, and which bodies will cross the
+    /// gravity-well, sphere-of-influence or despawn boundary. Scenario
+    /// actions due in the window are not applied.
+    pub fn preview_step(&self, dt: Seconds) -> StepPreview {
+        let time = self.sim_time().0 + dt.0;
+        let primaries = self.primaries_at(time);
+        let mut bodies = Vec::with_capacity(self.bodies.len());
+        let mut soi_changes = Vec::new();
+        for (body, state) in self.bodies.iter().zip(self.orbit_batch().propagate(time)) {
+            let Some((position, velocity)) = state else {
+                continue;
+            };
+            let primary = primaries.get(body.parent);
+            let (position, velocity) = (primary.position + position, primary.velocity + velocity);
+            let transition = if position.length() > DESPAWN_RADIUS_M {
+                Some(SoiTransition::Despawned)
+            } else if let Some(handover) =
+                central::handover(body.parent, (position, velocity), &primaries, time)
+            {
+                Some(handover.transition)
+            } else if body.parent.is_some() {
+                None
+            } else {
+                let inside_now = self.is_inside_gravity_well(body);
+                let inside_then =
+                    position.distance(primary.position) <= primary.gravity_well_radius;
+                match (inside_now, inside_then) {
+                    (false, true) => Some(SoiTransition::EnteredGravityWell),
+                    (true, false) => Some(SoiTransition::LeftGravityWell),
+                    _ => None,
+                }
+            };
+            if let Some(transition) = transition {
+                soi_changes.push(SoiChange {
+                    body_id: body.id,
+                    transition,
+                });
+            }
+            bodies.push(PredictedBody {
+                id: body.id,
+                position,
+                velocity,
+            });
+        }
+        StepPreview {
+            time,
+            bodies,
+            collisions: self.detect_collisions(dt),
+            soi_changes,
+        }
+    }
+
+    /// Every body's orbit, in `bodies` order, ready for batch propagation.
+    /// Each propagates relative to its primary; add the primary's state at
+    /// the same time to place it in the world.
+    pub fn orbit_batch(&self) -> OrbitBatch {
+        let primaries = self.primaries();
+        let mut batch = OrbitBatch::with_capacity(self.bodies.len());
+        for body in &self.bodies {
+            batch.push(&body.orbit, primaries.get(body.parent).mu);
+        }
+        batch
+    }
+
+    /// Every collision between now and `sim_time + dt`. A grid over the
+    /// bodies' paths this tick picks out the pairs worth a narrow-phase
+    /// check. Each event's `time` is when the bodies first touched; see
+    /// `collision` for how the tick is swept.
+    pub fn detect_collisions(&self, dt: Seconds) -> Vec<CollisionEvent> {
+        let mut events = Vec::new();
+        let mut scratch = CollisionScratch::new();
+        self.detect_collisions_with(dt, &mut scratch, &mut events);
+        events
+    }
+
+    /// `detect_collisions` into `events` (cleared first), reusing the
+    /// world's own scratch buffers. Once those and `events` have grown to
+    /// fit, a call makes no heap allocations.
+    pub fn detect_collisions_into(&mut self, dt: Seconds, events: &mut Vec<CollisionEvent>) {
+        let mut scratch = core::mem::take(&mut self.collision_scratch);
+        self.detect_collisions_with(dt, &mut scratch, events);
+        self.collision_scratch = scratch;
+    }
+
+    /// `detect_collisions` with caller-provided buffers, for callers that
+    /// only hold a shared reference to the world.
+    pub fn detect_collisions_with(
+        &self,
+        dt: Seconds,
+        scratch: &mut CollisionScratch,
+        events: &mut Vec<CollisionEvent>,
+    ) {
+        let _span = tracing::trace_span!("collisions", bodies = self.bodies.len()).entered();
+        let target_time = self.sim_time().0 + dt.0;
+        events.clear();
+        collision::detect_all(
+            &self.bodies,
+            &self.primaries(),
+            &self.primaries_at(target_time),
+            (self.sim_time().0, target_time),
+            |id| self.sleep_tracker.is_dormant(id) || self.landed.contains_key(&id),
+            collision::Filters::new(&self.collision_filters, &self.config.debris),
+            scratch,
+            events,
+        );
+        if !events.is_empty() {
+            tracing::debug!(count = events.len(), "collisions detected");
+        }
+    }
+
+    /// Same result as `detect_collisions`, but only checks the pairs whose
+    /// encounter risk says they could be touching by now. Meant to be
+    /// called once per tick; see `collision` for the scheduling.
+    pub fn detect_collisions_tiered(&mut self, dt: Seconds) -> Vec<CollisionEvent> {
+        let _span = tracing::trace_span!("collisions", bodies = self.bodies.len()).entered();
+        let target_time = self.sim_time().0 + dt.0;
+        let start = self.primaries();
+        let end = self.primaries_at(target_time);
+        let events = self.collision_scheduler.detect(
+            &self.bodies,
+            &start,
+            &end,
+            (self.sim_time().0, target_time),
+            &self.sleep_tracker,
+            &self.landed,
+            collision::Filters::new(&self.collision_filters, &self.config.debris),
+        );
+        if !events.is_empty() {
+            tracing::debug!(count = events.len(), "collisions detected");
+        }
+        events
+    }
+
+    /// Give `body_id` its own collision `filter`, or with `None` go back to
+    /// the one for its type; see `CollisionFilter`.
+    pub fn set_collision_filter(
+        &mut self,
+        body_id: u64,
+        filter: Option<CollisionFilter>,
+    ) -> error::Result<()> {
+        self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        match filter {
+            Some(filter) => self.collision_filters.insert(body_id, filter),
+            None => self.collision_filters.remove(&body_id),
+        };
+        Ok(())
+    }
+
+    /// `body_id`'s collision filter, or `None` if there is no such body.
+    pub fn collision_filter(&self, body_id: u64) -> Option<CollisionFilter> {
+        let body = self.body(body_id)?;
+        Some(collision::Filters::new(&self.collision_filters, &self.config.debris).of(body))
+    }
+    /// Pair counts from the last `detect_collisions_tiered` call.
+    pub fn collision_stats(&self) -> CollisionStats {
+        self.collision_scheduler.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
     const MU_EARTH: f64 = 3.986004418e14;
 
     fn approx_eq(a: f64, b: f64, eps: f64) {
@@ -448,7 +2997,7 @@ mod tests {
         let speeds = [0.0, 100.0, 1_000.0, 10_000.0];
         let expected_speed = (MU_EARTH / a).sqrt();
         for t in speeds.iter().copied() {
-            let (pos, vel) = orbit_to_cartesian(&orbit, MU_EARTH, t);
+            let (pos, vel) = orbit_to_cartesian(&orbit, MU_EARTH, t).unwrap();
             approx_eq(pos.length(), a, 1e-3);
             approx_eq(vel.length(), expected_speed, 1e-6);
         }
@@ -464,41 +3013,614 @@ mod tests {
             epoch: 1000.0,
         };
         let t = 1234.5;
-        let (pos, vel) = orbit_to_cartesian(&orbit, MU_EARTH, t);
-        let recovered = cartesian_to_orbit(pos, vel, MU_EARTH, t);
+        let (pos, vel) = orbit_to_cartesian(&orbit, MU_EARTH, t).unwrap();
+        let recovered = cartesian_to_orbit(pos, vel, MU_EARTH, t).unwrap();
         approx_eq(recovered.semi_major_axis, orbit.semi_major_axis, 1e-3);
         approx_eq(recovered.eccentricity, orbit.eccentricity, 1e-9);
         approx_eq(recovered.arg_of_periapsis, orbit.arg_of_periapsis, 1e-9);
     }
 
     #[test]
-    fn thrust_event_changes_orbit() {
+    fn sampled_points_trace_the_orbit() {
+        let orbit = OrbitState {
+            semi_major_axis: 20_000_000.0,
+            eccentricity: 0.3,
+            arg_of_periapsis: 1.2,
+            mean_anomaly_at_epoch: -0.8,
+            epoch: 1000.0,
+        };
+        let points = orbit.sample_points(MU_EARTH, 64).unwrap();
+        assert_eq!(points.len(), 64);
+        let (at_epoch, _) = orbit_to_cartesian(&orbit, MU_EARTH, orbit.epoch).unwrap();
+        assert!(points[0].distance(at_epoch) < 1e-6);
+        // Sampling from a later time starts where the body is then.
+        let (later, _) = orbit_to_cartesian(&orbit, MU_EARTH, 2234.5).unwrap();
+        let from_later = orbit
+            .sample_points_at(MU_EARTH, Seconds(2234.5), 8)
+            .unwrap();
+        assert!(from_later[0].distance(later) < 1e-6);
+        // Every point lies on the ellipse, going anticlockwise.
+        let p = orbit.semi_major_axis * (1.0 - orbit.eccentricity * orbit.eccentricity);
+        for pair in points.windows(2) {
+            let r = pair[0].length();
+            let nu = Float::atan2(pair[0].y, pair[0].x) - orbit.arg_of_periapsis;
+            approx_eq(r, p / (1.0 + orbit.eccentricity * Float::cos(nu)), 1e-3);
+            assert!(pair[0].cross(pair[1]) > 0.0);
+        }
+
+        // Near-circular orbits come out round, and nothing is asked for
+        // nothing.
+        let circle = OrbitState {
+            eccentricity: 1e-14,
+            ..orbit
+        };
+        for point in circle.sample_points(MU_EARTH, 16).unwrap() {
+            approx_eq(point.length(), circle.semi_major_axis, 1e-3);
+        }
+        assert!(orbit.sample_points(MU_EARTH, 0).unwrap().is_empty());
+
         let mut world = World::new(MU_EARTH, GameConfig::default());
-        let a = 7_000_000.0;
-        let body = BodyState {
-            id: 0,
-            mass: 1_000.0,
-            radius: 5.0,
-            orbit: OrbitState {
-                semi_major_axis: a,
-                eccentricity: 0.0,
+        let id = world
+            .add_body(BodyState::circular(7_000_000.0, 0.5))
+            .unwrap();
+        world.step(Seconds(30.0));
+        let path = world.orbit_path(id, 32).unwrap();
+        assert!(path[0].distance(world.body(id).unwrap().position) < 1e-6);
+        assert_eq!(
+            world.orbit_path(id + 1, 32),
+            Err(GgwError::UnknownBody(id + 1))
+        );
+    }
+
+    #[test]
+    fn invalid_states_return_errors() {
+        let mut orbit = OrbitState {
+            semi_major_axis: 7_000_000.0,
+            eccentricity: 1.5,
+            arg_of_periapsis: 0.0,
+            mean_anomaly_at_epoch: 0.0,
+            epoch: 0.0,
+        };
+        assert_eq!(
+            orbit_to_cartesian(&orbit, MU_EARTH, 0.0),
+            Err(GgwError::InvalidEccentricity(1.5))
+        );
+        orbit.eccentricity = 0.0;
+        orbit.semi_major_axis = -1.0;
+        assert!(orbit_to_cartesian(&orbit, MU_EARTH, 0.0).is_err());
+
+        let radial = cartesian_to_orbit(
+            Vec2::new(7_000_000.0, 0.0),
+            Vec2::new(100.0, 0.0),
+            MU_EARTH,
+            0.0,
+        );
+        assert_eq!(radial.unwrap_err(), GgwError::DegenerateOrbit);
+
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let id = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let before = world.bodies[0].orbit;
+        let escape = ThrustEvent {
+            body_id: id,
+            time: 0.0,
+            delta_v: Vec2::new(0.0, 20_000.0),
+            thrust_type: ThrustType::Chemical,
+        };
+        assert_eq!(
+            world.apply_thrust_event(&escape),
+            Err(GgwError::UnboundOrbit)
+        );
+        assert_eq!(
+            world.bodies[0].orbit.semi_major_axis,
+            before.semi_major_axis
+        );
+        let missing = ThrustEvent {
+            body_id: 99,
+            ..escape
+        };
+        assert_eq!(
+            world.apply_thrust_event(&missing),
+            Err(GgwError::UnknownBody(99))
+        );
+        assert_eq!(
+            world.drain_events()[1..],
+            [
+                WorldEvent::ThrustRejected {
+                    event: escape,
+                    error: GgwError::UnboundOrbit,
+                },
+                WorldEvent::ThrustRejected {
+                    event: missing,
+                    error: GgwError::UnknownBody(99),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn vec2_operators_and_helpers() {
+        let mut v = Vec2::new(3.0, 4.0);
+        assert_eq!(-v, Vec2::new(-3.0, -4.0));
+        assert_eq!(v / 2.0, Vec2::new(1.5, 2.0));
+        v += Vec2::new(1.0, 1.0);
+        v -= Vec2::new(2.0, 0.0);
+        v *= 2.0;
+        assert_eq!(v, Vec2::new(4.0, 10.0));
+        v /= 2.0;
+        assert_eq!(2.0 * v, Vec2::new(4.0, 10.0));
+        assert_eq!(0.5_f32 * Vec2::new(2.0_f32, 4.0), Vec2::new(1.0, 2.0));
+
+        let x = Vec2::new(1.0, 0.0);
+        let y = Vec2::new(0.0, 1.0);
+        assert_eq!(x.cross(y), 1.0);
+        assert_eq!(x.perp(), y);
+        let rotated = x.rotate(PI / 2.0);
+        approx_eq(rotated.x, 0.0, 1e-12);
+        approx_eq(rotated.y, 1.0, 1e-12);
+        assert_eq!(x.lerp(y, 0.5), Vec2::new(0.5, 0.5));
+        approx_eq(Vec2::zero().distance(Vec2::new(3.0, 4.0)), 5.0, 1e-12);
+        approx_eq(x.angle_to(y), PI / 2.0, 1e-12);
+        approx_eq(y.angle_to(x), -PI / 2.0, 1e-12);
+    }
+
+    #[test]
+    fn stale_epochs_are_rebased_in_place() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let mut body = BodyState::circular(7_500_000.0, 2.0);
+        body.orbit.eccentricity = 0.1;
+        let original = body.orbit;
+        let id = world.add_body(body).unwrap();
+
+        world.warp_to(Seconds(EPOCH_REBASE_S - 60.0));
+        assert_eq!(world.body(id).unwrap().orbit, original);
+        world.warp_to(Seconds(3.0 * EPOCH_REBASE_S));
+        let body = world.body(id).unwrap();
+        let now = world.sim_time().0;
+        assert!(now - body.orbit.epoch <= EPOCH_REBASE_S);
+        assert!(body.orbit.mean_anomaly_at_epoch.abs() <= PI);
+        assert_eq!(
+            (body.orbit.semi_major_axis, body.orbit.eccentricity),
+            (original.semi_major_axis, original.eccentricity)
+        );
+        let (expected, _) = orbit_to_cartesian(&original, MU_EARTH, now).unwrap();
+        assert!(body.position.distance(expected) < 1e-3);
+        let (rebased, _) = orbit_to_cartesian(&body.orbit, MU_EARTH, now).unwrap();
+        assert!(rebased.distance(expected) < 1e-3);
+    }
+
+    #[test]
+    fn missiles_targeting_follows_assignments() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let missile = world
+            .add_body(BodyState {
+                body_type: BodyType::Missile,
+                ..BodyState::circular(7_000_000.0, 0.1)
+            })
+            .unwrap();
+        assert_eq!(
+            world.set_missile_target(ship, missile),
+            Err(GgwError::NotAMissile(ship))
+        );
+        assert_eq!(
+            world.set_missile_target(missile, 99),
+            Err(GgwError::UnknownBody(99))
+        );
+        world.set_missile_target(missile, ship).unwrap();
+        let ids: Vec<u64> = world.missiles_targeting(ship).map(|m| m.id).collect();
+        assert_eq!(ids, [missile]);
+        assert_eq!(world.ships().count(), 1);
+
+        world.get_body_mut(ship).unwrap().position = Vec2::new(DESPAWN_RADIUS_M * 2.0, 0.0);
+        world.cull_despawned_bodies();
+        assert_eq!(world.missile_target(missile), None);
+        assert_eq!(
+            world.drain_despawn_events(),
+            [BodyDespawned {
+                time: 0.0,
+                id: ship,
+                reason: DespawnReason::LeftDespawnRadius,
+            }]
+        );
+        assert!(world.despawn_events().is_empty());
+    }
+
+    #[test]
+    fn removed_ids_are_never_reused() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let first = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let second = world
+            .add_body(BodyState::circular(7_000_000.0, 0.1))
+            .unwrap();
+        world
+            .plan_node(ManeuverNode {
+                body_id: second,
+                time: Seconds(10.0),
+                delta_v: Vec2::new(0.0, 1.0),
+            })
+            .unwrap();
+
+        let removed = world.remove_body(second).unwrap();
+        assert_eq!(removed.id, second);
+        assert!(world.body(second).is_none());
+        assert!(world.maneuver_nodes().is_empty());
+        assert!(world.remove_body(second).is_none());
+        assert_eq!(
+            world.drain_despawn_events(),
+            [BodyDespawned {
+                time: 0.0,
+                id: second,
+                reason: DespawnReason::Removed,
+            }]
+        );
+
+        let third = world
+            .add_body(BodyState::circular(7_000_000.0, 0.2))
+            .unwrap();
+        assert!(third > second);
+        // A body brought in with its own id pushes fresh ids past it.
+        let imported = world
+            .add_body(BodyState {
+                id: third + 10,
+                ..BodyState::circular(7_000_000.0, 0.3)
+            })
+            .unwrap();
+        let fresh = world
+            .add_body(BodyState::circular(7_000_000.0, 0.4))
+            .unwrap();
+        assert!(fresh > imported);
+        assert_ne!(fresh, first);
+        // Ids already handed out, live or removed, can't be asked for again.
+        for id in [first, second, third + 5, u64::MAX] {
+            assert_eq!(
+                world.add_body(BodyState {
+                    id,
+                    ..BodyState::circular(7_000_000.0, 0.5)
+                }),
+                Err(GgwError::InvalidBodyId(id))
+            );
+        }
+        assert!(world.body(u64::MAX).is_none());
+    }
+
+    #[test]
+    fn preview_step_predicts_without_mutating() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let id = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let escaping = world
+            .add_body(BodyState::orbiting(OrbitState {
+                semi_major_axis: 1.0e9,
+                eccentricity: 0.6,
                 arg_of_periapsis: 0.0,
                 mean_anomaly_at_epoch: 0.0,
                 epoch: 0.0,
-            },
-            position: Vec2::zero(),
-            velocity: Vec2::zero(),
-            body_type: BodyType::Ship,
-            hull_shape: None,
+            }))
+            .unwrap();
+        let hash = world.state_hash();
+        // Half a period takes the eccentric body from periapsis to an
+        // apoapsis beyond the gravity well.
+        let half_period = PI * (1.0e27 / MU_EARTH).sqrt();
+
+        let preview = world.preview_step(Seconds(half_period));
+        assert_eq!(world.state_hash(), hash);
+        assert_eq!(preview.time, half_period);
+        assert!(preview.collisions.is_empty());
+        assert_eq!(
+            preview.soi_changes,
+            [SoiChange {
+                body_id: escaping,
+                transition: SoiTransition::LeftGravityWell,
+            }]
+        );
+
+        let (position, _) =
+            orbit_to_cartesian(&world.body(id).unwrap().orbit, MU_EARTH, half_period).unwrap();
+        assert_eq!(preview.body(id).unwrap().position, position);
+    }
+
+    #[test]
+    fn predicted_trajectories_fly_planned_burns() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let id = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        world
+            .plan_node(ManeuverNode {
+                body_id: id,
+                time: Seconds(100.0),
+                delta_v: Vec2::new(0.0, 200.0),
+            })
+            .unwrap();
+        world
+            .schedule_thrust(ThrustEvent {
+                body_id: id,
+                time: 1_000.0,
+                delta_v: Vec2::new(50.0, 0.0),
+                thrust_type: ThrustType::Chemical,
+            })
+            .unwrap();
+        assert_eq!(
+            world.predict_trajectory(99, Seconds(1.0), 2),
+            Err(GgwError::UnknownBody(99))
+        );
+
+        let path = world.predict_trajectory(id, Seconds(3_000.0), 31).unwrap();
+        assert_eq!(path.len(), 31);
+        assert_eq!(path[0].position, world.body(id).unwrap().position);
+        assert!((path[1].position.length() - 7_000_000.0).abs() < 1.0);
+        assert!(path[30].position.length() > 7_100_000.0);
+
+        world.warp_to(Seconds(3_000.0));
+        let flown = world.body(id).unwrap();
+        assert!(flown.position.distance(path[30].position) < 1.0);
+        assert!(flown.velocity.distance(path[30].velocity) < 1e-3);
+    }
+
+    #[test]
+    fn f32_propagation_tracks_f64() {
+        let orbit = OrbitState {
+            semi_major_axis: 7_500_000.0,
+            eccentricity: 0.2,
+            arg_of_periapsis: 0.7,
+            mean_anomaly_at_epoch: 0.3,
+            epoch: 0.0,
         };
-        let body_id = world.add_body(body);
+        let t = 1.0e7;
+        let (pos, vel) = orbit_to_cartesian(&orbit, MU_EARTH, t).unwrap();
+        let (pos_f, vel_f) = orbit_to_cartesian_in::<f32>(&orbit, MU_EARTH, t).unwrap();
+        assert!(pos.distance(pos_f.cast()) < 50.0);
+        assert!(vel.distance(vel_f.cast()) < 0.05);
+        let v: Vec2f = Vec2::new(3.0, 4.0);
+        assert_eq!(v.length(), 5.0_f32);
+        assert_eq!((v * 2.0).cast::<f64>(), Vec2::new(6.0, 8.0));
+    }
 
-        let burn_time = 500.0;
-        let (pos, _vel) = orbit_to_cartesian(
-            &world.bodies.iter().find(|b| b.id == body_id).unwrap().orbit,
-            world.mu,
-            burn_time,
+    /// Bit patterns recorded from the `libm` crate. Any platform running
+    /// with `deterministic-math` has to reproduce them exactly.
+    #[cfg(feature = "deterministic-math")]
+    #[test]
+    fn deterministic_math_matches_golden_bits() {
+        let orbit = OrbitState {
+            semi_major_axis: 9_000_000.0,
+            eccentricity: 0.6,
+            arg_of_periapsis: 1.1,
+            mean_anomaly_at_epoch: 2.5,
+            epoch: 0.0,
+        };
+        let (pos, vel) = orbit_to_cartesian(&orbit, MU_EARTH, 123_456.789).unwrap();
+        let back = cartesian_to_orbit(pos, vel, MU_EARTH, 0.0).unwrap();
+        assert_eq!(pos.x.to_bits(), 0x41539dcbf16814ad);
+        assert_eq!(pos.y.to_bits(), 0xc1450a6ff2889979);
+        assert_eq!(vel.x.to_bits(), 0xc080249e7c9380d4);
+        assert_eq!(vel.y.to_bits(), 0x40c2bd81035ddabe);
+        assert_eq!(back.mean_anomaly_at_epoch.to_bits(), 0xbfdd56f56e4f1f35);
+    }
+
+    #[test]
+    fn warp_to_reaches_target_time() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let id = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        world.warp_to(Seconds(3_600.0));
+        approx_eq(world.sim_time().0, 3_600.0, 1e-9);
+        let body = world.body(id).unwrap();
+        let (expected, _) = orbit_to_cartesian(&body.orbit, MU_EARTH, 3_600.0).unwrap();
+        approx_eq(body.position.distance(expected), 0.0, 1e-6);
+    }
+
+    #[test]
+    fn warp_to_next_periapsis() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let mut body = BodyState::circular(8_000_000.0, 1.0);
+        body.orbit.eccentricity = 0.1;
+        let id = world.add_body(body).unwrap();
+        let reached = world
+            .warp_to_next(id, WarpTarget::Periapsis, Seconds(1.0e6))
+            .expect("periapsis within horizon");
+        let body = world.body(id).unwrap();
+        let periapsis = body.orbit.semi_major_axis * (1.0 - body.orbit.eccentricity);
+        assert_eq!(reached, world.sim_time());
+        approx_eq(body.position.length(), periapsis, 1.0);
+    }
+
+    #[test]
+    fn warp_to_next_encounter() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let mut rock = BodyState::circular(7_050_000.0, 0.5);
+        rock.body_type = BodyType::Asteroid;
+        world.add_body(rock).unwrap();
+        assert!(world
+            .next_warp_time(ship, WarpTarget::Maneuver, Seconds(1.0e6))
+            .is_none());
+        let reached = world
+            .warp_to_next(ship, WarpTarget::Encounter, Seconds(1.0e6))
+            .expect("encounter within horizon");
+        assert!(reached > Seconds::ZERO);
+        let dist = world.bodies[0].position.distance(world.bodies[1].position);
+        assert!(dist <= ENCOUNTER_DISTANCE_M + world.bodies[1].radius);
+    }
+
+    #[test]
+    fn state_hash_tracks_simulation() {
+        let mut a = World::new(MU_EARTH, GameConfig::default());
+        let mut b = World::new(MU_EARTH, GameConfig::default());
+        a.add_body(BodyState::circular(7_000_000.0, 0.0)).unwrap();
+        b.add_body(BodyState::circular(7_000_000.0, 0.0)).unwrap();
+        for _ in 0..10 {
+            a.step(Seconds(0.5));
+            b.step(Seconds(0.5));
+        }
+        assert_eq!(a.state_hash(), b.state_hash());
+        b.step(Seconds(0.5));
+        assert_ne!(a.state_hash(), b.state_hash());
+    }
+
+    #[test]
+    fn state_hash_covers_per_body_state() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let id = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let mut hash = world.state_hash();
+        let mut changed = |world: &World| {
+            let last = core::mem::replace(&mut hash, world.state_hash());
+            last != hash
+        };
+
+        world
+            .plan_burn(BurnProgram {
+                body_id: id,
+                start: 100.0,
+                duration: 10.0,
+                acceleration: Vec2::new(1.0, 0.0),
+            })
+            .unwrap();
+        assert!(changed(&world));
+        world.set_attitude_target(id, Some(Radians(1.0))).unwrap();
+        assert!(changed(&world));
+        world.set_numeric_propagation(id, true).unwrap();
+        assert!(changed(&world));
+        world.reentry_damage.insert(id, 0.5);
+        assert!(changed(&world));
+        world.landed.insert(id, 0.0);
+        assert!(changed(&world));
+        world
+            .set_sensor_suite(id, Some(SensorSuite::omni(1_000.0, 0.0)))
+            .unwrap();
+        assert!(changed(&world));
+    }
+
+    #[test]
+    fn time_to_apsides_wraps_mean_anomaly() {
+        let orbit = OrbitState {
+            semi_major_axis: 10_000_000.0,
+            eccentricity: 0.5,
+            arg_of_periapsis: 1.0,
+            mean_anomaly_at_epoch: -3.0,
+            epoch: 100.0,
+        };
+        let n = (MU_EARTH / 1.0e21).sqrt();
+        let period = 2.0 * PI / n;
+        approx_eq(orbit.mean_motion(MU_EARTH), n, 1e-15);
+        approx_eq(orbit.period(MU_EARTH), period, 1e-9);
+        approx_eq(orbit.periapsis(), 5_000_000.0, 1e-9);
+        approx_eq(orbit.apoapsis(), 15_000_000.0, 1e-9);
+        approx_eq(orbit.specific_energy(MU_EARTH), -MU_EARTH / 2e7, 1e-9);
+        approx_eq(
+            orbit.time_to_periapsis(MU_EARTH, Seconds(100.0)).0,
+            3.0 / n,
+            1e-6,
         );
+        // Many orbits on, and just past apoapsis.
+        let now = Seconds(100.0 + 10.0 * period + (PI + 3.0 + 0.01) / n);
+        let to_apoapsis = orbit.time_to_apoapsis(MU_EARTH, now);
+        approx_eq(to_apoapsis.0, period - 0.01 / n, 1e-3);
+        let to_periapsis = orbit.time_to_periapsis(MU_EARTH, now);
+        let at = now + to_periapsis;
+        let (position, _) = orbit_to_cartesian(&orbit, MU_EARTH, at.0).unwrap();
+        assert!((position.length() - 5_000_000.0).abs() < 1.0);
+        // Exactly at periapsis, the next one is a whole orbit away.
+        approx_eq(orbit.time_to_periapsis(MU_EARTH, at).0, period, 1e-3);
+    }
+
+    #[test]
+    fn anomaly_conversions_round_trip() {
+        for e in [0.0, 0.3, 0.9] {
+            for m in [-3.0, -1.0, 0.0, 0.5, 2.5] {
+                let e_anom = mean_to_eccentric(m, e);
+                approx_eq(e_anom - e * e_anom.sin(), m, 1e-9);
+                approx_eq(true_to_mean(eccentric_to_true(e_anom, e), e), m, 1e-9);
+            }
+        }
+        // On a circle the anomalies all agree.
+        approx_eq(eccentric_to_true(PI / 2.0, 0.0), PI / 2.0, 1e-12);
+
+        let orbit = OrbitState {
+            semi_major_axis: 10_000_000.0,
+            eccentricity: 0.5,
+            arg_of_periapsis: 1.0,
+            mean_anomaly_at_epoch: -3.0,
+            epoch: 100.0,
+        };
+        let now = 5_000.0;
+        let (position, _) = orbit_to_cartesian(&orbit, MU_EARTH, now).unwrap();
+        let nu = orbit.true_anomaly_at(MU_EARTH, now).unwrap();
+        approx_eq(
+            angle::wrap_pi(position.y.atan2(position.x) - orbit.arg_of_periapsis),
+            nu,
+            1e-9,
+        );
+        let later = now + orbit.time_to_true_anomaly(MU_EARTH, Seconds(now), 2.0).0;
+        approx_eq(orbit.true_anomaly_at(MU_EARTH, later).unwrap(), 2.0, 1e-9);
+    }
+
+    #[test]
+    fn spin_turns_bodies_each_step() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let id = world
+            .add_body(BodyState {
+                orientation: 3.0,
+                angular_velocity: 0.1,
+                ..BodyState::circular(7_000_000.0, 0.0)
+            })
+            .unwrap();
+        let still = world
+            .add_body(BodyState::circular(7_000_000.0, 1.0))
+            .unwrap();
+        for _ in 0..4 {
+            world.step(Seconds(0.5));
+        }
+        // 3.2 rad wraps round past π.
+        let orientation = world.body(id).unwrap().orientation;
+        assert!((orientation - (3.2 - 2.0 * PI)).abs() < 1e-12);
+        assert_eq!(world.body(still).unwrap().orientation, 0.0);
+    }
+
+    #[test]
+    fn exterior_and_interior_halves_commute() {
+        let mut stepped = World::new(MU_EARTH, GameConfig::default());
+        let mut reordered = World::new(MU_EARTH, GameConfig::default());
+        stepped
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        reordered
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        for _ in 0..20 {
+            stepped.step(Seconds(0.25));
+
+            let dt = reordered.clock.advance_by(Seconds(0.25)).0;
+            reordered.tick_count += 1;
+            let (mut exterior, interior, config) = reordered.split();
+            interior.step(dt, config);
+            exterior.step(dt);
+            reordered.finish_exterior_step();
+        }
+        assert_eq!(stepped.state_hash(), reordered.state_hash());
+    }
+
+    #[test]
+    fn thrust_event_changes_orbit() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let a = 7_000_000.0;
+        let body = BodyState::circular(a, 0.0);
+        let body_id = world.add_body(body).unwrap();
+
+        let burn_time = 500.0;
+        let (pos, _vel) =
+            orbit_to_cartesian(&world.body(body_id).unwrap().orbit, world.mu, burn_time).unwrap();
         let radial_dir = pos.normalized();
         let delta_v = radial_dir.scale(50.0);
         let event = ThrustEvent {
@@ -507,9 +3629,73 @@ mod tests {
             delta_v,
             thrust_type: ThrustType::Chemical,
         };
-        world.apply_thrust_event(&event);
-        let body = world.bodies.iter().find(|b| b.id == body_id).unwrap();
+        world.apply_thrust_event(&event).unwrap();
+        let body = world.body(body_id).unwrap();
         assert!(body.orbit.eccentricity > 0.0);
         assert!((body.orbit.semi_major_axis - a).abs() > 1.0);
     }
+
+    #[test]
+    fn timeline_spawns_wave_during_warp() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        world.timeline.schedule(
+            7_200.0,
+            ScenarioAction::SpawnBodies(vec![
+                BodyState::circular(7_100_000.0, 1.0),
+                BodyState::circular(7_100_000.0, 1.1),
+            ]),
+        );
+        world.warp_to(Seconds(7_190.0));
+        assert_eq!(world.bodies.len(), 1);
+        world.warp_to(Seconds(7_300.0));
+        assert_eq!(world.bodies.len(), 3);
+        let events = world.timeline.drain_events();
+        match events.as_slice() {
+            [ScenarioEvent::BodiesSpawned { time, body_ids }] => {
+                approx_eq(*time, 7_200.0, 1e-9);
+                assert_eq!(body_ids.len(), 2);
+            }
+            other => panic!("unexpected events {:?}", other),
+        }
+        assert!(world.bodies.iter().all(|b| b.position.length() > 0.0));
+    }
+
+    #[test]
+    fn solar_storm_starts_and_ends() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        world
+            .timeline
+            .schedule(1.0, ScenarioAction::SolarStorm { duration_s: 2.0 });
+        world.step(Seconds(1.5));
+        assert!(world.timeline.solar_storm_active(world.sim_time().0));
+        world.step(Seconds(2.0));
+        assert!(!world.timeline.solar_storm_active(world.sim_time().0));
+        assert_eq!(
+            world.timeline.drain_events(),
+            vec![
+                ScenarioEvent::SolarStormStarted {
+                    time: 1.0,
+                    until: 3.0
+                },
+                ScenarioEvent::SolarStormEnded { time: 3.0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn timeline_drops_actions_at_non_finite_times() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        for time in [f64::NAN, f64::INFINITY, 1.0] {
+            world
+                .timeline
+                .schedule(time, ScenarioAction::SolarStorm { duration_s: 2.0 });
+        }
+        assert_eq!(world.timeline.pending_len(), 1);
+        world.step(Seconds(1.5));
+        assert!(world.timeline.solar_storm_active(world.sim_time().0));
+        assert_eq!(world.timeline.pending_len(), 0);
+    }
 }
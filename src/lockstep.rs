@@ -0,0 +1,243 @@
+#[cfg(feature = "std")]
+use alloc::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a hasher used for per-tick desync detection. Unlike
+/// `std::collections::hash_map::DefaultHasher` its output is stable across
+/// processes, platforms, and Rust versions.
+#[derive(Clone, Copy, Debug)]
+pub struct StateHasher {
+    state: u64,
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_f64(&mut self, value: f64) {
+        self.write_u64(value.to_bits());
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_bytes(&[value as u8]);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+struct InputFrame<C> {
+    commands: Vec<C>,
+    complete: bool,
+}
+
+#[cfg(feature = "std")]
+impl<C> Default for InputFrame<C> {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            complete: false,
+        }
+    }
+}
+
+/// Collects per-tick input frames from every participant and decides when
+/// the simulation may advance. A tick is ready once every participant has
+/// closed its frame for that tick, or once `timeout` has elapsed since the
+/// tick opened (late inputs then roll into the next tick). Needs `std` for
+/// its wall-clock timeout.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct LockstepSession<C> {
+    tick: u64,
+    timeout: Duration,
+    tick_started: Instant,
+    participants: BTreeSet<u64>,
+    frames: BTreeMap<u64, BTreeMap<u64, InputFrame<C>>>,
+}
+
+#[cfg(feature = "std")]
+impl<C> LockstepSession<C> {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            tick: 0,
+            timeout,
+            tick_started: Instant::now(),
+            participants: BTreeSet::new(),
+            frames: BTreeMap::new(),
+        }
+    }
+
+    /// Index of the tick currently collecting input.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn participants(&self) -> impl Iterator<Item = u64> + '_ {
+        self.participants.iter().copied()
+    }
+
+    pub fn join(&mut self, client_id: u64) {
+        self.participants.insert(client_id);
+    }
+
+    pub fn leave(&mut self, client_id: u64) {
+        self.participants.remove(&client_id);
+        for frames in self.frames.values_mut() {
+            frames.remove(&client_id);
+        }
+    }
+
+    /// Queue a command for `tick`, or for the current tick when `tick` is
+    /// `None` or already in the past.
+    pub fn push_command(&mut self, client_id: u64, tick: Option<u64>, command: C) {
+        let tick = tick.unwrap_or(self.tick).max(self.tick);
+        self.frames
+            .entry(tick)
+            .or_default()
+            .entry(client_id)
+            .or_default()
+            .commands
+            .push(command);
+    }
+
+    /// Mark `client_id`'s frame for `tick` as complete. Frames for ticks that
+    /// have already been advanced are ignored.
+    pub fn end_frame(&mut self, client_id: u64, tick: u64) {
+        if tick < self.tick {
+            return;
+        }
+        self.frames
+            .entry(tick)
+            .or_default()
+            .entry(client_id)
+            .or_default()
+            .complete = true;
+    }
+
+    pub fn all_inputs_received(&self) -> bool {
+        let frames = self.frames.get(&self.tick);
+        self.participants.iter().all(|id| {
+            frames
+                .and_then(|frames| frames.get(id))
+                .map(|frame| frame.complete)
+                .unwrap_or(false)
+        })
+    }
+
+    pub fn is_ready(&self, now: Instant) -> bool {
+        self.all_inputs_received() || now.duration_since(self.tick_started) >= self.timeout
+    }
+
+    /// Close the current tick and return its commands in deterministic
+    /// order (by client id, then arrival order).
+    pub fn advance(&mut self, now: Instant) -> Vec<(u64, C)> {
+        let frames = self.frames.remove(&self.tick).unwrap_or_default();
+        self.tick += 1;
+        self.tick_started = now;
+        frames
+            .into_iter()
+            .flat_map(|(client_id, frame)| {
+                frame
+                    .commands
+                    .into_iter()
+                    .map(move |command| (client_id, command))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn waits_for_every_participant() {
+        let now = Instant::now();
+        let mut session = LockstepSession::new(Duration::from_secs(60));
+        session.join(1);
+        session.join(2);
+        session.push_command(2, Some(0), "b");
+        session.end_frame(2, 0);
+        assert!(!session.is_ready(now));
+        session.push_command(1, Some(0), "a");
+        session.end_frame(1, 0);
+        assert!(session.is_ready(now));
+        let commands = session.advance(now);
+        assert_eq!(commands, vec![(1, "a"), (2, "b")]);
+        assert_eq!(session.tick(), 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn timeout_advances_without_stragglers() {
+        let start = Instant::now();
+        let mut session: LockstepSession<()> = LockstepSession::new(Duration::from_millis(10));
+        session.join(1);
+        session.tick_started = start;
+        assert!(!session.is_ready(start));
+        assert!(session.is_ready(start + Duration::from_millis(10)));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn late_commands_roll_forward() {
+        let now = Instant::now();
+        let mut session = LockstepSession::new(Duration::from_secs(60));
+        session.join(1);
+        session.end_frame(1, 0);
+        session.advance(now);
+        session.push_command(1, Some(0), 7);
+        session.end_frame(1, 1);
+        assert_eq!(session.advance(now), vec![(1, 7)]);
+    }
+
+    #[test]
+    fn hasher_is_order_sensitive() {
+        let mut a = StateHasher::new();
+        a.write_u64(1);
+        a.write_u64(2);
+        let mut b = StateHasher::new();
+        b.write_u64(2);
+        b.write_u64(1);
+        assert_ne!(a.finish(), b.finish());
+    }
+}
@@ -1,17 +1,36 @@
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::f64::consts::{FRAC_PI_4, PI};
+use std::fmt::{self, Write as _};
 use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::net::{TcpListener, TcpStream};
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
+use tracing::{debug, info, info_span, warn, Span};
+use tracing_subscriber::EnvFilter;
+
+#[cfg(feature = "autosave")]
+use ggw_world::autosave::{self, Autosaver};
+#[cfg(feature = "shm")]
+use ggw_world::shm::SnapshotRing;
 use ggw_world::{
+    collision::CollisionOutcome,
+    compression::Compression,
     config::GameConfig,
+    field::SizeDistribution,
+    frame,
+    interest::ViewFilter,
     interior::{DeviceAction, DeviceData, GasType, InteriorCommand, InteriorWorld},
-    BodyState, BodyType, HullShape, OrbitState, Vec2, World, DESPAWN_RADIUS_M,
-    GRAVITY_WELL_RADIUS_M, PLANET_RADIUS_M, TILE_SIZE_METERS,
+    landing::SurfaceState,
+    lockstep::LockstepSession,
+    maneuver::ManeuverNode,
+    preview::SoiTransition,
+    sunlight, BodyState, BodyType, DespawnReason, HullShape, Meters, OrbitState, Radians, Seconds,
+    TorqueEvent, Vec2, WarpTarget, World, WorldEvent, DESPAWN_RADIUS_M, GRAVITY_WELL_RADIUS_M,
+    MAX_WARP_DURATION_S, PLANET_RADIUS_M, TILE_SIZE_METERS,
 };
 
 const MU_EARTH: f64 = 3.986_004_418e14;
@@ -19,121 +38,592 @@ const DEFAULT_TIME_SCALE: f64 = 1.0;
 const MAX_TIME_SCALE: f64 = 10_000.0;
 const MAX_SIM_DT: f64 = 1.0;
 const SNAPSHOT_SLEEP_MS: u64 = 50;
+const DEFAULT_SESSION_ID: &str = "default";
+const MAX_SESSION_ID_LEN: usize = 64;
+const LOCKSTEP_TICK_S: f64 = 0.05;
+const LOCKSTEP_TIMEOUT_MS: u64 = 250;
+const LOCKSTEP_POLL_MS: u64 = 1;
 const SERVER_ADDR: &str = "127.0.0.1:40000";
+const DEFAULT_LOG_FILTER: &str = "info";
+#[cfg(feature = "shm")]
+const SHM_SLOTS: usize = 4;
+#[cfg(feature = "shm")]
+const SHM_MAX_BODIES: usize = 4096;
+#[cfg(feature = "grpc")]
+const GRPC_ADDR: &str = "127.0.0.1:40001";
 
 fn main() {
     let args: Vec<String> = env::args().collect();
+    init_logging(&args);
     if args.iter().any(|arg| arg == "--stdio") {
-        run_stdio_mode();
+        run_stdio_mode(&args);
+    } else if args.iter().any(|arg| arg == "--lockstep") {
+        run_lockstep_server();
+    } else if cfg!(feature = "grpc") && args.iter().any(|arg| arg == "--grpc") {
+        #[cfg(feature = "grpc")]
+        run_grpc_server();
     } else {
-        run_tcp_server();
+        run_tcp_server(args.iter().any(|arg| arg == "--resume"));
     }
 }
 
-fn run_stdio_mode() {
+/// Install the global subscriber. `--log-level <filter>` (or
+/// `--log-level=<filter>`) takes an `EnvFilter` directive such as `debug` or
+/// `ggw_world=trace,info`, falling back to `RUST_LOG` and then
+/// `DEFAULT_LOG_FILTER`. `--log-json` switches to one JSON object per line.
+/// Logs go to stderr so they never interleave with `--stdio` snapshots.
+fn init_logging(args: &[String]) {
+    let mut level = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--log-level" {
+            level = iter.next().cloned();
+        } else if let Some(value) = arg.strip_prefix("--log-level=") {
+            level = Some(value.to_string());
+        }
+    }
+    let filter = level
+        .and_then(|directive| EnvFilter::try_new(directive).ok())
+        .or_else(|| EnvFilter::try_from_default_env().ok())
+        .unwrap_or_else(|| EnvFilter::new(DEFAULT_LOG_FILTER));
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr);
+    if args.iter().any(|arg| arg == "--log-json") {
+        builder.json().init();
+    } else {
+        builder.init();
+    }
+}
+
+/// With `--shm <path>` (shm builds only), every snapshot is also published
+/// to a shared-memory ring at `path` for a renderer on the same machine.
+#[cfg_attr(not(feature = "shm"), allow(unused_variables))]
+fn run_stdio_mode(args: &[String]) {
+    #[cfg(feature = "shm")]
+    let mut ring = args
+        .iter()
+        .position(|arg| arg == "--shm")
+        .and_then(|index| args.get(index + 1))
+        .and_then(|path| {
+            SnapshotRing::create(path.as_ref(), SHM_SLOTS, SHM_MAX_BODIES)
+                .inspect_err(|err| warn!(path = %path, error = %err, "snapshot ring unavailable"))
+                .ok()
+        });
     let mut world = build_initial_world(GameConfig::load());
     let stdin_listener = spawn_command_listener();
     let stdout = io::stdout();
     let mut handle = stdout.lock();
     let mut time_scale = DEFAULT_TIME_SCALE;
     let mut last_real = Instant::now();
+    let mut seq = 0u64;
+    let mut snapshot = String::new();
 
     loop {
         for command in stdin_listener.drain_commands() {
             apply_command(&mut world, command, &mut time_scale);
         }
 
-        let snapshot_json = tick_world(&mut world, time_scale, &mut last_real);
-        if writeln!(handle, "{}", snapshot_json).is_err() {
+        tick_world(&mut world, time_scale, &mut last_real);
+        seq += 1;
+        #[cfg(feature = "shm")]
+        if let Some(ring) = ring.as_mut() {
+            ring.publish(&world);
+        }
+        write_snapshot_json(
+            &mut snapshot,
+            format_args!("\"seq\":{},", seq),
+            &world,
+            &SnapshotOptions::default(),
+        );
+        if writeln!(handle, "{}", snapshot).is_err() {
             break;
         }
         if handle.flush().is_err() {
             break;
         }
+        // Hand-overs and despawns go out once, in the snapshot after they
+        // happen.
+        world.drain_soi_events();
+        world.drain_despawn_events();
+        world.drain_events();
         thread::sleep(Duration::from_millis(SNAPSHOT_SLEEP_MS));
     }
 }
 
-fn run_tcp_server() {
+/// Multi-session server: each session id owns an isolated `World` with its
+/// own time scale, and all sessions are stepped round-robin by this loop.
+/// Clients start in `DEFAULT_SESSION_ID` and move with `join_session`.
+/// Sessions autosave per `[autosave]` in the config; `--resume` restores them.
+fn run_tcp_server(resume: bool) {
     let listener = TcpListener::bind(SERVER_ADDR).expect("failed to bind TCP listener");
-    println!("GGW server listening on {}", SERVER_ADDR);
+    info!(addr = SERVER_ADDR, "GGW server listening");
 
-    let mut world = build_initial_world(GameConfig::load());
-    let (cmd_tx, cmd_rx) = mpsc::channel::<Command>();
-    let (new_client_tx, new_client_rx) = mpsc::channel::<mpsc::Sender<String>>();
+    let config = GameConfig::load();
+    let (msg_tx, msg_rx) = mpsc::channel::<(u64, ClientMessage)>();
+    let (new_client_tx, new_client_rx) = mpsc::channel::<(u64, mpsc::Sender<String>)>();
+    thread::spawn(move || {
+        accept_clients(
+            listener,
+            msg_tx,
+            new_client_tx,
+            parse_client_message,
+            || ClientMessage::Disconnected,
+        )
+    });
+
+    let mut sessions: BTreeMap<String, WorldSession> = BTreeMap::new();
+    sessions.insert(
+        DEFAULT_SESSION_ID.to_string(),
+        WorldSession::new(DEFAULT_SESSION_ID, config.clone(), resume),
+    );
+    let mut client_sessions: HashMap<u64, String> = HashMap::new();
+    // Snapshot buffers, reused every frame: the shared one per session and
+    // one for each client with a view of its own.
+    let mut snapshot = String::new();
+    let mut culled = String::new();
+
+    loop {
+        while let Ok((client_id, sender)) = new_client_rx.try_recv() {
+            if let Some(session) = sessions.get_mut(DEFAULT_SESSION_ID) {
+                session.clients.push((client_id, sender));
+                client_sessions.insert(client_id, DEFAULT_SESSION_ID.to_string());
+            }
+        }
+
+        while let Ok((client_id, message)) = msg_rx.try_recv() {
+            let Some(current) = client_sessions.get(&client_id).cloned() else {
+                continue;
+            };
+            match message {
+                ClientMessage::Command(command) => {
+                    if let Some(session) = sessions.get_mut(&current) {
+                        apply_command(&mut session.world, command, &mut session.time_scale);
+                    }
+                }
+                ClientMessage::JoinSession(target) => {
+                    if target == current {
+                        continue;
+                    }
+                    let sender = sessions
+                        .get_mut(&current)
+                        .and_then(|session| session.take_client(client_id));
+                    if let Some(sender) = sender {
+                        sessions
+                            .entry(target.clone())
+                            .or_insert_with(|| WorldSession::new(&target, config.clone(), resume))
+                            .clients
+                            .push((client_id, sender));
+                        info!(client_id, from = %current, to = %target, "client changed session");
+                        client_sessions.insert(client_id, target);
+                    }
+                }
+                ClientMessage::SetView(view) => {
+                    if let Some(session) = sessions.get_mut(&current) {
+                        session.update_options(client_id, |options| options.view = view);
+                    }
+                }
+                ClientMessage::SetOrigin(origin) => {
+                    if let Some(session) = sessions.get_mut(&current) {
+                        session.update_options(client_id, |options| options.origin = origin);
+                    }
+                }
+                ClientMessage::Disconnected => {
+                    if let Some(session) = sessions.get_mut(&current) {
+                        session.take_client(client_id);
+                    }
+                    client_sessions.remove(&client_id);
+                }
+            }
+        }
 
-    let accept_cmd_tx = cmd_tx.clone();
-    thread::spawn(move || accept_clients(listener, accept_cmd_tx, new_client_tx));
+        for (session_id, session) in sessions.iter_mut() {
+            tick_world(
+                &mut session.world,
+                session.time_scale,
+                &mut session.last_real,
+            );
+            #[cfg(feature = "autosave")]
+            session.autosave(session_id);
+            let header = format_args!("\"session\":\"{}\",", session_id);
+            let world = &session.world;
+            let options = &session.options;
+            if options.len() < session.clients.len() {
+                write_snapshot_json(&mut snapshot, header, world, &SnapshotOptions::default());
+            }
+            session.clients.retain(|(client_id, sender)| {
+                let message = match options.get(client_id) {
+                    Some(options) => {
+                        write_snapshot_json(&mut culled, header, world, options);
+                        culled.clone()
+                    }
+                    None => snapshot.clone(),
+                };
+                let alive = sender.send(message).is_ok();
+                if !alive {
+                    client_sessions.remove(client_id);
+                }
+                alive
+            });
+            session.world.drain_soi_events();
+            session.world.drain_despawn_events();
+            session.world.drain_events();
+        }
+        sessions.retain(|session_id, session| {
+            session_id == DEFAULT_SESSION_ID || !session.clients.is_empty()
+        });
+        thread::sleep(Duration::from_millis(SNAPSHOT_SLEEP_MS));
+    }
+}
 
-    let mut clients: Vec<mpsc::Sender<String>> = Vec::new();
+/// What a client asked to have changed in its snapshots.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct SnapshotOptions {
+    /// Leave out bodies outside this region.
+    view: Option<ViewFilter>,
+    /// Send body positions as `f32` offsets from this body, with its own
+    /// full-precision state under `"origin"`, for clients that render in
+    /// single precision.
+    origin: Option<u64>,
+}
+
+/// One isolated game session hosted by the multi-session server.
+struct WorldSession {
+    world: World,
+    time_scale: f64,
+    last_real: Instant,
+    clients: Vec<(u64, mpsc::Sender<String>)>,
+    /// Clients whose snapshots differ from the shared default one.
+    options: HashMap<u64, SnapshotOptions>,
+    #[cfg(feature = "autosave")]
+    autosaver: Option<Autosaver>,
+}
+
+impl WorldSession {
+    /// With `resume`, the session starts from its newest autosave when one
+    /// exists (autosave builds only).
+    #[cfg_attr(not(feature = "autosave"), allow(unused_variables))]
+    fn new(session_id: &str, config: GameConfig, resume: bool) -> Self {
+        #[cfg(feature = "autosave")]
+        let autosaver = config
+            .autosave
+            .enabled
+            .then(|| Autosaver::new(&config.autosave, session_id));
+        #[cfg(feature = "autosave")]
+        let world = match autosaver
+            .as_ref()
+            .filter(|_| resume)
+            .and_then(|saver| load_latest_autosave(saver, session_id))
+        {
+            Some(world) => world,
+            None => build_initial_world(config),
+        };
+        #[cfg(not(feature = "autosave"))]
+        let world = build_initial_world(config);
+
+        Self {
+            world,
+            time_scale: DEFAULT_TIME_SCALE,
+            last_real: Instant::now(),
+            clients: Vec::new(),
+            options: HashMap::new(),
+            #[cfg(feature = "autosave")]
+            autosaver,
+        }
+    }
+
+    #[cfg(feature = "autosave")]
+    fn autosave(&mut self, session_id: &str) {
+        let Some(saver) = self.autosaver.as_mut() else {
+            return;
+        };
+        match saver.tick(&self.world, Instant::now()) {
+            Ok(Some(path)) => debug!(session = session_id, path = %path.display(), "autosaved"),
+            Ok(None) => {}
+            Err(err) => warn!(session = session_id, error = %err, "autosave failed"),
+        }
+    }
+
+    fn take_client(&mut self, client_id: u64) -> Option<mpsc::Sender<String>> {
+        let index = self.clients.iter().position(|(id, _)| *id == client_id)?;
+        self.options.remove(&client_id);
+        Some(self.clients.remove(index).1)
+    }
+
+    fn update_options(&mut self, client_id: u64, update: impl FnOnce(&mut SnapshotOptions)) {
+        let mut options = self.options.remove(&client_id).unwrap_or_default();
+        update(&mut options);
+        if options != SnapshotOptions::default() {
+            self.options.insert(client_id, options);
+        }
+    }
+}
+
+#[cfg(feature = "autosave")]
+fn load_latest_autosave(saver: &Autosaver, session_id: &str) -> Option<World> {
+    let path = saver.latest()?;
+    match autosave::load_world(&path) {
+        Ok(world) => {
+            info!(session = session_id, path = %path.display(), "resumed from autosave");
+            Some(world)
+        }
+        Err(err) => {
+            warn!(
+                session = session_id,
+                path = %path.display(),
+                error = %err,
+                "failed to load autosave"
+            );
+            None
+        }
+    }
+}
+
+/// gRPC server: the world is shared with a background thread that steps it
+/// in real time and publishes its events, while `WorldService` answers
+/// queries and commands.
+#[cfg(feature = "grpc")]
+fn run_grpc_server() {
+    let addr = GRPC_ADDR.parse().expect("invalid gRPC address");
+    let world = Arc::new(Mutex::new(build_initial_world(GameConfig::load())));
+    let service = ggw_world::grpc::WorldService::new(Arc::clone(&world));
+
+    let sim_service = service.clone();
+    let sim_world = Arc::clone(&world);
+    thread::spawn(move || {
+        let mut last_real = Instant::now();
+        loop {
+            let now = Instant::now();
+            let real_dt = now.duration_since(last_real).as_secs_f64();
+            last_real = now;
+            if let Ok(mut world) = sim_world.lock() {
+                let sim_dt = (DEFAULT_TIME_SCALE * real_dt).clamp(0.0, MAX_SIM_DT);
+                world.step(Seconds(sim_dt));
+                sim_service.publish_events(&mut world);
+                world.drain_soi_events();
+                world.drain_despawn_events();
+            }
+            thread::sleep(Duration::from_millis(SNAPSHOT_SLEEP_MS));
+        }
+    });
+
+    let runtime = tokio::runtime::Runtime::new().expect("failed to start tokio runtime");
+    info!(addr = GRPC_ADDR, "GGW gRPC server listening");
+    runtime
+        .block_on(
+            tonic::transport::Server::builder()
+                .add_service(service.into_server())
+                .serve(addr),
+        )
+        .expect("gRPC server failed");
+}
+
+/// Lockstep server: every connected client is a participant, and the world
+/// only advances by a fixed `LOCKSTEP_TICK_S` once all participants have
+/// closed their input frame for the tick (or `LOCKSTEP_TIMEOUT_MS` passes).
+fn run_lockstep_server() {
+    let listener = TcpListener::bind(SERVER_ADDR).expect("failed to bind TCP listener");
+    info!(addr = SERVER_ADDR, "GGW lockstep server listening");
+
+    let mut world = build_initial_world(GameConfig::load());
+    let mut session = LockstepSession::new(Duration::from_millis(LOCKSTEP_TIMEOUT_MS));
+    let (msg_tx, msg_rx) = mpsc::channel::<(u64, LockstepMessage)>();
+    let (new_client_tx, new_client_rx) = mpsc::channel::<(u64, mpsc::Sender<String>)>();
+    thread::spawn(move || {
+        accept_clients(
+            listener,
+            msg_tx,
+            new_client_tx,
+            parse_lockstep_message,
+            || LockstepMessage::Disconnected,
+        )
+    });
+
+    let mut clients: Vec<(u64, mpsc::Sender<String>)> = Vec::new();
     let mut time_scale = DEFAULT_TIME_SCALE;
-    let mut last_real = Instant::now();
+    let tick_interval = Duration::from_secs_f64(LOCKSTEP_TICK_S);
+    let mut last_advance = Instant::now();
+    let mut snapshot = String::new();
 
     loop {
-        while let Ok(new_client) = new_client_rx.try_recv() {
-            clients.push(new_client);
+        while let Ok((client_id, sender)) = new_client_rx.try_recv() {
+            session.join(client_id);
+            clients.push((client_id, sender));
+        }
+
+        while let Ok((client_id, message)) = msg_rx.try_recv() {
+            match message {
+                LockstepMessage::Command { tick, command } => {
+                    session.push_command(client_id, tick, command);
+                }
+                LockstepMessage::EndFrame { tick } => session.end_frame(client_id, tick),
+                LockstepMessage::Disconnected => session.leave(client_id),
+            }
         }
 
-        while let Ok(command) = cmd_rx.try_recv() {
+        let now = Instant::now();
+        if now.duration_since(last_advance) < tick_interval || !session.is_ready(now) {
+            thread::sleep(Duration::from_millis(LOCKSTEP_POLL_MS));
+            continue;
+        }
+        last_advance = now;
+        let tick = session.tick();
+        for (_, command) in session.advance(now) {
             apply_command(&mut world, command, &mut time_scale);
         }
+        let sim_dt = (time_scale * LOCKSTEP_TICK_S).clamp(0.0, MAX_SIM_DT);
+        world.step(Seconds(sim_dt));
 
-        let snapshot_json = tick_world(&mut world, time_scale, &mut last_real);
-        clients.retain(|sender| sender.send(snapshot_json.clone()).is_ok());
-        thread::sleep(Duration::from_millis(SNAPSHOT_SLEEP_MS));
+        write_snapshot_json(
+            &mut snapshot,
+            format_args!(
+                "\"lockstep\":{{\"tick\":{},\"next_input_tick\":{},\"state_hash\":\"{:016x}\"}},",
+                tick,
+                session.tick(),
+                world.state_hash()
+            ),
+            &world,
+            &SnapshotOptions::default(),
+        );
+        clients.retain(|(client_id, sender)| {
+            let alive = sender.send(snapshot.clone()).is_ok();
+            if !alive {
+                session.leave(*client_id);
+            }
+            alive
+        });
+        world.drain_soi_events();
+        world.drain_despawn_events();
+        world.drain_events();
     }
 }
 
-fn accept_clients(
+/// Accept connections forever, assigning each client an id. Every parsed
+/// line is forwarded as `(client_id, message)`, followed by `closed()` once
+/// the client hangs up.
+fn accept_clients<T: Send + 'static>(
     listener: TcpListener,
-    cmd_tx: mpsc::Sender<Command>,
-    new_client_tx: mpsc::Sender<mpsc::Sender<String>>,
+    msg_tx: mpsc::Sender<(u64, T)>,
+    new_client_tx: mpsc::Sender<(u64, mpsc::Sender<String>)>,
+    parse: fn(&str) -> Option<T>,
+    closed: fn() -> T,
 ) {
+    let mut next_client_id = 1u64;
     for stream in listener.incoming() {
         match stream {
             Ok(stream) => {
-                let sender = spawn_client_connection(stream, cmd_tx.clone());
-                if new_client_tx.send(sender).is_err() {
+                let client_id = next_client_id;
+                next_client_id += 1;
+                let _span = info_span!("protocol", client_id).entered();
+                match stream.peer_addr() {
+                    Ok(peer) => info!(%peer, "client connected"),
+                    Err(_) => info!("client connected"),
+                }
+                let sender = spawn_client_connection(
+                    stream,
+                    msg_tx.clone(),
+                    move |line| parse(line).map(|msg| (client_id, msg)),
+                    Some((client_id, closed())),
+                );
+                if new_client_tx.send((client_id, sender)).is_err() {
                     break;
                 }
             }
             Err(err) => {
-                eprintln!("Failed to accept client: {}", err);
+                warn!(error = %err, "failed to accept client");
             }
         }
     }
 }
 
-fn spawn_client_connection(
+/// Spawn reader/writer threads for one client. Lines read are turned into
+/// messages by `parse`; `on_close` is sent once the read side hangs up.
+/// `hello` lines are handled here and never reach `parse`: they switch the
+/// connection's outbound compression after a `hello_ack` is written.
+fn spawn_client_connection<T: Send + 'static>(
     stream: TcpStream,
-    cmd_tx: mpsc::Sender<Command>,
+    msg_tx: mpsc::Sender<T>,
+    parse: impl Fn(&str) -> Option<T> + Send + 'static,
+    on_close: Option<T>,
 ) -> mpsc::Sender<String> {
     let (snapshot_tx, snapshot_rx) = mpsc::channel::<String>();
+    let pending_ack: Arc<Mutex<Option<Compression>>> = Arc::new(Mutex::new(None));
 
     let reader_stream = stream
         .try_clone()
         .expect("failed to clone stream for reader");
-    let reader_cmd_tx = cmd_tx.clone();
+    let reader_ack = Arc::clone(&pending_ack);
+    let reader_span = Span::current();
+    let writer_span = Span::current();
     thread::spawn(move || {
+        let _span = reader_span.entered();
         let reader = BufReader::new(reader_stream);
         for line in reader.lines() {
             match line {
                 Ok(line) => {
-                    if let Some(command) = parse_command(&line) {
-                        if reader_cmd_tx.send(command).is_err() {
-                            break;
+                    if let Some(requested) = parse_hello(&line) {
+                        let mode = requested.negotiate();
+                        debug!(
+                            requested = requested.as_str(),
+                            negotiated = mode.as_str(),
+                            "hello"
+                        );
+                        if let Ok(mut pending) = reader_ack.lock() {
+                            *pending = Some(mode);
+                        }
+                        continue;
+                    }
+                    match parse(&line) {
+                        Some(message) => {
+                            if msg_tx.send(message).is_err() {
+                                return;
+                            }
                         }
+                        None => debug!(%line, "ignored unrecognised message"),
                     }
                 }
-                Err(_) => break,
+                Err(err) => {
+                    debug!(error = %err, "read failed");
+                    break;
+                }
             }
         }
+        info!("client disconnected");
+        if let Some(message) = on_close {
+            let _ = msg_tx.send(message);
+        }
     });
 
     thread::spawn(move || {
+        let _span = writer_span.entered();
         let mut writer = BufWriter::new(stream);
+        let mut compression = Compression::None;
+        let mut seq = 0u64;
+        let mut stamped = String::new();
         while let Ok(snapshot) = snapshot_rx.recv() {
-            if writeln!(writer, "{}", snapshot).is_err() {
+            let ack = pending_ack
+                .lock()
+                .ok()
+                .and_then(|mut pending| pending.take());
+            if let Some(mode) = ack {
+                seq += 1;
+                let ack_line = format!(
+                    "{{\"seq\":{},\"type\":\"hello_ack\",\"compression\":\"{}\"}}\n",
+                    seq,
+                    mode.as_str()
+                );
+                if writer.write_all(ack_line.as_bytes()).is_err() {
+                    break;
+                }
+                compression = mode;
+            }
+            seq += 1;
+            stamp_sequence(&mut stamped, &snapshot, seq);
+            if writer
+                .write_all(&compression.encode_message(&stamped))
+                .is_err()
+            {
                 break;
             }
             if writer.flush().is_err() {
@@ -145,8 +635,38 @@ fn spawn_client_connection(
     snapshot_tx
 }
 
+/// Prefix an outbound JSON object with this connection's sequence number.
+/// Every message on a stream gets the next number, so a gap means a dropped
+/// frame. `tick` and `sim_time` are already part of each snapshot. Writes
+/// into `out`, replacing its contents, so the writer keeps one buffer.
+fn stamp_sequence(out: &mut String, message: &str, seq: u64) {
+    out.clear();
+    match message.strip_prefix('{') {
+        Some(rest) => {
+            let _ = write!(out, "{{\"seq\":{},", seq);
+            out.push_str(rest);
+        }
+        None => out.push_str(message),
+    }
+}
+
+/// Append `text` to `out` as a quoted JSON string.
+fn write_json_string(out: &mut String, text: &str) -> fmt::Result {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c < ' ' => write!(out, "\\u{:04x}", u32::from(c))?,
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    Ok(())
+}
+
 fn build_initial_world(config: GameConfig) -> World {
-    let mut world = World::new(MU_EARTH, config);
+    let interior = InteriorWorld::new_test_ship(&config);
     let r_planet = PLANET_RADIUS_M;
 
     let ship_orbit = OrbitState {
@@ -177,42 +697,47 @@ fn build_initial_world(config: GameConfig) -> World {
         epoch: 0.0,
     };
 
-    let ship_hull = world.interior.ship.hull_shape.clone();
-    world.add_body(sample_body(
-        1,
-        BodyType::Ship,
-        ship_orbit,
-        20.0,
-        Some(ship_hull),
-    ));
-    world.add_body(sample_body(
-        2,
-        BodyType::Asteroid,
-        asteroid_orbit,
-        1_000.0,
-        None,
-    ));
-    world.add_body(sample_body(3, BodyType::Debris, debris_orbit, 10.0, None));
-
-    world.step(0.0);
+    let ship_hull = interior.ship.hull_shape.clone();
+    let bodies = [
+        sample_body(1, BodyType::Ship, ship_orbit, 20.0, Some(ship_hull)),
+        sample_body(2, BodyType::Asteroid, asteroid_orbit, 1_000.0, None),
+        sample_body(3, BodyType::Debris, debris_orbit, 10.0, None),
+    ];
+    let mut world = World::builder()
+        .mu(MU_EARTH)
+        .planet_radius(Meters(r_planet))
+        .config(config)
+        .interior(interior)
+        .with_bodies(bodies)
+        .interior_body(1)
+        .build()
+        .expect("sample scenario orbits are valid");
+    // A belt of rubble around the big asteroid.
+    let belt = (
+        Meters(r_planet + 2_800_000.0),
+        Meters(r_planet + 3_200_000.0),
+    );
+    let sizes = SizeDistribution::PowerLaw {
+        min_m: 20.0,
+        max_m: 400.0,
+        exponent: 2.0,
+    };
+    world
+        .spawn_asteroid_field(world.seed, 24, belt, sizes)
+        .expect("sample asteroid field is valid");
+
+    world.step(Seconds::ZERO);
     world
 }
 
-fn tick_world(world: &mut World, time_scale: f64, last_real: &mut Instant) -> String {
+fn tick_world(world: &mut World, time_scale: f64, last_real: &mut Instant) {
     let now = Instant::now();
     let real_dt = now.duration_since(*last_real).as_secs_f64();
     *last_real = now;
 
-    let mut sim_dt = time_scale * real_dt;
-    if sim_dt > MAX_SIM_DT {
-        sim_dt = MAX_SIM_DT;
-    }
-    if sim_dt < 0.0 {
-        sim_dt = 0.0;
-    }
+    let sim_dt = (time_scale * real_dt).clamp(0.0, MAX_SIM_DT);
 
-    world.step(sim_dt);
-    build_snapshot_json(world)
+    world.step(Seconds(sim_dt));
 }
 
 fn apply_command(world: &mut World, command: Command, time_scale: &mut f64) {
@@ -243,6 +768,84 @@ fn apply_command(world: &mut World, command: Command, time_scale: &mut f64) {
                 .interior
                 .queue_command(InteriorCommand::ShipComputerToggle { device_id });
         }
+        Command::SetDevice {
+            device_id,
+            online,
+            setpoint,
+        } => {
+            world.interior.queue_command(InteriorCommand::SetDevice {
+                device_id,
+                online,
+                setpoint,
+            });
+        }
+        Command::SetDispenserGas { device_id, gas } => {
+            world
+                .interior
+                .queue_command(InteriorCommand::SetDispenserGas { device_id, gas });
+        }
+        Command::ReactorThrottle {
+            device_id,
+            throttle,
+        } => {
+            world
+                .interior
+                .queue_command(InteriorCommand::ReactorThrottle {
+                    device_id,
+                    throttle,
+                });
+        }
+        Command::WarpTo { sim_time } => {
+            let limit = world.sim_time() + Seconds(MAX_WARP_DURATION_S);
+            world.warp_to(Seconds(sim_time).min(limit));
+        }
+        Command::WarpToNext { body_id, target } => {
+            world.warp_to_next(body_id, target, Seconds(MAX_WARP_DURATION_S));
+        }
+        Command::PlanTransfer { body_id, to_radius } => {
+            let body_id = body_id.or_else(|| world.ships().next().map(|body| body.id));
+            let Some(body_id) = body_id else {
+                return;
+            };
+            let planned = world.plan_hohmann(body_id, to_radius).and_then(|burns| {
+                for burn in [burns.0, burns.1] {
+                    world.plan_node(ManeuverNode {
+                        body_id,
+                        time: Seconds(burn.time),
+                        delta_v: burn.delta_v,
+                    })?;
+                }
+                Ok(())
+            });
+            if let Err(err) = planned {
+                warn!(body_id, to_radius, error = %err, "transfer not planned");
+            }
+        }
+        Command::Torque {
+            body_id,
+            delta_angular_velocity,
+        } => {
+            let body_id = body_id.or_else(|| world.ships().next().map(|body| body.id));
+            let Some(body_id) = body_id else {
+                return;
+            };
+            let event = TorqueEvent {
+                body_id,
+                delta_angular_velocity,
+            };
+            if let Err(err) = world.apply_torque_event(&event) {
+                warn!(body_id, error = %err, "torque not applied");
+            }
+        }
+        Command::SetAttitudeTarget { body_id, heading } => {
+            let body_id = body_id.or_else(|| world.ships().next().map(|body| body.id));
+            let Some(body_id) = body_id else {
+                return;
+            };
+            if let Err(err) = world.set_attitude_target(body_id, heading.map(Radians)) {
+                warn!(body_id, error = %err, "attitude target not set");
+            }
+        }
     }
 }
 
@@ -306,71 +909,256 @@ fn sample_body(
         velocity: Vec2::zero(),
         body_type,
         hull_shape,
+        parent: None,
+        orientation: 0.0,
+        angular_velocity: 0.0,
+        tags: BTreeMap::new(),
     }
 }
 
-fn build_snapshot_json(world: &World) -> String {
-    let mut json = format!(
-        "{{\"sim_time\":{},\"planet_radius_m\":{},\"gravity_well_radius_m\":{},\"despawn_radius_m\":{},\"mu\":{},\"bodies\":[",
-        world.sim_time,
+/// Write the fields of a snapshot object, without its braces, so callers can
+/// put their own framing fields in front without copying the body.
+fn write_snapshot_fields(
+    json: &mut String,
+    world: &World,
+    options: &SnapshotOptions,
+) -> fmt::Result {
+    let view = options.view.as_ref();
+    let origin = options
+        .origin
+        .and_then(|id| world.bodies.iter().find(|body| body.id == id));
+    write!(
+        json,
+        "\"tick\":{},\"sim_time\":{},\"planet_radius_m\":{},\"gravity_well_radius_m\":{},\"despawn_radius_m\":{},\"mu\":{},",
+        world.tick_count,
+        world.sim_time().0,
         world.planet_radius,
         GRAVITY_WELL_RADIUS_M,
         DESPAWN_RADIUS_M,
         world.mu
-    );
-    let nav_context = nav_context_json(world);
-    for (index, body) in world.bodies.iter().enumerate() {
-        if index > 0 {
+    )?;
+    let sun = world.sun_direction();
+    let primaries = world.primaries();
+    write!(json, "\"sun_x\":{},\"sun_y\":{},\"bodies\":[", sun.x, sun.y)?;
+    let mut visible = 0;
+    let mut bodies = world.bodies.iter();
+    let mut culled_bodies;
+    let bodies: &mut dyn Iterator<Item = &BodyState> = match view {
+        Some(view) => {
+            culled_bodies = view.iter_visible(world);
+            &mut culled_bodies
+        }
+        None => &mut bodies,
+    };
+    for body in bodies {
+        if visible > 0 {
             json.push(',');
         }
-        json.push('{');
-        json.push_str(&format!(
-            "\"id\":{},\"body_type\":\"{}\",\"radius_m\":{},\"x\":{},\"y\":{},\"vx\":{},\"vy\":{}",
+        visible += 1;
+        write!(
+            json,
+            "{{\"id\":{},\"body_type\":\"{}\",\"radius_m\":{},",
             body.id,
             body_type_name(body.body_type),
-            body.radius,
-            body.position.x,
-            body.position.y,
-            body.velocity.x,
-            body.velocity.y
-        ));
+            body.radius
+        )?;
+        match origin {
+            Some(origin) => {
+                let offset = frame::floating_origin_offset(body.position, origin.position);
+                write!(json, "\"x\":{},\"y\":{}", offset.x, offset.y)?;
+            }
+            None => write!(json, "\"x\":{},\"y\":{}", body.position.x, body.position.y)?,
+        }
+        write!(
+            json,
+            ",\"vx\":{},\"vy\":{},\"orientation\":{}",
+            body.velocity.x, body.velocity.y, body.orientation
+        )?;
+        write!(
+            json,
+            ",\"sunlit\":{}",
+            sunlight::is_sunlit(body.position, sun, &primaries)
+        )?;
+        if let Some(target) = world.attitude_target(body.id) {
+            write!(json, ",\"attitude_target\":{}", target.0)?;
+        }
+        if let Some(SurfaceState::Landed { longitude }) = world.surface_state(body.id) {
+            write!(json, ",\"landed_longitude\":{}", longitude)?;
+        }
+        let passengers: Vec<u64> = world.passengers(body.id).collect();
+        if !passengers.is_empty() {
+            json.push_str(",\"docked\":[");
+            for (idx, passenger) in passengers.iter().enumerate() {
+                if idx > 0 {
+                    json.push(',');
+                }
+                write!(json, "{}", passenger)?;
+            }
+            json.push(']');
+        }
+        if !body.tags.is_empty() {
+            json.push_str(",\"tags\":{");
+            for (idx, (key, value)) in body.tags.iter().enumerate() {
+                if idx > 0 {
+                    json.push(',');
+                }
+                write_json_string(json, key)?;
+                json.push(':');
+                write_json_string(json, value)?;
+            }
+            json.push('}');
+        }
         if let Some(hull) = &body.hull_shape {
-            json.push_str(",\"hull_shape\":{");
-            json.push_str(&format!("\"tile_size_m\":{}", TILE_SIZE_METERS));
-            json.push_str(",\"vertices\":[");
+            write!(
+                json,
+                ",\"hull_shape\":{{\"tile_size_m\":{},\"vertices\":[",
+                TILE_SIZE_METERS
+            )?;
             for (idx, vertex) in hull.vertices.iter().enumerate() {
                 if idx > 0 {
                     json.push(',');
                 }
-                json.push_str(&format!("{{\"x\":{},\"y\":{}}}", vertex.x, vertex.y));
+                write!(json, "{{\"x\":{},\"y\":{}}}", vertex.x, vertex.y)?;
             }
             json.push_str("]}");
         }
         json.push('}');
     }
-    json.push_str("]");
+    json.push(']');
+    if !world.central_bodies.is_empty() {
+        json.push_str(",\"central_bodies\":[");
+        let primaries = world.primaries();
+        for (idx, moon) in world.central_bodies.iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+            let soi_radius = primaries
+                .find(Some(moon.id))
+                .map_or(0.0, |primary| primary.gravity_well_radius);
+            write!(
+                json,
+                "{{\"id\":{},\"radius_m\":{},\"soi_radius_m\":{},",
+                moon.id, moon.radius, soi_radius
+            )?;
+            match moon.parent {
+                Some(parent) => write!(json, "\"parent\":{},", parent)?,
+                None => json.push_str("\"parent\":null,"),
+            }
+            match origin {
+                Some(origin) => {
+                    let offset = frame::floating_origin_offset(moon.position, origin.position);
+                    write!(json, "\"x\":{},\"y\":{}", offset.x, offset.y)?;
+                }
+                None => write!(json, "\"x\":{},\"y\":{}", moon.position.x, moon.position.y)?,
+            }
+            write!(
+                json,
+                ",\"vx\":{},\"vy\":{}}}",
+                moon.velocity.x, moon.velocity.y
+            )?;
+        }
+        json.push(']');
+    }
+    if !world.soi_events().is_empty() {
+        json.push_str(",\"soi_events\":[");
+        for (idx, event) in world.soi_events().iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "{{\"time\":{},\"body_id\":{},\"transition\":\"{}\"",
+                event.time,
+                event.body_id,
+                soi_transition_name(event.transition)
+            )?;
+            if let SoiTransition::EnteredSoi { central_body }
+            | SoiTransition::LeftSoi { central_body } = event.transition
+            {
+                write!(json, ",\"central_body\":{}", central_body)?;
+            }
+            json.push('}');
+        }
+        json.push(']');
+    }
+    if !world.despawn_events().is_empty() {
+        json.push_str(",\"despawn_events\":[");
+        for (idx, event) in world.despawn_events().iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "{{\"time\":{},\"id\":{},\"reason\":\"{}\"",
+                event.time,
+                event.id,
+                despawn_reason_name(event.reason)
+            )?;
+            if let DespawnReason::Merged { survivor } = event.reason {
+                write!(json, ",\"survivor\":{}", survivor)?;
+            }
+            json.push('}');
+        }
+        json.push(']');
+    }
+    if !world.events().is_empty() {
+        json.push_str(",\"events\":[");
+        for (idx, event) in world.events().iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+            write_world_event(json, event)?;
+        }
+        json.push(']');
+    }
+    if let Some(view) = view {
+        let center = view.resolve_center(world).unwrap_or_else(Vec2::zero);
+        write!(
+            json,
+            ",\"view\":{{\"x\":{},\"y\":{},\"radius_m\":{},\"culled\":{}}}",
+            center.x,
+            center.y,
+            view.radius_m,
+            world.bodies.len() - visible
+        )?;
+    }
+    if let Some(origin) = origin {
+        write!(
+            json,
+            ",\"origin\":{{\"id\":{},\"x\":{},\"y\":{},\"vx\":{},\"vy\":{}}}",
+            origin.id, origin.position.x, origin.position.y, origin.velocity.x, origin.velocity.y
+        )?;
+    }
     json.push(',');
-    json.push_str(&build_interior_json(
-        &world.interior,
-        nav_context.as_deref(),
-        &world.config,
-    ));
+    write_interior_json(json, world)
+}
+
+/// Write a snapshot object into `json`, replacing its contents, with the
+/// `header` fields (each followed by a comma) ahead of the snapshot's own.
+/// The buffer is meant to be kept between frames so its capacity is reused.
+fn write_snapshot_json(
+    json: &mut String,
+    header: fmt::Arguments,
+    world: &World,
+    options: &SnapshotOptions,
+) {
+    json.clear();
+    json.push('{');
+    json.write_fmt(header)
+        .and_then(|_| write_snapshot_fields(json, world, options))
+        .expect("writing to a String cannot fail");
     json.push('}');
-    json
 }
 
-fn build_interior_json(
-    interior: &InteriorWorld,
-    nav_context: Option<&str>,
-    config: &GameConfig,
-) -> String {
+fn write_interior_json(json: &mut String, world: &World) -> fmt::Result {
+    let interior = &world.interior;
+    let config = &world.config;
     let ship = &interior.ship;
-    let mut json = String::new();
-    json.push_str("\"interior\":{");
-    json.push_str(&format!(
-        "\"width\":{},\"height\":{},",
+    write!(
+        json,
+        "\"interior\":{{\"width\":{},\"height\":{},",
         ship.width, ship.height
-    ));
+    )?;
     json.push_str("\"tiles\":[");
     for y in 0..ship.height {
         if y > 0 {
@@ -382,13 +1170,13 @@ fn build_interior_json(
                 json.push(',');
             }
             let tile_type = ship.tile_type(x, y);
-            json.push('{');
-            json.push_str(&format!("\"type\":\"{}\"", tile_type.as_str()));
+            write!(json, "{{\"type\":\"{}\"", tile_type.as_str())?;
             if let Some(sample) = ship.tile_atmos_sample(x, y, &config.atmosphere) {
-                json.push_str(&format!(
+                write!(
+                    json,
                     ",\"atmos\":{{\"pressure_kpa\":{},\"o2_kg\":{},\"n2_kg\":{},\"co2_kg\":{}}}",
                     sample.pressure_kpa, sample.o2_kg, sample.n2_kg, sample.co2_kg
-                ));
+                )?;
             } else {
                 json.push_str(",\"atmos\":null");
             }
@@ -397,185 +1185,182 @@ fn build_interior_json(
         json.push(']');
     }
     json.push_str("],");
-    if let Some(nav) = nav_context {
-        json.push_str("\"nav_context\":");
-        json.push_str(nav);
-        json.push_str(",");
-    }
+    write_nav_context_json(json, world)?;
     json.push_str("\"devices\":[");
     for (index, device) in ship.devices.iter().enumerate() {
         if index > 0 {
             json.push(',');
         }
-        json.push('{');
-        json.push_str(&format!(
-            "\"id\":{},\"kind\":\"{}\",\"x\":{},\"y\":{},\"w\":{},\"h\":{},\"online\":{},\"power_kw\":{}",
+        write!(
+            json,
+            "{{\"id\":{},\"kind\":\"{}\",\"x\":{},\"y\":{},\"w\":{},\"h\":{},\"online\":{},\"power_kw\":{}",
             device.id,
             device.device_type.as_str(),
             device.x,
             device.y,
             device.w,
             device.h,
-            if device.online { "true" } else { "false" },
+            device.online,
             device.power_kw
-        ));
+        )?;
         match &device.data {
             DeviceData::Reactor(data) => {
-                json.push_str(&format!(
-                    ",\"fuel_kg\":{},\"max_fuel_kg\":{},\"power_output_kw\":{},\"fuel_burn_rate_kg_per_s\":{},\"reactor_online\":{}",
+                write!(
+                    json,
+                    ",\"fuel_kg\":{},\"max_fuel_kg\":{},\"power_output_kw\":{},\"fuel_burn_rate_kg_per_s\":{},\"throttle\":{},\"reactor_online\":{}",
                     data.fuel_kg,
                     data.max_fuel_kg,
                     data.power_output_kw,
                     data.fuel_burn_rate_kg_per_s,
-                    if data.online { "true" } else { "false" }
-                ));
+                    data.throttle,
+                    data.online
+                )?;
             }
             DeviceData::Tank(data) => {
-                json.push_str(&format!(
-                    ",\"o2_kg\":{},\"n2_kg\":{},\"co2_kg\":{},\"xenon_kg\":{},\"capacity_kg\":{}",
-                    data.o2_kg, data.n2_kg, data.co2_kg, data.xenon_kg, data.capacity_kg
-                ));
+                write!(
+                    json,
+                    ",\"o2_kg\":{},\"n2_kg\":{},\"co2_kg\":{},\"xenon_kg\":{},\"fuel_kg\":{},\"capacity_kg\":{}",
+                    data.o2_kg, data.n2_kg, data.co2_kg, data.xenon_kg, data.fuel_kg, data.capacity_kg
+                )?;
             }
             DeviceData::Dispenser(data) => {
-                json.push_str(&format!(
-                    ",\"active\":{},\"rate_kg_per_s\":{},\"gas_type\":\"{}\",\"connected_tank_id\":{}",
-                    if data.active { "true" } else { "false" },
+                write!(
+                    json,
+                    ",\"active\":{},\"rate_kg_per_s\":{},\"gas_type\":\"{}\",\"connected_tank_id\":",
+                    data.active,
                     data.rate_kg_per_s,
-                    gas_type_name(data.gas_type),
-                    data
-                        .connected_tank_id
-                        .map(|id| id.to_string())
-                        .unwrap_or_else(|| "null".to_string())
-                ));
+                    gas_type_name(data.gas_type)
+                )?;
+                match data.connected_tank_id {
+                    Some(id) => write!(json, "{}", id)?,
+                    None => json.push_str("null"),
+                }
             }
             DeviceData::Light(data) => {
-                json.push_str(&format!(
+                write!(
+                    json,
                     ",\"intensity\":{},\"light_online\":{}",
-                    data.intensity,
-                    if data.online { "true" } else { "false" }
-                ));
+                    data.intensity, data.online
+                )?;
             }
             DeviceData::NavStation(data) => {
-                json.push_str(&format!(
-                    ",\"nav_online\":{}",
-                    if data.online { "true" } else { "false" }
-                ));
+                write!(json, ",\"nav_online\":{}", data.online)?;
             }
             DeviceData::Transponder(data) => {
-                json.push_str(&format!(
+                write!(
+                    json,
                     ",\"callsign\":\"{}\",\"transponder_online\":{},\"dm_code\":{}",
-                    data.callsign,
-                    if data.online { "true" } else { "false" },
-                    data.dm_code
-                ));
+                    data.callsign, data.online, data.dm_code
+                )?;
             }
             DeviceData::ShipComputer(data) => {
-                json.push_str(&format!(
-                    ",\"ship_computer_online\":{}",
-                    if data.online { "true" } else { "false" }
-                ));
+                write!(json, ",\"ship_computer_online\":{}", data.online)?;
             }
             DeviceData::DoorDevice(data) => {
-                json.push_str(&format!(
-                    ",\"open\":{}",
-                    if data.open { "true" } else { "false" }
-                ));
+                write!(json, ",\"open\":{}", data.open)?;
             }
             DeviceData::FoodGenerator(data) => {
-                json.push_str(&format!(
+                write!(
+                    json,
                     ",\"food_units\":{},\"max_food_units\":{},\"food_online\":{}",
-                    data.food_units,
-                    data.max_food_units,
-                    if data.online { "true" } else { "false" }
-                ));
+                    data.food_units, data.max_food_units, data.online
+                )?;
             }
             DeviceData::BedDevice(_)
             | DeviceData::Toilet(_)
             | DeviceData::RCSThruster(_)
             | DeviceData::PowerLine(_)
             | DeviceData::GasLine(_) => {}
+            DeviceData::Custom(data) => {
+                write!(json, ",\"custom_kind\":\"{}\"", data.kind())?;
+            }
         }
         json.push('}');
     }
     json.push_str("],");
     let totals = ship.total_atmos();
-    json.push_str(&format!(
+    write!(
+        json,
         "\"atmos_totals\":{{\"o2_kg\":{},\"n2_kg\":{},\"co2_kg\":{}}},",
         totals.o2_kg, totals.n2_kg, totals.co2_kg
-    ));
-    json.push_str(&format!(
+    )?;
+    write!(
+        json,
         "\"power\":{{\"net_kw\":{},\"total_production_kw\":{},\"total_consumption_kw\":{}}},",
         ship.power.net_kw, ship.power.total_production_kw, ship.power.total_consumption_kw
-    ));
+    )?;
     let summary = &ship.power_summary;
-    json.push_str(&format!(
+    write!(
+        json,
         "\"power_summary\":{{\"generation_kw\":{},\"load_kw\":{},\"net_kw\":{},\"devices\":[",
         summary.generation_kw, summary.load_kw, summary.net_kw
-    ));
+    )?;
     for (idx, device) in summary.devices.iter().enumerate() {
         if idx > 0 {
             json.push(',');
         }
-        json.push_str(&format!(
+        write!(
+            json,
             "{{\"id\":{},\"name\":\"{}\",\"group\":\"{}\",\"draw_kw\":{},\"online\":{},\"controllable\":{}}}",
             device.id,
             device.name,
             device.group.as_str(),
             device.draw_kw,
-            if device.online { "true" } else { "false" },
-            if device.controllable { "true" } else { "false" }
-        ));
+            device.online,
+            device.controllable
+        )?;
     }
     json.push_str("]},");
     let pawn = &interior.pawn;
-    json.push_str("\"pawn\":{");
-    json.push_str(&format!(
-        "\"x\":{},\"y\":{},\"status\":\"{}\",\"suffocation_time\":{}",
+    write!(
+        json,
+        "\"pawn\":{{\"x\":{},\"y\":{},\"status\":\"{}\",\"suffocation_time\":{}",
         pawn.x,
         pawn.y,
         pawn.status.as_str(),
         pawn.suffocation_time
-    ));
-    json.push_str(&format!(
+    )?;
+    write!(
+        json,
         ",\"needs\":{{\"hunger\":{},\"thirst\":{},\"rest\":{}}}",
         pawn.needs.hunger, pawn.needs.thirst, pawn.needs.rest
-    ));
+    )?;
     json.push_str(",\"health\":{\"body_parts\":[");
     for (idx, part) in pawn.health.body_parts.iter().enumerate() {
         if idx > 0 {
             json.push(',');
         }
-        json.push_str(&format!(
+        write!(
+            json,
             "{{\"name\":\"{}\",\"hp\":{},\"max_hp\":{},\"vital\":{}}}",
-            part.name,
-            part.hp,
-            part.max_hp,
-            if part.vital { "true" } else { "false" }
-        ));
+            part.name, part.hp, part.max_hp, part.vital
+        )?;
     }
-    json.push_str("]}");
-    json.push('}');
-    json.push('}');
-    json
+    json.push_str("]}}}");
+    Ok(())
 }
 
-fn nav_context_json(world: &World) -> Option<String> {
-    let ship = world
-        .bodies
-        .iter()
-        .find(|body| body.body_type == BodyType::Ship)?;
+/// Contacts listed in the nav context, nearest first.
+const NAV_CONTACTS: usize = 4;
+
+/// Write `"nav_context":{...},` for the first ship, or nothing if there is
+/// no ship on a bound orbit.
+fn write_nav_context_json(json: &mut String, world: &World) -> fmt::Result {
+    let Some(ship) = world.ships().next() else {
+        return Ok(());
+    };
     let position = ship.position;
     let velocity = ship.velocity;
     let r = position.length();
     if r <= 0.0 {
-        return None;
+        return Ok(());
     }
     let v = velocity.length();
     let mu = world.mu;
-    let dot_rv = position.x * velocity.x + position.y * velocity.y;
+    let dot_rv = position.dot(velocity);
     let energy = 0.5 * v * v - mu / r;
     if !energy.is_finite() || energy >= 0.0 {
-        return None;
+        return Ok(());
     }
     let semi_major = -mu / (2.0 * energy);
     let e_vec = position
@@ -589,32 +1374,27 @@ fn nav_context_json(world: &World) -> Option<String> {
     let apo_alt = (apoapsis - world.planet_radius).max(0.0);
     let peri_alt = (periapsis - world.planet_radius).max(0.0);
     let period = 2.0 * PI * (semi_major.powi(3) / mu).sqrt();
-    let heading = if position.x * velocity.y - position.y * velocity.x >= 0.0 {
+    let heading = if position.cross(velocity) >= 0.0 {
         "Prograde"
     } else {
         "Retrograde"
     };
-    let mut contacts: Vec<(u64, &'static str, f64, f64, f64)> = world
-        .bodies
-        .iter()
-        .filter(|body| body.id != ship.id)
-        .map(|body| {
-            let dx = body.position.x - position.x;
-            let dy = body.position.y - position.y;
-            let dist = (dx * dx + dy * dy).sqrt();
-            (
-                body.id,
-                body_type_name(body.body_type),
-                body.position.x,
-                body.position.y,
-                dist,
-            )
-        })
-        .collect();
-    contacts.sort_by(|a, b| a.4.partial_cmp(&b.4).unwrap_or(Ordering::Equal));
-    contacts.truncate(4);
-    let mut nav_json = format!(
-        "{{\"altitude_m\":{},\"apoapsis_m\":{},\"periapsis_m\":{},\"speed_mps\":{},\"orbital_period_s\":{},\"heading\":\"{}\",\"ship_position\":{{\"x_m\":{},\"y_m\":{}}},\"ship_velocity\":{{\"x_mps\":{},\"y_mps\":{}}}",
+    // Nearest few bodies by insertion; ties keep world order.
+    let mut contacts: [Option<(&BodyState, f64)>; NAV_CONTACTS] = [None; NAV_CONTACTS];
+    for body in world.bodies.iter().filter(|body| body.id != ship.id) {
+        let dist = body.position.distance(position);
+        let slot = contacts.iter().position(|contact| match contact {
+            Some((_, other)) => dist.partial_cmp(other) == Some(Ordering::Less),
+            None => true,
+        });
+        if let Some(slot) = slot {
+            contacts[slot..].rotate_right(1);
+            contacts[slot] = Some((body, dist));
+        }
+    }
+    write!(
+        json,
+        "\"nav_context\":{{\"altitude_m\":{},\"apoapsis_m\":{},\"periapsis_m\":{},\"speed_mps\":{},\"orbital_period_s\":{},\"heading\":\"{}\",\"ship_position\":{{\"x_m\":{},\"y_m\":{}}},\"ship_velocity\":{{\"x_mps\":{},\"y_mps\":{}}}",
         altitude,
         apo_alt,
         peri_alt,
@@ -625,19 +1405,23 @@ fn nav_context_json(world: &World) -> Option<String> {
         position.y,
         velocity.x,
         velocity.y
-    );
-    nav_json.push_str(",\"contacts\":[");
-    for (index, (id, kind, x, y, _)) in contacts.iter().enumerate() {
+    )?;
+    json.push_str(",\"contacts\":[");
+    for (index, (body, _)) in contacts.iter().flatten().enumerate() {
         if index > 0 {
-            nav_json.push(',');
+            json.push(',');
         }
-        nav_json.push_str(&format!(
+        write!(
+            json,
             "{{\"id\":{},\"body_type\":\"{}\",\"x_m\":{},\"y_m\":{}}}",
-            id, kind, x, y
-        ));
+            body.id,
+            body_type_name(body.body_type),
+            body.position.x,
+            body.position.y
+        )?;
     }
-    nav_json.push_str("]}");
-    Some(nav_json)
+    json.push_str("]},");
+    Ok(())
 }
 
 fn body_type_name(body_type: BodyType) -> &'static str {
@@ -649,6 +1433,153 @@ fn body_type_name(body_type: BodyType) -> &'static str {
     }
 }
 
+/// One entry of a snapshot's `events` list.
+fn write_world_event(json: &mut String, event: &WorldEvent) -> fmt::Result {
+    match event {
+        WorldEvent::BodySpawned { time, id } => write!(
+            json,
+            "{{\"type\":\"body_spawned\",\"time\":{},\"id\":{}}}",
+            time, id
+        ),
+        WorldEvent::Despawned(event) => write!(
+            json,
+            "{{\"type\":\"despawned\",\"time\":{},\"id\":{},\"reason\":\"{}\"}}",
+            event.time,
+            event.id,
+            despawn_reason_name(event.reason)
+        ),
+        WorldEvent::SoiChanged(event) => write!(
+            json,
+            "{{\"type\":\"soi_changed\",\"time\":{},\"body_id\":{},\"transition\":\"{}\"}}",
+            event.time,
+            event.body_id,
+            soi_transition_name(event.transition)
+        ),
+        WorldEvent::ThrustApplied(event) => write!(
+            json,
+            "{{\"type\":\"thrust_applied\",\"time\":{},\"body_id\":{},\"dv_x\":{},\"dv_y\":{}}}",
+            event.time, event.body_id, event.delta_v.x, event.delta_v.y
+        ),
+        WorldEvent::ThrustRejected { event, error } => {
+            write!(
+                json,
+                "{{\"type\":\"thrust_rejected\",\"time\":{},\"body_id\":{},\"error\":",
+                event.time, event.body_id
+            )?;
+            write_json_string(json, &error.to_string())?;
+            json.push('}');
+            Ok(())
+        }
+        WorldEvent::Collision { event, outcome } => write!(
+            json,
+            "{{\"type\":\"collision\",\"time\":{},\"body_a\":{},\"body_b\":{},\"outcome\":\"{}\"}}",
+            event.time,
+            event.body_a,
+            event.body_b,
+            collision_outcome_name(*outcome)
+        ),
+        WorldEvent::Landed {
+            time,
+            body_id,
+            longitude,
+        } => write!(
+            json,
+            "{{\"type\":\"landed\",\"time\":{},\"body_id\":{},\"longitude\":{}}}",
+            time, body_id, longitude
+        ),
+        WorldEvent::Damaged {
+            time,
+            body_id,
+            amount,
+            hp,
+        } => write!(
+            json,
+            "{{\"type\":\"damaged\",\"time\":{},\"body_id\":{},\"amount\":{},\"hp\":{}}}",
+            time, body_id, amount, hp
+        ),
+        WorldEvent::Launched { time, body_id } => write!(
+            json,
+            "{{\"type\":\"launched\",\"time\":{},\"body_id\":{}}}",
+            time, body_id
+        ),
+        WorldEvent::ReachedOrbit { time, body_id } => write!(
+            json,
+            "{{\"type\":\"reached_orbit\",\"time\":{},\"body_id\":{}}}",
+            time, body_id
+        ),
+        WorldEvent::ConjunctionWarning(conjunction) => write!(
+            json,
+            "{{\"type\":\"conjunction_warning\",\"time\":{},\"body_a\":{},\"body_b\":{},\"miss_distance\":{}}}",
+            conjunction.time, conjunction.body_a, conjunction.body_b, conjunction.miss_distance
+        ),
+        WorldEvent::Docked {
+            time,
+            host,
+            passenger,
+        } => write!(
+            json,
+            "{{\"type\":\"docked\",\"time\":{},\"host\":{},\"passenger\":{}}}",
+            time, host, passenger
+        ),
+        WorldEvent::Undocked {
+            time,
+            host,
+            passenger,
+        } => write!(
+            json,
+            "{{\"type\":\"undocked\",\"time\":{},\"host\":{},\"passenger\":{}}}",
+            time, host, passenger
+        ),
+        WorldEvent::MessageDelivered(message) => {
+            write!(
+                json,
+                "{{\"type\":\"message_delivered\",\"time\":{},\"from\":{},\"to\":{},\"sent\":{},\"payload\":[",
+                message.arrives, message.from, message.to, message.sent
+            )?;
+            for (i, byte) in message.payload.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                write!(json, "{}", byte)?;
+            }
+            json.push_str("]}");
+            Ok(())
+        }
+    }
+}
+
+fn collision_outcome_name(outcome: CollisionOutcome) -> &'static str {
+    match outcome {
+        CollisionOutcome::Separating => "separating",
+        CollisionOutcome::Bounced => "bounced",
+        CollisionOutcome::Merged { .. } => "merged",
+        CollisionOutcome::Fragmented => "fragmented",
+    }
+}
+
+fn soi_transition_name(transition: SoiTransition) -> &'static str {
+    match transition {
+        SoiTransition::EnteredGravityWell => "entered_gravity_well",
+        SoiTransition::LeftGravityWell => "left_gravity_well",
+        SoiTransition::Despawned => "despawned",
+        SoiTransition::EnteredSoi { .. } => "entered_soi",
+        SoiTransition::LeftSoi { .. } => "left_soi",
+    }
+}
+
+fn despawn_reason_name(reason: DespawnReason) -> &'static str {
+    match reason {
+        DespawnReason::LeftDespawnRadius => "left_despawn_radius",
+        DespawnReason::Merged { .. } => "merged",
+        DespawnReason::Fragmented => "fragmented",
+        DespawnReason::Removed => "removed",
+        DespawnReason::ReentryBurnup => "reentry_burnup",
+        DespawnReason::Detonated { .. } => "detonated",
+        DespawnReason::MinedOut => "mined_out",
+        DespawnReason::Destroyed => "destroyed",
+    }
+}
+
 fn gas_type_name(gas: GasType) -> &'static str {
     match gas {
         GasType::O2 => "O2",
@@ -690,6 +1621,81 @@ fn parse_command(line: &str) -> Option<Command> {
                 let device_id = extract_number::<u64>(trimmed, "\"device_id\"")?;
                 return Some(Command::ShipComputerToggle { device_id });
             }
+            "set_device" => {
+                let device_id = extract_number::<u64>(trimmed, "\"device_id\"")
+                    .or_else(|| extract_number::<u64>(trimmed, "\"id\""))?;
+                let online = extract_number::<bool>(trimmed, "\"online\"");
+                let setpoint = extract_number::<f32>(trimmed, "\"setpoint\"");
+                if online.is_none() && setpoint.is_none() {
+                    return None;
+                }
+                return Some(Command::SetDevice {
+                    device_id,
+                    online,
+                    setpoint,
+                });
+            }
+            "set_dispenser_gas" => {
+                let device_id = extract_number::<u64>(trimmed, "\"device_id\"")?;
+                let gas = extract_string(trimmed, "\"gas\"")
+                    .as_deref()
+                    .and_then(GasType::from_name)?;
+                return Some(Command::SetDispenserGas { device_id, gas });
+            }
+            "reactor_throttle" => {
+                let device_id = extract_number::<u64>(trimmed, "\"device_id\"")?;
+                let throttle = extract_number::<f32>(trimmed, "\"throttle\"")?;
+                return Some(Command::ReactorThrottle {
+                    device_id,
+                    throttle,
+                });
+            }
+            "warp_to" => {
+                let sim_time = extract_number::<f64>(trimmed, "\"sim_time\"")?;
+                if !sim_time.is_finite() {
+                    return None;
+                }
+                return Some(Command::WarpTo { sim_time });
+            }
+            "warp_to_next" => {
+                let body_id = extract_number::<u64>(trimmed, "\"body_id\"")?;
+                let target = extract_string(trimmed, "\"target\"")?;
+                let target = match target.to_ascii_lowercase().as_str() {
+                    "maneuver" => WarpTarget::Maneuver,
+                    "periapsis" => WarpTarget::Periapsis,
+                    "encounter" => WarpTarget::Encounter,
+                    _ => return None,
+                };
+                return Some(Command::WarpToNext { body_id, target });
+            }
+            "plan_transfer" => {
+                let to_radius = extract_number::<f64>(trimmed, "\"to_radius\"")?;
+                if !to_radius.is_finite() {
+                    return None;
+                }
+                let body_id = extract_number::<u64>(trimmed, "\"body_id\"");
+                return Some(Command::PlanTransfer { body_id, to_radius });
+            }
+            "torque" => {
+                let delta_angular_velocity =
+                    extract_number::<f64>(trimmed, "\"delta_angular_velocity\"")?;
+                if !delta_angular_velocity.is_finite() {
+                    return None;
+                }
+                let body_id = extract_number::<u64>(trimmed, "\"body_id\"");
+                return Some(Command::Torque {
+                    body_id,
+                    delta_angular_velocity,
+                });
+            }
+            "set_attitude_target" => {
+                let heading = extract_number::<f64>(trimmed, "\"heading\"");
+                if heading.is_some_and(|heading| !heading.is_finite()) {
+                    return None;
+                }
+                let body_id = extract_number::<u64>(trimmed, "\"body_id\"");
+                return Some(Command::SetAttitudeTarget { body_id, heading });
+            }
             _ => {}
         }
     }
@@ -712,6 +1718,76 @@ fn parse_command(line: &str) -> Option<Command> {
     None
 }
 
+fn parse_hello(line: &str) -> Option<Compression> {
+    let trimmed = line.trim();
+    if extract_string(trimmed, "\"type\"").as_deref() != Some("hello") {
+        return None;
+    }
+    let requested = extract_string(trimmed, "\"compression\"")
+        .and_then(|name| Compression::from_name(&name))
+        .unwrap_or(Compression::None);
+    Some(requested)
+}
+
+fn parse_client_message(line: &str) -> Option<ClientMessage> {
+    let trimmed = line.trim();
+    if extract_string(trimmed, "\"type\"").as_deref() == Some("join_session") {
+        let session = extract_string(trimmed, "\"session\"")?;
+        if !is_valid_session_id(&session) {
+            return None;
+        }
+        return Some(ClientMessage::JoinSession(session));
+    }
+    match extract_string(trimmed, "\"type\"").as_deref() {
+        Some("set_view") => {
+            return parse_view(trimmed).map(|view| ClientMessage::SetView(Some(view)));
+        }
+        Some("clear_view") => return Some(ClientMessage::SetView(None)),
+        Some("set_origin") => {
+            return extract_number::<u64>(trimmed, "\"body\"")
+                .map(|body_id| ClientMessage::SetOrigin(Some(body_id)));
+        }
+        Some("clear_origin") => return Some(ClientMessage::SetOrigin(None)),
+        _ => {}
+    }
+    parse_command(trimmed).map(ClientMessage::Command)
+}
+
+/// `set_view` takes `radius_m` plus either `focus_body` or an `x`/`y` point.
+fn parse_view(line: &str) -> Option<ViewFilter> {
+    let radius_m = extract_number::<f64>(line, "\"radius_m\"")?;
+    if !radius_m.is_finite() || radius_m <= 0.0 {
+        return None;
+    }
+    if let Some(body_id) = extract_number::<u64>(line, "\"focus_body\"") {
+        return Some(ViewFilter::around_body(body_id, radius_m));
+    }
+    let x = extract_number::<f64>(line, "\"x\"")?;
+    let y = extract_number::<f64>(line, "\"y\"")?;
+    Some(ViewFilter::around_point(Vec2::new(x, y), radius_m))
+}
+
+/// Session ids are echoed into snapshot JSON unescaped, so keep them to a
+/// short, quote-free alphabet.
+fn is_valid_session_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_SESSION_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn parse_lockstep_message(line: &str) -> Option<LockstepMessage> {
+    let trimmed = line.trim();
+    if extract_string(trimmed, "\"type\"").as_deref() == Some("end_frame") {
+        let tick = extract_number::<u64>(trimmed, "\"tick\"")?;
+        return Some(LockstepMessage::EndFrame { tick });
+    }
+    let command = parse_command(trimmed)?;
+    let tick = extract_number::<u64>(trimmed, "\"tick\"");
+    Some(LockstepMessage::Command { tick, command })
+}
+
 fn parse_time_scale_command(line: &str) -> Option<f64> {
     extract_number::<f64>(line, "\"time_scale\"").map(clamp_time_scale)
 }
@@ -720,7 +1796,7 @@ fn clamp_time_scale(value: f64) -> f64 {
     if value.is_nan() {
         DEFAULT_TIME_SCALE
     } else {
-        value.max(0.0).min(MAX_TIME_SCALE)
+        value.clamp(0.0, MAX_TIME_SCALE)
     }
 }
 
@@ -729,9 +1805,7 @@ fn extract_number<T: core::str::FromStr>(line: &str, key: &str) -> Option<T> {
     let after_key = line.get(start..)?;
     let colon_index = after_key.find(':')?;
     let after_colon = after_key.get(colon_index + 1..)?.trim_start();
-    let end_index = after_colon
-        .find(|c: char| c == ',' || c == '}')
-        .unwrap_or(after_colon.len());
+    let end_index = after_colon.find([',', '}']).unwrap_or(after_colon.len());
     let value_str = after_colon[..end_index].trim();
     value_str.parse::<T>().ok()
 }
@@ -756,4 +1830,46 @@ enum Command {
     InteractAt { x: u32, y: u32 },
     DeviceAction { device_id: u64, action: DeviceAction },
     ShipComputerToggle { device_id: u64 },
+    WarpTo { sim_time: f64 },
+    WarpToNext { body_id: u64, target: WarpTarget },
+    PlanTransfer {
+        body_id: Option<u64>,
+        to_radius: f64,
+    },
+    Torque {
+        body_id: Option<u64>,
+        delta_angular_velocity: f64,
+    },
+    /// `heading: None` releases the body to spin freely.
+    SetAttitudeTarget {
+        body_id: Option<u64>,
+        heading: Option<f64>,
+    },
+    SetDevice {
+        device_id: u64,
+        online: Option<bool>,
+        setpoint: Option<f32>,
+    },
+    SetDispenserGas {
+        device_id: u64,
+        gas: GasType,
+    },
+    ReactorThrottle {
+        device_id: u64,
+        throttle: f32,
+    },
+}
+
+enum ClientMessage {
+    Command(Command),
+    JoinSession(String),
+    SetView(Option<ViewFilter>),
+    SetOrigin(Option<u64>),
+    Disconnected,
+}
+
+enum LockstepMessage {
+    Command { tick: Option<u64>, command: Command },
+    EndFrame { tick: u64 },
+    Disconnected,
 }
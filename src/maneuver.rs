@@ -0,0 +1,269 @@
+//! Planned burns.
+//!
+//! A `ManeuverNode` is an impulsive burn scheduled for a future sim time.
+//! Planning one changes nothing yet, so a UI can draw the orbit it would
+//! produce with `World::predict_orbit_after_node` before committing.
+//! `World::step` and `World::warp_to` execute each node on the first tick
+//! that reaches its time, applying the burn at exactly that time even when
+//! the tick overshoots it. A raw `ThrustEvent` can be queued the same way
+//! with `World::schedule_thrust`, and then the step is also split at the
+//! burn, so everything after it in the step sees the new orbit.
+//!
+//! A `BurnProgram` is the finite version for low-thrust engines: a steady
+//! acceleration held for a while, integrated across however many ticks it
+//! spans. Each tick's share is split into impulses no longer than
+//! `BURN_STEP_S`, and the orbit is refit after each, so an ion burn traces
+//! the same spiral whether it is stepped or warped through.
+
+use crate::math::Float;
+use crate::{cartesian_to_orbit, error, orbit_to_cartesian, OrbitState, Seconds, Vec2};
+
+/// Longest slice of a `BurnProgram` applied as a single impulse.
+pub const BURN_STEP_S: f64 = 1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ManeuverNode {
+    pub body_id: u64,
+    /// Sim time of the burn.
+    pub time: Seconds,
+    /// Change in velocity, in the frame of the body's primary.
+    pub delta_v: Vec2,
+}
+
+/// A burn held from `start` for `duration` seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BurnProgram {
+    pub body_id: u64,
+    /// Sim time the engine lights.
+    pub start: f64,
+    pub duration: f64,
+    /// Thrust per unit mass, in m/s², in the body's prograde frame: `x`
+    /// along its velocity relative to its primary, `y` to the left of it.
+    /// The direction turns with the velocity as the burn goes on.
+    pub acceleration: Vec2,
+}
+
+impl BurnProgram {
+    /// Sim time the engine cuts off.
+    pub fn end(&self) -> f64 {
+        self.start + self.duration
+    }
+}
+
+/// Orbit after the part of `program` between `from` and `to`, for a body on
+/// `orbit` around a primary of gravitational parameter `mu`. Unchanged if
+/// the two don't overlap.
+pub fn orbit_after_program(
+    orbit: &OrbitState,
+    mu: f64,
+    program: &BurnProgram,
+    from: f64,
+    to: f64,
+) -> error::Result<OrbitState> {
+    let (from, to) = (from.max(program.start), to.min(program.end()));
+    let mut orbit = *orbit;
+    if to <= from {
+        return Ok(orbit);
+    }
+    // Ceiling, as slices are at most `BURN_STEP_S` long.
+    let steps = (-Float::floor((from - to) / BURN_STEP_S)).max(1.0);
+    let step = (to - from) / steps;
+    for i in 0..steps as u64 {
+        // Each slice is applied at its midpoint, along the velocity there.
+        let time = from + (i as f64 + 0.5) * step;
+        let (position, velocity) = orbit_to_cartesian(&orbit, mu, time)?;
+        let along = velocity.normalized();
+        let delta_v =
+            (along * program.acceleration.x + along.perp() * program.acceleration.y) * step;
+        orbit = cartesian_to_orbit(position, velocity + delta_v, mu, time)?;
+    }
+    Ok(orbit)
+}
+
+/// Orbit after adding `delta_v` at `time` to a body on `orbit` around a
+/// primary of gravitational parameter `mu`.
+pub fn orbit_after_burn(
+    orbit: &OrbitState,
+    mu: f64,
+    time: f64,
+    delta_v: Vec2,
+) -> error::Result<OrbitState> {
+    let (position, velocity) = orbit_to_cartesian(orbit, mu, time)?;
+    cartesian_to_orbit(position, velocity + delta_v, mu, time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::error::GgwError;
+    use crate::{BodyState, Seconds, ThrustEvent, ThrustType, WarpTarget, World, WorldEvent};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn planned_nodes_predict_and_then_execute() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let before = world.body(ship).unwrap().orbit;
+
+        world.step(Seconds(10.0));
+        let past = ManeuverNode {
+            body_id: ship,
+            time: Seconds(5.0),
+            delta_v: Vec2::new(0.0, 10.0),
+        };
+        assert_eq!(world.plan_node(past), Err(GgwError::ManeuverInPast(5.0)));
+        let stranger = ManeuverNode {
+            body_id: ship + 1,
+            ..past
+        };
+        assert_eq!(
+            world.predict_orbit_after_node(&stranger),
+            Err(GgwError::UnknownBody(ship + 1))
+        );
+
+        // Two prograde burns; predicting the second accounts for the first.
+        let first = ManeuverNode {
+            body_id: ship,
+            time: Seconds(100.0),
+            delta_v: Vec2::new(0.0, 50.0),
+        };
+        let second = ManeuverNode {
+            time: Seconds(1_000.0),
+            ..first
+        };
+        world.plan_node(second).unwrap();
+        world.plan_node(first).unwrap();
+        assert_eq!(world.maneuver_nodes(), [first, second]);
+        let after_first = world.predict_orbit_after_node(&first).unwrap();
+        let after_second = world.predict_orbit_after_node(&second).unwrap();
+        assert!(after_first.semi_major_axis > before.semi_major_axis);
+        assert!(after_second.semi_major_axis > after_first.semi_major_axis);
+        // Nothing has happened yet.
+        assert_eq!(world.body(ship).unwrap().orbit, before);
+
+        assert_eq!(
+            world.next_warp_time(ship, WarpTarget::Maneuver, Seconds(3_600.0)),
+            Some(Seconds(100.0))
+        );
+        world.warp_to(Seconds(130.0));
+        assert_eq!(world.body(ship).unwrap().orbit, after_first);
+        assert_eq!(world.maneuver_nodes(), [second]);
+
+        world.warp_to_next(ship, WarpTarget::Maneuver, Seconds(3_600.0));
+        world.step(Seconds(1.0));
+        assert_eq!(world.body(ship).unwrap().orbit, after_second);
+        assert!(world.maneuver_nodes().is_empty());
+
+        world
+            .plan_node(ManeuverNode {
+                time: Seconds(2_000.0),
+                ..first
+            })
+            .unwrap();
+        assert_eq!(world.cancel_nodes(ship), 1);
+        assert!(world.maneuver_nodes().is_empty());
+    }
+
+    #[test]
+    fn finite_burns_spiral_out_the_same_under_warp() {
+        let ship = |world: &mut World| {
+            world
+                .add_body(BodyState::circular(7_000_000.0, 0.0))
+                .unwrap()
+        };
+        let mut stepped = World::new(MU_EARTH, GameConfig::default());
+        let mut warped = World::new(MU_EARTH, GameConfig::default());
+        let id = ship(&mut stepped);
+        ship(&mut warped);
+
+        // A gentle prograde ion burn for 1000 s.
+        let program = BurnProgram {
+            body_id: id,
+            start: 10.0,
+            duration: 1_000.0,
+            acceleration: Vec2::new(0.01, 0.0),
+        };
+        assert_eq!(
+            stepped.plan_burn(BurnProgram {
+                duration: 0.0,
+                ..program
+            }),
+            Err(GgwError::InvalidBurn)
+        );
+        stepped.plan_burn(program).unwrap();
+        warped.plan_burn(program).unwrap();
+
+        for _ in 0..1_100 {
+            stepped.step(Seconds(1.0));
+        }
+        warped.warp_to(Seconds(1_100.0));
+        assert!(stepped.burn_programs().is_empty());
+        assert!(warped.burn_programs().is_empty());
+
+        let a = stepped.body(id).unwrap().orbit;
+        let b = warped.body(id).unwrap().orbit;
+        assert!((a.semi_major_axis - b.semi_major_axis).abs() < 1.0);
+        assert!((a.eccentricity - b.eccentricity).abs() < 1e-6);
+        // da/dt = 2 a^(3/2) f / sqrt(mu) for thrust f along a circular orbit.
+        let expected = 2.0 * 7_000_000f64.powf(1.5) * 0.01 * 1_000.0 / MU_EARTH.sqrt();
+        let gained = a.semi_major_axis - 7_000_000.0;
+        assert!((gained - expected).abs() < 0.01 * expected);
+    }
+
+    #[test]
+    fn scheduled_thrusts_split_the_step_they_fall_in() {
+        let ship = |world: &mut World| {
+            world
+                .add_body(BodyState::circular(7_000_000.0, 0.0))
+                .unwrap()
+        };
+        let mut scheduled = World::new(MU_EARTH, GameConfig::default());
+        let mut by_hand = World::new(MU_EARTH, GameConfig::default());
+        let id = ship(&mut scheduled);
+        ship(&mut by_hand);
+        let burn = ThrustEvent {
+            body_id: id,
+            time: 1.5,
+            delta_v: Vec2::new(0.0, 20.0),
+            thrust_type: ThrustType::Chemical,
+        };
+        assert_eq!(
+            scheduled.schedule_thrust(ThrustEvent {
+                body_id: id + 1,
+                ..burn.clone()
+            }),
+            Err(GgwError::UnknownBody(id + 1))
+        );
+        scheduled.schedule_thrust(burn.clone()).unwrap();
+        assert_eq!(scheduled.scheduled_thrusts().len(), 1);
+
+        // One long step burns at 1.5 s, the same as stopping there by hand.
+        scheduled.step(Seconds(3.0));
+        by_hand.step(Seconds(1.5));
+        by_hand.apply_thrust_event(&burn).unwrap();
+        by_hand.step(Seconds(1.5));
+        assert!(scheduled.scheduled_thrusts().is_empty());
+        assert!(scheduled
+            .events()
+            .contains(&WorldEvent::ThrustApplied(burn.clone())));
+        assert_eq!(scheduled.state_hash(), by_hand.state_hash());
+
+        assert_eq!(
+            scheduled.schedule_thrust(burn.clone()),
+            Err(GgwError::ManeuverInPast(1.5))
+        );
+        scheduled
+            .schedule_thrust(ThrustEvent {
+                time: 600.0,
+                ..burn
+            })
+            .unwrap();
+        assert_eq!(scheduled.cancel_scheduled_thrusts(id), 1);
+    }
+}
@@ -0,0 +1,187 @@
+//! The float types `Vec2` and the Kepler solver are generic over. With
+//! `std` the methods forward to the inherent `f32`/`f64` ones; without it,
+//! or with `deterministic-math`, the functions that live in `std` rather
+//! than `core` are routed through `libm`. The platform libm is free to
+//! differ in the last bit between OSes, which is enough to desync lockstep
+//! peers; the `libm` crate gives the same answer everywhere.
+//!
+//! Simulation code calls these as `Float::sin(x)` rather than `x.sin()` so
+//! the inherent `std` method can't win method resolution.
+
+use core::fmt::Debug;
+use core::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+pub trait Float:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const EPSILON: Self;
+    const PI: Self;
+
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn abs(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn sqrt(self) -> Self;
+    fn powf(self, n: Self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+    fn atan2(self, other: Self) -> Self;
+    fn floor(self) -> Self;
+    fn rem_euclid(self, rhs: Self) -> Self;
+}
+
+macro_rules! impl_float {
+    ($ty:ident, $sqrt:ident, $pow:ident, $exp:ident, $ln:ident, $sin:ident, $cos:ident, $sincos:ident, $atan2:ident, $floor:ident, $fmod:ident) => {
+        impl Float for $ty {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const EPSILON: Self = $ty::EPSILON;
+            const PI: Self = core::$ty::consts::PI;
+
+            fn from_f64(value: f64) -> Self {
+                value as $ty
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn abs(self) -> Self {
+                $ty::abs(self)
+            }
+
+            fn min(self, other: Self) -> Self {
+                $ty::min(self, other)
+            }
+
+            fn max(self, other: Self) -> Self {
+                $ty::max(self, other)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "deterministic-math")))]
+            fn sqrt(self) -> Self {
+                $ty::sqrt(self)
+            }
+
+            #[cfg(any(not(feature = "std"), feature = "deterministic-math"))]
+            fn sqrt(self) -> Self {
+                libm::$sqrt(self)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "deterministic-math")))]
+            fn powf(self, n: Self) -> Self {
+                $ty::powf(self, n)
+            }
+
+            #[cfg(any(not(feature = "std"), feature = "deterministic-math"))]
+            fn powf(self, n: Self) -> Self {
+                libm::$pow(self, n)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "deterministic-math")))]
+            fn exp(self) -> Self {
+                $ty::exp(self)
+            }
+
+            #[cfg(any(not(feature = "std"), feature = "deterministic-math"))]
+            fn exp(self) -> Self {
+                libm::$exp(self)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "deterministic-math")))]
+            fn ln(self) -> Self {
+                $ty::ln(self)
+            }
+
+            #[cfg(any(not(feature = "std"), feature = "deterministic-math"))]
+            fn ln(self) -> Self {
+                libm::$ln(self)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "deterministic-math")))]
+            fn sin(self) -> Self {
+                $ty::sin(self)
+            }
+
+            #[cfg(any(not(feature = "std"), feature = "deterministic-math"))]
+            fn sin(self) -> Self {
+                libm::$sin(self)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "deterministic-math")))]
+            fn cos(self) -> Self {
+                $ty::cos(self)
+            }
+
+            #[cfg(any(not(feature = "std"), feature = "deterministic-math"))]
+            fn cos(self) -> Self {
+                libm::$cos(self)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "deterministic-math")))]
+            fn sin_cos(self) -> (Self, Self) {
+                $ty::sin_cos(self)
+            }
+
+            #[cfg(any(not(feature = "std"), feature = "deterministic-math"))]
+            fn sin_cos(self) -> (Self, Self) {
+                libm::$sincos(self)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "deterministic-math")))]
+            fn atan2(self, other: Self) -> Self {
+                $ty::atan2(self, other)
+            }
+
+            #[cfg(any(not(feature = "std"), feature = "deterministic-math"))]
+            fn atan2(self, other: Self) -> Self {
+                libm::$atan2(self, other)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "deterministic-math")))]
+            fn floor(self) -> Self {
+                $ty::floor(self)
+            }
+
+            #[cfg(any(not(feature = "std"), feature = "deterministic-math"))]
+            fn floor(self) -> Self {
+                libm::$floor(self)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "deterministic-math")))]
+            fn rem_euclid(self, rhs: Self) -> Self {
+                $ty::rem_euclid(self, rhs)
+            }
+
+            #[cfg(any(not(feature = "std"), feature = "deterministic-math"))]
+            fn rem_euclid(self, rhs: Self) -> Self {
+                let r = libm::$fmod(self, rhs);
+                if r < 0.0 {
+                    r + rhs.abs()
+                } else {
+                    r
+                }
+            }
+        }
+    };
+}
+
+impl_float!(f64, sqrt, pow, exp, log, sin, cos, sincos, atan2, floor, fmod);
+impl_float!(f32, sqrtf, powf, expf, logf, sinf, cosf, sincosf, atan2f, floorf, fmodf);
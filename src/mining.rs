@@ -0,0 +1,222 @@
+//! What asteroids are made of, and digging it out.
+//!
+//! An asteroid's composition gives the share of its mass that is each
+//! resource in `config.resources`; whatever is left over is rock worth
+//! nothing. Asteroids from `World::spawn_asteroid_field` get their own mix
+//! scattered around each resource's `abundance`, and any other asteroid is
+//! taken to be the typical mix, with exactly those shares, until given one
+//! with `World::set_composition`.
+//!
+//! `World::extract` digs a mass out of an asteroid and hands back the
+//! resources in it. The asteroid is the same mix all the way through, so
+//! its composition doesn't change as it shrinks; its radius and hull
+//! shrink with the cube root of its mass, and once nothing is left it is
+//! gone with a `DespawnReason::MinedOut`.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use crate::config::ResourceConfig;
+use crate::math::Float;
+use crate::{error, BodyType, DespawnReason, GgwError, World};
+
+/// Density of the worthless rock that makes up the rest of an asteroid.
+pub const ROCK_DENSITY_KG_M3: f64 = 2_000.0;
+
+/// Mass share of each resource in a typical asteroid: its `abundance`.
+pub fn typical_composition(resources: &BTreeMap<String, ResourceConfig>) -> BTreeMap<String, f64> {
+    resources
+        .iter()
+        .filter(|(_, resource)| resource.abundance > 0.0)
+        .map(|(name, resource)| (name.clone(), f64::from(resource.abundance)))
+        .collect()
+}
+
+/// Density of a body whose mass is split as `composition`, with the rest
+/// rock.
+pub fn bulk_density(
+    composition: &BTreeMap<String, f64>,
+    resources: &BTreeMap<String, ResourceConfig>,
+) -> f64 {
+    let mut rock = 1.0;
+    let mut volume_per_kg = 0.0;
+    for (name, share) in composition {
+        let Some(resource) = resources.get(name) else {
+            continue;
+        };
+        let density = f64::from(resource.density_kg_per_m3);
+        if density > 0.0 {
+            rock -= share;
+            volume_per_kg += share / density;
+        }
+    }
+    1.0 / (volume_per_kg + rock.max(0.0) / ROCK_DENSITY_KG_M3)
+}
+
+impl World {
+    /// Set what asteroid `body_id` is made of, as mass shares of resources
+    /// from `config.resources`. Fails with `NotAnAsteroid` for any other
+    /// kind of body, with `UnknownResource` for a name the config doesn't
+    /// have, and with `InvalidComposition` if a share is outside `[0, 1]`
+    /// or they add up to more than 1.
+    pub fn set_composition(
+        &mut self,
+        body_id: u64,
+        composition: BTreeMap<String, f64>,
+    ) -> error::Result<()> {
+        match self.body(body_id) {
+            Some(body) if body.body_type == BodyType::Asteroid => {}
+            Some(_) => return Err(GgwError::NotAnAsteroid(body_id)),
+            None => return Err(GgwError::UnknownBody(body_id)),
+        }
+        if let Some(name) = composition
+            .keys()
+            .find(|name| !self.config.resources.contains_key(*name))
+        {
+            return Err(GgwError::UnknownResource(name.clone()));
+        }
+        let shares_are_valid = composition
+            .values()
+            .all(|share| (0.0..=1.0).contains(share));
+        if !shares_are_valid || composition.values().sum::<f64>() > 1.0 + 1e-9 {
+            return Err(GgwError::InvalidComposition);
+        }
+        self.compositions.insert(body_id, composition);
+        Ok(())
+    }
+
+    /// What asteroid `body_id` is made of, or `None` if it isn't an
+    /// asteroid.
+    pub fn composition(&self, body_id: u64) -> Option<BTreeMap<String, f64>> {
+        let body = self.body(body_id)?;
+        if body.body_type != BodyType::Asteroid {
+            return None;
+        }
+        Some(match self.compositions.get(&body_id) {
+            Some(composition) => composition.clone(),
+            None => typical_composition(&self.config.resources),
+        })
+    }
+
+    /// Dig `kg` out of asteroid `body_id`, or all of it if it has less,
+    /// returning the mass of each resource that came out with it. Fails
+    /// with `NotAnAsteroid` for any other kind of body.
+    pub fn extract(&mut self, body_id: u64, kg: f64) -> error::Result<BTreeMap<String, f64>> {
+        let composition = match self.composition(body_id) {
+            Some(composition) => composition,
+            None if self.body(body_id).is_some() => return Err(GgwError::NotAnAsteroid(body_id)),
+            None => return Err(GgwError::UnknownBody(body_id)),
+        };
+        let mass = self.body(body_id).map_or(0.0, |body| body.mass);
+        let kg = kg.max(0.0).min(mass);
+        let extracted = composition
+            .into_iter()
+            .map(|(name, share)| (name, share * kg))
+            .collect();
+
+        let left = mass - kg;
+        if left <= 0.0 {
+            tracing::debug!(body = body_id, "asteroid mined out");
+            self.forget_body(body_id, DespawnReason::MinedOut);
+            return Ok(extracted);
+        }
+        let scale = Float::powf(left / mass, 1.0 / 3.0);
+        if let Some(body) = self.get_body_mut(body_id) {
+            body.mass = left;
+            body.radius *= scale;
+            if let Some(hull) = &mut body.hull_shape {
+                for vertex in &mut hull.vertices {
+                    *vertex *= scale;
+                }
+            }
+        }
+        Ok(extracted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{BodyState, HullShape, Vec2};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn asteroid(body_type: BodyType) -> BodyState {
+        BodyState {
+            mass: 8_000.0,
+            radius: 10.0,
+            body_type,
+            hull_shape: Some(HullShape {
+                vertices: alloc::vec![
+                    Vec2::new(10.0, 0.0),
+                    Vec2::new(0.0, 10.0),
+                    Vec2::new(-10.0, 0.0),
+                ],
+            }),
+            ..BodyState::circular(7_000_000.0, 0.0)
+        }
+    }
+
+    #[test]
+    fn mining_takes_resources_in_proportion_and_shrinks_the_rock() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let rock = world.add_body(asteroid(BodyType::Asteroid)).unwrap();
+        let ship = world.add_body(asteroid(BodyType::Ship)).unwrap();
+        assert_eq!(world.composition(ship), None);
+        assert_eq!(world.extract(ship, 1.0), Err(GgwError::NotAnAsteroid(ship)));
+        assert_eq!(
+            world.composition(rock),
+            Some(typical_composition(&world.config.resources))
+        );
+
+        let mut composition = BTreeMap::new();
+        composition.insert(String::from("unobtainium"), 0.5);
+        assert_eq!(
+            world.set_composition(rock, composition),
+            Err(GgwError::UnknownResource(String::from("unobtainium")))
+        );
+        let mut composition = BTreeMap::new();
+        composition.insert(String::from("iron_ore"), 0.7);
+        composition.insert(String::from("gold_ore"), 0.4);
+        assert_eq!(
+            world.set_composition(rock, composition.clone()),
+            Err(GgwError::InvalidComposition)
+        );
+        composition.insert(String::from("gold_ore"), 0.1);
+        world.set_composition(rock, composition).unwrap();
+
+        // Seven eighths out halves the radius.
+        let yields = world.extract(rock, 7_000.0).unwrap();
+        assert!((yields["iron_ore"] - 4_900.0).abs() < 1e-9);
+        assert!((yields["gold_ore"] - 700.0).abs() < 1e-9);
+        let body = world.body(rock).unwrap();
+        assert!((body.mass - 1_000.0).abs() < 1e-9);
+        assert!((body.radius - 5.0).abs() < 1e-9);
+        let hull = &body.hull_shape.as_ref().unwrap().vertices;
+        assert!((hull[0].x - 5.0).abs() < 1e-9);
+
+        // Asking for more than is left takes the rest.
+        let yields = world.extract(rock, 5_000.0).unwrap();
+        assert!((yields["iron_ore"] - 700.0).abs() < 1e-9);
+        assert!(world.body(rock).is_none());
+        assert_eq!(
+            world.drain_despawn_events().pop().unwrap().reason,
+            DespawnReason::MinedOut
+        );
+    }
+
+    #[test]
+    fn ore_makes_rock_denser() {
+        let config = GameConfig::default();
+        assert_eq!(
+            bulk_density(&BTreeMap::new(), &config.resources),
+            ROCK_DENSITY_KG_M3
+        );
+        let mut gold = BTreeMap::new();
+        gold.insert(String::from("gold_ore"), 1.0);
+        assert!((bulk_density(&gold, &config.resources) - 19_300.0).abs() < 1e-6);
+        let typical = bulk_density(&typical_composition(&config.resources), &config.resources);
+        assert!(typical > ROCK_DENSITY_KG_M3 && typical < 19_300.0);
+    }
+}
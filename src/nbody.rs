@@ -0,0 +1,231 @@
+//! Numeric propagation under every attractor at once.
+//!
+//! Bodies normally follow the Kepler orbit around their primary alone. One
+//! switched over with `World::set_numeric_propagation` is integrated
+//! instead, pulled by the planet and every moon, in steps of at most
+//! `NUMERIC_STEP_S`. The integrator is Yoshida's fourth-order composition
+//! of leapfrog steps: symplectic, so a long run doesn't spiral in or out,
+//! and accurate enough that a lone body stays within a metre of its Kepler
+//! orbit over many revolutions.
+//!
+//! The body's `orbit` is refit from the integrated state after each tick
+//! for displays, previews and planners, but no longer drives it; a state
+//! with no closed prograde orbit around its primary keeps the last orbit
+//! that fit. Anything that changes the orbit from outside, such as a burn
+//! or a collision, restarts the integration from the new orbit.
+
+use alloc::vec::Vec;
+
+use crate::central::{CentralBody, Primaries};
+use crate::lockstep::StateHasher;
+use crate::math::Float;
+use crate::{cartesian_to_orbit, error, BodyState, OrbitState, Vec2};
+
+/// Longest single integration step.
+pub const NUMERIC_STEP_S: f64 = 1.0;
+
+/// Yoshida's weights, `1 / (2 - 2^(1/3))` and `-2^(1/3) / (2 - 2^(1/3))`.
+const W1: f64 = 1.351_207_191_959_657_8;
+const W0: f64 = -1.702_414_383_919_315_3;
+/// Drift fractions of a step; the kicks are `W1`, `W0`, `W1` in between.
+const DRIFTS: [f64; 4] = [W1 / 2.0, (W0 + W1) / 2.0, (W0 + W1) / 2.0, W1 / 2.0];
+/// Where in a step each kick is evaluated.
+const KICK_TIMES: [f64; 3] = [W1 / 2.0, 0.5, 1.0 - W1 / 2.0];
+
+/// Where the integration of a body left off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct NumericState {
+    time: f64,
+    position: Vec2,
+    velocity: Vec2,
+    /// The body's orbit as last written, to notice outside changes.
+    orbit: OrbitState,
+}
+
+impl NumericState {
+    /// Feed where the integration left off into `hasher`, since the next
+    /// tick picks up from there rather than from the orbit.
+    pub(crate) fn hash_state(&self, hasher: &mut StateHasher) {
+        let orbit = &self.orbit;
+        for value in [
+            self.time,
+            self.position.x,
+            self.position.y,
+            self.velocity.x,
+            self.velocity.y,
+            orbit.semi_major_axis,
+            orbit.eccentricity,
+            orbit.arg_of_periapsis,
+            orbit.mean_anomaly_at_epoch,
+            orbit.epoch,
+        ] {
+            hasher.write_f64(value);
+        }
+    }
+}
+
+/// The primaries at every instant the integrator needs across one tick,
+/// shared by all the numeric bodies.
+pub(crate) struct Field {
+    from: f64,
+    to: f64,
+    step: f64,
+    start: Primaries,
+    /// Placed at each step's `KICK_TIMES`, step after step.
+    kicks: Vec<Primaries>,
+    end: Primaries,
+}
+
+impl Field {
+    pub(crate) fn new(
+        mu: f64,
+        planet_radius: f64,
+        central_bodies: &[CentralBody],
+        from: f64,
+        to: f64,
+    ) -> Self {
+        // Ceiling, as steps are at most `NUMERIC_STEP_S` long.
+        let steps = (-Float::floor((from - to) / NUMERIC_STEP_S)).max(1.0) as usize;
+        let step = (to - from) / steps as f64;
+        let at = |time| Primaries::at(mu, planet_radius, central_bodies, time);
+        let kicks = (0..steps)
+            .flat_map(|i| KICK_TIMES.map(|kick| from + step * (i as f64 + kick)))
+            .map(at)
+            .collect();
+        Self {
+            from,
+            to,
+            step,
+            start: at(from),
+            kicks,
+            end: at(to),
+        }
+    }
+}
+
+/// Move `body` across `field`'s tick, picking up from `state` unless its
+/// orbit has been changed since, and refit its orbit at the end.
+pub(crate) fn propagate(
+    body: &mut BodyState,
+    state: &mut Option<NumericState>,
+    field: &Field,
+) -> error::Result<()> {
+    let (mut position, mut velocity) = match *state {
+        // Clock ticks don't convert to seconds exactly, so allow for
+        // rounding between the end of one tick and the start of the next.
+        Some(last) if (last.time - field.from).abs() < 1e-6 && last.orbit == body.orbit => {
+            (last.position, last.velocity)
+        }
+        _ => field
+            .start
+            .get(body.parent)
+            .state_at(&body.orbit, field.from)?,
+    };
+    if field.to > field.from {
+        for kicks in field.kicks.chunks_exact(3) {
+            for (stage, primaries) in kicks.iter().enumerate() {
+                position += velocity * (DRIFTS[stage] * field.step);
+                let weight = if stage == 1 { W0 } else { W1 };
+                velocity += primaries.gravity_at(position) * (weight * field.step);
+            }
+            position += velocity * (DRIFTS[3] * field.step);
+        }
+    }
+    body.position = position;
+    body.velocity = velocity;
+
+    let primary = field.end.get(body.parent);
+    let (offset, relative) = (position - primary.position, velocity - primary.velocity);
+    if offset.cross(relative) > 0.0 {
+        if let Ok(orbit) = cartesian_to_orbit(offset, relative, primary.mu, field.to) {
+            body.orbit = orbit;
+        }
+    }
+    *state = Some(NumericState {
+        time: field.to,
+        position,
+        velocity,
+        orbit: body.orbit,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::GameConfig;
+    use crate::{BodyState, OrbitState, Seconds, World};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+    const MU_MOON: f64 = 4.904_869_5e12;
+
+    fn circular(a: f64) -> OrbitState {
+        OrbitState {
+            semi_major_axis: a,
+            eccentricity: 0.0,
+            arg_of_periapsis: 0.0,
+            mean_anomaly_at_epoch: 0.0,
+            epoch: 0.0,
+        }
+    }
+
+    fn ship(orbit: OrbitState) -> BodyState {
+        BodyState::orbiting(orbit)
+    }
+
+    #[test]
+    fn numeric_bodies_follow_kepler_alone_and_feel_moons() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let kepler = world.add_body(ship(circular(7_000_000.0))).unwrap();
+        let numeric = world.add_body(ship(circular(7_000_000.0))).unwrap();
+        assert!(world.set_numeric_propagation(numeric + 1, true).is_err());
+        world.set_numeric_propagation(numeric, true).unwrap();
+        assert!(world.uses_numeric_propagation(numeric));
+
+        // With the planet alone the two agree for a whole orbit, stepped
+        // and warped.
+        for _ in 0..600 {
+            world.step(Seconds(0.5));
+        }
+        world.warp_to(Seconds(6_000.0));
+        let gap = |world: &World, a: u64, b: u64| {
+            let position = |id| world.body(id).unwrap().position;
+            position(a).distance(position(b))
+        };
+        assert!(
+            gap(&world, kepler, numeric) < 1.0,
+            "{}",
+            gap(&world, kepler, numeric)
+        );
+        let orbit = world.body(numeric).unwrap().orbit;
+        assert!((orbit.semi_major_axis - 7_000_000.0).abs() < 1.0);
+
+        // A burn restarts the integration from the new orbit.
+        world
+            .apply_thrust_event(&crate::ThrustEvent {
+                body_id: numeric,
+                time: world.sim_time().0,
+                delta_v: world.body(numeric).unwrap().velocity * 0.01,
+                thrust_type: crate::ThrustType::Chemical,
+            })
+            .unwrap();
+        let raised = world.body(numeric).unwrap().orbit.semi_major_axis;
+        world.step(Seconds(10.0));
+        let orbit = world.body(numeric).unwrap().orbit;
+        assert!((orbit.semi_major_axis - raised).abs() < 1.0);
+
+        // Near a moon, but outside its sphere of influence, only the
+        // numeric body is pulled off its ellipse.
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        world
+            .add_central_body(MU_MOON, 1_737_000.0, circular(384_400_000.0))
+            .unwrap();
+        let kepler = world.add_body(ship(circular(300_000_000.0))).unwrap();
+        let numeric = world.add_body(ship(circular(300_000_000.0))).unwrap();
+        world.set_numeric_propagation(numeric, true).unwrap();
+        world.warp_to(Seconds(86_400.0));
+        assert!(gap(&world, kepler, numeric) > 1_000.0);
+        world.set_numeric_propagation(numeric, false).unwrap();
+        assert!(!world.uses_numeric_propagation(numeric));
+    }
+}
@@ -0,0 +1,82 @@
+use alloc::vec::Vec;
+
+use crate::{CollisionEvent, Vec2};
+
+/// Where a body will be at the end of a previewed step.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PredictedBody {
+    pub id: u64,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SoiTransition {
+    EnteredGravityWell,
+    LeftGravityWell,
+    /// The body will pass the despawn radius and be culled.
+    Despawned,
+    /// The body crossed into the sphere of influence of moon
+    /// `central_body` and now orbits it.
+    EnteredSoi {
+        central_body: u64,
+    },
+    /// The body left the sphere of influence of moon `central_body` and now
+    /// orbits the planet.
+    LeftSoi {
+        central_body: u64,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SoiChange {
+    pub body_id: u64,
+    pub transition: SoiTransition,
+}
+
+/// A hand-over between primaries that happened during a step, kept until
+/// drained with `World::drain_soi_events`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SoiEvent {
+    pub time: f64,
+    pub body_id: u64,
+    pub transition: SoiTransition,
+}
+
+/// One sample of a path from `World::predict_trajectory`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrajectoryPoint {
+    pub time: f64,
+    /// World frame.
+    pub position: Vec2,
+    pub velocity: Vec2,
+    /// Moon the body orbits by then, `None` for the planet.
+    pub parent: Option<u64>,
+}
+
+/// Result of `World::preview_step`: what the next step would do, computed
+/// without touching the world.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StepPreview {
+    /// Sim time at the end of the previewed step.
+    pub time: f64,
+    pub bodies: Vec<PredictedBody>,
+    pub collisions: Vec<CollisionEvent>,
+    pub soi_changes: Vec<SoiChange>,
+}
+
+impl StepPreview {
+    pub fn body(&self, id: u64) -> Option<&PredictedBody> {
+        self.bodies.iter().find(|body| body.id == id)
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        self.collisions.is_empty() && self.soi_changes.is_empty()
+    }
+}
@@ -0,0 +1,259 @@
+//! Paying for burns.
+//!
+//! A burn costs the propellant the rocket equation asks for at the engine's
+//! specific impulse, set per `ThrustType` in `config::PropulsionConfig`:
+//! RCS and the main engine burn fuel, ion engines burn xenon. The body
+//! carrying the ship interior draws on the interior's tanks; any other body
+//! draws on the reserve given it with `World::set_propellant`. Bodies with
+//! neither, like scripted debris or missiles steering on their own
+//! `fuel_mps`, burn for free.
+//!
+//! The propellant burnt comes off the body's mass, so every burn makes the
+//! next one cheaper. A burn the body can't afford in full is turned down or
+//! cut short as `PropulsionConfig::shortfall` says.
+
+use crate::config::{BurnShortfall, PropulsionConfig};
+use crate::math::Float;
+use crate::{error, GgwError, ThrustEvent, ThrustType, World};
+
+/// Standard gravity, turning specific impulse into exhaust velocity.
+pub const STANDARD_GRAVITY: f64 = 9.806_65;
+
+/// Propellant on hand, or used by a burn.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Propellant {
+    /// For RCS and the main engine.
+    pub fuel_kg: f64,
+    /// For ion engines.
+    pub xenon_kg: f64,
+}
+
+impl Propellant {
+    /// `kg` of what `thrust_type` burns and nothing else.
+    pub fn only(thrust_type: ThrustType, kg: f64) -> Self {
+        match thrust_type {
+            ThrustType::Rcs | ThrustType::Chemical => Self {
+                fuel_kg: kg,
+                xenon_kg: 0.0,
+            },
+            ThrustType::Ion => Self {
+                fuel_kg: 0.0,
+                xenon_kg: kg,
+            },
+        }
+    }
+
+    /// How much of what `thrust_type` burns there is.
+    pub fn kg(&self, thrust_type: ThrustType) -> f64 {
+        match thrust_type {
+            ThrustType::Rcs | ThrustType::Chemical => self.fuel_kg,
+            ThrustType::Ion => self.xenon_kg,
+        }
+    }
+
+    pub fn total_kg(&self) -> f64 {
+        self.fuel_kg + self.xenon_kg
+    }
+}
+
+/// Specific impulse of `thrust_type`'s engines, in seconds.
+pub fn isp_s(config: &PropulsionConfig, thrust_type: ThrustType) -> f64 {
+    match thrust_type {
+        ThrustType::Rcs => config.rcs_isp_s,
+        ThrustType::Chemical => config.chemical_isp_s,
+        ThrustType::Ion => config.ion_isp_s,
+    }
+}
+
+/// Propellant a body of `wet_mass` burns to change its velocity by
+/// `delta_v` with engines of `isp_s`.
+pub fn propellant_for(delta_v: f64, wet_mass: f64, isp_s: f64) -> f64 {
+    let exhaust_velocity = isp_s * STANDARD_GRAVITY;
+    if exhaust_velocity <= 0.0 {
+        return f64::INFINITY;
+    }
+    wet_mass * (1.0 - Float::exp(-delta_v.max(0.0) / exhaust_velocity))
+}
+
+/// Velocity change a body of `wet_mass` gets from burning `propellant_kg`
+/// with engines of `isp_s`.
+pub fn delta_v_for(propellant_kg: f64, wet_mass: f64, isp_s: f64) -> f64 {
+    let dry_mass = wet_mass - propellant_kg.max(0.0);
+    if dry_mass <= 0.0 {
+        return f64::INFINITY;
+    }
+    isp_s.max(0.0) * STANDARD_GRAVITY * Float::ln(wet_mass / dry_mass)
+}
+
+impl World {
+    /// Give `body_id` a reserve of propellant to burn, or let it burn for
+    /// free again with `None`. The body carrying the ship interior uses the
+    /// interior's tanks instead.
+    pub fn set_propellant(
+        &mut self,
+        body_id: u64,
+        propellant: Option<Propellant>,
+    ) -> error::Result<()> {
+        if self.body(body_id).is_none() {
+            return Err(GgwError::UnknownBody(body_id));
+        }
+        match propellant {
+            Some(propellant) => self.propellant.insert(body_id, propellant),
+            None => self.propellant.remove(&body_id),
+        };
+        Ok(())
+    }
+
+    /// Propellant `body_id` has left, or `None` if its burns are free.
+    pub fn propellant(&self, body_id: u64) -> Option<Propellant> {
+        if self.interior_body == Some(body_id) {
+            return Some(self.interior.ship.tank_propellant());
+        }
+        self.propellant.get(&body_id).copied()
+    }
+
+    /// `event` as far as the body can afford it, and the propellant that
+    /// takes.
+    pub(crate) fn afford_burn(
+        &self,
+        event: &ThrustEvent,
+    ) -> error::Result<(ThrustEvent, Propellant)> {
+        let body = self
+            .body(event.body_id)
+            .ok_or(GgwError::UnknownBody(event.body_id))?;
+        let available = match self.propellant(event.body_id) {
+            Some(available) => available.kg(event.thrust_type),
+            None => return Ok((event.clone(), Propellant::default())),
+        };
+        let isp = isp_s(&self.config.propulsion, event.thrust_type);
+        let delta_v = event.delta_v.length();
+        let needed = propellant_for(delta_v, body.mass, isp);
+        if needed <= available {
+            return Ok((event.clone(), Propellant::only(event.thrust_type, needed)));
+        }
+        let truncate = self.config.propulsion.shortfall == BurnShortfall::Truncate;
+        if !truncate || available <= 0.0 {
+            return Err(GgwError::InsufficientPropellant {
+                body_id: event.body_id,
+                needed_kg: needed,
+                available_kg: available,
+            });
+        }
+        let used = Propellant::only(event.thrust_type, available);
+        let mut event = event.clone();
+        event.delta_v *= delta_v_for(available, body.mass, isp) / delta_v;
+        Ok((event, used))
+    }
+
+    /// Take `used` from wherever `body_id` draws its propellant.
+    pub(crate) fn spend_propellant(&mut self, body_id: u64, used: Propellant) {
+        if self.interior_body == Some(body_id) {
+            self.interior.ship.drain_tanks(used);
+        } else if let Some(reserve) = self.propellant.get_mut(&body_id) {
+            reserve.fuel_kg = (reserve.fuel_kg - used.fuel_kg).max(0.0);
+            reserve.xenon_kg = (reserve.xenon_kg - used.xenon_kg).max(0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{BodyState, Vec2};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn burn(body_id: u64, delta_v: f64, thrust_type: ThrustType) -> ThrustEvent {
+        ThrustEvent {
+            body_id,
+            time: 0.0,
+            delta_v: Vec2::new(0.0, delta_v),
+            thrust_type,
+        }
+    }
+
+    #[test]
+    fn rocket_equation_round_trips() {
+        let used = propellant_for(500.0, 1_000.0, 320.0);
+        assert!(used > 140.0 && used < 150.0);
+        assert!((delta_v_for(used, 1_000.0, 320.0) - 500.0).abs() < 1e-9);
+        assert_eq!(delta_v_for(1_000.0, 1_000.0, 320.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn burns_cost_propellant_and_mass() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let free = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let tanked = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let reserve = Propellant {
+            fuel_kg: 50.0,
+            xenon_kg: 5.0,
+        };
+        world.set_propellant(tanked, Some(reserve)).unwrap();
+
+        world
+            .apply_thrust_event(&burn(free, 100.0, ThrustType::Chemical))
+            .unwrap();
+        assert_eq!(world.body(free).unwrap().mass, 1_000.0);
+        assert_eq!(world.propellant(free), None);
+
+        world
+            .apply_thrust_event(&burn(tanked, 100.0, ThrustType::Chemical))
+            .unwrap();
+        let used = propellant_for(100.0, 1_000.0, 320.0);
+        let left = world.propellant(tanked).unwrap();
+        assert!((left.fuel_kg - (50.0 - used)).abs() < 1e-9);
+        assert_eq!(left.xenon_kg, 5.0);
+        assert!((world.body(tanked).unwrap().mass - (1_000.0 - used)).abs() < 1e-9);
+
+        // Too much for what is left: turned down, with nothing spent.
+        assert!(matches!(
+            world.apply_thrust_event(&burn(tanked, 500.0, ThrustType::Chemical)),
+            Err(GgwError::InsufficientPropellant { .. })
+        ));
+        assert_eq!(world.propellant(tanked), Some(left));
+
+        // Or cut short to what the tank holds.
+        world.config.propulsion.shortfall = BurnShortfall::Truncate;
+        let (short, spent) = world
+            .afford_burn(&burn(tanked, 500.0, ThrustType::Chemical))
+            .unwrap();
+        assert!(short.delta_v.y > 50.0 && short.delta_v.y < 100.0);
+        assert_eq!(spent.fuel_kg, left.fuel_kg);
+        world
+            .apply_thrust_event(&burn(tanked, 500.0, ThrustType::Chemical))
+            .unwrap();
+        assert_eq!(world.propellant(tanked).unwrap().fuel_kg, 0.0);
+
+        // Ion engines run on xenon, at a tenth of the cost.
+        world
+            .apply_thrust_event(&burn(tanked, 100.0, ThrustType::Ion))
+            .unwrap();
+        assert!(world.propellant(tanked).unwrap().xenon_kg > 1.0);
+    }
+
+    #[test]
+    fn the_interior_ship_burns_from_its_tanks() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let id = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        world.set_interior_body(Some(id)).unwrap();
+        let full = world.propellant(id).unwrap();
+        assert_eq!(full, world.interior.ship.tank_propellant());
+        assert!(full.fuel_kg > 0.0);
+
+        world
+            .apply_thrust_event(&burn(id, 50.0, ThrustType::Rcs))
+            .unwrap();
+        let used = propellant_for(50.0, 1_000.0, 70.0);
+        let left = world.interior.ship.tank_propellant();
+        assert!((left.fuel_kg - (full.fuel_kg - used)).abs() < 1e-3);
+    }
+}
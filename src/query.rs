@@ -0,0 +1,188 @@
+//! Filter adapters for iterators over bodies, so callers can chain
+//! `world.ships().within(center, range).excluding(own_id)` instead of
+//! spelling out the closures each time.
+//!
+//! `World::bodies_within` and `World::nearest_bodies` answer the two
+//! commonest of those questions about the whole world without visiting
+//! every body, by bucketing the bodies into the same grid the collision
+//! broad phase uses.
+
+use alloc::vec::Vec;
+
+use crate::collision::Grid;
+use crate::{BodyState, BodyType, Vec2, World};
+
+pub trait BodyIterExt<'a>: Iterator<Item = &'a BodyState> + Sized {
+    fn of_type(self, body_type: BodyType) -> impl Iterator<Item = &'a BodyState> {
+        self.filter(move |body| body.body_type == body_type)
+    }
+
+    fn excluding(self, id: u64) -> impl Iterator<Item = &'a BodyState> {
+        self.filter(move |body| body.id != id)
+    }
+
+    /// Bodies whose extent reaches within `range` of `center`.
+    fn within(self, center: Vec2, range: f64) -> impl Iterator<Item = &'a BodyState> {
+        self.filter(move |body| body.position.distance(center) <= range + body.radius)
+    }
+
+    /// The body closest to `point`, measured centre to centre.
+    fn nearest_to(self, point: Vec2) -> Option<&'a BodyState> {
+        self.min_by(|a, b| {
+            a.position
+                .distance(point)
+                .total_cmp(&b.position.distance(point))
+        })
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a BodyState>> BodyIterExt<'a> for I {}
+
+impl World {
+    /// Bodies whose extent reaches within `radius` of `center`, in world
+    /// order; the same ones as `bodies.iter().within(center, radius)`.
+    pub fn bodies_within(&self, center: Vec2, radius: f64) -> Vec<&BodyState> {
+        let bounds = self.current_bounds();
+        let mut cells = Vec::new();
+        let grid = Grid::build(&bounds, &mut cells);
+        let reach = Vec2::new(radius, radius);
+        grid.touching(center - reach, center + reach)
+            .into_iter()
+            .map(|idx| &self.bodies[idx])
+            .within(center, radius)
+            .collect()
+    }
+
+    /// The `n` bodies closest to `center`, measured centre to centre,
+    /// nearest first and in world order between equals. Fewer if the world
+    /// holds fewer.
+    pub fn nearest_bodies(&self, center: Vec2, n: usize) -> Vec<&BodyState> {
+        if n == 0 || !(center.x.is_finite() && center.y.is_finite()) {
+            return Vec::new();
+        }
+        let bounds = self.current_bounds();
+        let mut cells = Vec::new();
+        let grid = Grid::build(&bounds, &mut cells);
+        // Look ever further out until `n` bodies turn up within reach, as
+        // any body not yet looked at is further away than that.
+        let mut reach = grid.size();
+        loop {
+            let corner = Vec2::new(reach, reach);
+            let (min, max) = (center - corner, center + corner);
+            let everything = grid.covered_by(min, max);
+            let mut found: Vec<&BodyState> = grid
+                .touching(min, max)
+                .into_iter()
+                .map(|idx| &self.bodies[idx])
+                .filter(|body| everything || body.position.distance(center) <= reach)
+                .collect();
+            if found.len() >= n || everything {
+                found.sort_by(|a, b| {
+                    a.position
+                        .distance(center)
+                        .total_cmp(&b.position.distance(center))
+                });
+                found.truncate(n);
+                return found;
+            }
+            reach *= 2.0;
+        }
+    }
+
+    /// Every body's extent where it is now, in `bodies` order.
+    fn current_bounds(&self) -> Vec<Option<(Vec2, Vec2)>> {
+        self.bodies
+            .iter()
+            .map(|body| {
+                let extent = Vec2::new(body.radius, body.radius);
+                Some((body.position - extent, body.position + extent))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::GameConfig, World};
+    use alloc::vec::Vec;
+
+    fn body(radius_m: f64, body_type: BodyType) -> BodyState {
+        BodyState {
+            body_type,
+            ..BodyState::circular(radius_m, 0.0)
+        }
+    }
+
+    #[test]
+    fn adapters_chain_over_world_bodies() {
+        let mut world = World::new(3.986004418e14, GameConfig::default());
+        let ship = world.add_body(body(7_000_000.0, BodyType::Ship)).unwrap();
+        let near = world.add_body(body(7_001_000.0, BodyType::Ship)).unwrap();
+        let far = world.add_body(body(9_000_000.0, BodyType::Ship)).unwrap();
+        world.add_body(body(7_000_500.0, BodyType::Debris)).unwrap();
+
+        let center = world.body(ship).unwrap().position;
+        let nearby: Vec<u64> = world
+            .ships()
+            .within(center, 10_000.0)
+            .excluding(ship)
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(nearby, [near]);
+
+        let farthest_point = world.body(far).unwrap().position;
+        let nearest = world
+            .bodies
+            .iter()
+            .excluding(far)
+            .nearest_to(farthest_point);
+        assert_eq!(nearest.map(|b| b.id), Some(near));
+        assert_eq!(world.bodies.iter().of_type(BodyType::Debris).count(), 1);
+    }
+
+    #[test]
+    fn spatial_queries_match_a_full_scan() {
+        let mut world = World::new(3.986004418e14, GameConfig::default());
+        for i in 0..40 {
+            let mut rock = body(7_000_000.0 + 997.0 * i as f64, BodyType::Asteroid);
+            rock.orbit.mean_anomaly_at_epoch = 0.0003 * (i % 7) as f64;
+            rock.radius = 1.0 + (i % 5) as f64 * 20.0;
+            world.add_body(rock).unwrap();
+        }
+        let center = world.bodies[3].position + Vec2::new(150.0, -40.0);
+
+        for radius in [0.0, 500.0, 3_000.0, 50_000.0] {
+            let expected: Vec<u64> = world
+                .bodies
+                .iter()
+                .within(center, radius)
+                .map(|b| b.id)
+                .collect();
+            let found: Vec<u64> = world
+                .bodies_within(center, radius)
+                .iter()
+                .map(|b| b.id)
+                .collect();
+            assert_eq!(found, expected, "radius {radius}");
+        }
+
+        let mut by_distance: Vec<&BodyState> = world.bodies.iter().collect();
+        by_distance.sort_by(|a, b| {
+            a.position
+                .distance(center)
+                .total_cmp(&b.position.distance(center))
+        });
+        for n in [0, 1, 5, 40, 100] {
+            let expected: Vec<u64> = by_distance.iter().take(n).map(|b| b.id).collect();
+            let found: Vec<u64> = world
+                .nearest_bodies(center, n)
+                .iter()
+                .map(|b| b.id)
+                .collect();
+            assert_eq!(found, expected, "n {n}");
+        }
+        let nearest = world.bodies.iter().nearest_to(center).unwrap().id;
+        assert_eq!(world.nearest_bodies(center, 1)[0].id, nearest);
+    }
+}
@@ -0,0 +1,105 @@
+//! Burning up on reentry.
+//!
+//! A body orbiting the planet whose periapsis dips below
+//! `config::ReentryConfig::altitude_m` heats up on every pass through the
+//! atmosphere, harder the deeper it goes. The damage adds up across passes
+//! and never cools off; once it reaches 1 the body is destroyed with a
+//! `DespawnReason::ReentryBurnup`, usually well before it would have hit
+//! the surface. Heating is summed over slices of at most `REENTRY_STEP_S`,
+//! so a pass costs the same under time warp as it does stepped. Moons have
+//! no atmosphere.
+
+use crate::config::ReentryConfig;
+use crate::{orbit_to_cartesian, OrbitState};
+
+/// Longest slice heating is summed over at once.
+pub const REENTRY_STEP_S: f64 = 1.0;
+
+/// Whether a body on `orbit` around a planet of `planet_radius` ever
+/// reaches the atmosphere.
+pub fn dips_into_atmosphere(
+    orbit: &OrbitState,
+    planet_radius: f64,
+    config: &ReentryConfig,
+) -> bool {
+    let periapsis = orbit.semi_major_axis * (1.0 - orbit.eccentricity);
+    config.enabled && periapsis < planet_radius + config.altitude_m
+}
+
+/// Heat damage taken by a body on `orbit` between `from` and `to`.
+pub fn heating(
+    orbit: &OrbitState,
+    mu: f64,
+    planet_radius: f64,
+    config: &ReentryConfig,
+    from: f64,
+    to: f64,
+) -> f64 {
+    if !dips_into_atmosphere(orbit, planet_radius, config) || config.altitude_m <= 0.0 {
+        return 0.0;
+    }
+    let mut damage = 0.0;
+    let mut time = from;
+    while time < to {
+        let step = (to - time).min(REENTRY_STEP_S);
+        // Sampled mid-slice, which also keeps a slice ending exactly on
+        // impact out of the planet.
+        if let Ok((position, _)) = orbit_to_cartesian(orbit, mu, time + 0.5 * step) {
+            let depth =
+                (1.0 - (position.length() - planet_radius) / config.altitude_m).clamp(0.0, 1.0);
+            damage += config.heating_rate_per_s.max(0.0) * depth * depth * step;
+        }
+        time += step;
+    }
+    damage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{BodyState, DespawnReason, Seconds, World, PLANET_RADIUS_M};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn skimming(periapsis_altitude: f64) -> BodyState {
+        let periapsis = PLANET_RADIUS_M + periapsis_altitude;
+        let apoapsis = PLANET_RADIUS_M + 400_000.0;
+        BodyState::orbiting(OrbitState {
+            semi_major_axis: 0.5 * (periapsis + apoapsis),
+            eccentricity: (apoapsis - periapsis) / (apoapsis + periapsis),
+            arg_of_periapsis: 0.0,
+            // Half an orbit from periapsis.
+            mean_anomaly_at_epoch: core::f64::consts::PI,
+            epoch: 0.0,
+        })
+    }
+
+    #[test]
+    fn deep_passes_burn_up_and_shallow_ones_wear_down() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let deep = world.add_body(skimming(20_000.0)).unwrap();
+        let shallow = world.add_body(skimming(90_000.0)).unwrap();
+        let high = world.add_body(skimming(150_000.0)).unwrap();
+
+        // Through the first periapsis pass, warped.
+        world.warp_to(Seconds(3_000.0));
+        assert!(world.body(deep).is_none());
+        let event = world.drain_despawn_events().pop().unwrap();
+        assert_eq!(
+            (event.id, event.reason),
+            (deep, DespawnReason::ReentryBurnup)
+        );
+        let worn = world.reentry_damage(shallow);
+        assert!(worn > 0.0 && worn < 1.0, "{worn}");
+        assert_eq!(world.reentry_damage(high), 0.0);
+
+        // The same pass stepped heats the same.
+        let mut stepped = World::new(MU_EARTH, GameConfig::default());
+        let shallow = stepped.add_body(skimming(90_000.0)).unwrap();
+        for _ in 0..3_000 {
+            stepped.step(Seconds(1.0));
+        }
+        assert!((stepped.reentry_damage(shallow) - worn).abs() < 1e-3 * worn);
+    }
+}
@@ -0,0 +1,283 @@
+//! Recording a run and playing it back.
+//!
+//! A `Recorder` takes over a `World`, keeps a copy of it as it started,
+//! and logs every `WorldCommand` it carries out, steps included. The
+//! simulation is deterministic, so `World::replay` running the same
+//! commands on the same starting state ends on the same state, bit for bit;
+//! `Recording::final_state_hash` checks that it did. A recording can go
+//! into a bug report whole, or be cut short with `Recording::truncate` to
+//! watch the lead-up to some moment again.
+
+use alloc::vec::Vec;
+
+use crate::autopilot::Autopilot;
+use crate::collision::CollisionFilter;
+use crate::interior::InteriorCommand;
+use crate::{
+    error, BodyState, GgwError, ManeuverNode, Meters, Radians, Seconds, ThrustEvent, TorqueEvent,
+    World,
+};
+
+/// Something done to a world, as a `Recorder` logs it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WorldCommand {
+    Step(Seconds),
+    WarpTo(Seconds),
+    AddBody(BodyState),
+    Thrust(ThrustEvent),
+    ScheduleThrust(ThrustEvent),
+    Torque(TorqueEvent),
+    PlanNode(ManeuverNode),
+    SetAttitudeTarget {
+        body_id: u64,
+        heading: Option<Radians>,
+    },
+    SetAutopilot {
+        body_id: u64,
+        autopilot: Option<Autopilot>,
+    },
+    TrackConjunctions {
+        body_id: u64,
+        track: bool,
+    },
+    Launch {
+        body_id: u64,
+        heading: f64,
+        target_apoapsis: Meters,
+    },
+    ApplyDamage {
+        body_id: u64,
+        amount: f64,
+    },
+    SetMaxHp {
+        body_id: u64,
+        max_hp: f64,
+    },
+    SendMessage {
+        from: u64,
+        to: u64,
+        payload: Vec<u8>,
+    },
+    SetCollisionFilter {
+        body_id: u64,
+        filter: Option<CollisionFilter>,
+    },
+    Dock {
+        host: u64,
+        passenger: u64,
+    },
+    Undock {
+        host: u64,
+        passenger: u64,
+    },
+    Interior(InteriorCommand),
+}
+
+impl WorldCommand {
+    /// Carry the command out on `world`.
+    pub fn apply(&self, world: &mut World) -> error::Result<()> {
+        match self {
+            WorldCommand::Step(dt) => world.step(*dt),
+            WorldCommand::WarpTo(time) => world.warp_to(*time),
+            WorldCommand::AddBody(body) => {
+                world.add_body(body.clone())?;
+            }
+            WorldCommand::Thrust(event) => world.apply_thrust_event(event)?,
+            WorldCommand::ScheduleThrust(event) => world.schedule_thrust(event.clone())?,
+            WorldCommand::Torque(event) => world.apply_torque_event(event)?,
+            WorldCommand::PlanNode(node) => world.plan_node(*node)?,
+            WorldCommand::SetAttitudeTarget { body_id, heading } => {
+                world.set_attitude_target(*body_id, *heading)?
+            }
+            WorldCommand::SetAutopilot { body_id, autopilot } => {
+                world.set_autopilot(*body_id, *autopilot)?
+            }
+            WorldCommand::TrackConjunctions { body_id, track } => {
+                world.track_conjunctions(*body_id, *track)?
+            }
+            WorldCommand::Launch {
+                body_id,
+                heading,
+                target_apoapsis,
+            } => world.launch(*body_id, *heading, *target_apoapsis)?,
+            WorldCommand::ApplyDamage { body_id, amount } => {
+                world.apply_damage(*body_id, *amount).map(drop)?
+            }
+            WorldCommand::SetMaxHp { body_id, max_hp } => world.set_max_hp(*body_id, *max_hp)?,
+            WorldCommand::SendMessage { from, to, payload } => {
+                world.send_message(*from, *to, payload.clone()).map(drop)?
+            }
+            WorldCommand::SetCollisionFilter { body_id, filter } => {
+                world.set_collision_filter(*body_id, *filter)?
+            }
+            WorldCommand::Dock { host, passenger } => world.dock(*host, *passenger)?,
+            WorldCommand::Undock { host, passenger } => world.undock(*host, *passenger)?,
+            WorldCommand::Interior(command) => world.interior.queue_command(command.clone()),
+        }
+        Ok(())
+    }
+}
+
+/// A world as it started and everything done to it since.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recording {
+    pub initial: World,
+    /// Every command in the order it was carried out, failed ones
+    /// included, since failing can't have changed anything.
+    pub commands: Vec<WorldCommand>,
+    /// `state_hash` of the world after the last command.
+    pub final_state_hash: u64,
+}
+
+impl Recording {
+    /// Keep only the first `len` commands, for playing back part of a run.
+    /// The final hash can't be known without playing them, so it is taken
+    /// from a replay.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.commands.len() {
+            return;
+        }
+        self.commands.truncate(len);
+        let mut world = self.initial.clone();
+        for command in &self.commands {
+            let _ = command.apply(&mut world);
+        }
+        self.final_state_hash = world.state_hash();
+    }
+}
+
+/// Runs a world while logging everything done to it.
+pub struct Recorder {
+    world: World,
+    recording: Recording,
+}
+
+impl Recorder {
+    /// Start recording `world` from the state it is in now.
+    pub fn new(world: World) -> Self {
+        let recording = Recording {
+            initial: world.clone(),
+            commands: Vec::new(),
+            final_state_hash: world.state_hash(),
+        };
+        Self { world, recording }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Carry out `command` on the world and log it.
+    pub fn execute(&mut self, command: WorldCommand) -> error::Result<()> {
+        let result = command.apply(&mut self.world);
+        self.recording.commands.push(command);
+        self.recording.final_state_hash = self.world.state_hash();
+        result
+    }
+
+    /// The run so far.
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    /// Stop recording, handing back the world and the run.
+    pub fn finish(self) -> (World, Recording) {
+        (self.world, self.recording)
+    }
+}
+
+impl World {
+    /// Play `recording` back from its starting state. Fails with
+    /// `ReplayDiverged` if it ends anywhere but where the recorded run did,
+    /// say because the recording came from a build that steps differently.
+    pub fn replay(recording: &Recording) -> error::Result<World> {
+        let mut world = recording.initial.clone();
+        for command in &recording.commands {
+            // Failures happen again just as they did when recorded.
+            let _ = command.apply(&mut world);
+        }
+        let hash = world.state_hash();
+        if hash != recording.final_state_hash {
+            return Err(GgwError::ReplayDiverged {
+                expected: recording.final_state_hash,
+                actual: hash,
+            });
+        }
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::interior::DeviceAction;
+    use crate::{ThrustType, Vec2};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn replays_end_where_the_run_did() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let mut recorder = Recorder::new(world);
+        let device_id = recorder.world().interior.ship.devices[0].id;
+        let commands = [
+            WorldCommand::AddBody(BodyState::circular(7_000_000.0, 1.0)),
+            WorldCommand::Step(Seconds(1.0)),
+            WorldCommand::Interior(InteriorCommand::DeviceAction {
+                device_id,
+                action: DeviceAction::Toggle,
+            }),
+            WorldCommand::Interior(InteriorCommand::MovePawn { dx: 1, dy: 0 }),
+            WorldCommand::Thrust(ThrustEvent {
+                body_id: ship,
+                time: 1.0,
+                delta_v: Vec2::new(0.0, 5.0),
+                thrust_type: ThrustType::Chemical,
+            }),
+            WorldCommand::SetAttitudeTarget {
+                body_id: ship,
+                heading: Some(Radians(1.0)),
+            },
+            WorldCommand::ScheduleThrust(ThrustEvent {
+                body_id: ship,
+                time: 2.5,
+                delta_v: Vec2::new(0.0, -5.0),
+                thrust_type: ThrustType::Rcs,
+            }),
+            WorldCommand::Step(Seconds(2.0)),
+            WorldCommand::WarpTo(Seconds(600.0)),
+        ];
+        for command in commands {
+            recorder.execute(command).unwrap();
+        }
+        assert_eq!(
+            recorder.execute(WorldCommand::SetAttitudeTarget {
+                body_id: 99,
+                heading: None,
+            }),
+            Err(GgwError::UnknownBody(99))
+        );
+        recorder.execute(WorldCommand::Step(Seconds(1.0))).unwrap();
+        let (world, mut recording) = recorder.finish();
+
+        let replayed = World::replay(&recording).unwrap();
+        assert_eq!(replayed.state_hash(), world.state_hash());
+        assert_eq!(replayed.sim_time(), world.sim_time());
+
+        // Cut short, it stops just after the first two steps.
+        recording.truncate(8);
+        assert_eq!(World::replay(&recording).unwrap().sim_time(), Seconds(3.0));
+
+        recording.final_state_hash ^= 1;
+        assert!(matches!(
+            World::replay(&recording),
+            Err(GgwError::ReplayDiverged { .. })
+        ));
+    }
+}
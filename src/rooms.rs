@@ -0,0 +1,436 @@
+//! Two-level atmosphere solver for `AtmosSolver::Rooms`.
+//!
+//! Tiles are grouped into rooms: the regions of floor walled off from each
+//! other, with every door tile a room of its own. Each atmosphere tick:
+//!
+//! - a room whose tiles all hold (nearly) the same gas is settled and
+//!   flattened to its mean, so it costs one pass over its tiles;
+//! - any other room is disturbed (a pawn breathing, a vent, gas arriving
+//!   through a door) and diffuses tile by tile like the `Tiles` solver,
+//!   but only between tiles of the same room;
+//! - every `ROOM_EXCHANGE_INTERVAL` ticks, neighbouring rooms exchange gas
+//!   in proportion to the difference of their mean contents, across each
+//!   tile edge they share. Closed doors pass nothing.
+//!
+//! Gas is conserved: flattening keeps a room's total, and every exchange
+//! takes from one room what it gives the other.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::budget::WorkBudget;
+use crate::interior::{ShipInterior, TileAtmosphere, TileType};
+
+/// Atmosphere ticks between exchanges across room boundaries.
+pub const ROOM_EXCHANGE_INTERVAL: u32 = 4;
+
+/// A room counts as settled while every tile is within this many kg of the
+/// room mean for each gas.
+pub const SETTLED_TOLERANCE_KG: f32 = 1e-5;
+
+/// The rooms of a ship, derived from its tile types.
+#[derive(Clone, Debug, Default)]
+pub struct RoomLayout {
+    room_of: Vec<Option<u32>>,
+    rooms: Vec<Vec<usize>>,
+    /// Neighbouring tile pairs that lie in different rooms.
+    links: Vec<(usize, usize)>,
+    /// Number of `links` touching each room.
+    link_counts: Vec<u32>,
+}
+
+impl RoomLayout {
+    pub fn build(ship: &ShipInterior) -> Self {
+        LayoutBuild::new(ship)
+            .advance(ship, &mut WorkBudget::unlimited())
+            .expect("an unlimited budget finishes the build")
+    }
+
+    pub fn room_count(&self) -> usize {
+        self.rooms.len()
+    }
+
+    /// Room of the tile at index `idx`, or `None` if it holds no gas.
+    pub fn room_of(&self, idx: usize) -> Option<u32> {
+        self.room_of.get(idx).copied().flatten()
+    }
+
+    pub fn room_tiles(&self, room: u32) -> &[usize] {
+        &self.rooms[room as usize]
+    }
+
+    fn tile_count(&self) -> usize {
+        self.room_of.len()
+    }
+}
+
+/// A `RoomLayout` being built a budget's worth at a time: a flood fill over
+/// the tiles, then one pass to find the links between rooms.
+#[derive(Clone, Debug)]
+struct LayoutBuild {
+    room_of: Vec<Option<u32>>,
+    rooms: Vec<Vec<usize>>,
+    /// Next tile of the last room to expand.
+    frontier: usize,
+    /// Next tile to try as the start of a new room.
+    next_start: usize,
+}
+
+impl LayoutBuild {
+    fn new(ship: &ShipInterior) -> Self {
+        Self {
+            room_of: vec![None; ship.tiles.len()],
+            rooms: Vec::new(),
+            frontier: 0,
+            next_start: 0,
+        }
+    }
+
+    /// Continue the build, one work unit per tile and one per tile again
+    /// for the links. Returns the layout once it is complete.
+    fn advance(&mut self, ship: &ShipInterior, budget: &mut WorkBudget) -> Option<RoomLayout> {
+        let (width, height) = (ship.width as i32, ship.height as i32);
+        let tile_type = |idx: usize| ship.tiles[idx].tile_type;
+        loop {
+            let room = self.rooms.len().saturating_sub(1) as u32;
+            if let Some(tiles) = self.rooms.last_mut() {
+                while self.frontier < tiles.len() {
+                    if !budget.try_spend(1) {
+                        return None;
+                    }
+                    let idx = tiles[self.frontier];
+                    self.frontier += 1;
+                    if is_door(tile_type(idx)) {
+                        continue;
+                    }
+                    let (x, y) = ((idx as i32) % width, (idx as i32) / width);
+                    for (dx, dy) in ALL_NEIGHBORS {
+                        let (nx, ny) = (x + dx, y + dy);
+                        if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                            continue;
+                        }
+                        let n = (ny * width + nx) as usize;
+                        let kind = tile_type(n);
+                        if self.room_of[n].is_none()
+                            && ShipInterior::tile_supports_atmos(kind)
+                            && !is_door(kind)
+                        {
+                            self.room_of[n] = Some(room);
+                            tiles.push(n);
+                        }
+                    }
+                }
+            }
+            while self.next_start < self.room_of.len()
+                && (self.room_of[self.next_start].is_some()
+                    || !ShipInterior::tile_supports_atmos(tile_type(self.next_start)))
+            {
+                self.next_start += 1;
+            }
+            if self.next_start == self.room_of.len() {
+                break;
+            }
+            self.room_of[self.next_start] = Some(self.rooms.len() as u32);
+            self.rooms.push(vec![self.next_start]);
+            self.frontier = 0;
+        }
+
+        if !budget.try_spend(self.room_of.len() as u64) {
+            return None;
+        }
+        let mut links = Vec::new();
+        let mut link_counts = vec![0; self.rooms.len()];
+        ship.for_each_atmos_pair(|a, b| {
+            if let (Some(room_a), Some(room_b)) = (self.room_of[a], self.room_of[b]) {
+                if room_a != room_b {
+                    links.push((a, b));
+                    link_counts[room_a as usize] += 1;
+                    link_counts[room_b as usize] += 1;
+                }
+            }
+        });
+        Some(RoomLayout {
+            room_of: core::mem::take(&mut self.room_of),
+            rooms: core::mem::take(&mut self.rooms),
+            links,
+            link_counts,
+        })
+    }
+}
+
+/// Per-ship state of the room solver. Rebuilt from the tiles whenever they
+/// change, so it is never saved.
+#[derive(Clone, Debug, Default)]
+pub struct RoomSolver {
+    layout: Option<RoomLayout>,
+    build: Option<LayoutBuild>,
+    disturbed: Vec<bool>,
+    ticks: u32,
+}
+
+impl RoomSolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the room layout; the next tick rebuilds it.
+    pub fn invalidate(&mut self) {
+        self.layout = None;
+        self.build = None;
+    }
+
+    /// Whether the layout matches `ship`, so a tick needs no rebuild.
+    pub fn is_ready(&self, ship: &ShipInterior) -> bool {
+        self.layout
+            .as_ref()
+            .is_some_and(|layout| layout.tile_count() == ship.tiles.len())
+    }
+
+    pub fn layout(&self) -> Option<&RoomLayout> {
+        self.layout.as_ref()
+    }
+
+    /// Whether `room` needed tile-level diffusion on the last tick.
+    pub fn is_disturbed(&self, room: u32) -> bool {
+        self.disturbed.get(room as usize).copied().unwrap_or(false)
+    }
+
+    /// Bring the layout up to date with `ship` if `budget` allows,
+    /// picking up where an earlier call stopped. Returns whether it is ready.
+    pub(crate) fn rebuild(&mut self, ship: &ShipInterior, budget: &mut WorkBudget) -> bool {
+        if self.is_ready(ship) {
+            return true;
+        }
+        let build = match &mut self.build {
+            Some(build) if build.room_of.len() == ship.tiles.len() => build,
+            build => build.insert(LayoutBuild::new(ship)),
+        };
+        self.layout = build.advance(ship, budget);
+        if self.layout.is_some() {
+            self.build = None;
+        }
+        self.layout.is_some()
+    }
+}
+
+/// Flatten settled rooms and record in `disturbed` which rooms aren't.
+fn settle(layout: &RoomLayout, atmos: &mut [TileAtmosphere], disturbed: &mut Vec<bool>) {
+    disturbed.clear();
+    for tiles in &layout.rooms {
+        let mean = mean_gases(tiles, atmos);
+        let settled = tiles.iter().all(|&idx| {
+            let gases = gases(&atmos[idx]);
+            (0..3).all(|gas| (gases[gas] - mean[gas]).abs() <= SETTLED_TOLERANCE_KG)
+        });
+        if settled {
+            for &idx in tiles {
+                set_gases(&mut atmos[idx], mean);
+            }
+        }
+        disturbed.push(!settled);
+    }
+}
+
+/// Move gas across room boundaries. Each shared edge carries a share of
+/// the difference between the two rooms' means; it is taken from and added
+/// to the edge tiles of disturbed rooms, and spread evenly over settled
+/// ones so they stay flat. The share shrinks with the number of edges
+/// against the smaller room's size, so a one-tile door can't overshoot.
+fn exchange(layout: &RoomLayout, atmos: &mut [TileAtmosphere], disturbed: &[bool], factor: f32) {
+    let means: Vec<[f32; 3]> = layout
+        .rooms
+        .iter()
+        .map(|tiles| mean_gases(tiles, atmos))
+        .collect();
+    let mut room_deltas = vec![[0.0f32; 3]; layout.rooms.len()];
+    let mut tile_deltas: Vec<(usize, [f32; 3])> = Vec::new();
+    for &(a, b) in &layout.links {
+        let (Some(room_a), Some(room_b)) = (layout.room_of(a), layout.room_of(b)) else {
+            continue;
+        };
+        let (room_a, room_b) = (room_a as usize, room_b as usize);
+        let smaller = layout.rooms[room_a].len().min(layout.rooms[room_b].len());
+        let edges = layout.link_counts[room_a].max(layout.link_counts[room_b]);
+        let share = factor * (smaller as f32 / edges as f32).min(1.0);
+        let mut flow = [0.0f32; 3];
+        for gas in 0..3 {
+            flow[gas] = (means[room_b][gas] - means[room_a][gas]) * share;
+        }
+        for (room, tile, sign) in [(room_a, a, 1.0f32), (room_b, b, -1.0)] {
+            let signed = flow.map(|kg| kg * sign);
+            if disturbed[room] {
+                tile_deltas.push((tile, signed));
+            } else {
+                for gas in 0..3 {
+                    room_deltas[room][gas] += signed[gas];
+                }
+            }
+        }
+    }
+    for (tiles, delta) in layout.rooms.iter().zip(&room_deltas) {
+        let share = delta.map(|kg| kg / tiles.len() as f32);
+        for &idx in tiles {
+            add_gases(&mut atmos[idx], share);
+        }
+    }
+    for (idx, delta) in tile_deltas {
+        add_gases(&mut atmos[idx], delta);
+    }
+    for cell in atmos.iter_mut() {
+        cell.clamp_non_negative();
+    }
+}
+
+impl ShipInterior {
+    /// Spend up to `budget` bringing the room layout up to date. Returns
+    /// whether it is ready for `step_atmosphere_rooms`.
+    pub fn rebuild_rooms(&mut self, budget: &mut WorkBudget) -> bool {
+        let mut solver = core::mem::take(&mut self.room_solver);
+        let ready = solver.rebuild(self, budget);
+        self.room_solver = solver;
+        ready
+    }
+
+    /// One tick of the two-level solver; see the `rooms` module docs.
+    pub fn step_atmosphere_rooms(&mut self, dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+        let _span = tracing::trace_span!("atmosphere_rooms", dt).entered();
+        let mut solver = core::mem::take(&mut self.room_solver);
+        solver.rebuild(self, &mut WorkBudget::unlimited());
+        solver.ticks = solver.ticks.wrapping_add(1);
+        let RoomSolver {
+            layout: Some(layout),
+            disturbed,
+            ticks,
+            ..
+        } = &mut solver
+        else {
+            unreachable!("an unlimited rebuild always leaves a layout");
+        };
+        settle(layout, &mut self.tile_atmos, disturbed);
+
+        let factor = Self::diffusion_factor(dt);
+        if factor > 0.0 && disturbed.contains(&true) {
+            self.diffuse(factor, |a, b| {
+                match (layout.room_of(a), layout.room_of(b)) {
+                    (Some(room_a), Some(room_b)) => room_a == room_b && disturbed[room_a as usize],
+                    _ => false,
+                }
+            });
+        }
+        if *ticks % ROOM_EXCHANGE_INTERVAL == 0 {
+            let coarse = Self::diffusion_factor(dt * ROOM_EXCHANGE_INTERVAL as f32);
+            exchange(layout, &mut self.tile_atmos, disturbed, coarse);
+        }
+        self.room_solver = solver;
+    }
+}
+
+fn is_door(tile_type: TileType) -> bool {
+    matches!(tile_type, TileType::DoorOpen | TileType::DoorClosed)
+}
+
+const ALL_NEIGHBORS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+fn gases(cell: &TileAtmosphere) -> [f32; 3] {
+    [cell.o2_kg, cell.n2_kg, cell.co2_kg]
+}
+
+fn set_gases(cell: &mut TileAtmosphere, [o2, n2, co2]: [f32; 3]) {
+    cell.o2_kg = o2;
+    cell.n2_kg = n2;
+    cell.co2_kg = co2;
+}
+
+fn add_gases(cell: &mut TileAtmosphere, [o2, n2, co2]: [f32; 3]) {
+    cell.o2_kg += o2;
+    cell.n2_kg += n2;
+    cell.co2_kg += co2;
+}
+
+fn mean_gases(tiles: &[usize], atmos: &[TileAtmosphere]) -> [f32; 3] {
+    let mut total = [0.0f32; 3];
+    for &idx in tiles {
+        let cell = gases(&atmos[idx]);
+        for gas in 0..3 {
+            total[gas] += cell[gas];
+        }
+    }
+    total.map(|kg| kg / tiles.len().max(1) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+
+    /// The test ship split by a wall at x = 6 with a closed door at (6, 3),
+    /// and its outer door walled up.
+    fn two_rooms(config: &GameConfig) -> ShipInterior {
+        let mut ship = ShipInterior::new_test_layout(config);
+        let atmos = &config.atmosphere;
+        ship.set_tile_type(6, 7, TileType::Wall, atmos);
+        for y in 1..7 {
+            ship.set_tile_type(6, y, TileType::Wall, atmos);
+        }
+        ship.set_tile_type(6, 3, TileType::DoorClosed, atmos);
+        ship
+    }
+
+    fn room_spread(ship: &ShipInterior, room: u32) -> f32 {
+        let layout = ship.room_solver.layout().unwrap();
+        let co2 = layout
+            .room_tiles(room)
+            .iter()
+            .map(|&idx| ship.tile_atmos[idx].co2_kg);
+        co2.clone().fold(f32::MIN, f32::max) - co2.fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    fn rooms_diffuse_inside_and_exchange_through_open_doors() {
+        let config = GameConfig::default();
+        let dt = config.atmosphere.tick_interval_s;
+        let mut ship = two_rooms(&config);
+        let left = (4 * ship.width + 2) as usize;
+        let right = (4 * ship.width + 8) as usize;
+        ship.tile_atmos[left].co2_kg += 0.05;
+        let total = ship.total_atmos();
+        let right_co2 = ship.tile_atmos[right].co2_kg;
+
+        for _ in 0..160 {
+            ship.step_atmosphere_rooms(dt);
+        }
+        let layout = ship.room_solver.layout().unwrap();
+        assert_eq!(layout.room_count(), 3);
+        let (left_room, right_room) = (
+            layout.room_of(left).unwrap(),
+            layout.room_of(right).unwrap(),
+        );
+        // The burst spread through its own room but not past the door.
+        assert!(ship.tile_atmos[left].co2_kg < right_co2 + 0.01);
+        assert!(!ship.room_solver.is_disturbed(right_room));
+        assert_eq!(room_spread(&ship, right_room), 0.0);
+        assert!((ship.tile_atmos[right].co2_kg - right_co2).abs() < 1e-6);
+
+        ship.set_tile_type(6, 3, TileType::DoorOpen, &config.atmosphere);
+        for _ in 0..1600 {
+            ship.step_atmosphere_rooms(dt);
+        }
+        assert!(ship.tile_atmos[right].co2_kg > right_co2);
+        assert!(room_spread(&ship, left_room) < 1e-4);
+        let after = ship.total_atmos();
+        assert!((after.co2_kg - total.co2_kg).abs() < 1e-4);
+        assert!((after.o2_kg - total.o2_kg).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,285 @@
+//! Versioned saves of a whole `World`.
+//!
+//! `World::save` wraps the world in a `SaveGame` stamped with
+//! `SAVE_VERSION` and a hash of its config, ready for any serde format;
+//! `World::load` takes one back. Everything the simulation depends on is
+//! saved: bodies and their orbits, the clock, the id counter, moons, the
+//! ship interior with its tiles, atmosphere, devices and pawns, and every
+//! per-body table. Caches are left out and rebuilt on demand.
+//!
+//! A save from a different `SAVE_VERSION` is refused rather than read
+//! wrong, and so is one whose config no longer matches the hash it was
+//! saved with, which catches a config section edited by hand. Callers can
+//! compare `SaveGame::config_hash` with `config_hash` of their own config
+//! to tell whether a save was made under different settings.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use crate::config::{
+    AtmosSolver, AtmosphereConfig, AttitudeConfig, AutopilotConfig, AutosaveConfig, BlastConfig,
+    BurnShortfall, CollisionConfig, ConjunctionConfig, DamageConfig, DebrisConfig, DockingConfig,
+    FlybyConfig, GameConfig, GasConfig, InteriorConfig, ItemConfig, LaunchConfig, PowerConfig,
+    PropulsionConfig, ReentryConfig, ResourceConfig, SleepConfig, SunConfig, TankContentsConfig,
+};
+use crate::lockstep::StateHasher;
+use crate::{error, GgwError, World};
+
+/// Version of the save format. Bump it whenever a change to `World` would
+/// make older saves load as something they weren't.
+pub const SAVE_VERSION: u32 = 1;
+
+/// A world as saved, or `W = &World` on its way out.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SaveGame<W = World> {
+    pub version: u32,
+    /// `config_hash` of the world's config when it was saved.
+    pub config_hash: u64,
+    pub world: W,
+}
+
+/// Hash of every setting in `config`, stable across builds and platforms.
+pub fn config_hash(config: &GameConfig) -> u64 {
+    let mut hasher = StateHasher::new();
+    config.hash_setting(&mut hasher);
+    hasher.finish()
+}
+
+/// A setting, or a section of them, fed field by field into a
+/// `StateHasher`.
+trait HashSetting {
+    fn hash_setting(&self, hasher: &mut StateHasher);
+}
+
+impl HashSetting for f32 {
+    fn hash_setting(&self, hasher: &mut StateHasher) {
+        hasher.write_f32(*self);
+    }
+}
+
+impl HashSetting for f64 {
+    fn hash_setting(&self, hasher: &mut StateHasher) {
+        hasher.write_f64(*self);
+    }
+}
+
+impl HashSetting for u32 {
+    fn hash_setting(&self, hasher: &mut StateHasher) {
+        hasher.write_u32(*self);
+    }
+}
+
+impl HashSetting for u64 {
+    fn hash_setting(&self, hasher: &mut StateHasher) {
+        hasher.write_u64(*self);
+    }
+}
+
+impl HashSetting for bool {
+    fn hash_setting(&self, hasher: &mut StateHasher) {
+        hasher.write_bool(*self);
+    }
+}
+
+/// Length-prefixed, so neighbouring strings can't run into each other.
+impl HashSetting for String {
+    fn hash_setting(&self, hasher: &mut StateHasher) {
+        hasher.write_u64(self.len() as u64);
+        hasher.write_bytes(self.as_bytes());
+    }
+}
+
+impl<T: HashSetting> HashSetting for Option<T> {
+    fn hash_setting(&self, hasher: &mut StateHasher) {
+        hasher.write_bool(self.is_some());
+        if let Some(value) = self {
+            value.hash_setting(hasher);
+        }
+    }
+}
+
+impl<T: HashSetting> HashSetting for BTreeMap<String, T> {
+    fn hash_setting(&self, hasher: &mut StateHasher) {
+        hasher.write_u64(self.len() as u64);
+        for (key, value) in self {
+            key.hash_setting(hasher);
+            value.hash_setting(hasher);
+        }
+    }
+}
+
+impl HashSetting for AtmosSolver {
+    fn hash_setting(&self, hasher: &mut StateHasher) {
+        hasher.write_u32(*self as u32);
+    }
+}
+
+impl HashSetting for BurnShortfall {
+    fn hash_setting(&self, hasher: &mut StateHasher) {
+        hasher.write_u32(*self as u32);
+    }
+}
+
+/// Hashes each named field in turn. The pattern names every field, so a
+/// setting added to the struct but not here fails to compile rather than
+/// going unhashed.
+macro_rules! hash_fields {
+    ($($ty:ident { $($field:ident),* $(,)? })*) => {
+        $(
+            impl HashSetting for $ty {
+                fn hash_setting(&self, hasher: &mut StateHasher) {
+                    let $ty { $($field),* } = self;
+                    $($field.hash_setting(hasher);)*
+                }
+            }
+        )*
+    };
+}
+
+hash_fields! {
+    GameConfig {
+        atmosphere, items, resources, default_tank, power, autosave, sleep, interior, collision,
+        attitude, reentry, sun, propulsion, docking, autopilot, flyby, conjunction, launch, blast,
+        damage, debris,
+    }
+    AtmosphereConfig { tile_size_m, tile_height_m, baseline_temp_c, tick_interval_s, gases, solver }
+    GasConfig { display_name, molar_mass_kg_per_mol, default_mass_kg }
+    ItemConfig { display_name, idle_power_kw, online_power_kw, capacity_kg, flow_kg_per_s, gas_type }
+    ResourceConfig { density_kg_per_m3, abundance }
+    TankContentsConfig { o2_mass_kg, n2_mass_kg, co2_mass_kg, fuel_mass_kg, xenon_mass_kg }
+    PowerConfig {
+        reactor_output_kw, nav_station_kw, ship_computer_kw, transponder_kw, dispenser_kw,
+        food_generator_kw, light_kw, bed_kw, door_kw,
+    }
+    AutosaveConfig { enabled, directory, interval_s, slots }
+    SleepConfig { enabled, distance_m, propagation_interval_ticks }
+    InteriorConfig { work_budget }
+    CollisionConfig {
+        restitution, merge_speed_mps, fragmentation_speed_mps, fragment_count, fragment_spread,
+        max_landing_speed_mps,
+    }
+    AttitudeConfig {
+        max_angular_velocity_rad_s, max_angular_acceleration_rad_s2, proportional_gain,
+        derivative_gain,
+    }
+    ReentryConfig { enabled, altitude_m, heating_rate_per_s }
+    AutopilotConfig { interval_s, deadband_m, max_correction_mps, match_tolerance_mps }
+    FlybyConfig { min_altitude_m }
+    ConjunctionConfig { horizon_s, threshold_m, interval_s }
+    LaunchConfig { acceleration_mps2, vertical_rise_s }
+    BlastConfig { radius_m, damage, impulse_ns }
+    DamageConfig {
+        hp_per_kg, collision_hp_per_ns, debris_count, debris_speed_mps, asteroid_split_min_kg,
+        asteroid_split_speed_mps,
+    }
+    DebrisConfig { cascade_probability, min_fragment_kg, max_debris }
+    DockingConfig { capture_range_m, max_relative_speed_mps, separation_speed_mps }
+    PropulsionConfig { rcs_isp_s, chemical_isp_s, ion_isp_s, shortfall }
+    SunConfig { initial_angle_rad, rotation_rate_rad_s }
+}
+
+impl World {
+    /// This world, ready to serialize.
+    pub fn save(&self) -> SaveGame<&World> {
+        SaveGame {
+            version: SAVE_VERSION,
+            config_hash: config_hash(&self.config),
+            world: self,
+        }
+    }
+
+    /// The world in `save`. Fails with `UnsupportedSaveVersion` for a save
+    /// from another version of the format and with `SaveConfigMismatch`
+    /// if its config doesn't hash to what was saved.
+    pub fn load(save: SaveGame) -> error::Result<World> {
+        if save.version != SAVE_VERSION {
+            return Err(GgwError::UnsupportedSaveVersion(save.version));
+        }
+        if config_hash(&save.world.config) != save.config_hash {
+            return Err(GgwError::SaveConfigMismatch);
+        }
+        tracing::debug!(
+            time = save.world.sim_time().0,
+            bodies = save.world.bodies.len(),
+            "loaded world"
+        );
+        Ok(save.world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BodyState, OrbitState, Seconds};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn world() -> World {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        world
+            .add_body(BodyState::orbiting(OrbitState {
+                semi_major_axis: 7_000_000.0,
+                eccentricity: 0.01,
+                arg_of_periapsis: 0.0,
+                mean_anomaly_at_epoch: 0.0,
+                epoch: 0.0,
+            }))
+            .unwrap();
+        world.step(Seconds(2.0));
+        world
+    }
+
+    fn owned(world: World) -> SaveGame {
+        let SaveGame {
+            version,
+            config_hash,
+            ..
+        } = world.save();
+        SaveGame {
+            version,
+            config_hash,
+            world,
+        }
+    }
+
+    #[test]
+    fn saves_check_their_version_and_config() {
+        let expected = world().state_hash();
+        let save = owned(world());
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.config_hash, config_hash(&GameConfig::default()));
+        assert_eq!(World::load(save).unwrap().state_hash(), expected);
+
+        let mut save = owned(world());
+        save.version = SAVE_VERSION + 1;
+        assert_eq!(
+            World::load(save).err(),
+            Some(GgwError::UnsupportedSaveVersion(SAVE_VERSION + 1))
+        );
+
+        let mut save = owned(world());
+        save.world.config.atmosphere.baseline_temp_c += 1.0;
+        assert_eq!(World::load(save).err(), Some(GgwError::SaveConfigMismatch));
+    }
+
+    #[test]
+    fn config_hash_covers_names_as_well_as_numbers() {
+        let config = GameConfig::default();
+        let mut renamed = config.clone();
+        let (name, gas) = renamed.atmosphere.gases.pop_first().unwrap();
+        renamed.atmosphere.gases.insert(name + "_", gas);
+        assert_ne!(config_hash(&renamed), config_hash(&config));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn saves_round_trip_through_serde() {
+        let world = world();
+        let json = serde_json::to_string(&world.save()).unwrap();
+        let save: SaveGame = serde_json::from_str(&json).unwrap();
+        assert_eq!(save.config_hash, config_hash(&world.config));
+        assert_eq!(config_hash(&save.world.config), save.config_hash);
+        assert_eq!(World::load(save).unwrap().state_hash(), world.state_hash());
+    }
+}
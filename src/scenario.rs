@@ -0,0 +1,136 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::BodyState;
+
+/// Something a scenario asks the world to do at a scheduled time.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScenarioAction {
+    /// Add every body (e.g. a pirate wave). Bodies with `id == 0` are
+    /// assigned fresh ids as with `World::add_body`.
+    SpawnBodies(Vec<BodyState>),
+    /// Start (or extend) a solar storm lasting `duration_s`.
+    SolarStorm { duration_s: f64 },
+    /// Offer a contract to the players.
+    DeliverContract {
+        contract_id: u64,
+        description: String,
+    },
+}
+
+/// Record of a scenario action that has taken effect, kept until drained.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScenarioEvent {
+    BodiesSpawned {
+        time: f64,
+        body_ids: Vec<u64>,
+    },
+    SolarStormStarted {
+        time: f64,
+        until: f64,
+    },
+    SolarStormEnded {
+        time: f64,
+    },
+    ContractDelivered {
+        time: f64,
+        contract_id: u64,
+        description: String,
+    },
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ScheduledAction {
+    time: f64,
+    action: ScenarioAction,
+}
+
+/// Time-ordered list of pending scenario actions plus the effects that
+/// outlive a single tick. Actions scheduled for the same time fire in the
+/// order they were added.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timeline {
+    pending: Vec<ScheduledAction>,
+    fired: Vec<ScenarioEvent>,
+    solar_storm_until: Option<f64>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedule `action` at absolute simulation time `time`. An action at a
+    /// time that isn't finite would never come due, nor let any after it,
+    /// so it is dropped.
+    pub fn schedule(&mut self, time: f64, action: ScenarioAction) {
+        if !time.is_finite() {
+            tracing::warn!(time, "scenario action at a non-finite time dropped");
+            return;
+        }
+        let index = self.pending.partition_point(|entry| entry.time <= time);
+        self.pending.insert(index, ScheduledAction { time, action });
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Time of the earliest pending action or storm expiry.
+    pub fn next_time(&self) -> Option<f64> {
+        let next_action = self.pending.first().map(|entry| entry.time);
+        match (next_action, self.solar_storm_until) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    pub fn solar_storm_until(&self) -> Option<f64> {
+        self.solar_storm_until
+    }
+
+    pub fn solar_storm_active(&self, now: f64) -> bool {
+        self.solar_storm_until.is_some_and(|until| now < until)
+    }
+
+    /// Remove and return every action due at or before `now`.
+    pub(crate) fn take_due(&mut self, now: f64) -> Vec<(f64, ScenarioAction)> {
+        let due = self.pending.partition_point(|entry| entry.time <= now);
+        self.pending
+            .drain(..due)
+            .map(|entry| (entry.time, entry.action))
+            .collect()
+    }
+
+    pub(crate) fn start_solar_storm(&mut self, time: f64, duration_s: f64) {
+        let until = self
+            .solar_storm_until
+            .map_or(time + duration_s, |current| current.max(time + duration_s));
+        self.solar_storm_until = Some(until);
+        self.fired
+            .push(ScenarioEvent::SolarStormStarted { time, until });
+    }
+
+    pub(crate) fn expire_solar_storm(&mut self, now: f64) {
+        if let Some(until) = self.solar_storm_until {
+            if now >= until {
+                self.solar_storm_until = None;
+                self.fired
+                    .push(ScenarioEvent::SolarStormEnded { time: until });
+            }
+        }
+    }
+
+    pub(crate) fn record(&mut self, event: ScenarioEvent) {
+        self.fired.push(event);
+    }
+
+    /// Take the events fired since the last call.
+    pub fn drain_events(&mut self) -> Vec<ScenarioEvent> {
+        core::mem::take(&mut self.fired)
+    }
+}
@@ -0,0 +1,342 @@
+//! What a ship can see.
+//!
+//! A body given a `SensorSuite` with `World::set_sensor_suite` can
+//! `World::scan` for contacts: other bodies within range, inside its field
+//! of view, and not hidden behind the planet or a moon. Everything else is
+//! left out, so a client fed only its own ship's scans can't see a cold
+//! asteroid on the far side of the planet. `World::has_line_of_sight`
+//! answers the same question for any two bodies, sensors or not.
+//!
+//! A contact is identified by its transponder: the body carrying the
+//! world's ship interior (see `World::set_interior_body`) broadcasts its
+//! callsign and DM code while that interior's transponder is online.
+//! Everything else, including that ship with its transponder off, shows up
+//! as `ContactIdentity::Unknown`.
+//!
+//! Each contact's position is off by up to `SensorSuite::noise_per_m` per
+//! metre of range. The error is drawn from the world's seed, the tick and
+//! the two bodies, so every peer in a lockstep session gets the same
+//! reading and scanning again within a tick doesn't average it away.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::f64::consts::PI;
+
+use crate::central::Primary;
+use crate::interior::TransponderData;
+use crate::lockstep::StateHasher;
+use crate::math::Float;
+use crate::{angle, error, GgwError, Vec2, World};
+
+/// A body's sensors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorSuite {
+    /// Farthest a body's nearest edge can be and still be seen.
+    pub range_m: f64,
+    /// Half-width of the field of view around the body's `+x` axis, as its
+    /// `orientation` turns it; `None` sees all the way round.
+    pub half_angle: Option<f64>,
+    /// Largest position error per metre of range.
+    pub noise_per_m: f64,
+}
+
+impl SensorSuite {
+    /// Sensors that see all the way round out to `range_m`.
+    pub fn omni(range_m: f64, noise_per_m: f64) -> Self {
+        Self {
+            range_m,
+            half_angle: None,
+            noise_per_m,
+        }
+    }
+
+    /// Sensors that see `half_angle` either side of straight ahead.
+    pub fn cone(range_m: f64, half_angle: f64, noise_per_m: f64) -> Self {
+        Self {
+            range_m,
+            half_angle: Some(half_angle),
+            noise_per_m,
+        }
+    }
+}
+
+/// Who a contact says it is.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ContactIdentity {
+    Unknown,
+    /// Broadcast by an online transponder.
+    Identified {
+        callsign: String,
+        dm_code: u32,
+    },
+}
+
+/// A body picked up by a scan.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorContact {
+    pub body_id: u64,
+    /// Estimated world-frame position.
+    pub position: Vec2,
+    /// How far `position` may be from the truth.
+    pub uncertainty_m: f64,
+    pub identity: ContactIdentity,
+}
+
+/// Whether the line from `from` to `to` passes through `primary`.
+fn occluded(from: Vec2, to: Vec2, primary: &Primary) -> bool {
+    let line = to - from;
+    let length_sq = line.length_squared();
+    let t = if length_sq > 0.0 {
+        ((primary.position - from).dot(line) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (from + line * t).distance(primary.position) < primary.radius
+}
+
+/// Two numbers in `[0, 1)` drawn from `seed`, `tick` and the pair.
+fn draw(seed: u64, tick: u64, observer: u64, target: u64) -> (f64, f64) {
+    let mut hasher = StateHasher::new();
+    for value in [seed, tick, observer, target] {
+        hasher.write_u64(value);
+    }
+    let first = hasher.finish();
+    hasher.write_u64(first);
+    let unit = |bits: u64| (bits >> 11) as f64 / (1u64 << 53) as f64;
+    (unit(first), unit(hasher.finish()))
+}
+
+impl World {
+    /// Give `body_id` sensors, or take them away with `None`.
+    pub fn set_sensor_suite(
+        &mut self,
+        body_id: u64,
+        suite: Option<SensorSuite>,
+    ) -> error::Result<()> {
+        if self.body(body_id).is_none() {
+            return Err(GgwError::UnknownBody(body_id));
+        }
+        match suite {
+            Some(suite) => self.sensor_suites.insert(body_id, suite),
+            None => self.sensor_suites.remove(&body_id),
+        };
+        Ok(())
+    }
+
+    pub fn sensor_suite(&self, body_id: u64) -> Option<SensorSuite> {
+        self.sensor_suites.get(&body_id).copied()
+    }
+
+    /// Whether nothing but open space lies between `a` and `b`: the line
+    /// joining their centres misses the planet and every moon.
+    pub fn has_line_of_sight(&self, a: u64, b: u64) -> error::Result<bool> {
+        let from = self.body(a).ok_or(GgwError::UnknownBody(a))?.position;
+        let to = self.body(b).ok_or(GgwError::UnknownBody(b))?.position;
+        Ok(!self
+            .primaries()
+            .iter()
+            .any(|primary| occluded(from, to, primary)))
+    }
+
+    /// What `body_id`'s transponder is broadcasting, if it has one online.
+    pub fn transponder(&self, body_id: u64) -> Option<&TransponderData> {
+        if self.interior_body != Some(body_id) {
+            return None;
+        }
+        self.interior.ship.broadcasting_transponder()
+    }
+
+    /// Every body `observer_id`'s sensors pick up now, in world order. A
+    /// body without sensors sees nothing.
+    pub fn scan(&self, observer_id: u64) -> error::Result<Vec<SensorContact>> {
+        let observer = self
+            .body(observer_id)
+            .ok_or(GgwError::UnknownBody(observer_id))?;
+        let Some(suite) = self.sensor_suite(observer_id) else {
+            return Ok(Vec::new());
+        };
+        let primaries = self.primaries();
+        let from = observer.position;
+        Ok(self
+            .bodies
+            .iter()
+            .filter(|target| target.id != observer_id)
+            .filter_map(|target| {
+                let offset = target.position - from;
+                let distance = offset.length();
+                if distance - target.radius > suite.range_m {
+                    return None;
+                }
+                if let Some(half_angle) = suite.half_angle {
+                    let bearing = Float::atan2(offset.y, offset.x);
+                    let off_axis = angle::shortest_difference(observer.orientation, bearing);
+                    // A body overlapping the observer is seen whatever its
+                    // bearing.
+                    if distance > target.radius && off_axis.abs() > half_angle {
+                        return None;
+                    }
+                }
+                if primaries
+                    .iter()
+                    .any(|primary| occluded(from, target.position, primary))
+                {
+                    return None;
+                }
+                let uncertainty_m = suite.noise_per_m.max(0.0) * distance;
+                let (direction, reach) = draw(self.seed, self.tick_count, observer_id, target.id);
+                let error = Vec2::new(1.0, 0.0).rotate(2.0 * PI * direction)
+                    * (uncertainty_m * Float::sqrt(reach));
+                let identity = match self.transponder(target.id) {
+                    Some(transponder) => ContactIdentity::Identified {
+                        callsign: transponder.callsign.clone(),
+                        dm_code: transponder.dm_code,
+                    },
+                    None => ContactIdentity::Unknown,
+                };
+                Some(SensorContact {
+                    body_id: target.id,
+                    position: target.position + error,
+                    uncertainty_m,
+                    identity,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{BodyState, BodyType, Seconds};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn body_at(a: f64, mean_anomaly: f64) -> BodyState {
+        BodyState {
+            body_type: BodyType::Asteroid,
+            ..BodyState::circular(a, mean_anomaly)
+        }
+    }
+
+    #[test]
+    fn scans_see_what_is_near_ahead_and_in_the_open() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world.add_body(body_at(7_000_000.0, 0.0)).unwrap();
+        let near = world.add_body(body_at(7_000_000.0, 0.01)).unwrap();
+        let far = world.add_body(body_at(7_000_000.0, 0.5)).unwrap();
+        let _hidden = world.add_body(body_at(7_000_000.0, PI)).unwrap();
+        assert!(world.scan(ship).unwrap().is_empty());
+        assert_eq!(
+            world.set_sensor_suite(99, Some(SensorSuite::omni(1.0, 0.0))),
+            Err(GgwError::UnknownBody(99))
+        );
+
+        // Near is 70 km off, far 3,400 km; hidden is behind the planet
+        // whatever the range.
+        world
+            .set_sensor_suite(ship, Some(SensorSuite::omni(20_000_000.0, 0.01)))
+            .unwrap();
+        let contacts = world.scan(ship).unwrap();
+        let ids: Vec<u64> = contacts.iter().map(|c| c.body_id).collect();
+        assert_eq!(ids, [near, far]);
+        for contact in &contacts {
+            let truth = world.body(contact.body_id).unwrap().position;
+            assert!(contact.position.distance(truth) <= contact.uncertainty_m);
+        }
+        assert!(contacts[0].position != world.body(near).unwrap().position);
+        assert_eq!(world.scan(ship).unwrap(), contacts);
+
+        world
+            .set_sensor_suite(ship, Some(SensorSuite::omni(100_000.0, 0.01)))
+            .unwrap();
+        let ids: Vec<u64> = world
+            .scan(ship)
+            .unwrap()
+            .iter()
+            .map(|c| c.body_id)
+            .collect();
+        assert_eq!(ids, [near]);
+
+        // A cone pointed backwards misses what's ahead along the orbit.
+        let ahead = world.body(near).unwrap().position - world.body(ship).unwrap().position;
+        let heading = Float::atan2(ahead.y, ahead.x);
+        world
+            .set_sensor_suite(ship, Some(SensorSuite::cone(100_000.0, 0.3, 0.0)))
+            .unwrap();
+        world.get_body_mut(ship).unwrap().orientation = heading + PI;
+        assert!(world.scan(ship).unwrap().is_empty());
+        world.get_body_mut(ship).unwrap().orientation = heading;
+        let contacts = world.scan(ship).unwrap();
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].position, world.body(near).unwrap().position);
+
+        // The error changes from tick to tick.
+        world
+            .set_sensor_suite(ship, Some(SensorSuite::omni(100_000.0, 0.01)))
+            .unwrap();
+        let error = |world: &World| {
+            world.scan(ship).unwrap()[0].position - world.body(near).unwrap().position
+        };
+        let before = error(&world);
+        world.step(Seconds(1.0));
+        assert!(error(&world).distance(before) > 1e-3);
+    }
+
+    #[test]
+    fn the_planet_blocks_line_of_sight() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world.add_body(body_at(7_000_000.0, 0.0)).unwrap();
+        let near = world.add_body(body_at(7_000_000.0, 0.5)).unwrap();
+        let hidden = world.add_body(body_at(7_000_000.0, PI)).unwrap();
+        assert_eq!(world.has_line_of_sight(ship, near), Ok(true));
+        assert_eq!(world.has_line_of_sight(near, ship), Ok(true));
+        assert_eq!(world.has_line_of_sight(ship, hidden), Ok(false));
+        assert_eq!(world.has_line_of_sight(ship, ship), Ok(true));
+        assert_eq!(
+            world.has_line_of_sight(ship, 99),
+            Err(GgwError::UnknownBody(99))
+        );
+    }
+
+    #[test]
+    fn online_transponders_identify_their_ship() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world.add_body(body_at(7_000_000.0, 0.0)).unwrap();
+        let other = world.add_body(body_at(7_000_000.0, 0.01)).unwrap();
+        world
+            .set_sensor_suite(ship, Some(SensorSuite::omni(100_000.0, 0.0)))
+            .unwrap();
+        let identity = |world: &World| world.scan(ship).unwrap().remove(0).identity;
+        assert_eq!(identity(&world), ContactIdentity::Unknown);
+
+        assert_eq!(
+            world.set_interior_body(Some(99)),
+            Err(GgwError::UnknownBody(99))
+        );
+        world.set_interior_body(Some(other)).unwrap();
+        let transponder = world.transponder(other).unwrap().clone();
+        assert_eq!(
+            identity(&world),
+            ContactIdentity::Identified {
+                callsign: transponder.callsign,
+                dm_code: transponder.dm_code,
+            }
+        );
+
+        let device = world
+            .interior
+            .ship
+            .devices
+            .iter()
+            .find(|device| device.device_type == crate::interior::DeviceType::Transponder)
+            .unwrap()
+            .id;
+        world.interior.ship.set_device_online(device, false);
+        assert_eq!(identity(&world), ContactIdentity::Unknown);
+        assert!(world.transponder(other).is_none());
+    }
+}
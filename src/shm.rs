@@ -0,0 +1,293 @@
+//! Snapshot transport for a renderer on the same machine: each step's
+//! bodies are copied straight into a memory-mapped ring of slots, with no
+//! serialization or pipe in between.
+//!
+//! Put the file somewhere memory-backed, such as `/dev/shm` on Linux, so
+//! the kernel never writes it out. All fields are little-endian and 8-byte
+//! aligned.
+//!
+//! The file starts with a `HEADER_BYTES` header:
+//!
+//! | offset | type | field |
+//! |-------:|------|-------|
+//! | 0 | `[u8; 8]` | `MAGIC` |
+//! | 8 | `u64` | `VERSION` |
+//! | 16 | `u64` | slot count |
+//! | 24 | `u64` | slot size in bytes |
+//! | 32 | `u64` | sequence number of the newest complete snapshot, 0 if none |
+//!
+//! Snapshot `seq` goes in slot `seq % slot count`, which starts at
+//! `HEADER_BYTES + slot * slot size` with a `SLOT_HEADER_BYTES` header:
+//!
+//! | offset | type | field |
+//! |-------:|------|-------|
+//! | 0 | `u64` | `2 * seq` once written, odd while being written |
+//! | 8 | `u64` | tick |
+//! | 16 | `f64` | sim time |
+//! | 24 | `u64` | bodies in this slot |
+//! | 32 | `u64` | bodies in the world, more than the slot held if it was full |
+//!
+//! followed by one record per body, laid out like
+//! `wasm::BODY_STATE_STRIDE`: `[id, body_type, mass, radius, x, y, vx, vy]`
+//! as `f64`s, with body types numbered as in `BodyType`'s declaration.
+//!
+//! A reader takes the newest sequence number, copies its slot out, and
+//! keeps the copy only if the slot's first field read `2 * seq` both
+//! before and after. With more than one slot the writer is unlikely to
+//! lap a reader mid-copy, so a retry is rare.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::{BodyType, World};
+
+pub const MAGIC: [u8; 8] = *b"GGWSHM01";
+pub const VERSION: u64 = 1;
+pub const HEADER_BYTES: usize = 64;
+pub const SLOT_HEADER_BYTES: usize = 40;
+/// `f64`s per body record.
+pub const BODY_RECORD_LEN: usize = 8;
+const BODY_RECORD_BYTES: usize = BODY_RECORD_LEN * 8;
+
+const LATEST_OFFSET: usize = 32;
+
+/// One snapshot read back out of the ring.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RingSnapshot {
+    pub seq: u64,
+    pub tick: u64,
+    pub sim_time: f64,
+    /// Bodies in the world when the snapshot was taken; `bodies` is short
+    /// of this if the slot was too small.
+    pub total_bodies: u64,
+    /// `BODY_RECORD_LEN` values per body.
+    pub bodies: Vec<f64>,
+}
+
+/// The writing end, owned by the simulation.
+pub struct SnapshotRing {
+    map: MmapMut,
+    slot_count: usize,
+    slot_size: usize,
+    seq: u64,
+}
+
+impl SnapshotRing {
+    /// Create or truncate the ring file at `path`, sized for `slot_count`
+    /// slots of up to `max_bodies` bodies each.
+    pub fn create(path: &Path, slot_count: usize, max_bodies: usize) -> io::Result<Self> {
+        if slot_count == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a snapshot ring needs at least one slot",
+            ));
+        }
+        let slot_size = SLOT_HEADER_BYTES + max_bodies * BODY_RECORD_BYTES;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len((HEADER_BYTES + slot_count * slot_size) as u64)?;
+        // SAFETY: the mapping is only shared with readers that follow the
+        // sequence protocol in the module docs; nothing in this process
+        // truncates the file while it is mapped.
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        map[..8].copy_from_slice(&MAGIC);
+        map[8..16].copy_from_slice(&VERSION.to_le_bytes());
+        map[16..24].copy_from_slice(&(slot_count as u64).to_le_bytes());
+        map[24..32].copy_from_slice(&(slot_size as u64).to_le_bytes());
+        map.flush()?;
+        Ok(Self {
+            map,
+            slot_count,
+            slot_size,
+            seq: 0,
+        })
+    }
+
+    /// Bodies that fit in one slot.
+    pub fn max_bodies(&self) -> usize {
+        (self.slot_size - SLOT_HEADER_BYTES) / BODY_RECORD_BYTES
+    }
+
+    /// Copy `world`'s bodies into the next slot and mark it as the newest.
+    /// Returns the snapshot's sequence number.
+    pub fn publish(&mut self, world: &World) -> u64 {
+        self.seq += 1;
+        let seq = self.seq;
+        let start = HEADER_BYTES + (seq as usize % self.slot_count) * self.slot_size;
+        let stored = world.bodies.len().min(self.max_bodies());
+        let slot = &mut self.map[start..start + self.slot_size];
+        atomic_at(slot, 0).store(2 * seq - 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        put_u64(slot, 8, world.tick_count);
+        put_u64(slot, 16, world.sim_time().0.to_bits());
+        put_u64(slot, 24, stored as u64);
+        put_u64(slot, 32, world.bodies.len() as u64);
+        for (index, body) in world.bodies.iter().take(stored).enumerate() {
+            let record = [
+                body.id as f64,
+                body_type_code(body.body_type),
+                body.mass,
+                body.radius,
+                body.position.x,
+                body.position.y,
+                body.velocity.x,
+                body.velocity.y,
+            ];
+            let offset = SLOT_HEADER_BYTES + index * BODY_RECORD_BYTES;
+            for (field, value) in record.iter().enumerate() {
+                put_u64(slot, offset + field * 8, value.to_bits());
+            }
+        }
+
+        atomic_at(slot, 0).store(2 * seq, Ordering::Release);
+        atomic_at(&self.map, LATEST_OFFSET).store(seq, Ordering::Release);
+        seq
+    }
+}
+
+/// The reading end, for a renderer in this process or another Rust one.
+pub struct SnapshotReader {
+    map: Mmap,
+    slot_count: usize,
+    slot_size: usize,
+}
+
+impl SnapshotReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the writer never shrinks the file, and every copy out of
+        // it is checked against the slot's sequence number.
+        let map = unsafe { Mmap::map(&file)? };
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+        if map.len() < HEADER_BYTES || map[..8] != MAGIC {
+            return Err(invalid("not a snapshot ring"));
+        }
+        if get_u64(&map, 8) != VERSION {
+            return Err(invalid("unsupported snapshot ring version"));
+        }
+        let slot_count = get_u64(&map, 16) as usize;
+        let slot_size = get_u64(&map, 24) as usize;
+        if slot_count == 0
+            || slot_size < SLOT_HEADER_BYTES
+            || map.len() < HEADER_BYTES + slot_count * slot_size
+        {
+            return Err(invalid("snapshot ring header doesn't match the file"));
+        }
+        Ok(Self {
+            map,
+            slot_count,
+            slot_size,
+        })
+    }
+
+    /// Sequence number of the newest complete snapshot, 0 if none yet.
+    pub fn latest_seq(&self) -> u64 {
+        atomic_at(&self.map, LATEST_OFFSET).load(Ordering::Acquire)
+    }
+
+    /// Copy the newest snapshot into `out`, returning whether a consistent
+    /// one was read. Fails if nothing was published yet or the writer
+    /// overwrote the slot during the copy; try again next frame.
+    pub fn read_latest(&self, out: &mut RingSnapshot) -> bool {
+        let seq = self.latest_seq();
+        if seq == 0 {
+            return false;
+        }
+        let start = HEADER_BYTES + (seq as usize % self.slot_count) * self.slot_size;
+        let slot = &self.map[start..start + self.slot_size];
+        if atomic_at(slot, 0).load(Ordering::Acquire) != 2 * seq {
+            return false;
+        }
+        let stored = (get_u64(slot, 24) as usize)
+            .min((self.slot_size - SLOT_HEADER_BYTES) / BODY_RECORD_BYTES);
+        out.seq = seq;
+        out.tick = get_u64(slot, 8);
+        out.sim_time = f64::from_bits(get_u64(slot, 16));
+        out.total_bodies = get_u64(slot, 32);
+        out.bodies.clear();
+        out.bodies.extend(
+            (0..stored * BODY_RECORD_LEN)
+                .map(|field| f64::from_bits(get_u64(slot, SLOT_HEADER_BYTES + field * 8))),
+        );
+        fence(Ordering::Acquire);
+        atomic_at(slot, 0).load(Ordering::Relaxed) == 2 * seq
+    }
+}
+
+fn atomic_at(bytes: &[u8], offset: usize) -> &AtomicU64 {
+    let ptr = bytes[offset..offset + 8].as_ptr();
+    assert_eq!(ptr as usize % 8, 0, "ring fields are 8-byte aligned");
+    // SAFETY: in bounds and aligned, as checked above, and every access to
+    // these words from either end goes through an `AtomicU64`.
+    unsafe { &*(ptr as *const AtomicU64) }
+}
+
+fn put_u64(bytes: &mut [u8], offset: usize, value: u64) {
+    bytes[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+fn get_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut word = [0; 8];
+    word.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(word)
+}
+
+fn body_type_code(body_type: BodyType) -> f64 {
+    match body_type {
+        BodyType::Ship => 0.0,
+        BodyType::Asteroid => 1.0,
+        BodyType::Debris => 2.0,
+        BodyType::Missile => 3.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::GameConfig, BodyState, Seconds};
+
+    #[test]
+    fn reader_sees_the_newest_snapshot() {
+        let path = std::env::temp_dir().join(format!("ggw_shm_test_{}", std::process::id()));
+        let mut world = World::new(3.986_004_418e14, GameConfig::default());
+        for a in [7_000_000.0, 8_000_000.0, 9_000_000.0] {
+            world
+                .add_body(BodyState {
+                    body_type: BodyType::Debris,
+                    ..BodyState::circular(a, 0.0)
+                })
+                .unwrap();
+        }
+        let mut ring = SnapshotRing::create(&path, 3, 2).unwrap();
+        let reader = SnapshotReader::open(&path).unwrap();
+        let mut snapshot = RingSnapshot::default();
+        assert!(!reader.read_latest(&mut snapshot));
+
+        for _ in 0..4 {
+            world.step(Seconds(1.0));
+            ring.publish(&world);
+        }
+        assert!(reader.read_latest(&mut snapshot));
+        assert_eq!(snapshot.seq, 4);
+        assert_eq!(snapshot.tick, world.tick_count);
+        assert_eq!(snapshot.sim_time, world.sim_time().0);
+        // Only two of the three bodies fit.
+        assert_eq!(snapshot.total_bodies, 3);
+        assert_eq!(snapshot.bodies.len(), 2 * BODY_RECORD_LEN);
+        let body = &world.bodies[1];
+        let record = &snapshot.bodies[BODY_RECORD_LEN..];
+        assert_eq!(record[0], body.id as f64);
+        assert_eq!(record[1], 2.0);
+        assert_eq!((record[4], record[5]), (body.position.x, body.position.y));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
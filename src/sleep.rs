@@ -0,0 +1,282 @@
+//! Dormancy for inert bodies, so large asteroid belts and debris fields cost
+//! next to nothing while nobody is near them.
+//!
+//! An asteroid or piece of debris on an orbit that can neither hit the
+//! planet, despawn, cross the gravity well boundary nor stray into a moon's
+//! sphere of influence goes dormant once every ship and missile is more
+//! than `SleepConfig::distance_m` away. A dormant body drops out of
+//! collision checks and is propagated only every
+//! `propagation_interval_ticks` ticks.
+//!
+//! Dormancy lasts until the earliest time a ship or missile could close to
+//! that distance, using the same periapsis-speed bound as the collision
+//! scheduler. A burn or a new ship or missile invalidates every such bound,
+//! so it wakes everything; `World::wake_body` and `World::ping` wake bodies
+//! on demand.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::central::Primaries;
+use crate::collision::max_speed;
+use crate::config::SleepConfig;
+use crate::interest::is_mission_critical;
+use crate::lockstep::StateHasher;
+use crate::{
+    validate_orbit, BodyState, BodyType, OrbitState, DESPAWN_RADIUS_M, GRAVITY_WELL_RADIUS_M,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Dormancy {
+    /// Sim time at which the body must be awake again.
+    wake_time: f64,
+    /// Tick the body's position was last brought up to date.
+    propagated_tick: u64,
+}
+
+/// Which bodies are dormant, and the ship and missile orbits their wake
+/// times were worked out against.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SleepTracker {
+    dormant: BTreeMap<u64, Dormancy>,
+    watchers: BTreeMap<u64, OrbitState>,
+}
+
+impl SleepTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_dormant(&self, body_id: u64) -> bool {
+        self.dormant.contains_key(&body_id)
+    }
+
+    /// When a dormant body will wake by itself, or `None` if it is awake.
+    pub fn wake_time(&self, body_id: u64) -> Option<f64> {
+        self.dormant
+            .get(&body_id)
+            .map(|dormancy| dormancy.wake_time)
+    }
+
+    pub fn dormant_bodies(&self) -> impl Iterator<Item = u64> + '_ {
+        self.dormant.keys().copied()
+    }
+
+    pub fn dormant_count(&self) -> usize {
+        self.dormant.len()
+    }
+
+    /// Wake `body_id`, returning whether it was dormant.
+    pub fn wake(&mut self, body_id: u64) -> bool {
+        self.dormant.remove(&body_id).is_some()
+    }
+
+    pub fn wake_all(&mut self) {
+        self.dormant.clear();
+    }
+
+    /// Feed which bodies are dormant, and until when, into `hasher`. Bodies
+    /// that sleep differently are propagated on different ticks.
+    pub fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_u64(self.dormant.len() as u64);
+        for (id, dormancy) in &self.dormant {
+            hasher.write_u64(*id);
+            hasher.write_f64(dormancy.wake_time);
+            hasher.write_u64(dormancy.propagated_tick);
+        }
+    }
+
+    /// Wake bodies that are due, have been removed, or whose wake times no
+    /// longer hold because a ship or missile changed course. Run before
+    /// propagation.
+    pub(crate) fn wake_due(&mut self, bodies: &[BodyState], now: f64) {
+        let mut watcher_count = 0;
+        let mut watchers_changed = false;
+        for body in bodies.iter().filter(|body| is_mission_critical(body)) {
+            watcher_count += 1;
+            watchers_changed |= self.watchers.get(&body.id) != Some(&body.orbit);
+        }
+        if watchers_changed || watcher_count != self.watchers.len() {
+            self.watchers = bodies
+                .iter()
+                .filter(|body| is_mission_critical(body))
+                .map(|body| (body.id, body.orbit))
+                .collect();
+            self.wake_all();
+            return;
+        }
+        let present = bodies
+            .iter()
+            .filter(|body| self.dormant.contains_key(&body.id))
+            .count();
+        if present != self.dormant.len() {
+            let live: BTreeMap<u64, ()> = bodies.iter().map(|body| (body.id, ())).collect();
+            self.dormant.retain(|id, _| live.contains_key(id));
+        }
+        self.dormant.retain(|_, dormancy| dormancy.wake_time > now);
+    }
+
+    /// Whether `body_id` should be propagated on `tick`. Dormant bodies only
+    /// are every `interval` ticks.
+    pub(crate) fn should_propagate(&mut self, body_id: u64, tick: u64, interval: u64) -> bool {
+        match self.dormant.get_mut(&body_id) {
+            Some(dormancy) if tick < dormancy.propagated_tick.saturating_add(interval) => false,
+            Some(dormancy) => {
+                dormancy.propagated_tick = tick;
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Put to sleep every awake body that qualifies, given freshly
+    /// propagated positions. Run after propagation.
+    pub(crate) fn settle(
+        &mut self,
+        bodies: &[BodyState],
+        primaries: &Primaries,
+        config: &SleepConfig,
+        now: f64,
+        tick: u64,
+    ) {
+        let watchers: Vec<(&BodyState, f64)> = bodies
+            .iter()
+            .filter(|body| is_mission_critical(body))
+            .map(|body| {
+                let primary = primaries.get(body.parent);
+                (body, max_speed(&body.orbit, primary.mu) + primary.max_speed)
+            })
+            .collect();
+        let planet = primaries.planet();
+        for body in bodies {
+            if self.dormant.contains_key(&body.id)
+                || !can_sleep(body, planet.radius)
+                || primaries.may_enter_soi(&body.orbit)
+            {
+                continue;
+            }
+            let speed = max_speed(&body.orbit, planet.mu);
+            let mut wake_time = f64::INFINITY;
+            for (watcher, watcher_speed) in &watchers {
+                let gap = body.position.distance(watcher.position)
+                    - body.radius
+                    - watcher.radius
+                    - config.distance_m;
+                wake_time = wake_time.min(now + gap / (speed + watcher_speed));
+            }
+            // A wake time already past means a ship or missile is in range.
+            if wake_time > now {
+                self.dormant.insert(
+                    body.id,
+                    Dormancy {
+                        wake_time,
+                        propagated_tick: tick,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Whether `body` is inert enough to sleep: an asteroid or debris orbiting
+/// the planet on an orbit that stays clear of the planet, the despawn
+/// radius and the gravity well boundary. Moons carry their bodies through
+/// all of those, so nothing orbiting one sleeps.
+pub fn can_sleep(body: &BodyState, planet_radius: f64) -> bool {
+    if body.parent.is_some() || !matches!(body.body_type, BodyType::Asteroid | BodyType::Debris) {
+        return false;
+    }
+    let orbit = &body.orbit;
+    if validate_orbit(orbit).is_err() {
+        return false;
+    }
+    let periapsis = orbit.semi_major_axis * (1.0 - orbit.eccentricity);
+    let apoapsis = orbit.semi_major_axis * (1.0 + orbit.eccentricity);
+    let crosses_well = periapsis <= GRAVITY_WELL_RADIUS_M && apoapsis > GRAVITY_WELL_RADIUS_M;
+    periapsis > planet_radius + body.radius && apoapsis <= DESPAWN_RADIUS_M && !crosses_well
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{Meters, Seconds, ThrustEvent, ThrustType, Vec2, World};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    fn body(a: f64, mean_anomaly: f64, body_type: BodyType) -> BodyState {
+        BodyState {
+            mass: 100.0,
+            body_type,
+            ..BodyState::circular(a, mean_anomaly)
+        }
+    }
+
+    #[test]
+    fn far_debris_sleeps_until_woken() {
+        let mut config = GameConfig::default();
+        config.sleep.enabled = true;
+        config.sleep.propagation_interval_ticks = 5;
+        let mut world = World::new(MU_EARTH, config);
+        let ship = world
+            .add_body(body(7_000_000.0, 0.0, BodyType::Ship))
+            .unwrap();
+        let near = world
+            .add_body(body(7_000_000.0, 1e-3, BodyType::Debris))
+            .unwrap();
+        let far = world
+            .add_body(body(20_000_000.0, 2.0, BodyType::Asteroid))
+            .unwrap();
+        // Touching `far`, but dormant bodies don't collide.
+        world
+            .add_body(body(20_000_000.0, 2.0 + 1e-7, BodyType::Debris))
+            .unwrap();
+        let far_collides = |world: &World| {
+            world
+                .detect_collisions(Seconds(1.0))
+                .iter()
+                .any(|event| event.body_a == far || event.body_b == far)
+        };
+
+        world.step(Seconds(1.0));
+        assert!(world.is_dormant(far));
+        assert!(!world.is_dormant(near) && !world.is_dormant(ship));
+        assert!(world.sleep_tracker().wake_time(far).unwrap() > world.sim_time().0);
+
+        // Only propagated every few ticks while asleep.
+        let parked = world.body(far).unwrap().position;
+        world.step(Seconds(1.0));
+        assert_eq!(world.body(far).unwrap().position, parked);
+        for _ in 0..4 {
+            world.step(Seconds(1.0));
+        }
+        assert_ne!(world.body(far).unwrap().position, parked);
+        assert!(!far_collides(&world));
+        assert!(world.detect_collisions_tiered(Seconds(1.0)).is_empty());
+
+        let far_position = world.body(far).unwrap().position;
+        assert_eq!(world.ping(far_position, Meters(1_000.0)), 2);
+        assert!(!world.is_dormant(far));
+        assert!(far_collides(&world));
+        assert_eq!(world.detect_collisions_tiered(Seconds(1.0)).len(), 1);
+
+        world.step(Seconds(1.0));
+        assert!(world.is_dormant(far));
+        let wake_before_burn = world.sleep_tracker().wake_time(far);
+        world
+            .apply_thrust_event(&ThrustEvent {
+                body_id: ship,
+                time: world.sim_time().0,
+                delta_v: Vec2::new(0.0, 5.0),
+                thrust_type: ThrustType::Chemical,
+            })
+            .unwrap();
+        world.step(Seconds(1.0));
+        // The burn voided every wake time; `far` went back to sleep on a
+        // bound worked out from the new orbit.
+        let wake = world.sleep_tracker().wake_time(far);
+        assert!(wake.is_some() && wake != wake_before_burn);
+    }
+}
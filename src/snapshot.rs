@@ -0,0 +1,407 @@
+//! Keyframes and deltas for streaming a world to clients.
+//!
+//! A `SnapshotEncoder` turns the world into one `Snapshot` per call: a full
+//! `Keyframe` every `keyframe_interval` snapshots, and in between a `Delta`
+//! holding only what changed since the previous snapshot. A body is sent
+//! again once it has drifted more than `position_epsilon_m` from where the
+//! client last saw it, or its type, mass or radius changed; an interior
+//! tile once its type changed or its gases moved by more than
+//! `ATMOS_EPSILON_KG`. Comparing against what was last sent rather than
+//! last tick's state means slow drift still goes out eventually.
+//!
+//! Every snapshot carries a sequence number and each delta the number of
+//! the snapshot it builds on. A `SnapshotMirror` on the client side applies
+//! them in order and refuses a delta whose base it doesn't have, so a
+//! client that dropped one asks for a keyframe with
+//! `SnapshotEncoder::request_keyframe` instead of drifting out of sync.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::interior::{AtmosSample, TileType};
+use crate::{error, BodyType, GgwError, Vec2, World};
+
+/// Snapshots from one keyframe to the next, the keyframe included.
+pub const DEFAULT_KEYFRAME_INTERVAL: u64 = 100;
+/// How far a body drifts before a delta sends it again.
+pub const DEFAULT_POSITION_EPSILON_M: f64 = 0.01;
+/// How much of any gas a tile gains or loses before a delta sends it again.
+pub const ATMOS_EPSILON_KG: f32 = 1e-4;
+
+/// A body as a client sees it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BodyFrame {
+    pub id: u64,
+    pub body_type: BodyType,
+    pub mass: f64,
+    pub radius: f64,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub orientation: f64,
+}
+
+impl BodyFrame {
+    /// Whether a client showing `self` should be sent `current`.
+    fn is_stale(&self, current: &BodyFrame, position_epsilon_m: f64) -> bool {
+        self.body_type != current.body_type
+            || self.mass != current.mass
+            || self.radius != current.radius
+            || self.position.distance(current.position) > position_epsilon_m
+    }
+}
+
+/// An interior tile as a client sees it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileFrame {
+    pub x: u32,
+    pub y: u32,
+    pub tile_type: TileType,
+    /// `None` for tiles that hold no atmosphere.
+    pub atmos: Option<AtmosSample>,
+}
+
+impl TileFrame {
+    fn is_stale(&self, current: &TileFrame) -> bool {
+        let gas_moved = match (self.atmos, current.atmos) {
+            (Some(old), Some(new)) => [
+                old.o2_kg - new.o2_kg,
+                old.n2_kg - new.n2_kg,
+                old.co2_kg - new.co2_kg,
+            ]
+            .iter()
+            .any(|change| change.abs() > ATMOS_EPSILON_KG),
+            (old, new) => old.is_some() != new.is_some(),
+        };
+        self.tile_type != current.tile_type || gas_moved
+    }
+}
+
+/// Everything a client needs to start showing the world.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyframe {
+    pub seq: u64,
+    pub tick: u64,
+    pub sim_time: f64,
+    pub bodies: Vec<BodyFrame>,
+    pub interior_width: u32,
+    pub interior_height: u32,
+    /// Every tile, row by row.
+    pub tiles: Vec<TileFrame>,
+}
+
+/// What changed since snapshot `base_seq`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Delta {
+    pub seq: u64,
+    pub base_seq: u64,
+    pub tick: u64,
+    pub sim_time: f64,
+    /// Bodies added or changed.
+    pub bodies: Vec<BodyFrame>,
+    /// Ids of bodies gone from the world.
+    pub removed: Vec<u64>,
+    pub tiles: Vec<TileFrame>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Snapshot {
+    Keyframe(Keyframe),
+    Delta(Delta),
+}
+
+impl Snapshot {
+    pub fn seq(&self) -> u64 {
+        match self {
+            Snapshot::Keyframe(keyframe) => keyframe.seq,
+            Snapshot::Delta(delta) => delta.seq,
+        }
+    }
+}
+
+fn body_frames(world: &World) -> Vec<BodyFrame> {
+    world
+        .bodies
+        .iter()
+        .map(|body| BodyFrame {
+            id: body.id,
+            body_type: body.body_type,
+            mass: body.mass,
+            radius: body.radius,
+            position: body.position,
+            velocity: body.velocity,
+            orientation: body.orientation,
+        })
+        .collect()
+}
+
+fn tile_frames(world: &World) -> Vec<TileFrame> {
+    let ship = &world.interior.ship;
+    let atmosphere = &world.config.atmosphere;
+    (0..ship.height)
+        .flat_map(|y| (0..ship.width).map(move |x| (x, y)))
+        .map(|(x, y)| TileFrame {
+            x,
+            y,
+            tile_type: ship.tile_type(x, y),
+            atmos: ship.tile_atmos_sample(x, y, atmosphere),
+        })
+        .collect()
+}
+
+/// Server side of a snapshot stream to one client, or to several that all
+/// get the same snapshots.
+#[derive(Clone, Debug)]
+pub struct SnapshotEncoder {
+    pub keyframe_interval: u64,
+    pub position_epsilon_m: f64,
+    /// Sequence number of the last snapshot, 0 before the first.
+    seq: u64,
+    /// Sequence number of the last keyframe.
+    keyframe_seq: u64,
+    keyframe_requested: bool,
+    /// Bodies as the client last saw them.
+    sent_bodies: BTreeMap<u64, BodyFrame>,
+    /// Tiles as the client last saw them, row by row.
+    sent_tiles: Vec<TileFrame>,
+}
+
+impl Default for SnapshotEncoder {
+    fn default() -> Self {
+        Self::new(DEFAULT_KEYFRAME_INTERVAL, DEFAULT_POSITION_EPSILON_M)
+    }
+}
+
+impl SnapshotEncoder {
+    pub fn new(keyframe_interval: u64, position_epsilon_m: f64) -> Self {
+        Self {
+            keyframe_interval,
+            position_epsilon_m,
+            seq: 0,
+            keyframe_seq: 0,
+            keyframe_requested: true,
+            sent_bodies: BTreeMap::new(),
+            sent_tiles: Vec::new(),
+        }
+    }
+
+    /// Make the next snapshot a keyframe, for a client that joined late or
+    /// lost track.
+    pub fn request_keyframe(&mut self) {
+        self.keyframe_requested = true;
+    }
+
+    /// The next snapshot of `world`.
+    pub fn encode(&mut self, world: &World) -> Snapshot {
+        let base_seq = self.seq;
+        self.seq += 1;
+        let bodies = body_frames(world);
+        let tiles = tile_frames(world);
+        let keyframe_due = self.seq - self.keyframe_seq >= self.keyframe_interval.max(1);
+        // A refit that resized the interior can't be sent tile by tile.
+        if self.keyframe_requested || keyframe_due || tiles.len() != self.sent_tiles.len() {
+            self.keyframe_requested = false;
+            self.keyframe_seq = self.seq;
+            self.sent_bodies = bodies.iter().map(|body| (body.id, *body)).collect();
+            self.sent_tiles = tiles.clone();
+            let ship = &world.interior.ship;
+            return Snapshot::Keyframe(Keyframe {
+                seq: self.seq,
+                tick: world.tick_count,
+                sim_time: world.sim_time().0,
+                bodies,
+                interior_width: ship.width,
+                interior_height: ship.height,
+                tiles,
+            });
+        }
+
+        let removed: Vec<u64> = self
+            .sent_bodies
+            .keys()
+            .copied()
+            .filter(|id| world.body(*id).is_none())
+            .collect();
+        for id in &removed {
+            self.sent_bodies.remove(id);
+        }
+        let mut changed_bodies = Vec::new();
+        for body in bodies {
+            let stale = match self.sent_bodies.get(&body.id) {
+                Some(sent) => sent.is_stale(&body, self.position_epsilon_m),
+                None => true,
+            };
+            if stale {
+                self.sent_bodies.insert(body.id, body);
+                changed_bodies.push(body);
+            }
+        }
+        let mut changed_tiles = Vec::new();
+        for (sent, tile) in self.sent_tiles.iter_mut().zip(tiles) {
+            if sent.is_stale(&tile) {
+                *sent = tile;
+                changed_tiles.push(tile);
+            }
+        }
+        Snapshot::Delta(Delta {
+            seq: self.seq,
+            base_seq,
+            tick: world.tick_count,
+            sim_time: world.sim_time().0,
+            bodies: changed_bodies,
+            removed,
+            tiles: changed_tiles,
+        })
+    }
+}
+
+/// Client side of a snapshot stream: the world as the snapshots applied so
+/// far describe it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SnapshotMirror {
+    /// Last snapshot applied, `None` until the first keyframe.
+    pub seq: Option<u64>,
+    pub tick: u64,
+    pub sim_time: f64,
+    pub bodies: BTreeMap<u64, BodyFrame>,
+    pub interior_width: u32,
+    pub interior_height: u32,
+    pub tiles: Vec<TileFrame>,
+}
+
+impl SnapshotMirror {
+    /// Apply the next snapshot. A keyframe always applies; a delta only on
+    /// top of the snapshot it was built on, failing with `MissedSnapshot`
+    /// otherwise and leaving the mirror as it was until a keyframe comes.
+    pub fn apply(&mut self, snapshot: &Snapshot) -> error::Result<()> {
+        match snapshot {
+            Snapshot::Keyframe(keyframe) => {
+                *self = SnapshotMirror {
+                    seq: Some(keyframe.seq),
+                    tick: keyframe.tick,
+                    sim_time: keyframe.sim_time,
+                    bodies: keyframe
+                        .bodies
+                        .iter()
+                        .map(|body| (body.id, *body))
+                        .collect(),
+                    interior_width: keyframe.interior_width,
+                    interior_height: keyframe.interior_height,
+                    tiles: keyframe.tiles.clone(),
+                };
+            }
+            Snapshot::Delta(delta) => {
+                if self.seq != Some(delta.base_seq) {
+                    return Err(GgwError::MissedSnapshot(delta.base_seq));
+                }
+                self.seq = Some(delta.seq);
+                self.tick = delta.tick;
+                self.sim_time = delta.sim_time;
+                for id in &delta.removed {
+                    self.bodies.remove(id);
+                }
+                for body in &delta.bodies {
+                    self.bodies.insert(body.id, *body);
+                }
+                for tile in &delta.tiles {
+                    let index = (tile.y * self.interior_width + tile.x) as usize;
+                    if let Some(slot) = self.tiles.get_mut(index) {
+                        *slot = *tile;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{BodyState, Seconds, DESPAWN_RADIUS_M};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn deltas_carry_only_what_moved() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let other = world
+            .add_body(BodyState::circular(7_000_000.0, 1.0))
+            .unwrap();
+        let mut encoder = SnapshotEncoder::new(3, 1.0);
+        let mut mirror = SnapshotMirror::default();
+
+        let keyframe = encoder.encode(&world);
+        assert!(matches!(keyframe, Snapshot::Keyframe(_)));
+        mirror.apply(&keyframe).unwrap();
+        assert_eq!(mirror.bodies.len(), 2);
+        assert!(!mirror.tiles.is_empty());
+
+        // Nothing moves between steps.
+        let Snapshot::Delta(delta) = encoder.encode(&world) else {
+            panic!("expected a delta");
+        };
+        assert_eq!((delta.seq, delta.base_seq), (2, 1));
+        assert!(delta.bodies.is_empty() && delta.removed.is_empty() && delta.tiles.is_empty());
+        let skipped = Snapshot::Delta(delta);
+
+        world.step(Seconds(1.0));
+        world.get_body_mut(other).unwrap().position = Vec2::new(DESPAWN_RADIUS_M * 2.0, 0.0);
+        world.cull_despawned_bodies();
+        let snapshot = encoder.encode(&world);
+        let Snapshot::Delta(delta) = &snapshot else {
+            panic!("expected a delta");
+        };
+        assert_eq!(delta.bodies.len(), 1);
+        assert_eq!(delta.bodies[0].id, ship);
+        assert_eq!(delta.removed, [other]);
+
+        // Having missed a delta, the mirror waits for a keyframe.
+        assert_eq!(mirror.apply(&snapshot), Err(GgwError::MissedSnapshot(2)));
+        mirror.apply(&skipped).unwrap();
+        mirror.apply(&snapshot).unwrap();
+        assert_eq!(mirror.seq, Some(3));
+        assert_eq!(mirror.bodies.len(), 1);
+        assert_eq!(
+            mirror.bodies[&ship].position,
+            world.body(ship).unwrap().position
+        );
+
+        // The interval brings the next keyframe round.
+        assert!(matches!(encoder.encode(&world), Snapshot::Keyframe(_)));
+        encoder.request_keyframe();
+        assert!(matches!(encoder.encode(&world), Snapshot::Keyframe(_)));
+    }
+
+    #[test]
+    fn changed_tiles_go_out_alone() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let mut encoder = SnapshotEncoder::default();
+        let mut mirror = SnapshotMirror::default();
+        mirror.apply(&encoder.encode(&world)).unwrap();
+
+        let ship = &mut world.interior.ship;
+        let (x, y) = (0..ship.height)
+            .flat_map(|y| (0..ship.width).map(move |x| (x, y)))
+            .find(|&(x, y)| ship.tile_type(x, y) == TileType::Floor)
+            .unwrap();
+        let index = (y * ship.width + x) as usize;
+        ship.tile_atmos[index].o2_kg += 1.0;
+        let snapshot = encoder.encode(&world);
+        let Snapshot::Delta(delta) = &snapshot else {
+            panic!("expected a delta");
+        };
+        assert_eq!(delta.tiles.len(), 1);
+        assert_eq!((delta.tiles[0].x, delta.tiles[0].y), (x, y));
+        mirror.apply(&snapshot).unwrap();
+        assert_eq!(mirror.tiles, tile_frames(&world));
+    }
+}
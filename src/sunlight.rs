@@ -0,0 +1,88 @@
+//! Sunlight and eclipses.
+//!
+//! The sun is treated as infinitely far away, so its light falls on the
+//! whole system from one direction, `World::sun_direction`, which turns
+//! slowly as set by `config::SunConfig`. The planet and every moon cast a
+//! shadow as wide as themselves straight down-sun; a body whose centre is
+//! inside one is in eclipse. There is no penumbra: a body is either lit or
+//! it isn't.
+
+use crate::central::{Primaries, Primary};
+use crate::math::Float;
+use crate::{error, GgwError, Vec2, World};
+
+/// Whether `position` is inside the shadow `primary` casts away from `sun`.
+fn in_shadow(position: Vec2, sun: Vec2, primary: &Primary) -> bool {
+    let offset = position - primary.position;
+    let behind = offset.dot(sun) < 0.0;
+    behind && offset.cross(sun).abs() < primary.radius
+}
+
+/// Whether `position` is out of every shadow cast away from `sun` by
+/// `primaries`. For checking many positions against one set of primaries;
+/// `World::is_in_sunlight` looks them up itself.
+pub fn is_sunlit(position: Vec2, sun: Vec2, primaries: &Primaries) -> bool {
+    !primaries
+        .iter()
+        .any(|primary| in_shadow(position, sun, primary))
+}
+
+impl World {
+    /// Unit vector from the planet towards the sun now.
+    pub fn sun_direction(&self) -> Vec2 {
+        let sun = &self.config.sun;
+        let angle = sun.initial_angle_rad + sun.rotation_rate_rad_s * self.sim_time().0;
+        let (sin, cos) = Float::sin_cos(angle);
+        Vec2::new(cos, sin)
+    }
+
+    /// Whether `body_id` is out of the shadow of the planet and every moon.
+    pub fn is_in_sunlight(&self, body_id: u64) -> error::Result<bool> {
+        let body = self.body(body_id).ok_or(GgwError::UnknownBody(body_id))?;
+        Ok(is_sunlit(
+            body.position,
+            self.sun_direction(),
+            &self.primaries(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::{BodyState, Seconds};
+    use core::f64::consts::PI;
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn bodies_behind_the_planet_are_in_eclipse() {
+        let mut config = GameConfig::default();
+        config.sun.initial_angle_rad = 0.0;
+        config.sun.rotation_rate_rad_s = 0.0;
+        let mut world = World::new(MU_EARTH, config);
+        let day = world
+            .add_body(BodyState::circular(7_000_000.0, 0.0))
+            .unwrap();
+        let night = world
+            .add_body(BodyState::circular(7_000_000.0, PI))
+            .unwrap();
+        let dusk = world
+            .add_body(BodyState::circular(7_000_000.0, PI / 2.0))
+            .unwrap();
+
+        assert_eq!(world.sun_direction(), Vec2::new(1.0, 0.0));
+        assert_eq!(world.is_in_sunlight(day), Ok(true));
+        assert_eq!(world.is_in_sunlight(night), Ok(false));
+        assert_eq!(world.is_in_sunlight(dusk), Ok(true));
+        assert_eq!(world.is_in_sunlight(99), Err(GgwError::UnknownBody(99)));
+
+        // Half a turn later the sun has moved round to the far side.
+        world.config.sun.rotation_rate_rad_s = PI / 10.0;
+        world.step(Seconds(10.0));
+        assert!(world.sun_direction().x < -0.99);
+        assert_eq!(world.is_in_sunlight(day), Ok(false));
+        assert_eq!(world.is_in_sunlight(night), Ok(true));
+    }
+}
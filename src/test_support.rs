@@ -0,0 +1,37 @@
+//! Fixtures shared by the unit tests.
+
+use crate::{BodyState, BodyType, OrbitState, Vec2};
+use alloc::collections::BTreeMap;
+
+impl BodyState {
+    /// A 1 t, 5 m ship on `orbit`. Tests override what they care about with
+    /// struct update syntax.
+    pub(crate) fn orbiting(orbit: OrbitState) -> Self {
+        BodyState {
+            id: 0,
+            mass: 1_000.0,
+            radius: 5.0,
+            orbit,
+            position: Vec2::zero(),
+            velocity: Vec2::zero(),
+            body_type: BodyType::Ship,
+            hull_shape: None,
+            parent: None,
+            orientation: 0.0,
+            angular_velocity: 0.0,
+            tags: BTreeMap::new(),
+        }
+    }
+
+    /// The same on a circular orbit of radius `a`, `mean_anomaly` along it
+    /// at epoch 0.
+    pub(crate) fn circular(a: f64, mean_anomaly: f64) -> Self {
+        Self::orbiting(OrbitState {
+            semi_major_axis: a,
+            eccentricity: 0.0,
+            arg_of_periapsis: 0.0,
+            mean_anomaly_at_epoch: mean_anomaly,
+            epoch: 0.0,
+        })
+    }
+}
@@ -0,0 +1,363 @@
+//! Transfer planning for rendezvous gameplay.
+//!
+//! `lambert` finds the orbit that joins two points in a given time, the
+//! building block for intercepts: see `World::plan_intercept`, and
+//! `World::compute_intercept` for the cheapest one within a budget.
+//! `plan_hohmann` covers the common case of moving between circular
+//! orbits. Like the rest of the crate both only deal in single-revolution
+//! prograde (anticlockwise) transfers.
+
+use core::f64::consts::PI;
+
+use crate::math::Float;
+use crate::{error, orbit_to_cartesian, GgwError, OrbitState, ThrustEvent, ThrustType, Vec2};
+
+const MAX_ITERATIONS: usize = 200;
+/// `z` past which the bracket search gives up: transfers this fast would
+/// overflow the hyperbolic Stumpff functions.
+const MIN_Z: f64 = -1e5;
+
+/// Flight times tried, evenly spaced, by `cheapest_flight_time` before
+/// narrowing in on the best of them.
+pub const INTERCEPT_SEARCH_STEPS: usize = 64;
+/// Golden-section rounds `cheapest_flight_time` spends narrowing in.
+const INTERCEPT_REFINE_ITERATIONS: usize = 40;
+/// `1 / φ`, the share of the interval each golden-section round keeps.
+const INV_GOLDEN_RATIO: f64 = 0.618_033_988_749_895;
+
+/// Velocities at departure from `r1` and at arrival at `r2`, `tof` seconds
+/// later, on the prograde transfer orbit between them around a primary of
+/// gravitational parameter `mu`. Positions are relative to the primary.
+/// The transfer may be hyperbolic if `tof` is short. Fails with
+/// `GgwError::NoTransfer` when `r1` and `r2` lie on a line through the
+/// primary, which leaves the transfer plane undefined.
+///
+/// Solved in universal variables: the time of flight grows monotonically
+/// with `z`, the square of the change in generalized anomaly, so `z` is
+/// found by bisection, which is slower than Newton's method but can't
+/// diverge.
+pub fn lambert(r1: Vec2, r2: Vec2, tof: f64, mu: f64) -> error::Result<(Vec2, Vec2)> {
+    let (r1_len, r2_len) = (r1.length(), r2.length());
+    if !(tof > 0.0 && tof.is_finite() && mu > 0.0 && r1_len > 0.0 && r2_len > 0.0) {
+        return Err(GgwError::NoTransfer);
+    }
+    let mut dtheta = Float::atan2(r1.cross(r2), r1.dot(r2));
+    if dtheta < 0.0 {
+        dtheta += 2.0 * PI;
+    }
+    let a = Float::sin(dtheta) * Float::sqrt(r1_len * r2_len / (1.0 - Float::cos(dtheta)));
+    if !a.is_finite() || a.abs() < 1e-9 * (r1_len + r2_len) {
+        return Err(GgwError::NoTransfer);
+    }
+
+    let y = |z: f64| {
+        let (c, s) = stumpff(z);
+        r1_len + r2_len + a * (z * s - 1.0) / Float::sqrt(c)
+    };
+    // Time of flight for `z`; `None` where no orbit exists, which only
+    // happens below the solution.
+    let flight_time = |z: f64| {
+        let y = y(z);
+        if y.is_nan() || y < 0.0 {
+            return None;
+        }
+        let (c, s) = stumpff(z);
+        let x = Float::sqrt(y / c);
+        Some((x * x * x * s + a * Float::sqrt(y)) / Float::sqrt(mu))
+    };
+    let too_long = |z: f64| flight_time(z).is_some_and(|t| t > tof);
+
+    // A full revolution takes forever as `z` approaches (2 pi)^2.
+    let mut high = 4.0 * PI * PI;
+    let mut low = -4.0 * PI * PI;
+    while too_long(low) {
+        low *= 2.0;
+        if low < MIN_Z {
+            return Err(GgwError::NoTransfer);
+        }
+    }
+    for _ in 0..MAX_ITERATIONS {
+        let mid = 0.5 * (low + high);
+        if too_long(mid) {
+            high = mid;
+        } else {
+            low = mid;
+        }
+        if high - low <= 1e-14 * (1.0 + mid.abs()) {
+            break;
+        }
+    }
+
+    let z = 0.5 * (low + high);
+    let y = y(z);
+    if y.is_nan() || y <= 0.0 {
+        return Err(GgwError::NoTransfer);
+    }
+    let f = 1.0 - y / r1_len;
+    let g = a * Float::sqrt(y / mu);
+    let g_dot = 1.0 - y / r2_len;
+    Ok(((r2 - r1 * f) / g, (r2 * g_dot - r1) / g))
+}
+
+/// The two burns of a Hohmann transfer from circular `from_orbit` to a
+/// circular orbit of radius `to_radius`, around a primary of gravitational
+/// parameter `mu`: one at `now` onto the transfer ellipse, and one half an
+/// ellipse later to circularize. Either burn is retrograde when lowering
+/// the orbit. If `from_orbit` isn't quite circular the first burn also
+/// cancels its radial velocity, so the transfer starts from the current
+/// radius. `body_id` is left 0 for the caller to fill in;
+/// `World::plan_hohmann` does that.
+pub fn plan_hohmann(
+    from_orbit: &OrbitState,
+    to_radius: f64,
+    mu: f64,
+    now: f64,
+) -> error::Result<(ThrustEvent, ThrustEvent)> {
+    if !(to_radius > 0.0 && to_radius.is_finite()) {
+        return Err(GgwError::NoTransfer);
+    }
+    let (position, velocity) = orbit_to_cartesian(from_orbit, mu, now)?;
+    let r1 = position.length();
+    let r2 = to_radius;
+    let transfer_a = 0.5 * (r1 + r2);
+    // Prograde is anticlockwise, and at arrival the body is on the far
+    // side of the primary, heading the other way.
+    let prograde = position.perp() / r1;
+    let departure_speed = Float::sqrt(mu / r1 * r2 / transfer_a);
+    let arrival_speed = Float::sqrt(mu / r2 * r1 / transfer_a);
+    let transfer_time = PI * Float::sqrt(transfer_a * transfer_a * transfer_a / mu);
+    let burn = |time, delta_v| ThrustEvent {
+        body_id: 0,
+        time,
+        delta_v,
+        thrust_type: ThrustType::Chemical,
+    };
+    Ok((
+        burn(now, prograde * departure_speed - velocity),
+        burn(
+            now + transfer_time,
+            prograde * (arrival_speed - Float::sqrt(mu / r2)),
+        ),
+    ))
+}
+
+/// Flight time in `(0, max_tof]` for which `delta_v` is least, or `None`
+/// if none of them works out, shown by `delta_v` returning infinity. The
+/// cost of an intercept has many local minima as the target laps the
+/// shooter, so `INTERCEPT_SEARCH_STEPS` evenly spaced times are tried
+/// first and only the neighbourhood of the best is searched closely.
+pub fn cheapest_flight_time(max_tof: f64, delta_v: impl Fn(f64) -> f64) -> Option<f64> {
+    if !(max_tof > 0.0 && max_tof.is_finite()) {
+        return None;
+    }
+    let step = max_tof / INTERCEPT_SEARCH_STEPS as f64;
+    let (mut best, mut best_cost) = (0.0, f64::INFINITY);
+    for i in 1..=INTERCEPT_SEARCH_STEPS {
+        let tof = step * i as f64;
+        let cost = delta_v(tof);
+        if cost < best_cost {
+            (best, best_cost) = (tof, cost);
+        }
+    }
+    if !best_cost.is_finite() {
+        return None;
+    }
+    let (mut lo, mut hi) = ((best - step).max(1e-3 * step), (best + step).min(max_tof));
+    for _ in 0..INTERCEPT_REFINE_ITERATIONS {
+        let a = hi - INV_GOLDEN_RATIO * (hi - lo);
+        let b = lo + INV_GOLDEN_RATIO * (hi - lo);
+        if delta_v(a) < delta_v(b) {
+            hi = b;
+        } else {
+            lo = a;
+        }
+    }
+    let refined = 0.5 * (lo + hi);
+    if delta_v(refined) < best_cost {
+        Some(refined)
+    } else {
+        Some(best)
+    }
+}
+
+/// Stumpff functions `(C(z), S(z))`, by series near zero where the closed
+/// forms cancel badly.
+fn stumpff(z: f64) -> (f64, f64) {
+    if z > 1e-3 {
+        let x = Float::sqrt(z);
+        ((1.0 - Float::cos(x)) / z, (x - Float::sin(x)) / (x * z))
+    } else if z < -1e-3 {
+        let x = Float::sqrt(-z);
+        let exp = Float::exp(x);
+        let (cosh, sinh) = (0.5 * (exp + 1.0 / exp), 0.5 * (exp - 1.0 / exp));
+        ((cosh - 1.0) / -z, (sinh - x) / (x * -z))
+    } else {
+        (
+            1.0 / 2.0 - z / 24.0 + z * z / 720.0 - z * z * z / 40_320.0,
+            1.0 / 6.0 - z / 120.0 + z * z / 5_040.0 - z * z * z / 362_880.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::maneuver::{orbit_after_burn, ManeuverNode};
+    use crate::{BodyState, Seconds, World};
+
+    const MU_EARTH: f64 = 3.986_004_418e14;
+
+    #[test]
+    fn lambert_recovers_known_arcs() {
+        let orbit = OrbitState {
+            semi_major_axis: 12_000_000.0,
+            eccentricity: 0.3,
+            arg_of_periapsis: 0.7,
+            mean_anomaly_at_epoch: 0.2,
+            epoch: 0.0,
+        };
+        let period = 2.0 * PI * Float::sqrt(orbit.semi_major_axis.powi(3) / MU_EARTH);
+        // Arcs short of and past half an orbit.
+        for fraction in [0.05, 0.3, 0.7, 0.95] {
+            let tof = fraction * period;
+            let (r1, v1) = orbit_to_cartesian(&orbit, MU_EARTH, 0.0).unwrap();
+            let (r2, v2) = orbit_to_cartesian(&orbit, MU_EARTH, tof).unwrap();
+            let (depart, arrive) = lambert(r1, r2, tof, MU_EARTH).unwrap();
+            assert!(
+                depart.distance(v1) < 1e-4,
+                "{fraction}: {depart:?} vs {v1:?}"
+            );
+            assert!(
+                arrive.distance(v2) < 1e-4,
+                "{fraction}: {arrive:?} vs {v2:?}"
+            );
+        }
+
+        let r1 = Vec2::new(7_000_000.0, 0.0);
+        assert_eq!(
+            lambert(r1, r1 * -2.0, 3_000.0, MU_EARTH),
+            Err(GgwError::NoTransfer)
+        );
+        let r2 = Vec2::new(0.0, 8_000_000.0);
+        assert_eq!(lambert(r1, r2, 0.0, MU_EARTH), Err(GgwError::NoTransfer));
+        // A quick hop is hyperbolic but still lands on target.
+        let (depart, _) = lambert(r1, r2, 300.0, MU_EARTH).unwrap();
+        assert!(depart.length_squared() > 2.0 * MU_EARTH / r1.length());
+    }
+
+    #[test]
+    fn plan_intercept_meets_the_target() {
+        // The quickest intercept dips through the atmosphere on the way.
+        let mut config = GameConfig::default();
+        config.reentry.enabled = false;
+        let mut world = World::new(MU_EARTH, config);
+        let mut ids = [0; 2];
+        for (id, (a, mean_anomaly)) in ids.iter_mut().zip([(7_000_000.0, 0.0), (7_500_000.0, 1.0)])
+        {
+            *id = world
+                .add_body(BodyState::circular(a, mean_anomaly))
+                .unwrap();
+        }
+        let [chaser, target] = ids;
+        world.step(Seconds(10.0));
+        let burn = world
+            .plan_intercept(chaser, target, Seconds(1_800.0))
+            .unwrap();
+        assert_eq!((burn.body_id, burn.time), (chaser, world.sim_time().0));
+        world.apply_thrust_event(&burn).unwrap();
+        world.warp_to(world.sim_time() + Seconds(1_800.0));
+        let gap = world
+            .body(chaser)
+            .unwrap()
+            .position
+            .distance(world.body(target).unwrap().position);
+        assert!(gap < 1.0, "{gap}");
+        assert_eq!(
+            world
+                .plan_intercept(chaser, target + 1, Seconds(1_800.0))
+                .unwrap_err(),
+            GgwError::UnknownBody(target + 1)
+        );
+    }
+
+    #[test]
+    fn compute_intercept_finds_the_cheapest_within_budget() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let mut ids = [0; 2];
+        for (id, mean_anomaly) in ids.iter_mut().zip([0.0, -0.5]) {
+            *id = world
+                .add_body(BodyState::circular(7_000_000.0, mean_anomaly))
+                .unwrap();
+        }
+        let [shooter, target] = ids;
+        assert_eq!(
+            world.compute_intercept(shooter, target + 1, 1_000.0),
+            Err(GgwError::UnknownBody(target + 1))
+        );
+        assert_eq!(world.compute_intercept(shooter, target, 1.0), Ok(None));
+
+        let burn = world
+            .compute_intercept(shooter, target, 1_000.0)
+            .unwrap()
+            .unwrap();
+        let cost = burn.delta_v.length();
+        assert!(cost <= 1_000.0);
+        for tof in [1_000.0, 2_000.0, 3_000.0] {
+            let other = world.plan_intercept(shooter, target, Seconds(tof)).unwrap();
+            assert!(cost <= other.delta_v.length() + 1e-6);
+        }
+
+        // The two paths meet within the shooter's next orbit.
+        world.apply_thrust_event(&burn).unwrap();
+        let horizon = Seconds(6_000.0);
+        let ours = world.predict_trajectory(shooter, horizon, 30_001).unwrap();
+        let theirs = world.predict_trajectory(target, horizon, 30_001).unwrap();
+        let closest = ours
+            .iter()
+            .zip(&theirs)
+            .map(|(a, b)| a.position.distance(b.position))
+            .fold(f64::INFINITY, f64::min);
+        assert!(closest < 100.0, "{closest}");
+    }
+
+    #[test]
+    fn hohmann_burns_reach_the_target_circle() {
+        let mut world = World::new(MU_EARTH, GameConfig::default());
+        let ship = world
+            .add_body(BodyState::circular(7_000_000.0, 0.4))
+            .unwrap();
+        world.step(Seconds(10.0));
+        let (raise, circularize) = world.plan_hohmann(ship, 9_000_000.0).unwrap();
+        assert_eq!((raise.body_id, circularize.body_id), (ship, ship));
+        assert!(circularize.time > raise.time);
+        for burn in [&raise, &circularize] {
+            world
+                .plan_node(ManeuverNode {
+                    body_id: burn.body_id,
+                    time: Seconds(burn.time),
+                    delta_v: burn.delta_v,
+                })
+                .unwrap();
+        }
+        world.warp_to(Seconds(circularize.time + 60.0));
+        let orbit = world.body(ship).unwrap().orbit;
+        assert!(
+            (orbit.semi_major_axis - 9_000_000.0).abs() < 1.0,
+            "{orbit:?}"
+        );
+        assert!(orbit.eccentricity < 1e-6, "{orbit:?}");
+
+        // Going down, both burns are retrograde.
+        let (_, velocity) = orbit_to_cartesian(&orbit, MU_EARTH, 0.0).unwrap();
+        let (lower, circularize) = plan_hohmann(&orbit, 7_000_000.0, MU_EARTH, 0.0).unwrap();
+        assert!(lower.delta_v.dot(velocity) < 0.0);
+        let descent = orbit_after_burn(&orbit, MU_EARTH, 0.0, lower.delta_v).unwrap();
+        let (_, velocity) = orbit_to_cartesian(&descent, MU_EARTH, circularize.time).unwrap();
+        assert!(circularize.delta_v.dot(velocity) < 0.0);
+        assert_eq!(
+            plan_hohmann(&orbit, -1.0, MU_EARTH, 0.0).unwrap_err(),
+            GgwError::NoTransfer
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! SI newtypes for the quantities the public API is easiest to get wrong.
+//! Each wraps an `f64` in base units; the named constructors are the only
+//! place other units are converted.
+
+use core::fmt;
+use core::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+macro_rules! unit {
+    ($(#[$meta:meta])* $name:ident, $symbol:literal) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(transparent)
+        )]
+        pub struct $name(pub f64);
+
+        impl $name {
+            pub const ZERO: Self = Self(0.0);
+
+            pub fn abs(self) -> Self {
+                Self(self.0.abs())
+            }
+
+            pub fn min(self, other: Self) -> Self {
+                Self(self.0.min(other.0))
+            }
+
+            pub fn max(self, other: Self) -> Self {
+                Self(self.0.max(other.0))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{} {}", self.0, $symbol)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl Mul<f64> for $name {
+            type Output = Self;
+
+            fn mul(self, k: f64) -> Self {
+                Self(self.0 * k)
+            }
+        }
+
+        impl Mul<$name> for f64 {
+            type Output = $name;
+
+            fn mul(self, rhs: $name) -> $name {
+                $name(self * rhs.0)
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = Self;
+
+            fn div(self, k: f64) -> Self {
+                Self(self.0 / k)
+            }
+        }
+
+        /// Ratio of two quantities of the same unit.
+        impl Div for $name {
+            type Output = f64;
+
+            fn div(self, rhs: Self) -> f64 {
+                self.0 / rhs.0
+            }
+        }
+    };
+}
+
+unit!(
+    /// A duration or absolute simulation time, in seconds.
+    Seconds,
+    "s"
+);
+unit!(
+    /// A distance, in meters.
+    Meters,
+    "m"
+);
+unit!(
+    /// A speed, in meters per second.
+    MetersPerSecond,
+    "m/s"
+);
+unit!(
+    /// An angle or heading, in radians.
+    Radians,
+    "rad"
+);
+
+impl Seconds {
+    pub fn from_minutes(minutes: f64) -> Self {
+        Self(minutes * 60.0)
+    }
+
+    pub fn from_hours(hours: f64) -> Self {
+        Self(hours * 3_600.0)
+    }
+}
+
+impl Meters {
+    pub fn from_km(km: f64) -> Self {
+        Self(km * 1_000.0)
+    }
+}
+
+impl MetersPerSecond {
+    pub fn from_km_per_s(km_per_s: f64) -> Self {
+        Self(km_per_s * 1_000.0)
+    }
+}
+
+impl Radians {
+    pub fn from_degrees(degrees: f64) -> Self {
+        Self(crate::angle::from_degrees(degrees))
+    }
+}
+
+impl Div<Seconds> for Meters {
+    type Output = MetersPerSecond;
+
+    fn div(self, rhs: Seconds) -> MetersPerSecond {
+        MetersPerSecond(self.0 / rhs.0)
+    }
+}
+
+impl Div<MetersPerSecond> for Meters {
+    type Output = Seconds;
+
+    fn div(self, rhs: MetersPerSecond) -> Seconds {
+        Seconds(self.0 / rhs.0)
+    }
+}
+
+impl Mul<Seconds> for MetersPerSecond {
+    type Output = Meters;
+
+    fn mul(self, rhs: Seconds) -> Meters {
+        Meters(self.0 * rhs.0)
+    }
+}
+
+impl Mul<MetersPerSecond> for Seconds {
+    type Output = Meters;
+
+    fn mul(self, rhs: MetersPerSecond) -> Meters {
+        Meters(self.0 * rhs.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_and_dimensional_arithmetic() {
+        assert_eq!(Seconds::from_minutes(2.0), Seconds(120.0));
+        assert_eq!(Meters::from_km(7.5), Meters(7_500.0));
+        let speed = Meters::from_km(3.0) / Seconds(10.0);
+        assert_eq!(speed, MetersPerSecond(300.0));
+        assert_eq!(speed * Seconds(2.0), Meters(600.0));
+        assert_eq!(Meters(600.0) / speed, Seconds(2.0));
+        assert_eq!(
+            Seconds(3.0) - Seconds(1.0) + 2.0 * Seconds(1.0),
+            Seconds(4.0)
+        );
+        assert_eq!(Meters(10.0) / Meters(4.0), 2.5);
+        assert_eq!(Radians::from_degrees(180.0), Radians(core::f64::consts::PI));
+    }
+}
@@ -0,0 +1,232 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    config::GameConfig, frame::floating_origin_offset, orbit_to_cartesian_in, BodyState, BodyType,
+    OrbitState, Seconds, ThrustEvent, ThrustType, Vec2, World,
+};
+
+/// Number of `f64`s per body in `WasmWorld::body_states`:
+/// `[id, body_type, mass, radius, x, y, vx, vy]`.
+pub const BODY_STATE_STRIDE: usize = 8;
+
+/// JS handle to a local copy of the simulation, used by the browser client
+/// to predict between server snapshots.
+#[wasm_bindgen]
+pub struct WasmWorld {
+    inner: World,
+}
+
+#[wasm_bindgen]
+impl WasmWorld {
+    #[wasm_bindgen(constructor)]
+    pub fn new(mu: f64) -> WasmWorld {
+        WasmWorld {
+            inner: World::new(mu, GameConfig::default()),
+        }
+    }
+
+    pub fn step(&mut self, dt: f64) {
+        self.inner.step(Seconds(dt));
+    }
+
+    #[wasm_bindgen(js_name = warpTo)]
+    pub fn warp_to(&mut self, target_time: f64) {
+        self.inner.warp_to(Seconds(target_time));
+    }
+
+    #[wasm_bindgen(getter, js_name = simTime)]
+    pub fn sim_time(&self) -> f64 {
+        self.inner.sim_time().0
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn tick(&self) -> u64 {
+        self.inner.tick_count
+    }
+
+    /// Spawn a body on the given orbit. `body_type` uses the codes from
+    /// `body_states`; `id` 0 lets the world assign one. Returns the id.
+    #[wasm_bindgen(js_name = spawnBody)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn spawn_body(
+        &mut self,
+        id: u64,
+        body_type: u8,
+        mass: f64,
+        radius: f64,
+        semi_major_axis: f64,
+        eccentricity: f64,
+        arg_of_periapsis: f64,
+        mean_anomaly_at_epoch: f64,
+        epoch: f64,
+    ) -> Result<u64, JsError> {
+        let body_type =
+            body_type_from_code(body_type).ok_or_else(|| JsError::new("unknown body type code"))?;
+        self.inner
+            .add_body(BodyState {
+                id,
+                mass,
+                radius,
+                orbit: OrbitState {
+                    semi_major_axis,
+                    eccentricity,
+                    arg_of_periapsis,
+                    mean_anomaly_at_epoch,
+                    epoch,
+                },
+                position: Vec2::zero(),
+                velocity: Vec2::zero(),
+                body_type,
+                hull_shape: None,
+                parent: None,
+                orientation: 0.0,
+                angular_velocity: 0.0,
+                tags: BTreeMap::new(),
+            })
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = removeBody)]
+    pub fn remove_body(&mut self, id: u64) -> bool {
+        self.inner.remove_body(id).is_some()
+    }
+
+    #[wasm_bindgen(js_name = applyThrust)]
+    pub fn apply_thrust(
+        &mut self,
+        body_id: u64,
+        time: f64,
+        delta_v_x: f64,
+        delta_v_y: f64,
+    ) -> Result<(), JsError> {
+        self.inner
+            .apply_thrust_event(&ThrustEvent {
+                body_id,
+                time,
+                delta_v: Vec2::new(delta_v_x, delta_v_y),
+                thrust_type: ThrustType::Chemical,
+            })
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    /// Flat snapshot of every body, `BODY_STATE_STRIDE` values each, ready
+    /// to be read as a `Float64Array` without per-body allocations on the
+    /// JS side.
+    #[wasm_bindgen(js_name = bodyStates)]
+    pub fn body_states(&self) -> Vec<f64> {
+        let mut out = Vec::with_capacity(self.inner.bodies.len() * BODY_STATE_STRIDE);
+        for body in &self.inner.bodies {
+            out.extend_from_slice(&[
+                body.id as f64,
+                body_type_code(body.body_type) as f64,
+                body.mass,
+                body.radius,
+                body.position.x,
+                body.position.y,
+                body.velocity.x,
+                body.velocity.y,
+            ]);
+        }
+        out
+    }
+
+    /// `[x, y]` per body, in `body_states` order, predicted at `time` in
+    /// single precision. Cheap enough to call every frame for rendering;
+    /// bodies whose orbit cannot be propagated report their last position.
+    #[wasm_bindgen(js_name = positionsAtF32)]
+    pub fn positions_at_f32(&self, time: f64) -> Vec<f32> {
+        let primaries = self.inner.primaries_at(time);
+        let mut out = Vec::with_capacity(self.inner.bodies.len() * 2);
+        for body in &self.inner.bodies {
+            let primary = primaries.get(body.parent);
+            let position = orbit_to_cartesian_in::<f32>(&body.orbit, primary.mu, time).map_or_else(
+                |_| body.position.cast(),
+                |(position, _)| primary.position.cast() + position,
+            );
+            out.extend_from_slice(&[position.x, position.y]);
+        }
+        out
+    }
+
+    /// `[x, y]` per body, in `body_states` order, as single-precision
+    /// offsets from body `origin_id`, so far-out scenes render without
+    /// jitter. Positions are those of the last step; `None` if there is no
+    /// such body.
+    #[wasm_bindgen(js_name = positionsRelativeF32)]
+    pub fn positions_relative_f32(&self, origin_id: u64) -> Option<Vec<f32>> {
+        let origin = self.inner.body(origin_id)?.position;
+        let mut out = Vec::with_capacity(self.inner.bodies.len() * 2);
+        for body in &self.inner.bodies {
+            let offset = floating_origin_offset(body.position, origin);
+            out.extend_from_slice(&[offset.x, offset.y]);
+        }
+        Some(out)
+    }
+
+    /// `[x, y]` for each of `points` points around body `body_id`'s orbit,
+    /// starting at its current position, for drawing its path.
+    #[wasm_bindgen(js_name = orbitPath)]
+    pub fn orbit_path(&self, body_id: u64, points: usize) -> Result<Vec<f64>, JsError> {
+        let path = self
+            .inner
+            .orbit_path(body_id, points)
+            .map_err(|err| JsError::new(&err.to_string()))?;
+        Ok(path.iter().flat_map(|point| [point.x, point.y]).collect())
+    }
+
+    #[wasm_bindgen(js_name = stateHash)]
+    pub fn state_hash(&self) -> u64 {
+        self.inner.state_hash()
+    }
+}
+
+fn body_type_code(body_type: BodyType) -> u8 {
+    match body_type {
+        BodyType::Ship => 0,
+        BodyType::Asteroid => 1,
+        BodyType::Debris => 2,
+        BodyType::Missile => 3,
+    }
+}
+
+fn body_type_from_code(code: u8) -> Option<BodyType> {
+    match code {
+        0 => Some(BodyType::Ship),
+        1 => Some(BodyType::Asteroid),
+        2 => Some(BodyType::Debris),
+        3 => Some(BodyType::Missile),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_states_use_fixed_stride() {
+        let mut world = WasmWorld::new(3.986004418e14);
+        let id = world
+            .spawn_body(0, 1, 1_000.0, 5.0, 7_000_000.0, 0.0, 0.0, 0.0, 0.0)
+            .ok()
+            .unwrap();
+        world.step(1.0);
+        let states = world.body_states();
+        assert_eq!(states.len(), BODY_STATE_STRIDE);
+        assert_eq!(states[0], id as f64);
+        assert_eq!(states[1], 1.0);
+        assert!(Vec2::new(states[4], states[5]).length() > 6_999_000.0);
+        let positions = world.positions_at_f32(1.0);
+        assert_eq!(positions.len(), 2);
+        assert!((positions[0] as f64 - states[4]).abs() < 1.0);
+        assert_eq!(world.positions_relative_f32(id), Some(vec![0.0, 0.0]));
+        assert_eq!(world.positions_relative_f32(id + 1), None);
+        assert!(world.remove_body(id));
+        assert!(world.body_states().is_empty());
+        assert_eq!(world.inner.despawn_events()[0].id, id);
+        assert!(!world.remove_body(id));
+    }
+}